@@ -25,14 +25,15 @@ use tonic::Status;
 use uuid::Uuid;
 
 use super::qdrant::{
-    BinaryQuantization, BoolIndexParams, CompressionRatio, DatetimeIndexParams, DatetimeRange,
-    Direction, FacetHit, FacetHitInternal, FacetValue, FacetValueInternal, FieldType,
-    FloatIndexParams, GeoIndexParams, GeoLineString, GroupId, HardwareUsage, HasVectorCondition,
-    KeywordIndexParams, LookupLocation, MaxOptimizationThreads, MultiVectorComparator,
-    MultiVectorConfig, OrderBy, OrderValue, Range, RawVector, RecommendStrategy, RetrievedPoint,
-    SearchMatrixPair, SearchPointGroups, SearchPoints, ShardKeySelector, StartFrom,
-    StrictModeMultivector, StrictModeMultivectorConfig, StrictModeSparse, StrictModeSparseConfig,
-    UuidIndexParams, VectorsOutput, WithLookup, raw_query, start_from,
+    BinaryQuantization, BoolIndexParams, ComparisonOperator, CompressionRatio, DatetimeIndexParams,
+    DatetimeRange, Direction, FacetHit, FacetHitInternal, FacetValue, FacetValueInternal,
+    FieldType, FloatIndexParams, GeoIndexParams, GeoLineString, GroupId, HardwareUsage,
+    HasIdRangeCondition, HasVectorCondition, KeywordIndexParams, KeywordRange, LookupLocation,
+    MaxOptimizationThreads, MultiVectorComparator, MultiVectorConfig, OrderBy, OrderValue, Range,
+    RangeMode, RawVector, RecommendStrategy, RetrievedPoint, SearchMatrixPair, SearchPointGroups,
+    SearchPoints, ShardKeySelector, StartFrom, StrictModeMultivector, StrictModeMultivectorConfig,
+    StrictModeSparse, StrictModeSparseConfig, UuidIndexParams, VectorsOutput,
+    VersionAtMostCondition, WithLookup, raw_query, start_from,
 };
 use super::stemming_algorithm::StemmingParams;
 use super::{Expression, Formula, RecoQuery, SnowballParams, StemmingAlgorithm, Usage};
@@ -44,16 +45,16 @@ use crate::grpc::qdrant::point_id::PointIdOptions;
 use crate::grpc::qdrant::with_payload_selector::SelectorOptions;
 use crate::grpc::qdrant::{
     CollectionDescription, CollectionOperationResponse, Condition, Distance, FieldCondition,
-    Filter, GeoBoundingBox, GeoPoint, GeoPolygon, GeoRadius, HasIdCondition, HealthCheckReply,
-    HnswConfigDiff, IntegerIndexParams, IsEmptyCondition, IsNullCondition, ListCollectionsResponse,
-    Match, MinShould, NamedVectors, NestedCondition, PayloadExcludeSelector,
-    PayloadIncludeSelector, PayloadIndexParams, PayloadSchemaInfo, PayloadSchemaType, PointId,
-    PointStruct, PointsOperationResponse, PointsOperationResponseInternal, ProductQuantization,
-    QuantizationConfig, QuantizationSearchParams, QuantizationType, RepeatedIntegers,
-    RepeatedStrings, ScalarQuantization, ScoredPoint, SearchParams, ShardKey, StopwordsSet,
-    StrictModeConfig, TextIndexParams, TokenizerType, UpdateResult, UpdateResultInternal,
-    ValuesCount, VectorsSelector, WithPayloadSelector, WithVectorsSelector, shard_key,
-    with_vectors_selector,
+    FieldVsFieldCondition, Filter, GeoBoundingBox, GeoPoint, GeoPolygon, GeoRadius, HasIdCondition,
+    HealthCheckReply, HnswConfigDiff, IntegerIndexParams, IsEmptyCondition, IsNullCondition,
+    ListCollectionsResponse, Match, MinShould, NamedVectors, NestedCondition,
+    PayloadExcludeSelector, PayloadIncludeSelector, PayloadIndexParams, PayloadSchemaInfo,
+    PayloadSchemaType, PointId, PointStruct, PointsOperationResponse,
+    PointsOperationResponseInternal, ProductQuantization, QuantizationConfig,
+    QuantizationSearchParams, QuantizationType, RepeatedIntegers, RepeatedStrings,
+    ScalarQuantization, ScoredPoint, SearchParams, ShardKey, StopwordsSet, StrictModeConfig,
+    TextIndexParams, TokenizerType, UpdateResult, UpdateResultInternal, ValuesCount,
+    VectorsSelector, WithPayloadSelector, WithVectorsSelector, shard_key, with_vectors_selector,
 };
 use crate::grpc::{
     self, BinaryQuantizationEncoding, BinaryQuantizationQueryEncoding, DecayParamsExpression,
@@ -152,11 +153,13 @@ impl From<segment::data_types::index::KeywordIndexParams> for PayloadIndexParams
             r#type: _,
             is_tenant,
             on_disk,
+            exists_only,
         } = params;
         PayloadIndexParams {
             index_params: Some(IndexParams::KeywordIndexParams(KeywordIndexParams {
                 is_tenant,
                 on_disk,
+                exists_only,
             })),
         }
     }
@@ -200,9 +203,16 @@ impl From<segment::data_types::index::FloatIndexParams> for PayloadIndexParams {
 
 impl From<segment::data_types::index::GeoIndexParams> for PayloadIndexParams {
     fn from(params: segment::data_types::index::GeoIndexParams) -> Self {
-        let segment::data_types::index::GeoIndexParams { r#type: _, on_disk } = params;
+        let segment::data_types::index::GeoIndexParams {
+            r#type: _,
+            on_disk,
+            max_query_regions,
+        } = params;
         PayloadIndexParams {
-            index_params: Some(IndexParams::GeoIndexParams(GeoIndexParams { on_disk })),
+            index_params: Some(IndexParams::GeoIndexParams(GeoIndexParams {
+                on_disk,
+                max_query_regions: max_query_regions.map(|regions| regions as u32),
+            })),
         }
     }
 }
@@ -410,11 +420,16 @@ impl From<segment::types::PayloadSchemaParams> for PayloadIndexParams {
 impl TryFrom<KeywordIndexParams> for segment::data_types::index::KeywordIndexParams {
     type Error = Status;
     fn try_from(params: KeywordIndexParams) -> Result<Self, Self::Error> {
-        let KeywordIndexParams { is_tenant, on_disk } = params;
+        let KeywordIndexParams {
+            is_tenant,
+            on_disk,
+            exists_only,
+        } = params;
         Ok(segment::data_types::index::KeywordIndexParams {
             r#type: KeywordIndexType::Keyword,
             is_tenant,
             on_disk,
+            exists_only,
         })
     }
 }
@@ -456,10 +471,14 @@ impl TryFrom<FloatIndexParams> for segment::data_types::index::FloatIndexParams
 impl TryFrom<GeoIndexParams> for segment::data_types::index::GeoIndexParams {
     type Error = Status;
     fn try_from(params: GeoIndexParams) -> Result<Self, Self::Error> {
-        let GeoIndexParams { on_disk } = params;
+        let GeoIndexParams {
+            on_disk,
+            max_query_regions,
+        } = params;
         Ok(segment::data_types::index::GeoIndexParams {
             r#type: GeoIndexType::Geo,
             on_disk,
+            max_query_regions: max_query_regions.map(|regions| regions as usize),
         })
     }
 }
@@ -1478,6 +1497,22 @@ pub fn grpc_condition_into_condition(
                 has_vector: has_vector.has_vector,
             },
         )),
+        ConditionOneOf::FieldVsField(field_vs_field) => Some(
+            segment::types::Condition::FieldVsField(field_vs_field.try_into()?),
+        ),
+        ConditionOneOf::VersionAtMost(version_at_most) => Some(
+            segment::types::Condition::VersionAtMost(segment::types::VersionAtMostCondition {
+                version_at_most: version_at_most.version_at_most,
+            }),
+        ),
+        ConditionOneOf::HasIdRange(has_id_range) => Some(segment::types::Condition::HasIdRange(
+            segment::types::HasIdRangeCondition {
+                has_id_range: segment::types::IdRange {
+                    gte: has_id_range.gte,
+                    lt: has_id_range.lt,
+                },
+            },
+        )),
     };
 
     Ok(condition)
@@ -1512,6 +1547,18 @@ impl From<segment::types::Condition> for Condition {
                     has_vector: has_vector.has_vector,
                 }))
             }
+            segment::types::Condition::FieldVsField(field_vs_field) => Some(
+                ConditionOneOf::FieldVsField(FieldVsFieldCondition::from(field_vs_field)),
+            ),
+            segment::types::Condition::VersionAtMost(version_at_most) => {
+                Some(ConditionOneOf::VersionAtMost(VersionAtMostCondition {
+                    version_at_most: version_at_most.version_at_most,
+                }))
+            }
+            segment::types::Condition::HasIdRange(has_id_range) => {
+                let segment::types::IdRange { gte, lt } = has_id_range.has_id_range;
+                Some(ConditionOneOf::HasIdRange(HasIdRangeCondition { gte, lt }))
+            }
         };
 
         Self { condition_one_of }
@@ -1589,6 +1636,59 @@ impl From<segment::types::IsNullCondition> for IsNullCondition {
     }
 }
 
+impl TryFrom<FieldVsFieldCondition> for segment::types::FieldVsFieldCondition {
+    type Error = Status;
+
+    fn try_from(value: FieldVsFieldCondition) -> Result<Self, Status> {
+        let FieldVsFieldCondition { left, op, right } = value;
+        let op = ComparisonOperator::try_from(op)
+            .map_err(|_| Status::invalid_argument(format!("Unknown comparison operator: {op}")))?;
+        Ok(segment::types::FieldVsFieldCondition {
+            field_vs_field: segment::types::FieldComparison {
+                left: json::json_path_from_proto(&left)?,
+                op: op.into(),
+                right: json::json_path_from_proto(&right)?,
+            },
+        })
+    }
+}
+
+impl From<segment::types::FieldVsFieldCondition> for FieldVsFieldCondition {
+    fn from(value: segment::types::FieldVsFieldCondition) -> Self {
+        let segment::types::FieldVsFieldCondition { field_vs_field } = value;
+        let segment::types::FieldComparison { left, op, right } = field_vs_field;
+        Self {
+            left: left.to_string(),
+            op: ComparisonOperator::from(op) as i32,
+            right: right.to_string(),
+        }
+    }
+}
+
+impl From<ComparisonOperator> for segment::types::ComparisonOperator {
+    fn from(value: ComparisonOperator) -> Self {
+        match value {
+            ComparisonOperator::Eq => segment::types::ComparisonOperator::Eq,
+            ComparisonOperator::Lt => segment::types::ComparisonOperator::Lt,
+            ComparisonOperator::Lte => segment::types::ComparisonOperator::Lte,
+            ComparisonOperator::Gt => segment::types::ComparisonOperator::Gt,
+            ComparisonOperator::Gte => segment::types::ComparisonOperator::Gte,
+        }
+    }
+}
+
+impl From<segment::types::ComparisonOperator> for ComparisonOperator {
+    fn from(value: segment::types::ComparisonOperator) -> Self {
+        match value {
+            segment::types::ComparisonOperator::Eq => ComparisonOperator::Eq,
+            segment::types::ComparisonOperator::Lt => ComparisonOperator::Lt,
+            segment::types::ComparisonOperator::Lte => ComparisonOperator::Lte,
+            segment::types::ComparisonOperator::Gt => ComparisonOperator::Gt,
+            segment::types::ComparisonOperator::Gte => ComparisonOperator::Gte,
+        }
+    }
+}
+
 impl TryFrom<HasIdCondition> for segment::types::HasIdCondition {
     type Error = Status;
 
@@ -1625,8 +1725,18 @@ impl TryFrom<FieldCondition> for segment::types::FieldCondition {
             datetime_range,
             is_empty,
             is_null,
+            range_mode,
+            keyword_range,
         } = value;
 
+        let range_mode = range_mode
+            .map(|mode| {
+                RangeMode::try_from(mode)
+                    .map(segment::types::RangeMode::from)
+                    .map_err(|_| Status::invalid_argument(format!("Unknown range mode: {mode}")))
+            })
+            .transpose()?;
+
         let geo_bounding_box =
             geo_bounding_box.map_or_else(|| Ok(None), |g| g.try_into().map(Some))?;
         let geo_radius = geo_radius.map_or_else(|| Ok(None), |g| g.try_into().map(Some))?;
@@ -1638,11 +1748,15 @@ impl TryFrom<FieldCondition> for segment::types::FieldCondition {
                 .map(segment::types::RangeInterface::try_from)
                 .transpose()?;
         }
+        if range.is_none() {
+            range = keyword_range.map(Into::into);
+        }
 
         Ok(Self {
             key: json::json_path_from_proto(&key)?,
             r#match: r#match.map_or_else(|| Ok(None), |m| m.try_into().map(Some))?,
             range,
+            range_mode,
             geo_bounding_box,
             geo_radius,
             geo_polygon,
@@ -1659,6 +1773,7 @@ impl From<segment::types::FieldCondition> for FieldCondition {
             key,
             r#match,
             range,
+            range_mode,
             geo_bounding_box,
             geo_radius,
             geo_polygon,
@@ -1667,10 +1782,15 @@ impl From<segment::types::FieldCondition> for FieldCondition {
             is_null,
         } = value;
 
-        let (range, datetime_range) = match range {
-            Some(segment::types::RangeInterface::Float(range)) => (Some(range.into()), None),
-            Some(segment::types::RangeInterface::DateTime(range)) => (None, Some(range.into())),
-            None => (None, None),
+        let (range, datetime_range, keyword_range) = match range {
+            Some(segment::types::RangeInterface::Float(range)) => (Some(range.into()), None, None),
+            Some(segment::types::RangeInterface::DateTime(range)) => {
+                (None, Some(range.into()), None)
+            }
+            Some(segment::types::RangeInterface::Keyword(range)) => {
+                (None, None, Some(range.into()))
+            }
+            None => (None, None, None),
         };
 
         Self {
@@ -1684,6 +1804,26 @@ impl From<segment::types::FieldCondition> for FieldCondition {
             datetime_range,
             is_empty,
             is_null,
+            range_mode: range_mode.map(|mode| RangeMode::from(mode) as i32),
+            keyword_range,
+        }
+    }
+}
+
+impl From<RangeMode> for segment::types::RangeMode {
+    fn from(value: RangeMode) -> Self {
+        match value {
+            RangeMode::Any => segment::types::RangeMode::Any,
+            RangeMode::All => segment::types::RangeMode::All,
+        }
+    }
+}
+
+impl From<segment::types::RangeMode> for RangeMode {
+    fn from(value: segment::types::RangeMode) -> Self {
+        match value {
+            segment::types::RangeMode::Any => RangeMode::Any,
+            segment::types::RangeMode::All => RangeMode::All,
         }
     }
 }
@@ -1852,6 +1992,20 @@ impl From<segment::types::Range<DateTimePayloadType>> for DatetimeRange {
     }
 }
 
+impl From<KeywordRange> for segment::types::RangeInterface {
+    fn from(value: KeywordRange) -> Self {
+        let KeywordRange { lt, gt, gte, lte } = value;
+        Self::Keyword(segment::types::Range { lt, gt, gte, lte })
+    }
+}
+
+impl From<segment::types::Range<String>> for KeywordRange {
+    fn from(value: segment::types::Range<String>) -> Self {
+        let segment::types::Range { lt, gt, gte, lte } = value;
+        Self { lt, gt, gte, lte }
+    }
+}
+
 impl From<ValuesCount> for segment::types::ValuesCount {
     fn from(value: ValuesCount) -> Self {
         let ValuesCount { lt, gt, gte, lte } = value;
@@ -1899,6 +2053,7 @@ impl TryFrom<Match> for segment::types::Match {
                 MatchValue::TextAny(text_any) => {
                     segment::types::Match::TextAny(segment::types::MatchTextAny { text_any })
                 }
+                MatchValue::Prefix(prefix) => segment::types::Match::Prefix(prefix.into()),
             }),
             _ => Err(Status::invalid_argument("Malformed Match condition")),
         }
@@ -1919,6 +2074,9 @@ impl From<segment::types::Match> for Match {
             segment::types::Match::Phrase(segment::types::MatchPhrase { phrase }) => {
                 MatchValue::Phrase(phrase)
             }
+            segment::types::Match::Prefix(segment::types::MatchPrefix { prefix }) => {
+                MatchValue::Prefix(prefix)
+            }
             segment::types::Match::Any(any) => match any.any {
                 segment::types::AnyVariants::Strings(strings) => {
                     let strings = strings.into_iter().collect();
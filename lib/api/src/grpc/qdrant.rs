@@ -1014,6 +1014,9 @@ pub struct KeywordIndexParams {
     /// If true - store index on disk.
     #[prost(bool, optional, tag = "2")]
     pub on_disk: ::core::option::Option<bool>,
+    /// If true - only index presence of the field, value lookups are not supported.
+    #[prost(bool, optional, tag = "3")]
+    pub exists_only: ::core::option::Option<bool>,
 }
 #[derive(serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -1050,6 +1053,9 @@ pub struct GeoIndexParams {
     /// If true - store index on disk.
     #[prost(bool, optional, tag = "1")]
     pub on_disk: ::core::option::Option<bool>,
+    /// Max number of geohash regions to cover the area of a geo query with. More regions give finer precision. Default: 12.
+    #[prost(uint32, optional, tag = "2")]
+    pub max_query_regions: ::core::option::Option<u32>,
 }
 #[derive(serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -6649,7 +6655,7 @@ pub struct MinShould {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Condition {
-    #[prost(oneof = "condition::ConditionOneOf", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[prost(oneof = "condition::ConditionOneOf", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10")]
     #[validate(nested)]
     pub condition_one_of: ::core::option::Option<condition::ConditionOneOf>,
 }
@@ -6673,6 +6679,12 @@ pub mod condition {
         Nested(super::NestedCondition),
         #[prost(message, tag = "7")]
         HasVector(super::HasVectorCondition),
+        #[prost(message, tag = "8")]
+        FieldVsField(super::FieldVsFieldCondition),
+        #[prost(message, tag = "9")]
+        VersionAtMost(super::VersionAtMostCondition),
+        #[prost(message, tag = "10")]
+        HasIdRange(super::HasIdRangeCondition),
     }
 }
 #[derive(serde::Serialize)]
@@ -6699,10 +6711,42 @@ pub struct HasIdCondition {
 #[derive(serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HasIdRangeCondition {
+    /// point.id >= gte
+    #[prost(uint64, optional, tag = "1")]
+    pub gte: ::core::option::Option<u64>,
+    /// point.id < lt
+    #[prost(uint64, optional, tag = "2")]
+    pub lt: ::core::option::Option<u64>,
+}
+#[derive(serde::Serialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct HasVectorCondition {
     #[prost(string, tag = "1")]
     pub has_vector: ::prost::alloc::string::String,
 }
+#[derive(serde::Serialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VersionAtMostCondition {
+    #[prost(uint64, tag = "1")]
+    pub version_at_most: u64,
+}
+#[derive(serde::Serialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FieldVsFieldCondition {
+    /// Payload key of the left operand
+    #[prost(string, tag = "1")]
+    pub left: ::prost::alloc::string::String,
+    /// Comparison operator
+    #[prost(enumeration = "ComparisonOperator", tag = "2")]
+    pub op: i32,
+    /// Payload key of the right operand
+    #[prost(string, tag = "3")]
+    pub right: ::prost::alloc::string::String,
+}
 #[derive(validator::Validate)]
 #[derive(serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -6749,12 +6793,18 @@ pub struct FieldCondition {
     /// Check if field is null
     #[prost(bool, optional, tag = "10")]
     pub is_null: ::core::option::Option<bool>,
+    /// Whether any or all values of the field have to lie in the range, default is `Any`
+    #[prost(enumeration = "RangeMode", optional, tag = "11")]
+    pub range_mode: ::core::option::Option<i32>,
+    /// Check if keyword lies in a given lexicographic range
+    #[prost(message, optional, tag = "12")]
+    pub keyword_range: ::core::option::Option<KeywordRange>,
 }
 #[derive(serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Match {
-    #[prost(oneof = "r#match::MatchValue", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10")]
+    #[prost(oneof = "r#match::MatchValue", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11")]
     pub match_value: ::core::option::Option<r#match::MatchValue>,
 }
 /// Nested message and enum types in `Match`.
@@ -6793,6 +6843,9 @@ pub mod r#match {
         /// Match any word in the text
         #[prost(string, tag = "10")]
         TextAny(::prost::alloc::string::String),
+        /// Match keywords starting with the prefix
+        #[prost(string, tag = "11")]
+        Prefix(::prost::alloc::string::String),
     }
 }
 #[derive(serde::Serialize)]
@@ -6843,6 +6896,19 @@ pub struct DatetimeRange {
 #[derive(serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeywordRange {
+    #[prost(string, optional, tag = "1")]
+    pub lt: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub gt: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "3")]
+    pub gte: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub lte: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoBoundingBox {
     /// north-west corner
     #[prost(message, optional, tag = "1")]
@@ -7138,6 +7204,76 @@ impl Direction {
         }
     }
 }
+#[derive(serde::Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ComparisonOperator {
+    /// left == right
+    Eq = 0,
+    /// left < right
+    Lt = 1,
+    /// left <= right
+    Lte = 2,
+    /// left > right
+    Gt = 3,
+    /// left >= right
+    Gte = 4,
+}
+impl ComparisonOperator {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ComparisonOperator::Eq => "Eq",
+            ComparisonOperator::Lt => "Lt",
+            ComparisonOperator::Lte => "Lte",
+            ComparisonOperator::Gt => "Gt",
+            ComparisonOperator::Gte => "Gte",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "Eq" => Some(Self::Eq),
+            "Lt" => Some(Self::Lt),
+            "Lte" => Some(Self::Lte),
+            "Gt" => Some(Self::Gt),
+            "Gte" => Some(Self::Gte),
+            _ => None,
+        }
+    }
+}
+#[derive(serde::Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum RangeMode {
+    /// At least one value is in the range
+    Any = 0,
+    /// The field has values and all of them are in the range
+    All = 1,
+}
+impl RangeMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            RangeMode::Any => "Any",
+            RangeMode::All => "All",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "Any" => Some(Self::Any),
+            "All" => Some(Self::All),
+            _ => None,
+        }
+    }
+}
 /// How to use positive and negative vectors to find the results, default is `AverageVector`.
 #[derive(serde::Serialize)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
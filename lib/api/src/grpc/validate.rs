@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 
 use common::validation::{validate_range_generic, validate_shard_different_peers};
-use segment::data_types::index::validate_integer_index_params;
+use segment::data_types::index::{validate_geo_index_params, validate_integer_index_params};
 use validator::{Validate, ValidationError, ValidationErrors};
 
 use super::qdrant as grpc;
@@ -190,8 +190,11 @@ impl Validate for grpc::condition::ConditionOneOf {
             ConditionOneOf::Filter(filter) => filter.validate(),
             ConditionOneOf::IsEmpty(_) => Ok(()),
             ConditionOneOf::HasId(_) => Ok(()),
+            ConditionOneOf::HasIdRange(_) => Ok(()),
             ConditionOneOf::IsNull(_) => Ok(()),
             ConditionOneOf::HasVector(_) => Ok(()),
+            ConditionOneOf::FieldVsField(_) => Ok(()),
+            ConditionOneOf::VersionAtMost(_) => Ok(()),
         }
     }
 }
@@ -228,11 +231,14 @@ impl Validate for grpc::FieldCondition {
             values_count,
             is_empty,
             is_null,
+            range_mode: _,
+            keyword_range,
         } = self;
 
         let all_fields_none = r#match.is_none()
             && range.is_none()
             && datetime_range.is_none()
+            && keyword_range.is_none()
             && geo_bounding_box.is_none()
             && geo_radius.is_none()
             && geo_polygon.is_none()
@@ -457,7 +463,9 @@ impl Validate for super::qdrant::payload_index_params::IndexParams {
                 integer_index_params.validate()
             }
             grpc::payload_index_params::IndexParams::FloatIndexParams(_) => Ok(()),
-            grpc::payload_index_params::IndexParams::GeoIndexParams(_) => Ok(()),
+            grpc::payload_index_params::IndexParams::GeoIndexParams(geo_index_params) => {
+                geo_index_params.validate()
+            }
             grpc::payload_index_params::IndexParams::TextIndexParams(_) => Ok(()),
             grpc::payload_index_params::IndexParams::BoolIndexParams(_) => Ok(()),
             grpc::payload_index_params::IndexParams::DatetimeIndexParams(_) => Ok(()),
@@ -478,6 +486,16 @@ impl Validate for super::qdrant::IntegerIndexParams {
     }
 }
 
+impl Validate for super::qdrant::GeoIndexParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let super::qdrant::GeoIndexParams {
+            on_disk: _,
+            max_query_regions,
+        } = &self;
+        validate_geo_index_params(max_query_regions.map(|regions| regions as usize))
+    }
+}
+
 impl Validate for super::qdrant::points_selector::PointsSelectorOneOf {
     fn validate(&self) -> Result<(), ValidationErrors> {
         match self {
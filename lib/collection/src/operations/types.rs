@@ -1209,6 +1209,8 @@ impl From<OperationError> for CollectionError {
             },
             OperationError::MissingRangeIndexForOrderBy { .. } => Self::bad_input(format!("{err}")),
             OperationError::MissingMapIndexForFacet { .. } => Self::bad_input(format!("{err}")),
+            OperationError::MissingGeoIndexForOrdering { .. } => Self::bad_input(format!("{err}")),
+            OperationError::MissingPayloadIndex { .. } => Self::bad_input(format!("{err}")),
             OperationError::VariableTypeError { .. } => Self::bad_input(format!("{err}")),
             OperationError::NonFiniteNumber { .. } => Self::bad_input(format!("{err}")),
             OperationError::RocksDbColumnFamilyNotFound { .. } => Self::ServiceError {
@@ -215,6 +215,7 @@ fn infer_index_from_field_condition(field_condition: &FieldCondition) -> Vec<Fie
         key: _key,
         r#match,
         range,
+        range_mode: _,
         geo_bounding_box,
         geo_radius,
         geo_polygon,
@@ -233,6 +234,7 @@ fn infer_index_from_field_condition(field_condition: &FieldCondition) -> Vec<Fie
             Match::Any(match_any) => infer_index_from_any_variants(&match_any.any),
             Match::Except(match_except) => infer_index_from_any_variants(&match_except.except),
             Match::TextAny(_match_text_any) => vec![FieldIndexType::Text],
+            Match::Prefix(_match_prefix) => vec![FieldIndexType::KeywordMatch],
         })
     }
     if let Some(range_interface) = range {
@@ -244,6 +246,9 @@ fn infer_index_from_field_condition(field_condition: &FieldCondition) -> Vec<Fie
                 required_indexes.push(FieldIndexType::FloatRange);
                 required_indexes.push(FieldIndexType::IntRange);
             }
+            RangeInterface::Keyword(_) => {
+                required_indexes.push(FieldIndexType::KeywordMatch);
+            }
         }
     }
     if geo_bounding_box.is_some() || geo_radius.is_some() || geo_polygon.is_some() {
@@ -382,8 +387,12 @@ impl<'a> Extractor<'a> {
             }
             // No index needed
             Condition::HasId(_) => return,
+            Condition::HasIdRange(_) => return,
             Condition::CustomIdChecker(_) => return,
             Condition::HasVector(_) => return,
+            Condition::VersionAtMost(_) => return,
+            // Field indexes can't be used for comparing fields
+            Condition::FieldVsField(_) => return,
         };
 
         let full_key = JsonPath::extend_or_new(nested_prefix, key);
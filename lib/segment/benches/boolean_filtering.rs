@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use atomic_refcell::AtomicRefCell;
 use common::counter::hardware_counter::HardwareCounterCell;
+use common::types::PointOffsetType;
 use criterion::{Criterion, criterion_group, criterion_main};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -18,6 +19,7 @@ use tempfile::Builder;
 mod prof;
 
 const NUM_POINTS: usize = 100000;
+const BATCH_SIZE: usize = 1024;
 
 fn random_bool_filter<R: Rng + ?Sized>(rng: &mut R) -> Filter {
     Filter::new_must(Condition::Field(FieldCondition::new_match(
@@ -137,18 +139,59 @@ pub fn keyword_index_boolean_query_points(c: &mut Criterion) {
     group.finish();
 }
 
+/// Per-point `check` vs `check_batch` of a filter context over a full scan of candidates
+pub fn struct_boolean_filter_context_check(c: &mut Criterion) {
+    let seed = 42;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let dir = Builder::new().prefix("storage_dir").tempdir().unwrap();
+    let struct_index = create_struct_payload_index(dir.path(), NUM_POINTS, seed);
+
+    let mut group = c.benchmark_group("boolean-filter-context");
+    let hw_counter = HardwareCounterCell::new();
+
+    let point_ids: Vec<PointOffsetType> = (0..NUM_POINTS as PointOffsetType).collect();
+    let filter = random_bool_filter(&mut rng);
+    let filter_context = struct_index.filter_context(&filter, &hw_counter);
+
+    group.bench_function("check", |b| {
+        b.iter(|| {
+            point_ids
+                .iter()
+                .filter(|&&point_id| filter_context.check(point_id))
+                .count()
+        })
+    });
+
+    let mut out = vec![false; BATCH_SIZE];
+    group.bench_function("check-batch", |b| {
+        b.iter(|| {
+            let mut matched = 0;
+            for ids in point_ids.chunks(BATCH_SIZE) {
+                let out = &mut out[..ids.len()];
+                filter_context.check_batch(ids, out);
+                matched += out.iter().filter(|&&is_match| is_match).count();
+            }
+            matched
+        })
+    });
+
+    group.finish();
+}
+
 #[cfg(not(target_os = "windows"))]
 criterion_group! {
     name = benches;
     config = Criterion::default().with_profiler(prof::FlamegraphProfiler::new(100));
-    targets = plain_boolean_query_points, struct_boolean_query_points, keyword_index_boolean_query_points
+    targets = plain_boolean_query_points, struct_boolean_query_points, keyword_index_boolean_query_points, struct_boolean_filter_context_check
 }
 
 #[cfg(target_os = "windows")]
 criterion_group! {
     name = benches;
     config = Criterion::default();
-    targets = plain_boolean_query_points, struct_boolean_query_points, keyword_index_boolean_query_points
+    targets = plain_boolean_query_points, struct_boolean_query_points, keyword_index_boolean_query_points, struct_boolean_filter_context_check
 }
 
 criterion_main!(benches);
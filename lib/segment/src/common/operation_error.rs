@@ -62,6 +62,12 @@ pub enum OperationError {
         "No appropriate index for faceting: `{key}`. Please create one to facet on this field. Check https://qdrant.tech/documentation/concepts/indexing/#payload-index to see which payload schemas support Match conditions"
     )]
     MissingMapIndexForFacet { key: String },
+    #[error(
+        "No geo index for ordering by distance: `{key}`. Please create one to order by distance on this field. Check https://qdrant.tech/documentation/concepts/indexing/#payload-index to see which payload schemas support Geo conditions"
+    )]
+    MissingGeoIndexForOrdering { key: String },
+    #[error("Field `{key}` is not indexed, payload blocks require a payload index")]
+    MissingPayloadIndex { key: String },
     #[error(
         "Expected {expected_type} value for {field_name} in the payload and/or in the formula defaults. Error: {description}"
     )]
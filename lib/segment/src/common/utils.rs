@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -11,7 +12,10 @@ use crate::data_types::vectors::VectorInternal;
 use crate::index::field_index::FieldIndex;
 use crate::types::{PayloadKeyType, VectorNameBuf};
 
-pub type IndexesMap = HashMap<PayloadKeyType, Vec<FieldIndex>>;
+/// Indexes of each field.
+///
+/// Indexes of a field are shared, so query snapshots can keep using them after they are replaced.
+pub type IndexesMap = HashMap<PayloadKeyType, Arc<Vec<FieldIndex>>>;
 
 /// A container for JSON values, optimized for the common case of a single value.
 pub type MultiValue<T> = SmallVec<[T; 1]>;
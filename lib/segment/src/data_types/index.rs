@@ -28,6 +28,11 @@ pub struct KeywordIndexParams {
     /// If true, store the index on disk. Default: false.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub on_disk: Option<bool>,
+
+    /// If true, only index the presence of the field. Serves `is_empty` and `is_null` conditions,
+    /// but not value matching. Default: false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists_only: Option<bool>,
 }
 
 // Integer
@@ -157,6 +162,39 @@ pub struct GeoIndexParams {
     /// If true, store the index on disk. Default: false.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub on_disk: Option<bool>,
+
+    /// Max number of geohash regions to cover the area of a geo query with.
+    /// More regions cover the area with finer precision, so fewer points outside of the area
+    /// have to be checked, at the cost of looking up more regions. Default: 12.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_query_regions: Option<usize>,
+}
+
+/// Upper bound of [`GeoIndexParams::max_query_regions`]
+pub const GEO_MAX_QUERY_REGIONS_LIMIT: usize = 1024;
+
+impl Validate for GeoIndexParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let GeoIndexParams {
+            r#type: _,
+            on_disk: _,
+            max_query_regions,
+        } = &self;
+        validate_geo_index_params(*max_query_regions)
+    }
+}
+
+pub fn validate_geo_index_params(max_query_regions: Option<usize>) -> Result<(), ValidationErrors> {
+    if let Some(max_query_regions) = max_query_regions
+        && !(1..=GEO_MAX_QUERY_REGIONS_LIMIT).contains(&max_query_regions)
+    {
+        let mut errors = ValidationErrors::new();
+        let error = ValidationError::new("range")
+            .with_message(format!("must be between 1 and {GEO_MAX_QUERY_REGIONS_LIMIT}").into());
+        errors.add("max_query_regions", error);
+        return Err(errors);
+    }
+    Ok(())
 }
 
 // Text
@@ -257,15 +257,14 @@ pub fn create_struct_payload_index(
     ));
     let id_tracker = Arc::new(AtomicRefCell::new(FixtureIdTracker::new(num_points)));
 
-    let mut index = StructPayloadIndex::open(
-        payload_storage,
-        id_tracker,
-        std::collections::HashMap::new(),
-        path,
-        true,
-        true,
-    )
-    .unwrap();
+    let mut index = StructPayloadIndex::builder()
+        .payload(payload_storage)
+        .id_tracker(id_tracker)
+        .path(path)
+        .appendable(true)
+        .create(true)
+        .build()
+        .unwrap();
 
     let hw_counter = HardwareCounterCell::new();
 
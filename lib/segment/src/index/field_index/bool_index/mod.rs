@@ -552,6 +552,47 @@ mod tests {
         assert_eq!(blocks[1].cardinality, 6);
     }
 
+    #[test]
+    fn test_get_point_values() {
+        #[cfg(feature = "rocksdb")]
+        get_point_values::<SimpleBoolIndex>();
+        get_point_values::<MutableBoolIndex>();
+    }
+
+    fn get_point_values<I: OpenIndex>() {
+        let tmp_dir = Builder::new().prefix(DB_NAME).tempdir().unwrap();
+        let mut index = I::open_at(tmp_dir.path());
+
+        let hw_counter = HardwareCounterCell::new();
+
+        bools_fixture()
+            .into_iter()
+            .enumerate()
+            .for_each(|(i, value)| {
+                index.add_point(i as u32, &[&value], &hw_counter).unwrap();
+            });
+
+        // Values are reconstructed as a set, `true` first
+        let expected: [&[bool]; 12] = [
+            &[true],
+            &[false],
+            &[true, false],
+            &[true, false],
+            &[true],
+            &[false],
+            &[true, false],
+            &[],
+            &[],
+            &[],
+            &[false],
+            &[true],
+        ];
+        for (point_id, expected) in expected.into_iter().enumerate() {
+            assert_eq!(index.get_point_values(point_id as u32), expected);
+            assert_eq!(index.values_is_empty(point_id as u32), expected.is_empty());
+        }
+    }
+
     #[test]
     fn test_estimate_cardinality() {
         #[cfg(feature = "rocksdb")]
@@ -410,6 +410,7 @@ impl PayloadFieldIndex for MutableBoolIndex {
                         }),
                     ),
                     cardinality: count,
+                    point_offsets: None,
                 })
             } else {
                 None
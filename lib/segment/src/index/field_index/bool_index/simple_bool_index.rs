@@ -407,6 +407,7 @@ impl PayloadFieldIndex for SimpleBoolIndex {
                         }),
                     ),
                     cardinality: count,
+                    point_offsets: None,
                 })
             } else {
                 None
@@ -0,0 +1,394 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use common::types::PointOffsetType;
+use itertools::{Either, Itertools};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::Flusher;
+use crate::common::operation_error::{OperationError, OperationResult};
+use crate::index::index_storage::{IndexStorage, compress_entry, decompress_entry};
+use crate::index::payload_config::IndexCompression;
+use crate::types::{PayloadContainer, PayloadKeyType, ValueVariants};
+
+/// Version of the persisted index format, increase on any change of its layout
+///
+/// - 0: postings only, without a header
+/// - 1: postings with a versioned header
+///
+/// Memory mapped and gridstore field index files don't carry such a version yet.
+const COMPOSITE_INDEX_VERSION: u16 = 1;
+
+/// Values of all fields of a composite index, in order of the fields
+pub type CompositeKey = Vec<ValueVariants>;
+
+type Postings = Vec<(CompositeKey, Vec<PointOffsetType>)>;
+
+#[derive(Deserialize)]
+struct CompositeIndexHeader {
+    version: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompositeIndexFile {
+    version: u16,
+    postings: Postings,
+}
+
+/// Index over a combination of fields, which are always filtered together.
+///
+/// Points are indexed by the tuple of values of all fields, so a conjunction of exact matches on
+/// those fields is answered by a single posting list, instead of intersecting one per field.
+/// A point with multiple values in a field is indexed under every combination of values.
+/// Points missing any of the fields are not indexed.
+#[derive(Debug)]
+pub struct CompositeIndex {
+    fields: Vec<PayloadKeyType>,
+    storage: Arc<dyn IndexStorage>,
+    postings: HashMap<CompositeKey, BTreeSet<PointOffsetType>>,
+    point_keys: HashMap<PointOffsetType, Vec<CompositeKey>>,
+    is_dirty: Arc<AtomicBool>,
+}
+
+impl CompositeIndex {
+    /// Deterministic name of the [`IndexStorage`] entry persisting the index of `fields`
+    pub fn file_name(fields: &[PayloadKeyType]) -> String {
+        let joined: String = fields
+            .iter()
+            .join("+")
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("composite-{joined}.json")
+    }
+
+    /// Create an empty index of `fields`, persisted in the `storage`
+    pub fn new(fields: Vec<PayloadKeyType>, storage: Arc<dyn IndexStorage>) -> Self {
+        Self {
+            fields,
+            storage,
+            postings: HashMap::new(),
+            point_keys: HashMap::new(),
+            // Not persisted yet
+            is_dirty: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Load a previously persisted index of `fields`, `None` if it was never persisted
+    pub fn load(
+        fields: Vec<PayloadKeyType>,
+        storage: Arc<dyn IndexStorage>,
+    ) -> OperationResult<Option<Self>> {
+        let name = Self::file_name(&fields);
+        let Some(bytes) = storage.get(&name)? else {
+            return Ok(None);
+        };
+
+        let mut index = Self::new(fields, storage);
+        let (postings, is_migrated) = read_postings(&decompress_entry(bytes)?, &name)?;
+        for (key, point_ids) in postings {
+            for &point_id in &point_ids {
+                index
+                    .point_keys
+                    .entry(point_id)
+                    .or_default()
+                    .push(key.clone());
+            }
+            index.postings.insert(key, point_ids.into_iter().collect());
+        }
+        // Migrated index is persisted in the current format with the next flush
+        index.is_dirty.store(is_migrated, Ordering::Relaxed);
+        Ok(Some(index))
+    }
+
+    pub fn fields(&self) -> &[PayloadKeyType] {
+        &self.fields
+    }
+
+    /// Index the point with the current values of its `payload`, replacing previous values
+    pub fn add_point(&mut self, point_id: PointOffsetType, payload: &impl PayloadContainer) {
+        self.remove_point(point_id);
+
+        let keys = self.payload_keys(payload);
+        if keys.is_empty() {
+            return;
+        }
+        for key in &keys {
+            self.postings
+                .entry(key.clone())
+                .or_default()
+                .insert(point_id);
+        }
+        self.point_keys.insert(point_id, keys);
+        self.is_dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Index the point `to` under the keys of the point `from` in the `other` index of the same
+    /// fields, replacing previous values
+    pub fn copy_point_from(
+        &mut self,
+        other: &CompositeIndex,
+        from: PointOffsetType,
+        to: PointOffsetType,
+    ) {
+        debug_assert_eq!(self.fields, other.fields);
+        self.remove_point(to);
+
+        let Some(keys) = other.point_keys.get(&from) else {
+            return;
+        };
+        for key in keys {
+            self.postings.entry(key.clone()).or_default().insert(to);
+        }
+        self.point_keys.insert(to, keys.clone());
+        self.is_dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub fn remove_point(&mut self, point_id: PointOffsetType) {
+        let Some(keys) = self.point_keys.remove(&point_id) else {
+            return;
+        };
+        for key in keys {
+            if let Some(points) = self.postings.get_mut(&key) {
+                points.remove(&point_id);
+                if points.is_empty() {
+                    self.postings.remove(&key);
+                }
+            }
+        }
+        self.is_dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Points indexed under the `key`, in ascending order
+    pub fn get_points(&self, key: &[ValueVariants]) -> impl Iterator<Item = PointOffsetType> + '_ {
+        self.postings
+            .get(key)
+            .into_iter()
+            .flat_map(|points| points.iter().copied())
+    }
+
+    /// Number of points indexed under the `key`
+    pub fn points_count(&self, key: &[ValueVariants]) -> usize {
+        self.postings.get(key).map_or(0, BTreeSet::len)
+    }
+
+    /// Number of points with values in all fields
+    pub fn count_indexed_points(&self) -> usize {
+        self.point_keys.len()
+    }
+
+    /// All combinations of values of the fields in `payload`
+    fn payload_keys(&self, payload: &impl PayloadContainer) -> Vec<CompositeKey> {
+        self.fields
+            .iter()
+            .map(|field| {
+                payload
+                    .get_value(field)
+                    .into_iter()
+                    .flat_map(|value| match value {
+                        Value::Array(values) => Either::Left(values.iter()),
+                        value => Either::Right(std::iter::once(value)),
+                    })
+                    .filter_map(value_variant)
+                    .unique()
+                    .collect_vec()
+            })
+            .multi_cartesian_product()
+            .collect()
+    }
+
+    /// Persist the index if it changed, compressed with the `compression`, if any
+    pub fn flusher(&self, compression: Option<IndexCompression>) -> Flusher {
+        if !self.is_dirty.swap(false, Ordering::Relaxed) {
+            return Box::new(|| Ok(()));
+        }
+
+        let file = CompositeIndexFile {
+            version: COMPOSITE_INDEX_VERSION,
+            postings: self
+                .postings
+                .iter()
+                .map(|(key, points)| (key.clone(), points.iter().copied().collect()))
+                .collect(),
+        };
+        let name = Self::file_name(&self.fields);
+        let storage = self.storage.clone();
+        let is_dirty = self.is_dirty.clone();
+        Box::new(move || {
+            let result = serde_json::to_vec(&file)
+                .map_err(OperationError::from)
+                .and_then(|bytes| compress_entry(bytes, compression))
+                .and_then(|bytes| storage.put(&name, &bytes));
+            if result.is_err() {
+                // Retry with the next flush
+                is_dirty.store(true, Ordering::Relaxed);
+            }
+            result
+        })
+    }
+
+    pub fn cleanup(self) -> OperationResult<()> {
+        self.storage.delete(&Self::file_name(&self.fields))
+    }
+}
+
+/// Read postings of an index persisted under `name`, upgrading older formats to the current one.
+///
+/// Returns whether the index was stored in an older format.
+fn read_postings(bytes: &[u8], name: &str) -> OperationResult<(Postings, bool)> {
+    let value: Value = serde_json::from_slice(bytes)?;
+
+    let version = match &value {
+        Value::Array(_) => 0,
+        _ => serde_json::from_value::<CompositeIndexHeader>(value.clone())?.version,
+    };
+
+    match version {
+        0 => Ok((serde_json::from_value(value)?, true)),
+        COMPOSITE_INDEX_VERSION => {
+            let file: CompositeIndexFile = serde_json::from_value(value)?;
+            Ok((file.postings, false))
+        }
+        version => Err(OperationError::service_error(format!(
+            "Composite index {name} has unsupported version {version}, \
+             latest supported version is {COMPOSITE_INDEX_VERSION}",
+        ))),
+    }
+}
+
+/// Value, which can be compared with an exact match condition
+fn value_variant(value: &Value) -> Option<ValueVariants> {
+    match value {
+        Value::String(value) => Some(ValueVariants::String(value.clone())),
+        Value::Bool(value) => Some(ValueVariants::Bool(*value)),
+        Value::Number(value) => value.as_i64().map(ValueVariants::Integer),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use io::file_operations::{atomic_save_json, read_json};
+    use tempfile::{Builder, TempDir};
+
+    use super::*;
+    use crate::index::index_storage::FsIndexStorage;
+    use crate::json_path::JsonPath;
+    use crate::types::Payload;
+
+    fn fields() -> Vec<PayloadKeyType> {
+        vec![
+            JsonPath::from_str("tenant").unwrap(),
+            JsonPath::from_str("status").unwrap(),
+        ]
+    }
+
+    fn fs_storage(dir: &TempDir) -> Arc<dyn IndexStorage> {
+        Arc::new(FsIndexStorage::new(dir.path()))
+    }
+
+    fn key(tenant: &str, status: &str) -> CompositeKey {
+        vec![
+            ValueVariants::String(tenant.to_string()),
+            ValueVariants::String(status.to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_persist_and_load() {
+        let dir = Builder::new().prefix("composite_index").tempdir().unwrap();
+
+        let mut index = CompositeIndex::new(fields(), fs_storage(&dir));
+        let payload: Payload = serde_json::from_value(serde_json::json!({
+            "tenant": "a",
+            "status": ["new", "open"],
+        }))
+        .unwrap();
+        index.add_point(1, &payload);
+        index.flusher(None)().unwrap();
+
+        let loaded = CompositeIndex::load(fields(), fs_storage(&dir))
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.get_points(&key("a", "new")).collect_vec(), vec![1]);
+        assert_eq!(loaded.get_points(&key("a", "open")).collect_vec(), vec![1]);
+        assert_eq!(loaded.count_indexed_points(), 1);
+        assert!(!loaded.is_dirty.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_persist_compressed() {
+        let dir = Builder::new().prefix("composite_index").tempdir().unwrap();
+        let path = dir.path().join(CompositeIndex::file_name(&fields()));
+
+        let mut index = CompositeIndex::new(fields(), fs_storage(&dir));
+        for point_id in 0..100 {
+            let payload: Payload = serde_json::from_value(serde_json::json!({
+                "tenant": format!("tenant-{}", point_id % 3),
+                "status": "open",
+            }))
+            .unwrap();
+            index.add_point(point_id, &payload);
+        }
+        index.flusher(Some(IndexCompression { level: 3 }))().unwrap();
+
+        let compressed = std::fs::read(&path).unwrap();
+        assert!(compressed.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]));
+
+        let loaded = CompositeIndex::load(fields(), fs_storage(&dir))
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.points_count(&key("tenant-1", "open")), 33);
+        assert_eq!(loaded.count_indexed_points(), 100);
+
+        // Switching compression off writes plain JSON again
+        loaded.is_dirty.store(true, Ordering::Relaxed);
+        loaded.flusher(None)().unwrap();
+        let header: CompositeIndexHeader = read_json(&path).unwrap();
+        assert_eq!(header.version, COMPOSITE_INDEX_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_file() {
+        let dir = Builder::new().prefix("composite_index").tempdir().unwrap();
+        let path = dir.path().join(CompositeIndex::file_name(&fields()));
+
+        let postings: Postings = vec![(key("a", "new"), vec![1, 2])];
+        atomic_save_json(&path, &postings).unwrap();
+
+        let index = CompositeIndex::load(fields(), fs_storage(&dir))
+            .unwrap()
+            .unwrap();
+        assert_eq!(index.points_count(&key("a", "new")), 2);
+
+        // Upgraded file is written with the next flush
+        assert!(index.is_dirty.load(Ordering::Relaxed));
+        index.flusher(None)().unwrap();
+        let header: CompositeIndexHeader = read_json(&path).unwrap();
+        assert_eq!(header.version, COMPOSITE_INDEX_VERSION);
+    }
+
+    #[test]
+    fn test_reject_future_version() {
+        let dir = Builder::new().prefix("composite_index").tempdir().unwrap();
+        let path = dir.path().join(CompositeIndex::file_name(&fields()));
+
+        let file = CompositeIndexFile {
+            version: COMPOSITE_INDEX_VERSION + 1,
+            postings: vec![],
+        };
+        atomic_save_json(&path, &file).unwrap();
+
+        assert!(CompositeIndex::load(fields(), fs_storage(&dir)).is_err());
+    }
+}
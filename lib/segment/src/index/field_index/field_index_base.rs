@@ -1,8 +1,10 @@
 use std::fmt::Formatter;
+use std::fs;
 use std::path::PathBuf;
 
 use common::counter::hardware_counter::HardwareCounterCell;
 use common::types::PointOffsetType;
+use itertools::Itertools;
 use serde_json::Value;
 
 use super::bool_index::BoolIndex;
@@ -13,7 +15,7 @@ use super::full_text_index::text_index::{FullTextGridstoreIndexBuilder, FullText
 use super::geo_index::{GeoMapIndexGridstoreBuilder, GeoMapIndexMmapBuilder};
 #[cfg(feature = "rocksdb")]
 use super::map_index::MapIndexBuilder;
-use super::map_index::{MapIndex, MapIndexGridstoreBuilder, MapIndexMmapBuilder};
+use super::map_index::{IdIter, MapIndex, MapIndexGridstoreBuilder, MapIndexMmapBuilder};
 #[cfg(feature = "rocksdb")]
 use super::numeric_index::NumericIndexBuilder;
 use super::numeric_index::{
@@ -21,7 +23,7 @@ use super::numeric_index::{
 };
 use crate::common::Flusher;
 use crate::common::operation_error::OperationResult;
-use crate::data_types::order_by::OrderValue;
+use crate::data_types::order_by::{Direction, OrderValue};
 use crate::index::field_index::geo_index::GeoMapIndex;
 use crate::index::field_index::null_index::MutableNullIndex;
 use crate::index::field_index::null_index::mutable_null_index::MutableNullIndexBuilder;
@@ -33,7 +35,7 @@ use crate::index::payload_config::{
 use crate::telemetry::PayloadIndexTelemetry;
 use crate::types::{
     DateTimePayloadType, FieldCondition, FloatPayloadType, IntPayloadType, Match, MatchPhrase,
-    MatchText, PayloadKeyType, RangeInterface, UuidIntType, UuidPayloadType,
+    MatchText, PayloadKeyType, Range, RangeInterface, UuidIntType, UuidPayloadType,
 };
 
 pub trait PayloadFieldIndex {
@@ -122,6 +124,31 @@ pub trait ValueIndexer {
         self.add_many(id, flatten_values, hw_counter)
     }
 
+    /// Update the point from `old` payload values to `new` ones.
+    ///
+    /// The index is not touched, if the values it extracts from both are the same.
+    fn update_point(
+        &mut self,
+        id: PointOffsetType,
+        old: &[&Value],
+        new: &[&Value],
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()>
+    where
+        Self::ValueType: PartialEq,
+    {
+        let extract = |payload: &[&Value]| -> Vec<_> {
+            payload
+                .iter()
+                .flat_map(|value| Self::get_values(value))
+                .collect()
+        };
+        if extract(old) == extract(new) {
+            return Ok(());
+        }
+        self.add_point(id, new, hw_counter)
+    }
+
     /// remove a point from the index
     fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()>;
 }
@@ -241,6 +268,29 @@ impl FieldIndex {
         self.get_payload_field_index().files()
     }
 
+    /// Total size of the index files, in bytes.
+    ///
+    /// Files which can't be read, for example not yet flushed ones, are not counted.
+    pub fn disk_usage_bytes(&self) -> usize {
+        self.files()
+            .iter()
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len() as usize)
+            .sum()
+    }
+
+    /// Approximate RAM usage of the index, in bytes.
+    ///
+    /// Indexes kept in RAM are loaded from their files, so the size of the files is used.
+    /// Data of on-disk indexes is only cached by the OS on demand, and is not counted.
+    pub fn ram_usage_bytes(&self) -> usize {
+        if self.is_on_disk() {
+            0
+        } else {
+            self.disk_usage_bytes()
+        }
+    }
+
     pub fn immutable_files(&self) -> Vec<PathBuf> {
         self.get_payload_field_index().immutable_files()
     }
@@ -314,6 +364,32 @@ impl FieldIndex {
         }
     }
 
+    /// Update the point from `old` payload values of the field to `new` ones.
+    ///
+    /// Values, which the index keeps, are compared first, so the index is only written if they
+    /// changed.
+    pub fn update_point(
+        &mut self,
+        id: PointOffsetType,
+        old: &[&Value],
+        new: &[&Value],
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        match self {
+            FieldIndex::IntIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::DatetimeIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::IntMapIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::KeywordIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::FloatIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::GeoIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::BoolIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::FullTextIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::UuidIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::UuidMapIndex(index) => index.update_point(id, old, new, hw_counter),
+            FieldIndex::NullIndex(index) => index.update_point(id, old, new, hw_counter),
+        }
+    }
+
     pub fn remove_point(&mut self, point_id: PointOffsetType) -> OperationResult<()> {
         match self {
             FieldIndex::IntIndex(index) => index.mut_inner().remove_point(point_id),
@@ -330,6 +406,73 @@ impl FieldIndex {
         }
     }
 
+    /// Index values, which the `other` index holds for the point `from`, under the point `to`,
+    /// without reading payload.
+    ///
+    /// Values are read from `other` of any mutability. Returns `None` if values can't be copied:
+    /// this index is immutable, the indexes are of different types, or the index holds derived
+    /// values only, like tokens of a full-text index. Such indexes are to be rebuilt from payload.
+    pub fn copy_point_from(
+        &mut self,
+        other: &FieldIndex,
+        from: PointOffsetType,
+        to: PointOffsetType,
+        hw_counter: &HardwareCounterCell,
+    ) -> Option<OperationResult<()>> {
+        if self.get_mutability_type() == IndexMutability::Immutable {
+            return None;
+        }
+        let result = match (self, other) {
+            (FieldIndex::IntIndex(index), FieldIndex::IntIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            (FieldIndex::DatetimeIndex(index), FieldIndex::DatetimeIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            (FieldIndex::IntMapIndex(index), FieldIndex::IntMapIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            (FieldIndex::KeywordIndex(index), FieldIndex::KeywordIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            (FieldIndex::FloatIndex(index), FieldIndex::FloatIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            (FieldIndex::UuidIndex(index), FieldIndex::UuidIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            (FieldIndex::UuidMapIndex(index), FieldIndex::UuidMapIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            (FieldIndex::GeoIndex(index), FieldIndex::GeoIndex(other)) => {
+                let values: Vec<_> = other
+                    .get_values(from)
+                    .map(|values| values.collect())
+                    .unwrap_or_default();
+                if values.is_empty() {
+                    Ok(())
+                } else {
+                    index.add_many(to, values, hw_counter)
+                }
+            }
+            (FieldIndex::BoolIndex(index), FieldIndex::BoolIndex(other)) => {
+                let values = other.get_point_values(from);
+                if values.is_empty() {
+                    Ok(())
+                } else {
+                    index.add_many(to, values, hw_counter)
+                }
+            }
+            (FieldIndex::NullIndex(index), FieldIndex::NullIndex(other)) => {
+                index.copy_point_from(other, from, to, hw_counter)
+            }
+            // Full-text tokens can't be indexed again without the original text,
+            // other combinations are indexes of different types
+            _ => return None,
+        };
+        Some(result)
+    }
+
     pub fn get_telemetry_data(&self) -> PayloadIndexTelemetry {
         match self {
             FieldIndex::IntIndex(index) => index.get_telemetry_data(),
@@ -362,6 +505,66 @@ impl FieldIndex {
         }
     }
 
+    /// Whether [`Self::values_count`] counts every value of the field, so it can be used for
+    /// `values_count` conditions.
+    ///
+    /// Bool index is not suitable, as it does not count repeated values.
+    /// Full-text index counts tokens, and null index only tracks presence of values.
+    pub fn counts_values(&self) -> bool {
+        match self {
+            FieldIndex::IntIndex(_)
+            | FieldIndex::DatetimeIndex(_)
+            | FieldIndex::IntMapIndex(_)
+            | FieldIndex::KeywordIndex(_)
+            | FieldIndex::FloatIndex(_)
+            | FieldIndex::GeoIndex(_)
+            | FieldIndex::UuidIndex(_)
+            | FieldIndex::UuidMapIndex(_) => true,
+            FieldIndex::FullTextIndex(_) | FieldIndex::BoolIndex(_) | FieldIndex::NullIndex(_) => {
+                false
+            }
+        }
+    }
+
+    /// Whether the index tracks every point with a value of the field.
+    ///
+    /// Only the null index is complete, as it tracks values of any type. Other indexes skip
+    /// values of types they can't hold, so points missing from them might still have values.
+    pub fn is_complete(&self) -> bool {
+        match self {
+            FieldIndex::NullIndex(_) => true,
+            FieldIndex::IntIndex(_)
+            | FieldIndex::DatetimeIndex(_)
+            | FieldIndex::IntMapIndex(_)
+            | FieldIndex::KeywordIndex(_)
+            | FieldIndex::FloatIndex(_)
+            | FieldIndex::GeoIndex(_)
+            | FieldIndex::BoolIndex(_)
+            | FieldIndex::FullTextIndex(_)
+            | FieldIndex::UuidIndex(_)
+            | FieldIndex::UuidMapIndex(_) => false,
+        }
+    }
+
+    /// Exact number of points, which have at least one value of the field.
+    ///
+    /// Only known for complete indexes, see [`Self::is_complete`].
+    pub fn non_empty_points_count(&self) -> Option<usize> {
+        match self {
+            FieldIndex::NullIndex(index) => Some(index.has_values_count()),
+            FieldIndex::IntIndex(_)
+            | FieldIndex::DatetimeIndex(_)
+            | FieldIndex::IntMapIndex(_)
+            | FieldIndex::KeywordIndex(_)
+            | FieldIndex::FloatIndex(_)
+            | FieldIndex::GeoIndex(_)
+            | FieldIndex::BoolIndex(_)
+            | FieldIndex::FullTextIndex(_)
+            | FieldIndex::UuidIndex(_)
+            | FieldIndex::UuidMapIndex(_) => None,
+        }
+    }
+
     pub fn values_is_empty(&self, point_id: PointOffsetType) -> bool {
         match self {
             FieldIndex::IntIndex(index) => index.values_is_empty(point_id),
@@ -378,6 +581,40 @@ impl FieldIndex {
         }
     }
 
+    /// Offsets of points with values in the index, in ascending order without duplicates.
+    ///
+    /// Posting lists of all values are merged. Numeric and geo indexes order points by values,
+    /// so their points are sorted instead. `None` for indexes without accessible posting lists.
+    pub fn iter_indexed_points<'a>(
+        &'a self,
+        hw_counter: &'a HardwareCounterCell,
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
+        let full_range = RangeInterface::Float(Range::default());
+        let points = match self {
+            FieldIndex::IntMapIndex(index) => {
+                merge_postings(index.iter_values_map(hw_counter).map(|(_, ids)| ids))
+            }
+            FieldIndex::KeywordIndex(index) => {
+                merge_postings(index.iter_values_map(hw_counter).map(|(_, ids)| ids))
+            }
+            FieldIndex::UuidMapIndex(index) => {
+                merge_postings(index.iter_values_map(hw_counter).map(|(_, ids)| ids))
+            }
+            FieldIndex::BoolIndex(index) => {
+                merge_postings(index.iter_values_map(hw_counter).map(|(_, ids)| ids))
+            }
+            FieldIndex::IntIndex(index) => sorted_points(index.inner().stream_range(&full_range)),
+            FieldIndex::DatetimeIndex(index) => {
+                sorted_points(index.inner().stream_range(&full_range))
+            }
+            FieldIndex::FloatIndex(index) => sorted_points(index.inner().stream_range(&full_range)),
+            FieldIndex::UuidIndex(index) => sorted_points(index.inner().stream_range(&full_range)),
+            FieldIndex::GeoIndex(index) => Box::new(index.iter_points().sorted_unstable().dedup()),
+            FieldIndex::FullTextIndex(_) | FieldIndex::NullIndex(_) => return None,
+        };
+        Some(points)
+    }
+
     pub fn as_numeric(&self) -> Option<NumericFieldIndex<'_>> {
         match self {
             FieldIndex::IntIndex(index) => Some(NumericFieldIndex::IntIndex(index.inner())),
@@ -738,6 +975,25 @@ impl FieldIndexBuilderTrait for FieldIndexBuilder {
     }
 }
 
+/// Union of posting lists, each sorted by point offsets
+fn merge_postings<'a>(
+    postings: impl Iterator<Item = IdIter<'a>>,
+) -> Box<dyn Iterator<Item = PointOffsetType> + 'a> {
+    Box::new(postings.kmerge().dedup())
+}
+
+/// Offsets of points, streamed in order of their values, sorted in ascending order
+fn sorted_points<'a, T>(
+    values: impl Iterator<Item = (T, PointOffsetType)>,
+) -> Box<dyn Iterator<Item = PointOffsetType> + 'a> {
+    Box::new(
+        values
+            .map(|(_, point_id)| point_id)
+            .sorted_unstable()
+            .dedup(),
+    )
+}
+
 pub enum NumericFieldIndex<'a> {
     IntIndex(&'a NumericIndexInner<IntPayloadType>),
     FloatIndex(&'a NumericIndexInner<FloatPayloadType>),
@@ -764,6 +1020,34 @@ impl<'a> StreamRange<OrderValue> for NumericFieldIndex<'a> {
 }
 
 impl<'a> NumericFieldIndex<'a> {
+    /// Stream values from `offset` (inclusive) towards the end of the `direction`.
+    ///
+    /// Integer offsets are compared with values of an integer index as is, so offsets, which
+    /// can't be represented as a float, don't skip or repeat values.
+    pub fn stream_range_from(
+        &self,
+        direction: Direction,
+        offset: Option<OrderValue>,
+    ) -> Box<dyn DoubleEndedIterator<Item = (OrderValue, PointOffsetType)> + 'a> {
+        let range = match offset {
+            Some(OrderValue::Int(value)) => match self {
+                NumericFieldIndex::IntIndex(index) => {
+                    return Box::new(
+                        index
+                            .stream_index_range(&direction.as_range_from(value))
+                            .map(|(v, p)| (OrderValue::from(v), p)),
+                    );
+                }
+                NumericFieldIndex::FloatIndex(_) => {
+                    RangeInterface::Float(direction.as_range_from(value as FloatPayloadType))
+                }
+            },
+            Some(OrderValue::Float(value)) => RangeInterface::Float(direction.as_range_from(value)),
+            None => RangeInterface::Float(Range::default()),
+        };
+        self.stream_range(&range)
+    }
+
     pub fn get_ordering_values(
         &self,
         idx: PointOffsetType,
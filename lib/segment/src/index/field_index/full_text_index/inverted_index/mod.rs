@@ -362,6 +362,7 @@ pub trait InvertedIndex {
                 Some(PayloadBlockCondition {
                     condition: FieldCondition::new_match(key.clone(), Match::new_text(token)),
                     cardinality: postings_len,
+                    point_offsets: None,
                 })
             } else {
                 None
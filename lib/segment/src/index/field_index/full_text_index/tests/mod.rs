@@ -6,7 +6,9 @@ use tempfile::Builder;
 
 use crate::data_types::index::{TextIndexParams, TextIndexType, TokenizerType};
 use crate::index::field_index::full_text_index::text_index::FullTextIndex;
-use crate::index::field_index::{FieldIndexBuilderTrait as _, ValueIndexer};
+use crate::index::field_index::{FieldIndexBuilderTrait as _, PayloadFieldIndex, ValueIndexer};
+use crate::json_path::JsonPath;
+use crate::types::{FieldCondition, Match};
 
 fn movie_titles() -> Vec<String> {
     vec![
@@ -197,6 +199,77 @@ fn test_prefix_search() {
     assert!(index.parse_text_query("q231", &hw_counter).is_none());
 }
 
+#[test]
+fn test_token_postings_estimation_and_blocks() {
+    let hw_counter = HardwareCounterCell::default();
+    let temp_dir = Builder::new().prefix("test_dir").tempdir().unwrap();
+    let config = TextIndexParams {
+        r#type: TextIndexType::Text,
+        tokenizer: TokenizerType::Word,
+        min_token_len: None,
+        max_token_len: None,
+        lowercase: Some(true),
+        on_disk: None,
+        phrase_matching: None,
+        stopwords: None,
+        stemmer: None,
+    };
+
+    let mut index = FullTextIndex::builder_gridstore(temp_dir.path().to_path_buf(), config)
+        .make_empty()
+        .unwrap();
+
+    let documents = [
+        "The quick brown fox",
+        "A quick brown dog",
+        "Quick, red fox!",
+        "lazy dog",
+    ];
+    for (point_id, text) in documents.iter().enumerate() {
+        index
+            .add_many(
+                point_id as PointOffsetType,
+                vec![text.to_string()],
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    // Points have to contain all query tokens, punctuation and case are ignored
+    let query = index.parse_text_query("QUICK brown.", &hw_counter).unwrap();
+    let mut points: Vec<_> = index.filter_query(query, &hw_counter).collect();
+    points.sort_unstable();
+    assert_eq!(points, vec![0, 1]);
+
+    // Estimation is bounded by the shortest posting list of the query tokens
+    let key = JsonPath::new("text");
+    let condition = FieldCondition::new_match(key.clone(), Match::new_text("quick brown"));
+    let estimation = index.estimate_cardinality(&condition, &hw_counter).unwrap();
+    assert!(estimation.min <= 2 && 2 <= estimation.max);
+    assert!(estimation.max <= 2);
+    assert_eq!(estimation.primary_clauses.len(), 1);
+
+    // One block per token with enough points
+    let mut blocks: Vec<_> = PayloadFieldIndex::payload_blocks(&index, 2, key)
+        .map(|block| {
+            let Some(Match::Text(text)) = block.condition.r#match else {
+                panic!("unexpected block condition: {:?}", block.condition);
+            };
+            (text.text, block.cardinality)
+        })
+        .collect();
+    blocks.sort_unstable();
+    assert_eq!(
+        blocks,
+        vec![
+            ("brown".to_string(), 2),
+            ("dog".to_string(), 2),
+            ("fox".to_string(), 2),
+            ("quick".to_string(), 3),
+        ]
+    );
+}
+
 #[test]
 fn test_phrase_matching() {
     let hw_counter = HardwareCounterCell::default();
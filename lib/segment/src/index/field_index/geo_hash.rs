@@ -2,7 +2,7 @@ use std::fmt::Display;
 use std::ops::{Index, Range};
 
 use ecow::EcoString;
-use geo::{Coord, Distance, Haversine, Intersects, LineString, Point, Polygon};
+use geo::{Coord, Distance, Haversine, Intersects, LineString, Point, Polygon, Rect};
 use geohash::{Direction, GeohashError, decode, decode_bbox, encode};
 use itertools::Itertools;
 
@@ -182,6 +182,17 @@ impl GeoHash {
         self_shifted == other_shifted
     }
 
+    /// Geo-hashes of the next precision, which together cover this one
+    pub fn children(&self) -> impl Iterator<Item = GeoHash> {
+        let len = self.len();
+        assert!(len < GEOHASH_MAX_LENGTH);
+        // Clear length bits, characters stay in place
+        let prefix = self.packed & !0b1111;
+        (0..BASE32_CODES.len() as u64).map(move |index| Self {
+            packed: prefix | (index << Self::shift_value(len)) | (len as u64 + 1),
+        })
+    }
+
     // Returns the shift value. If we apply this shift to the packed value, we get the value of the `i`-th character.
     fn shift_value(i: usize) -> usize {
         assert!(i < GEOHASH_MAX_LENGTH);
@@ -378,6 +389,50 @@ fn check_circle_intersection(geohash: &str, circle: &GeoRadius) -> bool {
         )
 }
 
+/// Check if all corners of the geohash tile are within the circle
+pub fn circle_contains_tile(geo_hash: GeoHash, circle: &GeoRadius) -> bool {
+    if geo_hash.is_empty() {
+        return false;
+    }
+    let rect = decode_bbox(EcoString::from(geo_hash).as_str()).unwrap();
+    let (min, max) = (rect.min(), rect.max());
+    let center = Point::new(circle.center.lon, circle.center.lat);
+
+    [
+        Point::new(min.x, min.y),
+        Point::new(min.x, max.y),
+        Point::new(max.x, min.y),
+        Point::new(max.x, max.y),
+    ]
+    .into_iter()
+    .all(|corner| Haversine.distance(center, corner) <= circle.radius)
+}
+
+/// Estimate which fraction of the geohash tile area is within the circle
+///
+/// Checks a grid of `samples_per_side`^2 points, evenly distributed over the tile.
+pub fn circle_coverage(geo_hash: GeoHash, circle: &GeoRadius, samples_per_side: usize) -> f64 {
+    if geo_hash.is_empty() || samples_per_side == 0 {
+        return 0.0;
+    }
+    let rect = decode_bbox(EcoString::from(geo_hash).as_str()).unwrap();
+    let (min, max) = (rect.min(), rect.max());
+    let center = Point::new(circle.center.lon, circle.center.lat);
+
+    let step = |from: f64, to: f64, i: usize| {
+        from + (to - from) * (i as f64 + 0.5) / samples_per_side as f64
+    };
+    let covered = (0..samples_per_side)
+        .cartesian_product(0..samples_per_side)
+        .filter(|&(x, y)| {
+            let sample = Point::new(step(min.x, max.x, x), step(min.y, max.y, y));
+            Haversine.distance(center, sample) <= circle.radius
+        })
+        .count();
+
+    covered as f64 / (samples_per_side * samples_per_side) as f64
+}
+
 /// Check if geohash tile intersects the polygon
 fn check_polygon_intersection(geohash: &str, polygon: &Polygon) -> bool {
     let precision = geohash.len();
@@ -386,7 +441,15 @@ fn check_polygon_intersection(geohash: &str, polygon: &Polygon) -> bool {
     }
     let rect = decode_bbox(geohash).unwrap();
 
+    // Polygons crossing the antimeridian have longitudes in [0, 360), check the shifted tile too
     rect.intersects(polygon)
+        || (rect.min().x < 0.0 && {
+            let shift = |c: Coord| Coord {
+                x: c.x + 360.0,
+                y: c.y,
+            };
+            Rect::new(shift(rect.min()), shift(rect.max())).intersects(polygon)
+        })
 }
 
 fn create_hashes(
@@ -596,12 +659,14 @@ fn minimum_bounding_rectangle_for_boundary(boundary: &LineString) -> GeoBounding
         }
     }
 
+    // Longitudes of polygons crossing the antimeridian may exceed 180, wrap them back.
+    // The resulting rectangle then crosses the antimeridian as well.
     let top_left = GeoPoint {
-        lon: min_lon,
+        lon: sphere_lon(min_lon),
         lat: max_lat,
     };
     let bottom_right = GeoPoint {
-        lon: max_lon,
+        lon: sphere_lon(max_lon),
         lat: min_lat,
     };
 
@@ -629,6 +694,58 @@ mod tests {
         lon: -73.991516,
     };
 
+    #[test]
+    fn geohash_children() {
+        let hash = GeoHash::new("u33d").unwrap();
+        let children = hash.children().collect_vec();
+        assert_eq!(children.len(), 32);
+        assert!(children.iter().all(|child| child.len() == 5));
+        assert!(children.iter().all(|child| child.starts_with(hash)));
+        assert_eq!(children[0], GeoHash::new("u33d0").unwrap());
+        assert_eq!(children[31], GeoHash::new("u33dz").unwrap());
+        assert!(children.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn circle_coverage_of_tiles() {
+        let berlin_hash = encode_max_precision(BERLIN.lon, BERLIN.lat)
+            .unwrap()
+            .truncate(6);
+        let large_circle = GeoRadius {
+            center: BERLIN,
+            radius: 10_000.0,
+        };
+        assert_eq!(circle_coverage(berlin_hash, &large_circle, 4), 1.0);
+        assert!(circle_contains_tile(berlin_hash, &large_circle));
+
+        let far_circle = GeoRadius {
+            center: NYC,
+            radius: 10_000.0,
+        };
+        assert_eq!(circle_coverage(berlin_hash, &far_circle, 4), 0.0);
+        assert!(!circle_contains_tile(berlin_hash, &far_circle));
+
+        // Circle through the tile center covers part of it
+        let tile = geo_hash_to_box(berlin_hash);
+        let tile_center = GeoPoint {
+            lon: (tile.top_left.lon + tile.bottom_right.lon) / 2.0,
+            lat: (tile.top_left.lat + tile.bottom_right.lat) / 2.0,
+        };
+        let edge_circle = GeoRadius {
+            center: GeoPoint {
+                lon: tile_center.lon,
+                lat: tile_center.lat + 1.0,
+            },
+            radius: Haversine.distance(
+                Point::new(tile_center.lon, tile_center.lat + 1.0),
+                Point::new(tile_center.lon, tile_center.lat),
+            ),
+        };
+        let coverage = circle_coverage(berlin_hash, &edge_circle, 4);
+        assert!(coverage > 0.0 && coverage < 1.0, "coverage: {coverage}");
+        assert!(!circle_contains_tile(berlin_hash, &edge_circle));
+    }
+
     #[test]
     fn geohash_ordering() {
         let mut v: Vec<&[u8]> = vec![
@@ -51,6 +51,8 @@ pub struct ImmutableGeoMapIndex {
     max_values_per_point: usize,
     // Backing s torage, source of state, persists deletions
     storage: Storage,
+    /// Max number of geohash regions to cover the area of a query with
+    pub(super) max_query_regions: usize,
 }
 
 enum Storage {
@@ -132,6 +134,7 @@ impl ImmutableGeoMapIndex {
             points_values_count,
             max_values_per_point,
             storage: Storage::RocksDb(db_wrapper),
+            max_query_regions: super::GEO_QUERY_MAX_REGION,
         }))
     }
 
@@ -211,6 +214,7 @@ impl ImmutableGeoMapIndex {
             points_count: index.points_count(),
             points_values_count: index.points_values_count(),
             max_values_per_point: index.max_values_per_point(),
+            max_query_regions: index.max_query_regions,
             storage: Storage::Mmap(Box::new(index)),
         };
 
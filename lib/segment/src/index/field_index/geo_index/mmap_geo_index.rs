@@ -63,6 +63,8 @@ pub struct MmapGeoMapIndex {
     points_values_count: usize,
     max_values_per_point: usize,
     is_on_disk: bool,
+    /// Max number of geohash regions to cover the area of a query with
+    pub(super) max_query_regions: usize,
 }
 
 pub(super) struct Storage {
@@ -254,6 +256,7 @@ impl MmapGeoMapIndex {
             points_values_count: stats.points_values_count,
             max_values_per_point: stats.max_values_per_point,
             is_on_disk,
+            max_query_regions: super::GEO_QUERY_MAX_REGION,
         }))
     }
 
@@ -9,6 +9,7 @@ use std::sync::Arc;
 
 use common::counter::hardware_counter::HardwareCounterCell;
 use common::types::PointOffsetType;
+use geo::{Distance as GeoDistance, Haversine, Point};
 use itertools::Itertools;
 use mutable_geo_index::InMemoryGeoMapIndex;
 #[cfg(feature = "rocksdb")]
@@ -26,8 +27,9 @@ use super::FieldIndexBuilderTrait;
 use crate::common::Flusher;
 use crate::common::operation_error::{OperationError, OperationResult};
 use crate::index::field_index::geo_hash::{
-    GeoHash, circle_hashes, common_hash_prefix, geo_hash_to_box, polygon_hashes,
-    polygon_hashes_estimation, rectangle_hashes,
+    GEOHASH_MAX_LENGTH, GeoHash, circle_contains_tile, circle_coverage, circle_hashes,
+    common_hash_prefix, geo_hash_to_box, polygon_hashes, polygon_hashes_estimation,
+    rectangle_hashes,
 };
 use crate::index::field_index::stat_tools::estimate_multi_value_selection_cardinality;
 use crate::index::field_index::{
@@ -35,15 +37,43 @@ use crate::index::field_index::{
 };
 use crate::index::payload_config::{IndexMutability, StorageType};
 use crate::telemetry::PayloadIndexTelemetry;
-use crate::types::{FieldCondition, GeoPoint, PayloadKeyType};
+use crate::types::{FieldCondition, GeoPoint, GeoRadius, PayloadKeyType};
 
 pub mod immutable_geo_index;
 pub mod mmap_geo_index;
 pub mod mutable_geo_index;
 
-/// Max number of sub-regions computed for an input geo query
-// TODO discuss value, should it be dynamically computed?
-const GEO_QUERY_MAX_REGION: usize = 12;
+/// Default max number of sub-regions computed for an input geo query,
+/// configurable per index with
+/// [`GeoIndexParams::max_query_regions`](crate::data_types::index::GeoIndexParams::max_query_regions)
+pub const GEO_QUERY_MAX_REGION: usize = 12;
+
+/// Number of geohash precision levels to descend below query regions, when estimating
+/// the number of points within a geo radius on the edge of the circle
+const GEO_RADIUS_REFINE_DEPTH: usize = 2;
+
+/// Number of samples per tile side, used to estimate which part of a tile is within a circle
+const GEO_RADIUS_COVERAGE_SAMPLES: usize = 4;
+
+/// Estimated number of points of a tile within a geo radius
+#[derive(Debug, Default, Clone, Copy)]
+struct RadiusDensity {
+    /// Points of tiles which are entirely within the circle
+    covered: usize,
+    /// Points of covered tiles plus proportional part of points of tiles on the edge
+    expected: f64,
+}
+
+impl std::ops::Add for RadiusDensity {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            covered: self.covered + other.covered,
+            expected: self.expected + other.expected,
+        }
+    }
+}
 
 pub enum GeoMapIndex {
     Mutable(MutableGeoMapIndex),
@@ -116,6 +146,7 @@ impl GeoMapIndex {
             path: path.to_owned(),
             in_memory_index: InMemoryGeoMapIndex::new(),
             is_on_disk,
+            max_query_regions: GEO_QUERY_MAX_REGION,
         }
     }
 
@@ -123,6 +154,31 @@ impl GeoMapIndex {
         GeoMapIndexGridstoreBuilder::new(dir)
     }
 
+    /// Max number of geohash regions to cover the area of a query with.
+    ///
+    /// Points are indexed by geohashes of the max precision, so this only changes how precisely
+    /// query areas are covered, and can be changed without rebuilding the index.
+    pub fn max_query_regions(&self) -> usize {
+        match self {
+            GeoMapIndex::Mutable(index) => index.max_query_regions,
+            GeoMapIndex::Immutable(index) => index.max_query_regions,
+            GeoMapIndex::Mmap(index) => index.max_query_regions,
+        }
+    }
+
+    pub fn set_max_query_regions(&mut self, max_query_regions: usize) {
+        match self {
+            GeoMapIndex::Mutable(index) => index.max_query_regions = max_query_regions,
+            GeoMapIndex::Immutable(index) => index.max_query_regions = max_query_regions,
+            GeoMapIndex::Mmap(index) => index.max_query_regions = max_query_regions,
+        }
+    }
+
+    pub fn with_max_query_regions(mut self, max_query_regions: usize) -> Self {
+        self.set_max_query_regions(max_query_regions);
+        self
+    }
+
     fn points_count(&self) -> usize {
         match self {
             GeoMapIndex::Mutable(index) => index.points_count(),
@@ -265,6 +321,16 @@ impl GeoMapIndex {
         }
     }
 
+    /// Haversine distance in meters from `center` to the closest value of the point.
+    ///
+    /// Returns `None` if the point has no values.
+    pub fn min_distance(&self, idx: PointOffsetType, center: GeoPoint) -> Option<f64> {
+        let center = Point::from(center);
+        self.get_values(idx)?
+            .map(|value| Haversine.distance(center, Point::from(value)))
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
     pub fn match_cardinality(
         &self,
         values: &[GeoHash],
@@ -318,6 +384,75 @@ impl GeoMapIndex {
         }
     }
 
+    /// Cardinality of a geo radius, using point counts of geohash tiles within the circle.
+    ///
+    /// Tiles entirely within the circle are counted as is. Tiles on the edge of the circle are
+    /// refined by the point counts of their sub-tiles, the deepest ones are counted in proportion
+    /// to their area within the circle. Much tighter than [`Self::match_cardinality`] for
+    /// clustered points, which uses counts of whole tiles covering the circle.
+    fn radius_cardinality(
+        &self,
+        circle: &GeoRadius,
+        values: &[GeoHash],
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        let estimation = self.match_cardinality(values, hw_counter);
+        if estimation.max == 0 {
+            return estimation;
+        }
+
+        let density = values
+            .iter()
+            .map(|hash| {
+                self.tile_radius_density(*hash, circle, GEO_RADIUS_REFINE_DEPTH, hw_counter)
+            })
+            .fold(RadiusDensity::default(), |acc, density| acc + density);
+
+        // Same point may be counted in multiple tiles, if it has multiple values
+        let min = min(
+            density.covered / self.max_values_per_point(),
+            estimation.max,
+        );
+        CardinalityEstimation {
+            primary_clauses: vec![],
+            min,
+            exp: (density.expected.round() as usize).clamp(min, estimation.max),
+            max: estimation.max,
+        }
+    }
+
+    fn tile_radius_density(
+        &self,
+        hash: GeoHash,
+        circle: &GeoRadius,
+        depth: usize,
+        hw_counter: &HardwareCounterCell,
+    ) -> RadiusDensity {
+        let points = self.points_of_hash(&hash, hw_counter);
+        if points == 0 {
+            return RadiusDensity::default();
+        }
+
+        if circle_contains_tile(hash, circle) {
+            return RadiusDensity {
+                covered: points,
+                expected: points as f64,
+            };
+        }
+
+        if depth == 0 || hash.len() >= GEOHASH_MAX_LENGTH {
+            let coverage = circle_coverage(hash, circle, GEO_RADIUS_COVERAGE_SAMPLES);
+            return RadiusDensity {
+                covered: 0,
+                expected: points as f64 * coverage,
+            };
+        }
+
+        hash.children()
+            .map(|child| self.tile_radius_density(child, circle, depth - 1, hw_counter))
+            .fold(RadiusDensity::default(), |acc, density| acc + density)
+    }
+
     pub fn get_telemetry_data(&self) -> PayloadIndexTelemetry {
         PayloadIndexTelemetry {
             field_name: None,
@@ -332,6 +467,12 @@ impl GeoMapIndex {
         }
     }
 
+    /// All points with values in the index, without duplicates, in order of their geo hashes
+    pub fn iter_points(&self) -> Box<dyn Iterator<Item = PointOffsetType> + '_> {
+        // Empty hash is a prefix of all hashes
+        self.iterator(vec![GeoHash::default()])
+    }
+
     fn iterator(&self, values: Vec<GeoHash>) -> Box<dyn Iterator<Item = PointOffsetType> + '_> {
         match self {
             GeoMapIndex::Mutable(index) => Box::new(
@@ -458,6 +599,13 @@ impl GeoMapIndex {
 #[cfg(feature = "rocksdb")]
 pub struct GeoMapIndexBuilder(GeoMapIndex);
 
+#[cfg(feature = "rocksdb")]
+impl GeoMapIndexBuilder {
+    pub fn with_max_query_regions(self, max_query_regions: usize) -> Self {
+        Self(self.0.with_max_query_regions(max_query_regions))
+    }
+}
+
 #[cfg(feature = "rocksdb")]
 impl FieldIndexBuilderTrait for GeoMapIndexBuilder {
     type FieldIndexType = GeoMapIndex;
@@ -534,6 +682,14 @@ pub struct GeoMapIndexMmapBuilder {
     path: PathBuf,
     in_memory_index: InMemoryGeoMapIndex,
     is_on_disk: bool,
+    max_query_regions: usize,
+}
+
+impl GeoMapIndexMmapBuilder {
+    pub fn with_max_query_regions(mut self, max_query_regions: usize) -> Self {
+        self.max_query_regions = max_query_regions;
+        self
+    }
 }
 
 impl FieldIndexBuilderTrait for GeoMapIndexMmapBuilder {
@@ -558,11 +714,12 @@ impl FieldIndexBuilderTrait for GeoMapIndexMmapBuilder {
     }
 
     fn finalize(self) -> OperationResult<Self::FieldIndexType> {
-        Ok(GeoMapIndex::Mmap(Box::new(MmapGeoMapIndex::build(
+        let index = GeoMapIndex::Mmap(Box::new(MmapGeoMapIndex::build(
             self.in_memory_index,
             &self.path,
             self.is_on_disk,
-        )?)))
+        )?));
+        Ok(index.with_max_query_regions(self.max_query_regions))
     }
 }
 
@@ -616,11 +773,21 @@ impl ValueIndexer for GeoMapIndex {
 pub struct GeoMapIndexGridstoreBuilder {
     dir: PathBuf,
     index: Option<GeoMapIndex>,
+    max_query_regions: usize,
 }
 
 impl GeoMapIndexGridstoreBuilder {
     fn new(dir: PathBuf) -> Self {
-        Self { dir, index: None }
+        Self {
+            dir,
+            index: None,
+            max_query_regions: GEO_QUERY_MAX_REGION,
+        }
+    }
+
+    pub fn with_max_query_regions(mut self, max_query_regions: usize) -> Self {
+        self.max_query_regions = max_query_regions;
+        self
     }
 }
 
@@ -632,11 +799,11 @@ impl FieldIndexBuilderTrait for GeoMapIndexGridstoreBuilder {
             self.index.is_none(),
             "index must be initialized exactly once",
         );
-        self.index.replace(
-            GeoMapIndex::new_gridstore(self.dir.clone(), true)?.ok_or_else(|| {
-                OperationError::service_error("Failed to open GeoMapIndex after creating it")
-            })?,
-        );
+        let index = GeoMapIndex::new_gridstore(self.dir.clone(), true)?.ok_or_else(|| {
+            OperationError::service_error("Failed to open GeoMapIndex after creating it")
+        })?;
+        self.index
+            .replace(index.with_max_query_regions(self.max_query_regions));
         Ok(())
     }
 
@@ -708,7 +875,7 @@ impl PayloadFieldIndex for GeoMapIndex {
         hw_counter: &'a HardwareCounterCell,
     ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
         if let Some(geo_bounding_box) = &condition.geo_bounding_box {
-            let geo_hashes = rectangle_hashes(geo_bounding_box, GEO_QUERY_MAX_REGION).ok()?;
+            let geo_hashes = rectangle_hashes(geo_bounding_box, self.max_query_regions()).ok()?;
             let geo_condition_copy = geo_bounding_box.clone();
             return Some(Box::new(self.iterator(geo_hashes).filter(move |point| {
                 self.check_values_any(*point, hw_counter, |geo_point| {
@@ -718,7 +885,7 @@ impl PayloadFieldIndex for GeoMapIndex {
         }
 
         if let Some(geo_radius) = &condition.geo_radius {
-            let geo_hashes = circle_hashes(geo_radius, GEO_QUERY_MAX_REGION).ok()?;
+            let geo_hashes = circle_hashes(geo_radius, self.max_query_regions()).ok()?;
             let geo_condition_copy = geo_radius.clone();
             return Some(Box::new(self.iterator(geo_hashes).filter(move |point| {
                 self.check_values_any(*point, hw_counter, |geo_point| {
@@ -728,7 +895,7 @@ impl PayloadFieldIndex for GeoMapIndex {
         }
 
         if let Some(geo_polygon) = &condition.geo_polygon {
-            let geo_hashes = polygon_hashes(geo_polygon, GEO_QUERY_MAX_REGION).ok()?;
+            let geo_hashes = polygon_hashes(geo_polygon, self.max_query_regions()).ok()?;
             let geo_condition_copy = geo_polygon.convert();
             return Some(Box::new(self.iterator(geo_hashes).filter(move |point| {
                 self.check_values_any(*point, hw_counter, |geo_point| {
@@ -746,7 +913,7 @@ impl PayloadFieldIndex for GeoMapIndex {
         hw_counter: &HardwareCounterCell,
     ) -> Option<CardinalityEstimation> {
         if let Some(geo_bounding_box) = &condition.geo_bounding_box {
-            let geo_hashes = rectangle_hashes(geo_bounding_box, GEO_QUERY_MAX_REGION).ok()?;
+            let geo_hashes = rectangle_hashes(geo_bounding_box, self.max_query_regions()).ok()?;
             let mut estimation = self.match_cardinality(&geo_hashes, hw_counter);
             estimation
                 .primary_clauses
@@ -755,8 +922,8 @@ impl PayloadFieldIndex for GeoMapIndex {
         }
 
         if let Some(geo_radius) = &condition.geo_radius {
-            let geo_hashes = circle_hashes(geo_radius, GEO_QUERY_MAX_REGION).ok()?;
-            let mut estimation = self.match_cardinality(&geo_hashes, hw_counter);
+            let geo_hashes = circle_hashes(geo_radius, self.max_query_regions()).ok()?;
+            let mut estimation = self.radius_cardinality(geo_radius, &geo_hashes, hw_counter);
             estimation
                 .primary_clauses
                 .push(PrimaryCondition::Condition(Box::new(condition.clone())));
@@ -765,7 +932,7 @@ impl PayloadFieldIndex for GeoMapIndex {
 
         if let Some(geo_polygon) = &condition.geo_polygon {
             let (exterior_hashes, interior_hashes) =
-                polygon_hashes_estimation(geo_polygon, GEO_QUERY_MAX_REGION);
+                polygon_hashes_estimation(geo_polygon, self.max_query_regions());
             // The polygon cardinality estimation should consider its exterior and interiors.
             // Therefore, we compute exterior estimation first and then subtract all interior estimation.
             let mut exterior_estimation = self.match_cardinality(&exterior_hashes, hw_counter);
@@ -805,6 +972,7 @@ impl PayloadFieldIndex for GeoMapIndex {
                         geo_hash_to_box(geo_hash),
                     ),
                     cardinality: size,
+                    point_offsets: None,
                 }),
         )
     }
@@ -817,8 +985,8 @@ mod tests {
 
     use common::counter::hardware_accumulator::HwMeasurementAcc;
     use itertools::Itertools;
-    use rand::SeedableRng;
     use rand::prelude::StdRng;
+    use rand::{Rng, SeedableRng};
     use rstest::rstest;
     use serde_json::json;
     use tempfile::{Builder, TempDir};
@@ -1240,6 +1408,59 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case(IndexType::MutableGridstore)]
+    #[case(IndexType::Mmap)]
+    fn radius_cardinality_of_dense_cluster(#[case] index_type: IndexType) {
+        let num_points = 2000;
+        let hw_counter = HardwareCounterCell::new();
+        let mut rnd = StdRng::seed_from_u64(42);
+        let (mut builder, _temp_dir, _db) = create_builder(index_type);
+
+        // Dense cluster of points around Berlin
+        for idx in 0..num_points {
+            let point = json!({
+                "lon": BERLIN.lon + rnd.random_range(-0.15..0.15),
+                "lat": BERLIN.lat + rnd.random_range(-0.1..0.1),
+            });
+            builder
+                .add_point(idx as PointOffsetType, &[&point], &hw_counter)
+                .unwrap();
+        }
+        let field_index = builder.finalize().unwrap();
+
+        let geo_radius = GeoRadius {
+            center: BERLIN,
+            radius: 5_000.0,
+        };
+        let exact = (0..num_points as PointOffsetType)
+            .filter(|&idx| {
+                field_index
+                    .check_values_any(idx, &hw_counter, |point| geo_radius.check_point(point))
+            })
+            .count();
+
+        let condition = condition_for_geo_radius("test", geo_radius.clone());
+        let card = field_index
+            .estimate_cardinality(&condition, &hw_counter)
+            .unwrap();
+        assert!(
+            card.min <= exact && exact <= card.max,
+            "{card:?}, exact {exact}"
+        );
+
+        // Estimation by whole tiles covering the circle
+        let hashes = circle_hashes(&geo_radius, GEO_QUERY_MAX_REGION).unwrap();
+        let coarse = field_index.match_cardinality(&hashes, &hw_counter);
+
+        let error = card.exp.abs_diff(exact);
+        assert!(
+            error < coarse.exp.abs_diff(exact),
+            "{card:?}, {coarse:?}, exact {exact}"
+        );
+        assert!(error * 10 < exact, "{card:?}, exact {exact}");
+    }
+
     #[rstest]
     #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
     #[case(IndexType::MutableGridstore)]
@@ -1783,6 +2004,96 @@ mod tests {
         assert_eq!(point_offsets, vec![2]);
     }
 
+    #[rstest]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
+    #[case(IndexType::MutableGridstore)]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Immutable))]
+    #[case(IndexType::Mmap)]
+    #[case(IndexType::RamMmap)]
+    fn query_polygon_across_antimeridian(#[case] index_type: IndexType) {
+        let (mut builder, _, _) = create_builder(index_type);
+        let hw_counter = HardwareCounterCell::new();
+
+        // Both sides of the antimeridian around Fiji, and a point far away from it
+        let points = [(178.4, -18.1), (-179.9, -16.5), (0.0, -17.0)];
+        for (idx, (lon, lat)) in points.into_iter().enumerate() {
+            let geo_values = json!([{ "lon": lon, "lat": lat }]);
+            builder
+                .add_point(idx as PointOffsetType + 1, &[&geo_values], &hw_counter)
+                .unwrap();
+        }
+        let new_index = builder.finalize().unwrap();
+
+        let polygon = build_polygon(vec![
+            (175.0, -20.0),
+            (-175.0, -20.0),
+            (-175.0, -10.0),
+            (175.0, -10.0),
+            (175.0, -20.0),
+        ]);
+        assert!(polygon.crosses_antimeridian());
+
+        let field_condition = condition_for_geo_polygon("test", polygon);
+        let mut point_offsets = new_index
+            .filter(&field_condition, &hw_counter)
+            .unwrap()
+            .collect_vec();
+        point_offsets.sort_unstable();
+        assert_eq!(point_offsets, vec![1, 2]);
+
+        let card = new_index.estimate_cardinality(&field_condition, &hw_counter);
+        let card = card.unwrap();
+        assert!(card.min <= 2 && card.max >= 2);
+    }
+
+    #[rstest]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
+    #[case(IndexType::MutableGridstore)]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Immutable))]
+    #[case(IndexType::Mmap)]
+    #[case(IndexType::RamMmap)]
+    fn query_box_around_antimeridian(#[case] index_type: IndexType) {
+        let (mut builder, _, _) = create_builder(index_type);
+        let hw_counter = HardwareCounterCell::new();
+
+        let points = [(179.0, 0.0), (-179.0, 0.0), (0.0, 0.0), (169.0, 0.0)];
+        for (idx, (lon, lat)) in points.into_iter().enumerate() {
+            let geo_values = json!([{ "lon": lon, "lat": lat }]);
+            builder
+                .add_point(idx as PointOffsetType + 1, &[&geo_values], &hw_counter)
+                .unwrap();
+        }
+        let new_index = builder.finalize().unwrap();
+
+        // Box wraps across the antimeridian, from 170 to -170
+        let bounding_box = GeoBoundingBox {
+            top_left: GeoPoint {
+                lat: 10.0,
+                lon: 170.0,
+            },
+            bottom_right: GeoPoint {
+                lat: -10.0,
+                lon: -170.0,
+            },
+        };
+        for (lon, lat) in points {
+            let is_inside = bounding_box.check_point(&GeoPoint { lon, lat });
+            assert_eq!(is_inside, lon.abs() == 179.0);
+        }
+
+        let field_condition = condition_for_geo_box("test", bounding_box);
+        let mut point_offsets = new_index
+            .filter(&field_condition, &hw_counter)
+            .unwrap()
+            .collect_vec();
+        point_offsets.sort_unstable();
+        assert_eq!(point_offsets, vec![1, 2]);
+
+        let card = new_index.estimate_cardinality(&field_condition, &hw_counter);
+        let card = card.unwrap();
+        assert!(card.min <= 2 && card.max >= 2);
+    }
+
     #[rstest]
     #[cfg_attr(feature = "rocksdb", case(&[IndexType::Mutable, IndexType::MutableGridstore, IndexType::Immutable, IndexType::Mmap, IndexType::RamMmap], false))]
     #[cfg_attr(feature = "rocksdb", case(&[IndexType::Mutable, IndexType::MutableGridstore, IndexType::Immutable, IndexType::RamMmap], true))]
@@ -1898,4 +2209,48 @@ mod tests {
             }
         }
     }
+
+    #[rstest]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
+    #[case(IndexType::MutableGridstore)]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Immutable))]
+    #[case(IndexType::Mmap)]
+    #[case(IndexType::RamMmap)]
+    fn test_max_query_regions(#[case] index_type: IndexType) {
+        let (field_index, _temp_dir, _db) = build_random_index(1000, 20, index_type);
+        assert_eq!(field_index.max_query_regions(), GEO_QUERY_MAX_REGION);
+
+        let geo_radius = GeoRadius {
+            center: BERLIN,
+            radius: 2_000_000.0,
+        };
+        let condition = condition_for_geo_radius("test", geo_radius.clone());
+        let hw_counter = HardwareCounterCell::new();
+
+        let coarse_candidates = field_index
+            .iterator(circle_hashes(&geo_radius, GEO_QUERY_MAX_REGION).unwrap())
+            .count();
+        let coarse_points = field_index
+            .filter(&condition, &hw_counter)
+            .unwrap()
+            .collect::<HashSet<_>>();
+
+        let field_index = field_index.with_max_query_regions(128);
+        assert_eq!(field_index.max_query_regions(), 128);
+
+        // Finer cover of the circle gives fewer candidates to check, but the same result
+        let fine_candidates = field_index
+            .iterator(circle_hashes(&geo_radius, 128).unwrap())
+            .count();
+        let fine_points = field_index
+            .filter(&condition, &hw_counter)
+            .unwrap()
+            .collect::<HashSet<_>>();
+
+        assert!(
+            fine_candidates < coarse_candidates,
+            "{fine_candidates} >= {coarse_candidates}",
+        );
+        assert_eq!(fine_points, coarse_points);
+    }
 }
@@ -39,6 +39,8 @@ const GRIDSTORE_OPTIONS: StorageOptions = StorageOptions {
 pub struct MutableGeoMapIndex {
     in_memory_index: InMemoryGeoMapIndex,
     storage: Storage,
+    /// Max number of geohash regions to cover the area of a query with
+    pub(super) max_query_regions: usize,
 }
 
 enum Storage {
@@ -139,6 +141,7 @@ impl MutableGeoMapIndex {
         Ok(Some(Self {
             in_memory_index,
             storage: Storage::RocksDb(db_wrapper),
+            max_query_regions: super::GEO_QUERY_MAX_REGION,
         }))
     }
 
@@ -222,6 +225,7 @@ impl MutableGeoMapIndex {
         Ok(Some(Self {
             in_memory_index,
             storage: Storage::Gridstore(Arc::new(RwLock::new(store))),
+            max_query_regions: super::GEO_QUERY_MAX_REGION,
         }))
     }
 
@@ -54,6 +54,14 @@ pub trait Numericable: Num + PartialEq + PartialOrd + Copy {
     fn max_value() -> Self;
     fn to_f64(self) -> f64;
     fn from_f64(x: f64) -> Self;
+    /// Same as `from_f64`, but integer types round down instead of towards zero
+    fn from_f64_floor(x: f64) -> Self {
+        Self::from_f64(x)
+    }
+    /// Same as `from_f64`, but integer types round up instead of towards zero
+    fn from_f64_ceil(x: f64) -> Self {
+        Self::from_f64(x)
+    }
     fn from_u128(x: u128) -> Self;
     fn min(self, b: Self) -> Self {
         if self < b { self } else { b }
@@ -79,11 +87,18 @@ impl Numericable for i64 {
     fn from_f64(x: f64) -> Self {
         x as Self
     }
+    fn from_f64_floor(x: f64) -> Self {
+        x.floor() as Self
+    }
+    fn from_f64_ceil(x: f64) -> Self {
+        x.ceil() as Self
+    }
     fn from_u128(x: u128) -> Self {
         x as i64
     }
     fn abs_diff(self, b: Self) -> Self {
-        i64::abs_diff(self, b) as i64
+        // Distance between values of opposite signs may not fit into i64
+        i64::try_from(i64::abs_diff(self, b)).unwrap_or(i64::MAX)
     }
 }
 
@@ -254,6 +269,9 @@ impl<T: Numericable + Serialize + DeserializeOwned> Histogram<T> {
         Unbounded
     }
 
+    /// Estimates number of values in range between `from` and `to`, as `(min, exp, max)`.
+    ///
+    /// `Unbounded` side of the range spans up to the min or max stored value.
     pub fn estimate(&self, from: Bound<T>, to: Bound<T>) -> (usize, usize, usize) {
         let from_ = match &from {
             Included(val) => Included(Point {
@@ -279,17 +297,24 @@ impl<T: Numericable + Serialize + DeserializeOwned> Histogram<T> {
             Unbounded => Unbounded,
         };
 
-        // Value for range fraction estimation
+        // Value for range fraction estimation.
+        // Missing bound is the min or max stored value, so one-sided ranges are estimated tightly.
         let from_val = match from {
             Included(val) => val,
             Excluded(val) => val,
-            Unbounded => T::min_value(),
+            Unbounded => self
+                .borders
+                .first_key_value()
+                .map_or_else(T::min_value, |(border, _)| border.val),
         };
 
         let to_val = match to {
             Included(val) => val,
             Excluded(val) => val,
-            Unbounded => T::max_value(),
+            Unbounded => self
+                .borders
+                .last_key_value()
+                .map_or_else(T::max_value, |(border, _)| border.val),
         };
 
         let left_border = {
@@ -319,7 +344,8 @@ impl<T: Numericable + Serialize + DeserializeOwned> Histogram<T> {
             .tuple_windows()
             .map(
                 |((a, a_count), (b, _b_count)): ((&Point<T>, &Counts), (&Point<T>, _))| {
-                    let val_range = (b.val - a.val).to_f64();
+                    // Difference is computed as float, as it may overflow the integer type
+                    let val_range = b.val.to_f64() - a.val.to_f64();
 
                     if val_range == 0. {
                         // Zero-length range is always covered
@@ -332,7 +358,7 @@ impl<T: Numericable + Serialize + DeserializeOwned> Histogram<T> {
                         return (1, 1, 1);
                     }
 
-                    let cover_range = (to_val.min(b.val) - from_val.max(a.val)).to_f64();
+                    let cover_range = to_val.min(b.val).to_f64() - from_val.max(a.val).to_f64();
 
                     let covered_frac = cover_range / val_range;
                     let estimate = (a_count.right as f64 * covered_frac).round() as usize + 1;
@@ -1,3 +1,4 @@
+use std::fs;
 use std::path::{Path, PathBuf};
 #[cfg(feature = "rocksdb")]
 use std::sync::Arc;
@@ -8,7 +9,7 @@ use super::bool_index::BoolIndex;
 use super::bool_index::mutable_bool_index::MutableBoolIndex;
 #[cfg(feature = "rocksdb")]
 use super::bool_index::simple_bool_index::SimpleBoolIndex;
-use super::geo_index::{GeoMapIndexGridstoreBuilder, GeoMapIndexMmapBuilder};
+use super::geo_index::{GEO_QUERY_MAX_REGION, GeoMapIndexGridstoreBuilder, GeoMapIndexMmapBuilder};
 use super::histogram::Numericable;
 use super::map_index::{MapIndex, MapIndexGridstoreBuilder, MapIndexKey, MapIndexMmapBuilder};
 use super::mmap_point_to_values::MmapValue;
@@ -17,7 +18,7 @@ use super::numeric_index::{
 };
 use super::{FieldIndexBuilder, ValueIndexer};
 use crate::common::operation_error::{OperationError, OperationResult};
-use crate::data_types::index::TextIndexParams;
+use crate::data_types::index::{GeoIndexParams, TextIndexParams};
 use crate::index::field_index::FieldIndex;
 use crate::index::field_index::full_text_index::text_index::FullTextIndex;
 use crate::index::field_index::geo_index::GeoMapIndex;
@@ -46,6 +47,9 @@ pub struct IndexSelectorRocksDb<'a> {
     pub is_appendable: bool,
 }
 
+/// Mmap indexes are read in place from their files, so the files are never compressed.
+/// Reading a compressed file would require decompressing it into memory as a whole, which
+/// defeats keeping large immutable indexes on disk.
 #[derive(Copy, Clone)]
 pub struct IndexSelectorMmap<'a> {
     pub dir: &'a Path,
@@ -109,8 +113,8 @@ impl IndexSelector<'_> {
                 .numeric_new(field, create_if_missing)?
                 .map(FieldIndex::FloatIndex),
 
-            (PayloadIndexType::GeoIndex, PayloadSchemaParams::Geo(_)) => self
-                .geo_new(field, create_if_missing)?
+            (PayloadIndexType::GeoIndex, PayloadSchemaParams::Geo(params)) => self
+                .geo_new(field, params, create_if_missing)?
                 .map(FieldIndex::GeoIndex),
 
             (PayloadIndexType::FullTextIndex, PayloadSchemaParams::Text(params)) => self
@@ -154,6 +158,11 @@ impl IndexSelector<'_> {
         payload_schema: &PayloadFieldSchema,
         create_if_missing: bool,
     ) -> OperationResult<Option<Vec<FieldIndex>>> {
+        // Presence is fully served by the null index, which complements every index
+        if payload_schema.is_exists_only() {
+            return Ok(Some(vec![]));
+        }
+
         let indexes = match payload_schema.expand().as_ref() {
             PayloadSchemaParams::Keyword(_) => self
                 .map_new(field, create_if_missing)?
@@ -184,8 +193,8 @@ impl IndexSelector<'_> {
             PayloadSchemaParams::Float(_) => self
                 .numeric_new(field, create_if_missing)?
                 .map(|index| vec![FieldIndex::FloatIndex(index)]),
-            PayloadSchemaParams::Geo(_) => self
-                .geo_new(field, create_if_missing)?
+            PayloadSchemaParams::Geo(geo_index_params) => self
+                .geo_new(field, geo_index_params, create_if_missing)?
                 .map(|index| vec![FieldIndex::GeoIndex(index)]),
             PayloadSchemaParams::Text(text_index_params) => self
                 .text_new(field, text_index_params.clone(), create_if_missing)?
@@ -210,6 +219,11 @@ impl IndexSelector<'_> {
         field: &JsonPath,
         payload_schema: &PayloadFieldSchema,
     ) -> OperationResult<Vec<FieldIndexBuilder>> {
+        // Presence is fully served by the null index, which complements every index
+        if payload_schema.is_exists_only() {
+            return Ok(vec![]);
+        }
+
         let builders = match payload_schema.expand().as_ref() {
             PayloadSchemaParams::Keyword(_) => {
                 vec![self.map_builder(
@@ -259,9 +273,10 @@ impl IndexSelector<'_> {
                     FieldIndexBuilder::FloatGridstoreIndex,
                 )?]
             }
-            PayloadSchemaParams::Geo(_) => {
+            PayloadSchemaParams::Geo(geo_index_params) => {
                 vec![self.geo_builder(
                     field,
+                    geo_index_params,
                     #[cfg(feature = "rocksdb")]
                     FieldIndexBuilder::GeoIndex,
                     FieldIndexBuilder::GeoMmapIndex,
@@ -413,9 +428,11 @@ impl IndexSelector<'_> {
     fn geo_new(
         &self,
         field: &JsonPath,
+        params: &GeoIndexParams,
         create_if_missing: bool,
     ) -> OperationResult<Option<GeoMapIndex>> {
-        Ok(match self {
+        let max_query_regions = params.max_query_regions.unwrap_or(GEO_QUERY_MAX_REGION);
+        let index = match self {
             #[cfg(feature = "rocksdb")]
             IndexSelector::RocksDb(IndexSelectorRocksDb { db, is_appendable }) => {
                 GeoMapIndex::new_memory(
@@ -431,30 +448,36 @@ impl IndexSelector<'_> {
             IndexSelector::Gridstore(IndexSelectorGridstore { dir }) => {
                 GeoMapIndex::new_gridstore(map_dir(dir, field), create_if_missing)?
             }
-        })
+        };
+        Ok(index.map(|index| index.with_max_query_regions(max_query_regions)))
     }
 
     #[cfg_attr(not(feature = "rocksdb"), expect(clippy::unnecessary_wraps))]
     fn geo_builder(
         &self,
         field: &JsonPath,
+        params: &GeoIndexParams,
         #[cfg(feature = "rocksdb")] make_rocksdb: fn(
             super::geo_index::GeoMapIndexBuilder,
         ) -> FieldIndexBuilder,
         make_mmap: fn(GeoMapIndexMmapBuilder) -> FieldIndexBuilder,
         make_gridstore: fn(GeoMapIndexGridstoreBuilder) -> FieldIndexBuilder,
     ) -> OperationResult<FieldIndexBuilder> {
+        let max_query_regions = params.max_query_regions.unwrap_or(GEO_QUERY_MAX_REGION);
         Ok(match self {
             #[cfg(feature = "rocksdb")]
-            IndexSelector::RocksDb(IndexSelectorRocksDb { db, .. }) => {
-                make_rocksdb(GeoMapIndex::builder(Arc::clone(db), &field.to_string())?)
-            }
-            IndexSelector::Mmap(IndexSelectorMmap { dir, is_on_disk }) => {
-                make_mmap(GeoMapIndex::builder_mmap(&map_dir(dir, field), *is_on_disk))
-            }
-            IndexSelector::Gridstore(IndexSelectorGridstore { dir }) => {
-                make_gridstore(GeoMapIndex::builder_gridstore(map_dir(dir, field)))
-            }
+            IndexSelector::RocksDb(IndexSelectorRocksDb { db, .. }) => make_rocksdb(
+                GeoMapIndex::builder(Arc::clone(db), &field.to_string())?
+                    .with_max_query_regions(max_query_regions),
+            ),
+            IndexSelector::Mmap(IndexSelectorMmap { dir, is_on_disk }) => make_mmap(
+                GeoMapIndex::builder_mmap(&map_dir(dir, field), *is_on_disk)
+                    .with_max_query_regions(max_query_regions),
+            ),
+            IndexSelector::Gridstore(IndexSelectorGridstore { dir }) => make_gridstore(
+                GeoMapIndex::builder_gridstore(map_dir(dir, field))
+                    .with_max_query_regions(max_query_regions),
+            ),
         })
     }
 
@@ -465,6 +488,30 @@ impl IndexSelector<'_> {
         )?))
     }
 
+    /// Remove on-disk data of all indexes of the `field`, so they can be built from scratch
+    pub fn clear_field_dirs(dir: &Path, field: &JsonPath) -> OperationResult<()> {
+        for field_dir in field_dirs(dir, field) {
+            if field_dir.exists() {
+                fs::remove_dir_all(&field_dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move on-disk data of all indexes of the `field` from the `from` into the `to` directory
+    pub fn move_field_dirs(from: &Path, to: &Path, field: &JsonPath) -> OperationResult<()> {
+        fs::create_dir_all(to)?;
+        for (from_dir, to_dir) in field_dirs(from, field)
+            .into_iter()
+            .zip(field_dirs(to, field))
+        {
+            if from_dir.exists() {
+                fs::rename(&from_dir, &to_dir)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn new_null_index(
         dir: &Path,
         field: &JsonPath,
@@ -587,6 +634,17 @@ impl IndexSelector<'_> {
     }
 }
 
+/// Directories of all index types of the `field`
+fn field_dirs(dir: &Path, field: &JsonPath) -> [PathBuf; 5] {
+    [
+        map_dir(dir, field),
+        numeric_dir(dir, field),
+        text_dir(dir, field),
+        bool_dir(dir, field),
+        null_dir(dir, field),
+    ]
+}
+
 fn map_dir(dir: &Path, field: &JsonPath) -> PathBuf {
     dir.join(format!("{}-map", &field.filename()))
 }
@@ -12,6 +12,7 @@ use common::types::PointOffsetType;
 use gridstore::Blob;
 #[cfg(feature = "rocksdb")]
 use parking_lot::RwLock;
+use roaring::RoaringBitmap;
 #[cfg(feature = "rocksdb")]
 use rocksdb::DB;
 
@@ -29,8 +30,12 @@ use crate::index::field_index::immutable_point_to_values::ImmutablePointToValues
 use crate::index::field_index::mmap_point_to_values::MmapValue;
 use crate::index::payload_config::StorageType;
 
+/// Values present in at least `1 / DENSE_POSTINGS_RATIO` of the points keep their postings in a
+/// bitmap. From this density, a bitmap is more compact than a list of point IDs.
+const DENSE_POSTINGS_RATIO: usize = 32;
+
 pub struct ImmutableMapIndex<N: MapIndexKey + Key + ?Sized> {
-    value_to_points: HashMap<N::Owned, ContainerSegment>,
+    value_to_points: HashMap<N::Owned, Postings>,
     /// Container holding a slice of point IDs per value. `value_to_point` holds the range per value.
     /// Each slice MUST be sorted so that we can binary search over it.
     value_to_points_container: Vec<PointOffsetType>,
@@ -56,6 +61,25 @@ pub(super) struct ContainerSegment {
     count: u32,
 }
 
+/// Point IDs of a single value.
+///
+/// Representation is picked on load based on density, and is invisible outside of the index.
+pub(super) enum Postings {
+    /// Sorted slice of point IDs in the shared container
+    Sparse(ContainerSegment),
+    /// Bitmap of point IDs, for values present in a large share of points
+    Dense(RoaringBitmap),
+}
+
+impl Postings {
+    fn count(&self) -> usize {
+        match self {
+            Postings::Sparse(segment) => segment.count as usize,
+            Postings::Dense(bitmap) => bitmap.len() as usize,
+        }
+    }
+}
+
 impl<N: MapIndexKey + ?Sized> ImmutableMapIndex<N>
 where
     Vec<N::Owned>: Blob + Send + Sync,
@@ -88,43 +112,12 @@ where
             ..
         } = mutable;
 
-        let mut value_to_points = HashMap::new();
-        let mut value_to_points_container = Vec::with_capacity(values_count);
-
-        // flatten values-to-points map
-        for (value, points) in map {
-            let points = points.into_iter().collect::<Vec<_>>();
-            let container_len = value_to_points_container.len() as u32;
-            let range = container_len..container_len + points.len() as u32;
-            value_to_points.insert(
-                value,
-                ContainerSegment {
-                    count: range.len() as u32,
-                    range,
-                },
-            );
-            value_to_points_container.extend(points);
-        }
-
-        value_to_points.shrink_to_fit();
-
-        // Sort IDs in each slice of points
-        // This is very important because we binary search
-        for value in value_to_points.keys() {
-            if let Some((slice, _offset)) = Self::get_mut_point_ids_slice(
-                &value_to_points,
-                &mut value_to_points_container,
-                value.borrow(),
-            ) {
-                slice.sort_unstable();
-            } else {
-                debug_assert!(
-                    false,
-                    "value {} not found in value_to_points",
-                    value.borrow(),
-                );
-            }
-        }
+        let (value_to_points, value_to_points_container) = Self::flatten_postings(
+            map.into_iter()
+                .map(|(value, points)| (value, points.into_iter().collect())),
+            values_count,
+            point_to_values.len(),
+        );
 
         Ok(Some(Self {
             value_to_points,
@@ -154,7 +147,6 @@ where
 
         let mut indexed_points = 0;
         let mut values_count = 0;
-        let mut value_to_points = HashMap::new();
 
         // Create points to values mapping
         let mut point_to_values: Vec<Vec<N::Owned>> = vec![];
@@ -173,42 +165,15 @@ where
                 point_values.push(value.to_owned());
             }
         }
+        let total_points = point_to_values.len();
         let point_to_values = ImmutablePointToValues::new(point_to_values);
 
         // Create flattened values-to-points mapping
-        let mut value_to_points_container = Vec::with_capacity(values_count);
-        for (value, points) in mapping() {
-            let points = points.into_iter().collect::<Vec<_>>();
-            let container_len = value_to_points_container.len() as u32;
-            let range = container_len..container_len + points.len() as u32;
-            value_to_points.insert(
-                value.to_owned(),
-                ContainerSegment {
-                    count: range.len() as u32,
-                    range,
-                },
-            );
-            value_to_points_container.extend(points);
-        }
-        value_to_points.shrink_to_fit();
-
-        // Sort IDs in each slice of points
-        // This is very important because we binary search
-        for value in value_to_points.keys() {
-            if let Some((slice, _offset)) = Self::get_mut_point_ids_slice(
-                &value_to_points,
-                &mut value_to_points_container,
-                value.borrow(),
-            ) {
-                slice.sort_unstable();
-            } else {
-                debug_assert!(
-                    false,
-                    "value {} not found in value_to_points",
-                    value.borrow(),
-                );
-            }
-        }
+        let (value_to_points, value_to_points_container) = Self::flatten_postings(
+            mapping().map(|(value, points)| (value.to_owned(), points.collect())),
+            values_count,
+            total_points,
+        );
 
         debug_assert_eq!(indexed_points, index.get_indexed_points());
 
@@ -228,17 +193,56 @@ where
         }
     }
 
+    /// Build postings of each value.
+    ///
+    /// Dense postings are stored in a bitmap, the others are flattened into a single container.
+    /// IDs in each slice of the container are sorted, which is very important because we
+    /// binary search over them.
+    fn flatten_postings(
+        postings: impl Iterator<Item = (N::Owned, Vec<PointOffsetType>)>,
+        values_count: usize,
+        total_points: usize,
+    ) -> (HashMap<N::Owned, Postings>, Vec<PointOffsetType>) {
+        let mut value_to_points = HashMap::new();
+        let mut value_to_points_container = Vec::with_capacity(values_count);
+
+        for (value, mut points) in postings {
+            if points.len() * DENSE_POSTINGS_RATIO >= total_points {
+                let bitmap = points.into_iter().collect();
+                value_to_points.insert(value, Postings::Dense(bitmap));
+                continue;
+            }
+
+            points.sort_unstable();
+            let container_len = value_to_points_container.len() as u32;
+            let range = container_len..container_len + points.len() as u32;
+            value_to_points.insert(
+                value,
+                Postings::Sparse(ContainerSegment {
+                    count: range.len() as u32,
+                    range,
+                }),
+            );
+            value_to_points_container.extend(points);
+        }
+
+        value_to_points.shrink_to_fit();
+        value_to_points_container.shrink_to_fit();
+
+        (value_to_points, value_to_points_container)
+    }
+
     /// Return mutable slice of a container which holds point_ids for given value.
     ///
     /// The returned slice is sorted and does contain deleted values.
     /// The returned offset is the start of the range in the container.
     fn get_mut_point_ids_slice<'a>(
-        value_to_points: &HashMap<N::Owned, ContainerSegment>,
+        value_to_points: &HashMap<N::Owned, Postings>,
         value_to_points_container: &'a mut [PointOffsetType],
         value: &N,
     ) -> Option<(&'a mut [PointOffsetType], usize)> {
         match value_to_points.get(value) {
-            Some(entry) if entry.count > 0 => {
+            Some(Postings::Sparse(entry)) if entry.count > 0 => {
                 let range = entry.range.start as usize..entry.range.end as usize;
                 let vals = &mut value_to_points_container[range];
                 Some((vals, entry.range.start as usize))
@@ -250,11 +254,8 @@ where
     /// Shrinks the range of values-to-points by one.
     ///
     /// Returns true if the last element was removed.
-    fn shrink_value_range(
-        value_to_points: &mut HashMap<N::Owned, ContainerSegment>,
-        value: &N,
-    ) -> bool {
-        if let Some(entry) = value_to_points.get_mut(value) {
+    fn shrink_value_range(value_to_points: &mut HashMap<N::Owned, Postings>, value: &N) -> bool {
+        if let Some(Postings::Sparse(entry)) = value_to_points.get_mut(value) {
             entry.count = entry.count.saturating_sub(1);
             return entry.count == 0;
         }
@@ -289,12 +290,20 @@ where
     ///
     /// value_to_points_container -> [0, 1, 2, 4, (3), 5, 6, 7, 8, 9]
     fn remove_idx_from_value_list(
-        value_to_points: &mut HashMap<N::Owned, ContainerSegment>,
+        value_to_points: &mut HashMap<N::Owned, Postings>,
         value_to_points_container: &mut [PointOffsetType],
         deleted_value_to_points_container: &mut BitVec,
         value: &N,
         idx: PointOffsetType,
     ) {
+        if let Some(Postings::Dense(bitmap)) = value_to_points.get_mut(value) {
+            bitmap.remove(idx);
+            if bitmap.is_empty() {
+                value_to_points.remove(value);
+            }
+            return;
+        }
+
         let Some((values, offset)) =
             Self::get_mut_point_ids_slice(value_to_points, value_to_points_container, value)
         else {
@@ -447,15 +456,13 @@ where
     }
 
     pub fn get_count_for_value(&self, value: &N) -> Option<usize> {
-        self.value_to_points
-            .get(value)
-            .map(|entry| entry.count as usize)
+        self.value_to_points.get(value).map(Postings::count)
     }
 
     pub fn iter_counts_per_value(&self) -> impl Iterator<Item = (&N, usize)> + '_ {
         self.value_to_points
             .iter()
-            .map(|(k, entry)| (k.borrow(), entry.count as usize))
+            .map(|(k, postings)| (k.borrow(), postings.count()))
     }
 
     pub fn iter_values_map(&self) -> impl Iterator<Item = (&N, IdIter<'_>)> {
@@ -468,25 +475,27 @@ where
     }
 
     pub fn get_iterator(&self, value: &N) -> IdIter<'_> {
-        if let Some(entry) = self.value_to_points.get(value) {
-            let range = entry.range.start as usize..entry.range.end as usize;
-
-            let deleted_flags = self
-                .deleted_value_to_points_container
-                .iter()
-                .by_vals()
-                .skip(range.start)
-                .chain(std::iter::repeat(false));
-
-            let values = self.value_to_points_container[range]
-                .iter()
-                .zip(deleted_flags)
-                .filter(|(_, is_deleted)| !is_deleted)
-                .map(|(idx, _)| *idx);
-
-            Box::new(values)
-        } else {
-            Box::new(iter::empty::<PointOffsetType>())
+        match self.value_to_points.get(value) {
+            Some(Postings::Sparse(entry)) => {
+                let range = entry.range.start as usize..entry.range.end as usize;
+
+                let deleted_flags = self
+                    .deleted_value_to_points_container
+                    .iter()
+                    .by_vals()
+                    .skip(range.start)
+                    .chain(std::iter::repeat(false));
+
+                let values = self.value_to_points_container[range]
+                    .iter()
+                    .zip(deleted_flags)
+                    .filter(|(_, is_deleted)| !is_deleted)
+                    .map(|(idx, _)| *idx);
+
+                Box::new(values)
+            }
+            Some(Postings::Dense(bitmap)) => Box::new(bitmap.iter()),
+            None => Box::new(iter::empty::<PointOffsetType>()),
         }
     }
 
@@ -40,8 +40,8 @@ use crate::index::payload_config::{IndexMutability, StorageType};
 use crate::index::query_estimator::combine_should_estimations;
 use crate::telemetry::PayloadIndexTelemetry;
 use crate::types::{
-    AnyVariants, FieldCondition, IntPayloadType, Match, MatchAny, MatchExcept, MatchValue,
-    PayloadKeyType, UuidIntType, ValueVariants,
+    AnyVariants, FieldCondition, IntPayloadType, Match, MatchAny, MatchExcept, MatchPrefix,
+    MatchValue, PayloadKeyType, RangeInterface, UuidIntType, ValueVariants,
 };
 
 pub mod immutable_map_index;
@@ -200,6 +200,34 @@ where
         }
     }
 
+    /// Index values of the point `from` of the `other` index under the point `to`,
+    /// without converting them from payload.
+    pub fn copy_point_from(
+        &mut self,
+        other: &Self,
+        from: PointOffsetType,
+        to: PointOffsetType,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let values: Vec<N::Owned> = other
+            .get_values(from)
+            .map(|values| {
+                values
+                    .map(|v| N::to_owned(N::from_referenced(&v)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        match self {
+            MapIndex::Mutable(index) => index.add_many_to_map(to, values, hw_counter),
+            MapIndex::Immutable(_) => Err(OperationError::service_error(
+                "Can't add values to immutable map index",
+            )),
+            MapIndex::Mmap(_) => Err(OperationError::service_error(
+                "Can't add values to mmap map index",
+            )),
+        }
+    }
+
     pub fn values_count(&self, idx: PointOffsetType) -> usize {
         match self {
             MapIndex::Mutable(index) => index.values_count(idx).unwrap_or_default(),
@@ -560,6 +588,42 @@ where
     }
 }
 
+impl MapIndex<str> {
+    /// Union of points of all indexed keywords, which satisfy `select`.
+    ///
+    /// Used for conditions, which select many keywords at once, like prefix or range.
+    fn selected_keywords_set<'a>(
+        &'a self,
+        select: impl Fn(&str) -> bool + 'a,
+        hw_counter: &'a HardwareCounterCell,
+    ) -> Box<dyn Iterator<Item = PointOffsetType> + 'a> {
+        Box::new(
+            self.iter_values()
+                .filter(move |keyword| select(*keyword))
+                .flat_map(move |keyword| self.get_iterator(keyword, hw_counter))
+                .unique(),
+        )
+    }
+
+    /// Cardinality of union of points of all indexed keywords, which satisfy `select`.
+    fn selected_keywords_cardinality(
+        &self,
+        select: impl Fn(&str) -> bool,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        let estimations = self
+            .iter_values()
+            .filter(|keyword| select(*keyword))
+            .map(|keyword| self.match_cardinality(keyword, hw_counter))
+            .collect::<Vec<_>>();
+        match estimations.len() {
+            0 => CardinalityEstimation::exact(0),
+            1 => estimations.into_iter().next().unwrap(),
+            _ => combine_should_estimations(&estimations, self.get_indexed_points()),
+        }
+    }
+}
+
 pub struct MapIndexBuilder<N: MapIndexKey + ?Sized>(MapIndex<N>)
 where
     Vec<N::Owned>: Blob + Send + Sync;
@@ -782,7 +846,20 @@ impl PayloadFieldIndex for MapIndex<str> {
                     }
                 }
             },
-            _ => None,
+            // Empty prefix selects all keywords
+            Some(Match::Prefix(MatchPrefix { prefix })) => Some(self.selected_keywords_set(
+                move |keyword| keyword.starts_with(prefix.as_str()),
+                hw_counter,
+            )),
+            _ => match &condition.range {
+                Some(RangeInterface::Keyword(range)) if !condition.is_range_all() => {
+                    Some(self.selected_keywords_set(
+                        move |keyword| range.check_keyword(keyword),
+                        hw_counter,
+                    ))
+                }
+                _ => None,
+            },
         }
     }
 
@@ -844,7 +921,23 @@ impl PayloadFieldIndex for MapIndex<str> {
                     }
                 }
             },
-            _ => None,
+            Some(Match::Prefix(MatchPrefix { prefix })) => Some(
+                self.selected_keywords_cardinality(
+                    |keyword| keyword.starts_with(prefix.as_str()),
+                    hw_counter,
+                )
+                .with_primary_clause(PrimaryCondition::Condition(Box::new(condition.clone()))),
+            ),
+            _ => match &condition.range {
+                Some(RangeInterface::Keyword(range)) if !condition.is_range_all() => Some(
+                    self.selected_keywords_cardinality(
+                        |keyword| range.check_keyword(keyword),
+                        hw_counter,
+                    )
+                    .with_primary_clause(PrimaryCondition::Condition(Box::new(condition.clone()))),
+                ),
+                _ => None,
+            },
         }
     }
 
@@ -866,6 +959,7 @@ impl PayloadFieldIndex for MapIndex<str> {
                 .map(move |(value, count)| PayloadBlockCondition {
                     condition: FieldCondition::new_match(key.clone(), value.to_string().into()),
                     cardinality: count,
+                    point_offsets: None,
                 }),
         )
     }
@@ -1055,6 +1149,7 @@ impl PayloadFieldIndex for MapIndex<UuidIntType> {
                         Uuid::from_u128(*value).to_string().into(),
                     ),
                     cardinality: count,
+                    point_offsets: None,
                 }),
         )
     }
@@ -1203,6 +1298,7 @@ impl PayloadFieldIndex for MapIndex<IntPayloadType> {
                 .map(move |(value, count)| PayloadBlockCondition {
                     condition: FieldCondition::new_match(key.clone(), (*value).into()),
                     cardinality: count,
+                    point_offsets: None,
                 }),
         )
     }
@@ -1329,7 +1425,15 @@ impl ValueIndexer for MapIndex<UuidIntType> {
     }
 
     fn get_value(value: &Value) -> Option<Self::ValueType> {
-        Some(Uuid::parse_str(value.as_str()?).ok()?.as_u128())
+        let value = value.as_str()?;
+        match Uuid::parse_str(value) {
+            Ok(uuid) => Some(uuid.as_u128()),
+            Err(err) => {
+                // Not indexed, same as values of other types
+                log::warn!("Skipping invalid UUID {value:?} in payload index: {err}");
+                None
+            }
+        }
     }
 
     fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()> {
@@ -1597,6 +1701,61 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
+    #[case(IndexType::MutableGridstore)]
+    #[cfg_attr(feature = "rocksdb", case(IndexType::Immutable))]
+    #[case(IndexType::Mmap)]
+    #[case(IndexType::RamMmap)]
+    fn test_dense_and_sparse_postings(#[case] index_type: IndexType) {
+        // Boolean-ish values are dense, rare values are sparse
+        let data: Vec<Vec<IntPayloadType>> = (0..10_000)
+            .map(|idx| {
+                if idx % 1_000 == 0 {
+                    vec![idx % 2, 100]
+                } else {
+                    vec![idx % 2]
+                }
+            })
+            .collect();
+
+        let temp_dir = Builder::new().prefix("store_dir").tempdir().unwrap();
+        save_map_index::<IntPayloadType>(&data, temp_dir.path(), index_type, |v| (*v).into());
+        let mut index = load_map_index::<IntPayloadType>(&data, temp_dir.path(), index_type);
+
+        let hw_counter = HardwareCounterCell::new();
+
+        let check_postings = |index: &MapIndex<IntPayloadType>, data: &[Vec<IntPayloadType>]| {
+            for value in [0, 1, 100] {
+                let mut expected: Vec<_> = data
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, values)| values.contains(&value))
+                    .map(|(idx, _)| idx as PointOffsetType)
+                    .collect();
+                expected.sort_unstable();
+
+                let mut points: Vec<_> = index.get_iterator(&value, &hw_counter).collect();
+                points.sort_unstable();
+
+                assert_eq!(points, expected, "value {value}");
+            }
+        };
+        check_postings(&index, &data);
+
+        assert_eq!(index.get_count_for_value(&0, &hw_counter), Some(5_000));
+        assert_eq!(index.get_count_for_value(&1, &hw_counter), Some(5_000));
+        assert_eq!(index.get_count_for_value(&100, &hw_counter), Some(10));
+
+        // Removed points disappear from both representations
+        let mut data = data;
+        for idx in [0, 1, 2_000, 9_999] {
+            index.remove_point(idx).unwrap();
+            data[idx as usize].clear();
+        }
+        check_postings(&index, &data);
+    }
+
     #[rstest]
     #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
     #[case(IndexType::MutableGridstore)]
@@ -3,9 +3,10 @@ use std::ops::Deref;
 use ahash::AHashSet;
 use common::types::PointOffsetType;
 
-use crate::types::{Condition, FieldCondition, PointIdType, VectorNameBuf};
+use crate::types::{Condition, FieldCondition, IdRange, PointIdType, SeqNumberType, VectorNameBuf};
 
 pub mod bool_index;
+pub mod composite_index;
 pub(super) mod facet_index;
 mod field_index_base;
 pub mod full_text_index;
@@ -22,6 +23,7 @@ mod stat_tools;
 #[cfg(test)]
 mod tests;
 mod utils;
+pub mod version_index;
 
 pub use field_index_base::*;
 
@@ -36,13 +38,61 @@ pub struct ResolvedHasId {
     pub resolved_point_offsets: Vec<PointOffsetType>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedIdRange {
+    /// Range of IDs, as provided in filtering condition
+    pub range: IdRange,
+
+    /// Number of points with an ID in the `range`.
+    /// Offsets are not resolved, they are generated from the range on iteration.
+    pub points_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedVersionAtMost {
+    /// Version, as provided in filtering condition
+    pub version_at_most: SeqNumberType,
+
+    /// Expected number of points with a version not greater than `version_at_most`.
+    /// Offsets are not resolved, they are selected from the version index on iteration.
+    pub points_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedComposite {
+    /// Exact match conditions, answered together by a composite index
+    pub conditions: Vec<FieldCondition>,
+
+    /// Offsets of points matching all of the `conditions`
+    pub resolved_point_offsets: Vec<PointOffsetType>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum PrimaryCondition {
     Condition(Box<FieldCondition>),
     Ids(ResolvedHasId),
+    IdRange(ResolvedIdRange),
+    VersionAtMost(ResolvedVersionAtMost),
+    Composite(ResolvedComposite),
     HasVector(VectorNameBuf),
 }
 
+impl PrimaryCondition {
+    /// Whether matching points can be selected directly, without scanning all points
+    pub fn can_select_points(&self) -> bool {
+        match self {
+            PrimaryCondition::Condition(_)
+            | PrimaryCondition::Ids(_)
+            | PrimaryCondition::IdRange(_)
+            | PrimaryCondition::VersionAtMost(_)
+            | PrimaryCondition::Composite(_) => true,
+            // Points with a vector are found by scanning flags of all offsets in the storage,
+            // so any indexed clause is preferred
+            PrimaryCondition::HasVector(_) => false,
+        }
+    }
+}
+
 impl From<FieldCondition> for PrimaryCondition {
     fn from(condition: FieldCondition) -> Self {
         PrimaryCondition::Condition(Box::new(condition))
@@ -53,6 +103,23 @@ impl From<FieldCondition> for PrimaryCondition {
 pub struct PayloadBlockCondition {
     pub condition: FieldCondition,
     pub cardinality: usize,
+    /// Offsets of points the block is restricted to, if points matching the `condition` were
+    /// split into multiple blocks. Each part of a split value is a separate block with the same
+    /// `condition` and a disjoint range of offsets.
+    /// `None` if the block covers all points matching the `condition`.
+    ///
+    /// The `condition` alone matches points of all parts, so consumers of the block have to skip
+    /// points outside of the range, see [`Self::contains_point`].
+    pub point_offsets: Option<std::ops::Range<PointOffsetType>>,
+}
+
+impl PayloadBlockCondition {
+    /// Whether a point matching the `condition` belongs to this block
+    pub fn contains_point(&self, point_id: PointOffsetType) -> bool {
+        self.point_offsets
+            .as_ref()
+            .is_none_or(|offsets| offsets.contains(&point_id))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -113,6 +180,24 @@ impl CardinalityEstimation {
                     Condition::HasId(has_id) => ids.point_ids.deref() == has_id.has_id.deref(),
                     _ => false,
                 },
+                PrimaryCondition::IdRange(id_range) => match condition {
+                    Condition::HasIdRange(has_id_range) => {
+                        id_range.range == has_id_range.has_id_range
+                    }
+                    _ => false,
+                },
+                PrimaryCondition::VersionAtMost(versions) => match condition {
+                    Condition::VersionAtMost(version_condition) => {
+                        versions.version_at_most == version_condition.version_at_most
+                    }
+                    _ => false,
+                },
+                PrimaryCondition::Composite(composite) => match condition {
+                    Condition::Field(field_condition) => {
+                        composite.conditions.contains(field_condition)
+                    }
+                    _ => false,
+                },
                 PrimaryCondition::HasVector(has_vector) => match condition {
                     Condition::HasVector(vector_condition) => {
                         has_vector == &vector_condition.has_vector
@@ -100,40 +100,47 @@ impl MutableNullIndex {
         payload: &[&Value],
         hw_counter: &HardwareCounterCell,
     ) -> OperationResult<()> {
-        let mut is_null = false;
-        let mut has_values = false;
+        let (has_values, is_null) = payload_flags(payload);
+        self.set_flags(id, has_values, is_null, hw_counter);
+        Ok(())
+    }
 
-        for value in payload {
-            match value {
-                Value::Null => {
-                    is_null = true;
-                }
-                Value::Bool(_) => {
-                    has_values = true;
-                }
-                Value::Number(_) => {
-                    has_values = true;
-                }
-                Value::String(_) => {
-                    has_values = true;
-                }
-                Value::Array(array) => {
-                    if array.iter().any(|v| v.is_null()) {
-                        is_null = true;
-                    }
-                    if !array.is_empty() {
-                        has_values = true;
-                    }
-                }
-                Value::Object(_) => {
-                    has_values = true;
-                }
-            }
-            if is_null && has_values {
-                break;
-            }
+    /// Update flags of the point from `old` payload values to `new` ones, only if they changed
+    pub fn update_point(
+        &mut self,
+        id: PointOffsetType,
+        old: &[&Value],
+        new: &[&Value],
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let (has_values, is_null) = payload_flags(new);
+        if payload_flags(old) != (has_values, is_null) {
+            self.set_flags(id, has_values, is_null, hw_counter);
         }
+        Ok(())
+    }
 
+    /// Index flags of the point `from` of the `other` index under the point `to`
+    pub fn copy_point_from(
+        &mut self,
+        other: &Self,
+        from: PointOffsetType,
+        to: PointOffsetType,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let has_values = !other.values_is_empty(from);
+        let is_null = other.values_is_null(from);
+        self.set_flags(to, has_values, is_null, hw_counter);
+        Ok(())
+    }
+
+    fn set_flags(
+        &mut self,
+        id: PointOffsetType,
+        has_values: bool,
+        is_null: bool,
+        hw_counter: &HardwareCounterCell,
+    ) {
         self.storage.has_values_flags.set(id, has_values);
         self.storage.is_null_flags.set(id, is_null);
 
@@ -142,8 +149,6 @@ impl MutableNullIndex {
 
         // Account for I/O cost as if we were writing to disk now
         hw_counter.payload_index_io_write_counter().incr_delta(2);
-
-        Ok(())
     }
 
     pub fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()> {
@@ -177,6 +182,11 @@ impl MutableNullIndex {
         self.storage.is_null_flags.get(id)
     }
 
+    /// Number of points which have at least one value
+    pub fn has_values_count(&self) -> usize {
+        self.storage.has_values_flags.count_trues()
+    }
+
     pub fn get_telemetry_data(&self) -> PayloadIndexTelemetry {
         let points_count = self.storage.has_values_flags.len();
 
@@ -255,6 +265,7 @@ impl PayloadFieldIndex for MutableNullIndex {
             key: _,
             r#match: _,
             range: _,
+            range_mode: _,
             geo_bounding_box: _,
             geo_radius: _,
             geo_polygon: _,
@@ -297,6 +308,7 @@ impl PayloadFieldIndex for MutableNullIndex {
             key,
             r#match: _,
             range: _,
+            range_mode: _,
             geo_bounding_box: _,
             geo_radius: _,
             geo_polygon: _,
@@ -386,6 +398,45 @@ impl FieldIndexBuilderTrait for MutableNullIndexBuilder {
     }
 }
 
+/// Whether the payload values of a field have any value, and whether any of them is null
+fn payload_flags(payload: &[&Value]) -> (bool, bool) {
+    let mut is_null = false;
+    let mut has_values = false;
+
+    for value in payload {
+        match value {
+            Value::Null => {
+                is_null = true;
+            }
+            Value::Bool(_) => {
+                has_values = true;
+            }
+            Value::Number(_) => {
+                has_values = true;
+            }
+            Value::String(_) => {
+                has_values = true;
+            }
+            Value::Array(array) => {
+                if array.iter().any(|v| v.is_null()) {
+                    is_null = true;
+                }
+                if !array.is_empty() {
+                    has_values = true;
+                }
+            }
+            Value::Object(_) => {
+                has_values = true;
+            }
+        }
+        if is_null && has_values {
+            break;
+        }
+    }
+
+    (has_values, is_null)
+}
+
 #[cfg(test)]
 mod tests {
     use common::counter::hardware_accumulator::HwMeasurementAcc;
@@ -429,6 +480,7 @@ mod tests {
 
         let filter_is_not_empty = FieldCondition {
             key: key.clone(),
+            range_mode: None,
             r#match: None,
             range: None,
             geo_bounding_box: None,
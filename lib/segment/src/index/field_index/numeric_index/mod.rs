@@ -50,7 +50,7 @@ use crate::index::payload_config::{IndexMutability, StorageType};
 use crate::telemetry::PayloadIndexTelemetry;
 use crate::types::{
     DateTimePayloadType, FieldCondition, FloatPayloadType, IntPayloadType, Match, MatchValue,
-    PayloadKeyType, Range, RangeInterface, UuidIntType, UuidPayloadType, ValueVariants,
+    PayloadKeyType, Range, RangeInterface, RangeMode, UuidIntType, UuidPayloadType, ValueVariants,
 };
 
 const HISTOGRAM_MAX_BUCKET_SIZE: usize = 10_000;
@@ -164,6 +164,26 @@ impl<T: Encodable + Numericable> Range<T> {
     }
 }
 
+/// Convert a float range into a range of index values.
+///
+/// Bounds are rounded towards the inside of the range, so integer indexes select the same values
+/// as comparing them with the original float bounds, also for negative bounds.
+///
+/// Returns `None` if any bound is NaN. No value compares to NaN, so such range matches nothing,
+/// same as in [`Range::check_range`].
+fn index_range_from_float<T: Numericable>(range: &Range<FloatPayloadType>) -> Option<Range<T>> {
+    let Range { lt, gt, gte, lte } = *range;
+    if [lt, gt, gte, lte].into_iter().flatten().any(f64::is_nan) {
+        return None;
+    }
+    Some(Range {
+        lt: lt.map(T::from_f64_ceil),
+        gt: gt.map(T::from_f64_floor),
+        gte: gte.map(T::from_f64_ceil),
+        lte: lte.map(T::from_f64_floor),
+    })
+}
+
 pub enum NumericIndexInner<T: Encodable + Numericable + MmapValue + Send + Sync + Default>
 where
     Vec<T>: Blob,
@@ -289,6 +309,30 @@ where
         }
     }
 
+    /// Check values of the point with `check_fn`, requiring any or all of them to pass.
+    /// In `All` mode, points without values don't pass.
+    pub fn check_values(
+        &self,
+        idx: PointOffsetType,
+        mode: RangeMode,
+        check_fn: impl Fn(&T) -> bool,
+        hw_counter: &HardwareCounterCell,
+    ) -> bool {
+        match mode {
+            RangeMode::Any => self.check_values_any(idx, check_fn, hw_counter),
+            RangeMode::All => self.get_values(idx).is_some_and(|values| {
+                let mut has_values = false;
+                for value in values {
+                    if !check_fn(&value) {
+                        return false;
+                    }
+                    has_values = true;
+                }
+                has_values
+            }),
+        }
+    }
+
     pub fn get_values(&self, idx: PointOffsetType) -> Option<Box<dyn Iterator<Item = T> + '_>> {
         match self {
             NumericIndexInner::Mutable(index) => index.get_values(idx),
@@ -325,10 +369,15 @@ where
         }
 
         let range = match range {
-            RangeInterface::Float(float_range) => float_range.map(T::from_f64),
+            RangeInterface::Float(float_range) => match index_range_from_float(float_range) {
+                Some(range) => range,
+                None => return CardinalityEstimation::exact(0),
+            },
             RangeInterface::DateTime(datetime_range) => {
                 datetime_range.map(|dt| T::from_u128(dt.timestamp() as u128))
             }
+            // Numbers never lie in a keyword range
+            RangeInterface::Keyword(_) => return CardinalityEstimation::exact(0),
         };
 
         let lbound = if let Some(lte) = range.lte {
@@ -616,6 +665,33 @@ where
         &mut self.inner
     }
 
+    /// Index values of the point `from` of the `other` index under the point `to`,
+    /// without converting them from payload.
+    pub fn copy_point_from(
+        &mut self,
+        other: &Self,
+        from: PointOffsetType,
+        to: PointOffsetType,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let values: Vec<T> = other
+            .get_values(from)
+            .map(|values| values.collect())
+            .unwrap_or_default();
+        if values.is_empty() {
+            return Ok(());
+        }
+        match &mut self.inner {
+            NumericIndexInner::Mutable(index) => index.add_many_to_list(to, values, hw_counter),
+            NumericIndexInner::Immutable(_) => Err(OperationError::service_error(
+                "Can't add values to immutable numeric index",
+            )),
+            NumericIndexInner::Mmap(_) => Err(OperationError::service_error(
+                "Can't add values to mmap numeric index",
+            )),
+        }
+    }
+
     pub fn get_mutability_type(&self) -> IndexMutability {
         match &self.inner {
             NumericIndexInner::Mutable(_) => IndexMutability::Mutable,
@@ -637,6 +713,7 @@ where
     delegate! {
         to self.inner {
             pub fn check_values_any(&self, idx: PointOffsetType, check_fn: impl Fn(&T) -> bool, hw_counter: &HardwareCounterCell) -> bool;
+            pub fn check_values(&self, idx: PointOffsetType, mode: RangeMode, check_fn: impl Fn(&T) -> bool, hw_counter: &HardwareCounterCell) -> bool;
             pub fn cleanup(self) -> OperationResult<()>;
             pub fn get_telemetry_data(&self) -> PayloadIndexTelemetry;
             pub fn values_count(&self, idx: PointOffsetType) -> usize;
@@ -922,10 +999,14 @@ where
         let range_cond = condition.range.as_ref()?;
 
         let (start_bound, end_bound) = match range_cond {
-            RangeInterface::Float(float_range) => float_range.map(T::from_f64),
+            RangeInterface::Float(float_range) => match index_range_from_float(float_range) {
+                Some(range) => range,
+                None => return Some(Box::new(std::iter::empty())),
+            },
             RangeInterface::DateTime(datetime_range) => {
                 datetime_range.map(|dt| T::from_u128(dt.timestamp() as u128))
             }
+            RangeInterface::Keyword(_) => return None,
         }
         .as_index_key_bounds();
 
@@ -970,13 +1051,32 @@ where
             }
         }
 
-        condition.range.as_ref().map(|range| {
-            let mut cardinality = self.range_cardinality(range);
+        let range = condition.range.as_ref()?;
+        // Keyword ranges can't be served by numeric index
+        if matches!(range, RangeInterface::Keyword(_)) {
+            return None;
+        }
+
+        let mut cardinality = self.range_cardinality(range);
+        if condition.is_range_all() {
+            // Points with all values in range are a subset of points with any value in range.
+            // Postings only select candidates, which are then checked for all their values.
+            if self.max_values_per_point() > 1 {
+                cardinality.min = 0;
+            }
+            let any_condition = FieldCondition {
+                range_mode: None,
+                ..condition.clone()
+            };
             cardinality
                 .primary_clauses
-                .push(PrimaryCondition::Condition(Box::new(condition.clone())));
+                .push(PrimaryCondition::Condition(Box::new(any_condition)));
+        } else {
             cardinality
-        })
+                .primary_clauses
+                .push(PrimaryCondition::Condition(Box::new(condition.clone())));
+        }
+        Some(cardinality)
     }
 
     fn payload_blocks(
@@ -1020,6 +1120,7 @@ where
                 let condition = PayloadBlockCondition {
                     condition: FieldCondition::new_range(key.clone(), range),
                     cardinality: cardinality.exp,
+                    point_offsets: None,
                 };
 
                 payload_conditions.push(condition);
@@ -1036,6 +1137,7 @@ where
                         },
                     ),
                     cardinality: self.get_points_count(),
+                    point_offsets: None,
                 });
             }
 
@@ -1113,7 +1215,15 @@ impl ValueIndexer for NumericIndex<IntPayloadType, DateTimePayloadType> {
     }
 
     fn get_value(value: &Value) -> Option<DateTimePayloadType> {
-        DateTimePayloadType::from_str(value.as_str()?).ok()
+        let value = value.as_str()?;
+        match DateTimePayloadType::from_str(value) {
+            Ok(datetime) => Some(datetime),
+            Err(err) => {
+                // Not indexed, same as values of other types
+                log::warn!("Skipping invalid datetime {value:?} in payload index: {err}");
+                None
+            }
+        }
     }
 
     fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()> {
@@ -1139,7 +1249,11 @@ impl ValueIndexer for NumericIndex<FloatPayloadType, FloatPayloadType> {
         hw_counter: &HardwareCounterCell,
     ) -> OperationResult<()> {
         match &mut self.inner {
-            NumericIndexInner::Mutable(index) => index.add_many_to_list(id, values, hw_counter),
+            NumericIndexInner::Mutable(index) => {
+                // NaN can't be ordered against other values, so it is not indexed
+                let values = values.into_iter().filter(|value| !value.is_nan()).collect();
+                index.add_many_to_list(id, values, hw_counter)
+            }
             NumericIndexInner::Immutable(_) => Err(OperationError::service_error(
                 "Can't add values to immutable numeric index",
             )),
@@ -1190,7 +1304,15 @@ impl ValueIndexer for NumericIndex<UuidIntType, UuidPayloadType> {
     }
 
     fn get_value(value: &Value) -> Option<Self::ValueType> {
-        Uuid::parse_str(value.as_str()?).ok()
+        let value = value.as_str()?;
+        match Uuid::parse_str(value) {
+            Ok(uuid) => Some(uuid),
+            Err(err) => {
+                // Not indexed, same as values of other types
+                log::warn!("Skipping invalid UUID {value:?} in payload index: {err}");
+                None
+            }
+        }
     }
 
     fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()> {
@@ -1216,11 +1338,32 @@ where
         range: &RangeInterface,
     ) -> Box<dyn DoubleEndedIterator<Item = (T, PointOffsetType)> + '_> {
         let range = match range {
-            RangeInterface::Float(float_range) => float_range.map(T::from_f64),
+            RangeInterface::Float(float_range) => match index_range_from_float(float_range) {
+                Some(range) => range,
+                None => return Box::new(std::iter::empty()),
+            },
             RangeInterface::DateTime(datetime_range) => {
                 datetime_range.map(|dt| T::from_u128(dt.timestamp() as u128))
             }
+            RangeInterface::Keyword(_) => return Box::new(std::iter::empty()),
         };
+        self.stream_index_range(&range)
+    }
+}
+
+impl<T> NumericIndexInner<T>
+where
+    T: Encodable + Numericable + MmapValue + Send + Sync + Default,
+    Vec<T>: Blob,
+{
+    /// Stream values within a `range` of index values, ordered by value.
+    ///
+    /// Unlike [`StreamRange::stream_range`], bounds are not converted through floats, so they
+    /// are exact for any value of the index.
+    pub fn stream_index_range(
+        &self,
+        range: &Range<T>,
+    ) -> Box<dyn DoubleEndedIterator<Item = (T, PointOffsetType)> + '_> {
         let (start_bound, end_bound) = range.as_index_key_bounds();
 
         // map.range
@@ -8,6 +8,7 @@ use tempfile::{Builder, TempDir};
 use super::*;
 #[cfg(feature = "rocksdb")]
 use crate::common::rocksdb_wrapper::open_db_with_existing_cf;
+use crate::index::field_index::FieldIndex;
 use crate::json_path::JsonPath;
 
 #[cfg(feature = "rocksdb")]
@@ -264,6 +265,74 @@ fn test_cardinality_exp(#[case] index_type: IndexType) {
     );
 }
 
+#[rstest]
+#[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
+#[case(IndexType::MutableGridstore)]
+#[cfg_attr(feature = "rocksdb", case(IndexType::Immutable))]
+#[case(IndexType::Mmap)]
+#[case(IndexType::RamMmap)]
+fn test_open_ended_range_cardinality(#[case] index_type: IndexType) {
+    let num_points = 1000;
+    let (_temp_dir, index) = random_index(num_points, 1, index_type);
+    let hw_counter = HardwareCounterCell::new();
+    let tolerance = num_points / 10;
+
+    let count_values = |check: &dyn Fn(f64) -> bool| {
+        (0..num_points as PointOffsetType)
+            .filter(|&idx| index.inner().get_values(idx).unwrap().any(check))
+            .count()
+    };
+
+    // Everything >= 30
+    let query = Range {
+        lt: None,
+        gt: None,
+        gte: Some(30.0),
+        lte: None,
+    };
+    let estimation = cardinality_request(&index, query.clone(), HwMeasurementAcc::new());
+    let points = index
+        .inner()
+        .filter(
+            &FieldCondition::new_range(JsonPath::new("unused"), query),
+            &hw_counter,
+        )
+        .unwrap()
+        .count();
+    assert_eq!(points, count_values(&|value| value >= 30.0));
+    assert!(points.abs_diff(estimation.exp) < tolerance);
+
+    // Everything <= 30
+    let query = Range {
+        lt: None,
+        gt: None,
+        gte: None,
+        lte: Some(30.0),
+    };
+    let estimation = cardinality_request(&index, query.clone(), HwMeasurementAcc::new());
+    let points = index
+        .inner()
+        .filter(
+            &FieldCondition::new_range(JsonPath::new("unused"), query),
+            &hw_counter,
+        )
+        .unwrap()
+        .count();
+    assert_eq!(points, count_values(&|value| value <= 30.0));
+    assert!(points.abs_diff(estimation.exp) < tolerance);
+
+    // No bounds match every point
+    let query = Range {
+        lt: None,
+        gt: None,
+        gte: None,
+        lte: None,
+    };
+    let estimation = cardinality_request(&index, query, HwMeasurementAcc::new());
+    assert_eq!(estimation.max, num_points);
+    assert!(num_points.abs_diff(estimation.exp) < tolerance);
+}
+
 #[rstest]
 #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
 #[case(IndexType::MutableGridstore)]
@@ -344,6 +413,55 @@ fn test_payload_blocks_small(#[case] index_type: IndexType) {
     assert!(!blocks.is_empty());
 }
 
+#[rstest]
+#[case(IndexType::MutableGridstore)]
+#[case(IndexType::Mmap)]
+fn test_range_cardinality_after_reload(#[case] index_type: IndexType) {
+    let (temp_dir, index) = random_index(1000, 2, index_type);
+
+    let ranges = [
+        Range {
+            lt: Some(20.0),
+            gt: None,
+            gte: Some(10.0),
+            lte: None,
+        },
+        Range {
+            lt: None,
+            gt: Some(95.5),
+            gte: None,
+            lte: None,
+        },
+        Range {
+            lt: None,
+            gt: None,
+            gte: None,
+            lte: Some(0.5),
+        },
+    ];
+    let estimations = ranges
+        .iter()
+        .map(|range| cardinality_request(&index, range.clone(), HwMeasurementAcc::new()))
+        .collect_vec();
+    drop(index);
+
+    // Histogram is restored along with the index, so estimations stay the same
+    let reloaded = match index_type {
+        IndexType::MutableGridstore => NumericIndexInner::<FloatPayloadType>::new_gridstore(
+            temp_dir.path().to_path_buf(),
+            true,
+        ),
+        IndexType::Mmap => NumericIndexInner::<FloatPayloadType>::new_mmap(temp_dir.path(), true),
+        _ => unreachable!(),
+    }
+    .unwrap()
+    .unwrap();
+    for (range, estimation) in ranges.iter().zip(estimations) {
+        let reloaded_estimation = reloaded.range_cardinality(&RangeInterface::Float(range.clone()));
+        assert_eq!(reloaded_estimation, estimation);
+    }
+}
+
 #[rstest]
 #[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
 #[case(IndexType::MutableGridstore)]
@@ -624,3 +742,180 @@ fn test_empty_cardinality(#[case] index_type: IndexType) {
         HwMeasurementAcc::new(),
     );
 }
+
+#[rstest]
+#[case(IndexType::MutableGridstore)]
+#[case(IndexType::Mmap)]
+#[case(IndexType::RamMmap)]
+fn test_int_index_full_range(#[case] index_type: IndexType) {
+    let temp_dir = Builder::new()
+        .prefix("test_numeric_index")
+        .tempdir()
+        .unwrap();
+
+    let values = [i64::MIN, -100, -1, 0, 1, 100, i64::MAX];
+
+    fn build<B: FieldIndexBuilderTrait>(mut builder: B, values: &[i64]) {
+        let hw_counter = HardwareCounterCell::new();
+        builder.init().unwrap();
+        for (idx, value) in values.iter().enumerate() {
+            let value = Value::from(*value);
+            builder
+                .add_point(idx as PointOffsetType + 1, &[&value], &hw_counter)
+                .unwrap();
+        }
+        builder.finalize().unwrap();
+    }
+    match index_type {
+        IndexType::MutableGridstore => build(
+            NumericIndex::<IntPayloadType, IntPayloadType>::builder_gridstore(
+                temp_dir.path().to_path_buf(),
+            ),
+            &values,
+        ),
+        IndexType::Mmap | IndexType::RamMmap => build(
+            NumericIndex::<IntPayloadType, IntPayloadType>::builder_mmap(temp_dir.path(), false),
+            &values,
+        ),
+        #[cfg(feature = "rocksdb")]
+        IndexType::Mutable | IndexType::Immutable => unreachable!(),
+    }
+
+    let index = match index_type {
+        IndexType::MutableGridstore => {
+            NumericIndexInner::<IntPayloadType>::new_gridstore(temp_dir.path().to_path_buf(), true)
+        }
+        IndexType::Mmap => NumericIndexInner::<IntPayloadType>::new_mmap(temp_dir.path(), true),
+        IndexType::RamMmap => NumericIndexInner::<IntPayloadType>::new_mmap(temp_dir.path(), false),
+        #[cfg(feature = "rocksdb")]
+        IndexType::Mutable | IndexType::Immutable => unreachable!(),
+    }
+    .unwrap()
+    .unwrap();
+
+    let range = |gt, gte, lt, lte| Range { lt, gt, gte, lte };
+
+    // Range spanning zero
+    test_cond(
+        &index,
+        range(None, Some(-1.0), None, Some(1.0)),
+        vec![3, 4, 5],
+    );
+    test_cond(&index, range(None, None, Some(0.0), None), vec![1, 2, 3]);
+    test_cond(&index, range(Some(0.0), None, None, None), vec![5, 6, 7]);
+
+    // Fractional bounds are rounded towards the inside of the range, also for negatives
+    test_cond(
+        &index,
+        range(Some(-1.5), None, None, None),
+        vec![3, 4, 5, 6, 7],
+    );
+    test_cond(&index, range(None, None, None, Some(-0.5)), vec![1, 2, 3]);
+    test_cond(
+        &index,
+        range(None, Some(-100.5), Some(-0.5), None),
+        vec![2, 3],
+    );
+
+    // Extremes of the value range
+    test_cond(&index, range(None, None, None, Some(-1e18)), vec![1]);
+    test_cond(&index, range(None, Some(1e18), None, None), vec![7]);
+    test_cond(
+        &index,
+        range(None, None, None, None),
+        vec![1, 2, 3, 4, 5, 6, 7],
+    );
+
+    // Estimation over the whole value range doesn't overflow
+    let estimation = index.range_cardinality(&RangeInterface::Float(range(
+        None,
+        Some(-1.0),
+        None,
+        Some(1.0),
+    )));
+    assert!(estimation.min <= 3);
+    assert!(estimation.max >= 3);
+    let estimation = index.range_cardinality(&RangeInterface::Float(Range::default()));
+    assert!(estimation.min <= estimation.exp);
+    assert!(estimation.exp <= estimation.max);
+    assert!(estimation.max <= values.len());
+}
+
+#[rstest]
+#[cfg_attr(feature = "rocksdb", case(IndexType::Mutable))]
+#[case(IndexType::MutableGridstore)]
+#[cfg_attr(feature = "rocksdb", case(IndexType::Immutable))]
+#[case(IndexType::Mmap)]
+#[case(IndexType::RamMmap)]
+fn test_float_index_nan(#[case] index_type: IndexType) {
+    let (_temp_dir, mut index_builder) = get_index_builder(index_type);
+    let hw_counter = HardwareCounterCell::new();
+
+    // NaN can't be represented in JSON, so it is never indexed
+    let values = [1.0, f64::NAN, -1.0, f64::NAN, 0.5];
+    for (idx, value) in values.iter().enumerate() {
+        let value = Value::from(*value);
+        index_builder
+            .add_point(idx as PointOffsetType + 1, &[&value], &hw_counter)
+            .unwrap();
+    }
+    let index = index_builder.finalize().unwrap();
+    let index = index.inner();
+    assert_eq!(index.get_points_count(), 3);
+
+    let range = Range {
+        gte: Some(-10.0),
+        lte: Some(10.0),
+        ..Default::default()
+    };
+    test_cond(index, range.clone(), vec![1, 3, 5]);
+    assert!(range.check_range(0.0));
+    assert!(!range.check_range(f64::NAN));
+
+    // NaN bound never matches
+    for range in [
+        Range {
+            gte: Some(f64::NAN),
+            ..Default::default()
+        },
+        Range {
+            gt: Some(-10.0),
+            lt: Some(f64::NAN),
+            ..Default::default()
+        },
+    ] {
+        assert!(!range.check_range(0.0));
+        test_cond(index, range.clone(), vec![]);
+        let range = RangeInterface::Float(range);
+        assert_eq!(index.range_cardinality(&range).max, 0);
+        assert_eq!(index.stream_range(&range).count(), 0);
+    }
+}
+
+#[test]
+fn test_copy_point_from_immutable_index() {
+    let hw_counter = HardwareCounterCell::new();
+    let (_mmap_dir, mmap_index) = random_index(10, 2, IndexType::Mmap);
+    let (_dir, gridstore_index) = random_index(10, 2, IndexType::MutableGridstore);
+    let mut immutable = FieldIndex::FloatIndex(mmap_index);
+    let mut mutable = FieldIndex::FloatIndex(gridstore_index);
+
+    let get_values = |index: &FieldIndex, point_id| match index {
+        FieldIndex::FloatIndex(index) => index.get_values(point_id).unwrap().collect_vec(),
+        _ => unreachable!(),
+    };
+
+    // Values of immutable indexes are read
+    mutable
+        .copy_point_from(&immutable, 3, 20, &hw_counter)
+        .unwrap()
+        .unwrap();
+    assert_eq!(get_values(&mutable, 20), get_values(&immutable, 3));
+
+    // Immutable indexes can't take values, they are left to be rebuilt from payload
+    assert!(
+        immutable
+            .copy_point_from(&mutable, 3, 20, &hw_counter)
+            .is_none()
+    );
+}
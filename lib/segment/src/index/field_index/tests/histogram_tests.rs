@@ -255,6 +255,42 @@ fn test_build_histogram() {
     test_range_by_cardinality(&histogram);
 }
 
+#[test]
+fn test_open_ended_estimation() {
+    let max_bucket_size = 100;
+    let precision = 0.01;
+    let num_samples = 10_000;
+    let mut rnd = StdRng::seed_from_u64(42);
+
+    let points = (0..num_samples)
+        .map(|i| Point {
+            val: rnd.random_range(0.0..100.0),
+            idx: i,
+        })
+        .collect_vec();
+    let (histogram, points_index) = build_histogram(max_bucket_size, precision, points);
+    let tolerance = 2 * histogram.current_bucket_size();
+
+    // Everything >= 30
+    let (est_min, estimation, est_max) = histogram.estimate(Included(30.0), Unbounded);
+    let real = count_range(&points_index, 30.0, f64::MAX);
+    assert!(est_min <= real && real <= est_max);
+    assert!(real.abs_diff(estimation) < tolerance);
+    assert!(est_max - est_min < 2 * tolerance);
+
+    // Everything < 30
+    let (est_min, estimation, est_max) = histogram.estimate(Unbounded, Excluded(30.0));
+    let real = points_index.len() - real;
+    assert!(est_min <= real && real <= est_max);
+    assert!(real.abs_diff(estimation) < tolerance);
+    assert!(est_max - est_min < 2 * tolerance);
+
+    // No bounds at all covers every value
+    let (est_min, estimation, est_max) = histogram.estimate(Unbounded, Unbounded);
+    assert!(est_min <= points_index.len() && points_index.len() <= est_max);
+    assert!(points_index.len().abs_diff(estimation) < tolerance);
+}
+
 #[test]
 fn test_save_load_histogram() {
     let max_bucket_size = 1000;
@@ -0,0 +1,72 @@
+use std::fmt;
+use std::ops::Bound::{Included, Unbounded};
+
+use common::types::PointOffsetType;
+
+use super::histogram::Point;
+use super::numeric_index::mutable_numeric_index::InMemoryNumericIndex;
+use crate::id_tracker::IdTrackerSS;
+use crate::types::SeqNumberType;
+
+/// Versions of points, sorted for range queries.
+///
+/// The id tracker only keeps a version per point offset, so finding points by version would
+/// require a full scan. This index mirrors those versions in sorted order and estimates
+/// `version_at_most` conditions from a histogram, same as numeric ranges of field indexes.
+/// It is not persisted, but rebuilt from the id tracker on load.
+#[derive(Default)]
+pub struct VersionIndex {
+    versions: InMemoryNumericIndex<u128>,
+}
+
+impl VersionIndex {
+    /// Build the index from versions of all points of the `id_tracker`
+    pub fn build(id_tracker: &IdTrackerSS) -> Self {
+        let versions = id_tracker
+            .iter_ids()
+            .filter_map(|point_id| {
+                let version = id_tracker.internal_version(point_id)?;
+                Some((point_id, u128::from(version)))
+            })
+            .collect();
+        Self { versions }
+    }
+
+    /// Set version of the point, replacing the previous one
+    pub fn set_version(&mut self, point_id: PointOffsetType, version: SeqNumberType) {
+        self.versions.remove_point(point_id);
+        self.versions
+            .add_many_to_list(point_id, vec![u128::from(version)]);
+    }
+
+    /// Estimated number of points with a version not greater than `version`, as `(min, exp, max)`
+    pub fn estimate_at_most(&self, version: SeqNumberType) -> (usize, usize, usize) {
+        self.versions
+            .get_histogram()
+            .estimate(Unbounded, Included(u128::from(version)))
+    }
+
+    /// Points with a version not greater than `version`, in order of versions
+    pub fn points_at_most(
+        &self,
+        version: SeqNumberType,
+    ) -> impl Iterator<Item = PointOffsetType> + '_ {
+        self.versions.values_range(
+            Unbounded,
+            Included(Point::new(u128::from(version), PointOffsetType::MAX)),
+        )
+    }
+
+    /// Number of points with a known version
+    pub fn points_count(&self) -> usize {
+        self.versions.get_points_count()
+    }
+}
+
+impl fmt::Debug for VersionIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionIndex")
+            .field("points_count", &self.points_count())
+            .finish_non_exhaustive()
+    }
+}
@@ -36,6 +36,7 @@ use crate::common::operation_time_statistics::{
 use crate::data_types::query_context::VectorQueryContext;
 use crate::data_types::vectors::{QueryVector, VectorInternal, VectorRef};
 use crate::id_tracker::IdTrackerSS;
+use crate::index::field_index::PayloadBlockCondition;
 use crate::index::hnsw_index::HnswM;
 use crate::index::hnsw_index::build_condition_checker::BuildConditionChecker;
 use crate::index::hnsw_index::config::HnswGraphConfig;
@@ -60,9 +61,7 @@ use crate::payload_storage::FilterContext;
 use crate::segment_constructor::VectorIndexBuildArgs;
 use crate::telemetry::VectorIndexSearchesTelemetry;
 use crate::types::Condition::Field;
-use crate::types::{
-    FieldCondition, Filter, HnswConfig, HnswGlobalConfig, QuantizationSearchParams, SearchParams,
-};
+use crate::types::{Filter, HnswConfig, HnswGlobalConfig, QuantizationSearchParams, SearchParams};
 use crate::vector_storage::quantized::quantized_vectors::QuantizedVectors;
 use crate::vector_storage::query::DiscoveryQuery;
 use crate::vector_storage::{VectorStorage, VectorStorageEnum, new_raw_scorer};
@@ -546,8 +545,8 @@ impl HNSWIndex {
                         continue;
                     }
 
-                    let points_to_index = Self::condition_points(
-                        payload_block.condition,
+                    let points_to_index = Self::block_points(
+                        &payload_block,
                         id_tracker_ref.deref(),
                         &payload_index_ref,
                         &vector_storage_ref,
@@ -665,13 +664,13 @@ impl HNSWIndex {
     }
 
     /// Get list of points for indexing, associated with payload block filtering condition
-    fn condition_points(
-        condition: FieldCondition,
+    fn block_points(
+        payload_block: &PayloadBlockCondition,
         id_tracker: &IdTrackerSS,
         payload_index: &StructPayloadIndex,
         vector_storage: &VectorStorageEnum,
     ) -> Vec<PointOffsetType> {
-        let filter = Filter::new_must(Field(condition));
+        let filter = Filter::new_must(Field(payload_block.condition.clone()));
 
         let disposed_hw_counter = HardwareCounterCell::disposable(); // Internal operation. No measurements needed
 
@@ -688,6 +687,7 @@ impl HNSWIndex {
                 &disposed_hw_counter,
             )
             .filter(|&point_id| !deleted_bitslice.get_bit(point_id as usize).unwrap_or(false))
+            .filter(|&point_id| payload_block.contains_point(point_id))
             .collect()
     }
 
@@ -0,0 +1,260 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use common::counter::hardware_counter::HardwareCounterCell;
+
+use crate::id_tracker::IdTracker;
+use crate::index::PayloadIndex;
+use crate::index::field_index::{CardinalityEstimation, FieldIndex, PrimaryCondition};
+use crate::index::query_estimator::estimate_filter;
+use crate::index::struct_payload_index::StructPayloadIndex;
+use crate::payload_storage::PayloadStorage;
+use crate::payload_storage::query_checker::check_field_condition;
+use crate::types::{
+    Condition, FieldCondition, Filter, Match, PayloadKeyType, PayloadSchemaType, RangeInterface,
+};
+
+/// Number of points sampled from payload storage to approximate selectivity of a condition on a
+/// hypothetical index
+const HYPOTHETICAL_SAMPLE_SIZE: usize = 1_000;
+
+/// Cardinality estimation of a query as if some extra fields were indexed.
+///
+/// Not suitable for querying points, as primary clauses may refer to indexes which don't exist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HypotheticalEstimation {
+    pub estimation: CardinalityEstimation,
+    /// Fields with conditions estimated from a payload sample instead of an index.
+    /// If not empty, the estimation is approximate.
+    pub approximated_fields: Vec<PayloadKeyType>,
+}
+
+impl HypotheticalEstimation {
+    pub fn is_approximate(&self) -> bool {
+        !self.approximated_fields.is_empty()
+    }
+}
+
+/// Whether an index of the given type could serve the condition
+fn supports_condition(schema_type: PayloadSchemaType, condition: &FieldCondition) -> bool {
+    let FieldCondition {
+        key: _,
+        r#match,
+        range,
+        range_mode: _,
+        geo_bounding_box,
+        geo_radius,
+        geo_polygon,
+        values_count,
+        is_empty,
+        is_null,
+    } = condition;
+
+    if let Some(r#match) = r#match {
+        return match r#match {
+            Match::Value(_) | Match::Any(_) | Match::Except(_) => matches!(
+                schema_type,
+                PayloadSchemaType::Keyword
+                    | PayloadSchemaType::Integer
+                    | PayloadSchemaType::Bool
+                    | PayloadSchemaType::Uuid
+            ),
+            Match::Prefix(_) => schema_type == PayloadSchemaType::Keyword,
+            Match::Text(_) | Match::TextAny(_) | Match::Phrase(_) => {
+                schema_type == PayloadSchemaType::Text
+            }
+        };
+    }
+    if let Some(range) = range {
+        return match range {
+            RangeInterface::Keyword(_) => schema_type == PayloadSchemaType::Keyword,
+            RangeInterface::Float(_) | RangeInterface::DateTime(_) => matches!(
+                schema_type,
+                PayloadSchemaType::Integer | PayloadSchemaType::Float | PayloadSchemaType::Datetime
+            ),
+        };
+    }
+    if geo_bounding_box.is_some() || geo_radius.is_some() || geo_polygon.is_some() {
+        return schema_type == PayloadSchemaType::Geo;
+    }
+    values_count.is_some() || is_empty.is_some() || is_null.is_some()
+}
+
+impl StructPayloadIndex {
+    /// Estimate cardinality of the query as if `hypothetical` fields were indexed, without
+    /// building anything.
+    ///
+    /// Selectivity of conditions on hypothetical indexes is approximated from a random sample of
+    /// payloads. Fields which are indexed already are estimated with their actual indexes.
+    pub fn estimate_with_hypothetical(
+        &self,
+        query: &Filter,
+        hypothetical: &[(PayloadKeyType, PayloadSchemaType)],
+        hw_counter: &HardwareCounterCell,
+    ) -> HypotheticalEstimation {
+        let available_points = self.available_point_count();
+        let approximated_fields = RefCell::new(Vec::new());
+
+        let estimator = |condition: &Condition| match self.hypothetical_condition_cardinality(
+            condition,
+            hypothetical,
+            hw_counter,
+        ) {
+            Some((field, estimation)) => {
+                let mut approximated_fields = approximated_fields.borrow_mut();
+                if !approximated_fields.contains(field) {
+                    approximated_fields.push(field.clone());
+                }
+                estimation
+            }
+            None => self.condition_cardinality(condition, None, hw_counter),
+        };
+        let estimation = estimate_filter(&estimator, query, available_points);
+
+        HypotheticalEstimation {
+            estimation,
+            approximated_fields: approximated_fields.into_inner(),
+        }
+    }
+
+    /// Type of the hypothetical index which would serve the condition, if the field is not
+    /// indexed yet
+    fn hypothetical_schema(
+        &self,
+        condition: &FieldCondition,
+        hypothetical: &[(PayloadKeyType, PayloadSchemaType)],
+    ) -> Option<PayloadSchemaType> {
+        if self.field_indexes.contains_key(&condition.key) {
+            return None;
+        }
+        hypothetical
+            .iter()
+            .find(|(field, schema_type)| {
+                *field == condition.key && supports_condition(*schema_type, condition)
+            })
+            .map(|(_, schema_type)| *schema_type)
+    }
+
+    /// Approximate cardinality of a condition on a hypothetical index from a payload sample.
+    /// Returns the approximated field along with the estimation.
+    fn hypothetical_condition_cardinality<'a>(
+        &self,
+        condition: &'a Condition,
+        hypothetical: &[(PayloadKeyType, PayloadSchemaType)],
+        hw_counter: &HardwareCounterCell,
+    ) -> Option<(&'a PayloadKeyType, CardinalityEstimation)> {
+        let Condition::Field(field_condition) = condition else {
+            return None;
+        };
+        self.hypothetical_schema(field_condition, hypothetical)?;
+
+        let available_points = self.available_point_count();
+        let no_indexes = HashMap::<PayloadKeyType, Vec<FieldIndex>>::new();
+        let payload = self.payload.borrow();
+        let id_tracker = self.id_tracker.borrow();
+
+        let mut sampled = 0;
+        let mut matched = 0;
+        for (_, point_id) in id_tracker.iter_random().take(HYPOTHETICAL_SAMPLE_SIZE) {
+            let Ok(point_payload) = payload.get(point_id, hw_counter) else {
+                continue;
+            };
+            sampled += 1;
+            if check_field_condition(field_condition, &point_payload, &no_indexes, hw_counter) {
+                matched += 1;
+            }
+        }
+
+        if sampled == 0 {
+            return Some((&field_condition.key, CardinalityEstimation::exact(0)));
+        }
+
+        // Sampled points are known exactly, the rest is extrapolated from them
+        let exp = (matched as f64 / sampled as f64 * available_points as f64).round() as usize;
+        let min = matched;
+        let max = available_points.saturating_sub(sampled - matched).max(min);
+        let estimation = CardinalityEstimation {
+            primary_clauses: vec![PrimaryCondition::from(field_condition.clone())],
+            min,
+            exp: exp.clamp(min, max),
+            max,
+        };
+        Some((&field_condition.key, estimation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tempfile::Builder;
+
+    use super::*;
+    use crate::data_types::vectors::only_default_vector;
+    use crate::entry::SegmentEntry;
+    use crate::json_path::JsonPath;
+    use crate::segment_constructor::simple_segment_constructor::build_simple_segment;
+    use crate::types::{Distance, Payload};
+
+    #[test]
+    fn test_estimate_with_hypothetical() {
+        let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+        let hw_counter = HardwareCounterCell::new();
+        let key = JsonPath::from_str("color").unwrap();
+
+        let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+        for idx in 0..100u64 {
+            segment
+                .upsert_point(
+                    idx,
+                    idx.into(),
+                    only_default_vector(&[1.0, 1.0]),
+                    &hw_counter,
+                )
+                .unwrap();
+            let color = if idx % 10 == 0 { "red" } else { "blue" };
+            let payload: Payload =
+                serde_json::from_value(serde_json::json!({ "color": color })).unwrap();
+            segment
+                .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+                .unwrap();
+        }
+
+        let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+            key.clone(),
+            "red".to_string().into(),
+        )));
+        let payload_index = segment.payload_index.borrow();
+
+        let actual = payload_index.estimate_with_hypothetical(&filter, &[], &hw_counter);
+        assert!(!actual.is_approximate());
+        assert_eq!(
+            actual.estimation,
+            payload_index.estimate_cardinality(&filter, &hw_counter)
+        );
+
+        // Index of a type which can't serve the condition doesn't change anything
+        let text = payload_index.estimate_with_hypothetical(
+            &filter,
+            &[(key.clone(), PayloadSchemaType::Text)],
+            &hw_counter,
+        );
+        assert_eq!(text, actual);
+
+        // All points fit into the sample, so the estimation is exact
+        let keyword = payload_index.estimate_with_hypothetical(
+            &filter,
+            &[(key.clone(), PayloadSchemaType::Keyword)],
+            &hw_counter,
+        );
+        assert_eq!(keyword.approximated_fields, vec![key.clone()]);
+        assert_eq!(keyword.estimation.min, 10);
+        assert_eq!(keyword.estimation.exp, 10);
+        assert_eq!(keyword.estimation.max, 10);
+        assert_eq!(keyword.estimation.primary_clauses.len(), 1);
+
+        // Nothing is built
+        assert!(payload_index.indexed_fields().is_empty());
+        assert!(!payload_index.field_indexes.contains_key(&key));
+    }
+}
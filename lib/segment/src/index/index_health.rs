@@ -0,0 +1,109 @@
+/// Thresholds for recommendations of [`IndexHealth`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexHealthThresholds {
+    /// Recommend dropping the index, if a single value is present in at least this fraction of
+    /// available points
+    pub drop_index_fraction: f64,
+    /// Recommend an `exists_only` index, if the field has at most this many distinct values
+    pub exists_only_max_values: usize,
+}
+
+impl Default for IndexHealthThresholds {
+    fn default() -> Self {
+        Self {
+            drop_index_fraction: 0.9,
+            exists_only_max_values: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexRecommendation {
+    /// Index is selective enough
+    Healthy,
+    /// Values don't tell points apart, only their presence does
+    ExistsOnly,
+    /// Most points share the same value, scanning is cheaper than using the index
+    DropIndex,
+}
+
+/// Skew of posting lists of a field index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexHealth {
+    /// Fraction of available points in the largest posting list
+    pub max_posting_fraction: f64,
+    /// Number of distinct indexed values
+    pub distinct_values: usize,
+    pub recommendation: IndexRecommendation,
+}
+
+impl IndexHealth {
+    /// Assess health from the number of points of each indexed value
+    pub fn from_posting_sizes(
+        posting_sizes: impl IntoIterator<Item = usize>,
+        available_points: usize,
+        thresholds: &IndexHealthThresholds,
+    ) -> Self {
+        let (distinct_values, max_posting) = posting_sizes
+            .into_iter()
+            .filter(|&size| size > 0)
+            .fold((0, 0), |(distinct, max), size| {
+                (distinct + 1, max.max(size))
+            });
+
+        let max_posting_fraction = if available_points == 0 {
+            0.0
+        } else {
+            max_posting as f64 / available_points as f64
+        };
+
+        let recommendation = if distinct_values == 0 {
+            IndexRecommendation::Healthy
+        } else if distinct_values <= thresholds.exists_only_max_values {
+            IndexRecommendation::ExistsOnly
+        } else if max_posting_fraction >= thresholds.drop_index_fraction {
+            IndexRecommendation::DropIndex
+        } else {
+            IndexRecommendation::Healthy
+        };
+
+        Self {
+            max_posting_fraction,
+            distinct_values,
+            recommendation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_health_recommendation() {
+        let thresholds = IndexHealthThresholds::default();
+
+        let health = IndexHealth::from_posting_sizes([30, 40, 30], 100, &thresholds);
+        assert_eq!(health.distinct_values, 3);
+        assert_eq!(health.max_posting_fraction, 0.4);
+        assert_eq!(health.recommendation, IndexRecommendation::Healthy);
+
+        let health = IndexHealth::from_posting_sizes([95, 3, 2, 0], 100, &thresholds);
+        assert_eq!(health.distinct_values, 3);
+        assert_eq!(health.recommendation, IndexRecommendation::DropIndex);
+
+        let health = IndexHealth::from_posting_sizes([50], 100, &thresholds);
+        assert_eq!(health.recommendation, IndexRecommendation::ExistsOnly);
+
+        let health = IndexHealth::from_posting_sizes([], 0, &thresholds);
+        assert_eq!(health.max_posting_fraction, 0.0);
+        assert_eq!(health.recommendation, IndexRecommendation::Healthy);
+
+        let strict = IndexHealthThresholds {
+            drop_index_fraction: 0.3,
+            exists_only_max_values: 0,
+        };
+        let health = IndexHealth::from_posting_sizes([30, 40, 30], 100, &strict);
+        assert_eq!(health.recommendation, IndexRecommendation::DropIndex);
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use common::counter::hardware_counter::HardwareCounterCell;
+use common::types::PointOffsetType;
+
+use crate::index::PayloadIndex;
+use crate::index::field_index::{CardinalityEstimation, PayloadBlockCondition};
+use crate::index::struct_payload_index::StructPayloadIndex;
+use crate::json_path::JsonPath;
+use crate::payload_storage::FilterContext;
+use crate::types::{Filter, PayloadFieldSchema, PayloadKeyType, PayloadKeyTypeRef};
+
+/// Read-only view of a [`StructPayloadIndex`], see [`StructPayloadIndex::query_snapshot`].
+///
+/// Holds the field indexes which were current when the view was captured, so queries keep
+/// using them even if indexes of a field are replaced or dropped in the meantime.
+///
+/// This is not a snapshot of the data. Payload storage, id tracker and point versions are shared
+/// with the live index:
+///
+/// - payload updates and point deletions touching captured indexes fail while the guard is
+///   alive, before anything is written, so captured indexes always agree with payload storage
+/// - payload of other fields may change, conditions on them see the live payload
+/// - id and version conditions see the live id tracker and point versions
+///
+/// Dropping the guard releases superseded indexes.
+#[derive(Debug)]
+pub struct IndexReadGuard {
+    index: StructPayloadIndex,
+}
+
+impl IndexReadGuard {
+    pub(super) fn new(index: StructPayloadIndex) -> Self {
+        Self { index }
+    }
+
+    pub fn indexed_fields(&self) -> HashMap<PayloadKeyType, PayloadFieldSchema> {
+        self.index.indexed_fields()
+    }
+
+    pub fn estimate_cardinality(
+        &self,
+        query: &Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        self.index.estimate_cardinality(query, hw_counter)
+    }
+
+    pub fn estimate_nested_cardinality(
+        &self,
+        query: &Filter,
+        nested_path: &JsonPath,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        self.index
+            .estimate_nested_cardinality(query, nested_path, hw_counter)
+    }
+
+    pub fn query_points(
+        &self,
+        query: &Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> Vec<PointOffsetType> {
+        self.index.query_points(query, hw_counter)
+    }
+
+    pub fn indexed_points(&self, field: PayloadKeyTypeRef) -> usize {
+        self.index.indexed_points(field)
+    }
+
+    pub fn filter_context<'a>(
+        &'a self,
+        filter: &'a Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> Box<dyn FilterContext + 'a> {
+        self.index.filter_context(filter, hw_counter)
+    }
+
+    pub fn payload_blocks(
+        &self,
+        field: PayloadKeyTypeRef,
+        threshold: usize,
+    ) -> Box<dyn Iterator<Item = PayloadBlockCondition> + '_> {
+        self.index.payload_blocks(field, threshold)
+    }
+}
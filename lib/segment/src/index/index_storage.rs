@@ -0,0 +1,188 @@
+use std::fmt::Debug;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use io::file_operations::atomic_save;
+
+use crate::common::operation_error::{OperationError, OperationResult};
+use crate::index::payload_config::IndexCompression;
+
+/// Magic bytes, which every zstd frame starts with
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Persistence backend of payload index metadata and composite indexes.
+///
+/// Entries are opaque blobs addressed by name. Field index data itself is memory mapped or kept
+/// in gridstore, and stays on the local filesystem regardless of the backend.
+pub trait IndexStorage: Debug + Send + Sync {
+    /// Store `bytes` under `name`, replacing the previous value atomically
+    fn put(&self, name: &str, bytes: &[u8]) -> OperationResult<()>;
+
+    /// Read value stored under `name`, `None` if there is none
+    fn get(&self, name: &str) -> OperationResult<Option<Vec<u8>>>;
+
+    /// Remove value stored under `name`, no-op if there is none
+    fn delete(&self, name: &str) -> OperationResult<()>;
+
+    /// Names of all stored values
+    fn list(&self) -> OperationResult<Vec<String>>;
+}
+
+/// Compress `bytes` of an entry with the `compression`, if any
+pub fn compress_entry(
+    bytes: Vec<u8>,
+    compression: Option<IndexCompression>,
+) -> OperationResult<Vec<u8>> {
+    match compression {
+        Some(IndexCompression { level }) => Ok(zstd::encode_all(bytes.as_slice(), level)?),
+        None => Ok(bytes),
+    }
+}
+
+/// Decompress `bytes` of an entry, if they are compressed.
+///
+/// Compressed entries are detected by the zstd magic bytes, so entries written without
+/// compression are returned as is.
+pub fn decompress_entry(bytes: Vec<u8>) -> OperationResult<Vec<u8>> {
+    if bytes.starts_with(&ZSTD_MAGIC) {
+        Ok(zstd::decode_all(bytes.as_slice())?)
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Default [`IndexStorage`], storing each entry as a file in the payload index directory
+#[derive(Debug, Clone)]
+pub struct FsIndexStorage {
+    path: PathBuf,
+    /// Fail writes after this many bytes, as if the process was interrupted
+    #[cfg(test)]
+    interrupt_after: Option<usize>,
+}
+
+impl FsIndexStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            #[cfg(test)]
+            interrupt_after: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn entry_path(&self, name: &str) -> OperationResult<PathBuf> {
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(OperationError::service_error(format!(
+                "Invalid payload index storage entry name: {name:?}"
+            )));
+        }
+        Ok(self.path.join(name))
+    }
+}
+
+impl IndexStorage for FsIndexStorage {
+    fn put(&self, name: &str, bytes: &[u8]) -> OperationResult<()> {
+        let path = self.entry_path(name)?;
+        atomic_save(&path, |writer| {
+            #[cfg(test)]
+            if let Some(written) = self.interrupt_after {
+                writer.write_all(&bytes[..written.min(bytes.len())])?;
+                writer.flush()?;
+                return Err(std::io::Error::other("interrupted"));
+            }
+            writer.write_all(bytes)
+        })?;
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> OperationResult<Option<Vec<u8>>> {
+        let path = self.entry_path(name)?;
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn delete(&self, name: &str) -> OperationResult<()> {
+        let path = self.entry_path(name)?;
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn list(&self) -> OperationResult<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                names.push(name.to_string());
+            }
+        }
+        names.sort_unstable();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::Builder;
+
+    use super::*;
+
+    #[test]
+    fn test_fs_index_storage() {
+        let dir = Builder::new().prefix("index_storage").tempdir().unwrap();
+        let storage = FsIndexStorage::new(dir.path());
+
+        assert_eq!(storage.get("config.json").unwrap(), None);
+
+        storage.put("config.json", b"{}").unwrap();
+        storage.put("other", b"data").unwrap();
+        assert_eq!(
+            storage.get("config.json").unwrap().as_deref(),
+            Some(&b"{}"[..])
+        );
+        assert_eq!(fs::read(dir.path().join("config.json")).unwrap(), b"{}");
+        assert_eq!(storage.list().unwrap(), vec!["config.json", "other"]);
+
+        storage.delete("other").unwrap();
+        storage.delete("other").unwrap();
+        assert_eq!(storage.list().unwrap(), vec!["config.json"]);
+
+        assert!(storage.put("../escape", b"").is_err());
+    }
+
+    #[test]
+    fn test_fs_index_storage_interrupted_put() {
+        let dir = Builder::new().prefix("index_storage").tempdir().unwrap();
+        let storage = FsIndexStorage::new(dir.path());
+        storage.put("config.json", b"{\"indices\":{}}").unwrap();
+
+        let interrupted = FsIndexStorage {
+            interrupt_after: Some(5),
+            ..storage.clone()
+        };
+        assert!(
+            interrupted
+                .put("config.json", b"{\"indices\":{\"a\":{}}}")
+                .is_err()
+        );
+
+        // Previous value is intact and no temporary file is left behind
+        assert_eq!(
+            storage.get("config.json").unwrap().as_deref(),
+            Some(&b"{\"indices\":{}}"[..])
+        );
+        assert_eq!(storage.list().unwrap(), vec!["config.json"]);
+    }
+}
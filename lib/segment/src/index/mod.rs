@@ -1,16 +1,24 @@
 pub mod field_index;
 pub mod hnsw_index;
+pub mod hypothetical_index;
+pub mod index_health;
+pub mod index_read_guard;
+pub mod index_storage;
 mod key_encoding;
 pub mod payload_config;
 mod payload_index_base;
 pub mod plain_payload_index;
 pub mod plain_vector_index;
 pub mod query_estimator;
+pub mod query_explanation;
 pub mod query_optimization;
 mod sample_estimation;
 pub mod sparse_index;
 mod struct_filter_context;
 pub mod struct_payload_index;
+pub mod struct_payload_index_builder;
+pub mod value_transform;
+mod values_count_histogram;
 pub mod vector_index_base;
 mod vector_index_search_common;
 mod visited_pool;
@@ -6,7 +6,8 @@ use io::file_operations::{atomic_save_json, read_json};
 use serde::{Deserialize, Serialize};
 
 use crate::common::operation_error::OperationResult;
-use crate::types::{PayloadFieldSchema, PayloadKeyType};
+use crate::index::value_transform::ValueTransform;
+use crate::types::{PayloadFieldSchema, PayloadKeyType, PayloadSchemaType};
 
 pub const PAYLOAD_INDEX_CONFIG_FILE: &str = "config.json";
 
@@ -23,6 +24,38 @@ pub struct PayloadConfig {
     #[cfg(feature = "rocksdb")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub skip_rocksdb: Option<bool>,
+
+    /// Transforms applied to values of a field, both when indexing and when querying
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub value_transforms: HashMap<PayloadKeyType, Vec<ValueTransform>>,
+
+    /// Fields of composite indexes, each indexed by the combination of values of its fields
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub composite_indexes: Vec<Vec<PayloadKeyType>>,
+
+    /// Fraction of available points, above which the expected number of matches of a filter
+    /// makes a full scan preferred over selecting points with primary clauses.
+    /// If not set, primary clauses are always used when available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_scan_threshold: Option<f64>,
+
+    /// Compression of index files, which are persisted as a whole, like composite indexes.
+    /// Memory mapped and gridstore field index files are never compressed.
+    /// If not set, files are written uncompressed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<IndexCompression>,
+
+    /// If true, payloads with values of other types than the index of their field are rejected
+    /// on ingestion, instead of being stored without indexing these values.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub validate_payload_types: bool,
+}
+
+/// Zstd compression of index files
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IndexCompression {
+    /// Zstd compression level, higher levels compress better, but slower
+    pub level: i32,
 }
 
 impl PayloadConfig {
@@ -37,6 +70,14 @@ impl PayloadConfig {
     pub fn save(&self, path: &Path) -> OperationResult<()> {
         Ok(atomic_save_json(path, self)?)
     }
+
+    /// Value transforms configured for the field, empty if none
+    pub fn value_transforms(&self, field: &PayloadKeyType) -> &[ValueTransform] {
+        self.value_transforms
+            .get(field)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
 }
 
 /// Map of indexed fields with their schema and type
@@ -173,6 +214,29 @@ pub enum PayloadIndexType {
     NullIndex,
 }
 
+impl PayloadIndexType {
+    /// Payload type served by this kind of index.
+    ///
+    /// Returns `None` for the null index, as it is built for fields of any type.
+    pub fn schema_type(&self) -> Option<PayloadSchemaType> {
+        match self {
+            PayloadIndexType::IntIndex | PayloadIndexType::IntMapIndex => {
+                Some(PayloadSchemaType::Integer)
+            }
+            PayloadIndexType::DatetimeIndex => Some(PayloadSchemaType::Datetime),
+            PayloadIndexType::KeywordIndex => Some(PayloadSchemaType::Keyword),
+            PayloadIndexType::FloatIndex => Some(PayloadSchemaType::Float),
+            PayloadIndexType::GeoIndex => Some(PayloadSchemaType::Geo),
+            PayloadIndexType::FullTextIndex => Some(PayloadSchemaType::Text),
+            PayloadIndexType::BoolIndex => Some(PayloadSchemaType::Bool),
+            PayloadIndexType::UuidIndex | PayloadIndexType::UuidMapIndex => {
+                Some(PayloadSchemaType::Uuid)
+            }
+            PayloadIndexType::NullIndex => None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct FullPayloadIndexType {
     pub index_type: PayloadIndexType,
@@ -239,4 +303,35 @@ mod test {
             old_config
         );
     }
+
+    #[test]
+    fn test_bare_type_and_params_compatibility() {
+        // Fields indexed by bare schema type and by schema params can be loaded side by side,
+        // and are stored back in the format they were loaded from.
+
+        let config = r#"{"indexed_fields":{"a":"keyword","b":{"type":"text","tokenizer":"word","lowercase":true}}}"#;
+        let payload_config: PayloadConfig = serde_json::from_str(config).unwrap();
+
+        let schema_a = &payload_config
+            .indices
+            .get(&JsonPath::from_str("a").unwrap())
+            .unwrap()
+            .schema;
+        assert_eq!(
+            schema_a,
+            &PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)
+        );
+
+        let schema_b = &payload_config
+            .indices
+            .get(&JsonPath::from_str("b").unwrap())
+            .unwrap()
+            .schema;
+        assert!(matches!(schema_b, PayloadFieldSchema::FieldParams(_)));
+        assert_eq!(schema_b.expand().kind(), PayloadSchemaType::Text);
+
+        let stored: Value = serde_json::to_value(&payload_config).unwrap();
+        let original: Value = serde_json::from_str(config).unwrap();
+        assert_eq!(stored["indexed_fields"], original["indexed_fields"]);
+    }
 }
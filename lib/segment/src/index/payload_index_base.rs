@@ -87,6 +87,12 @@ pub trait PayloadIndex {
         hw_counter: &HardwareCounterCell,
     ) -> Vec<PointOffsetType>;
 
+    /// Return number of points, which satisfy filtering criteria
+    ///
+    /// Unlike [`Self::estimate_cardinality`], the count is always exact. Matched point ids are
+    /// counted as they are found, without collecting them as in [`Self::query_points`].
+    fn count_points(&self, query: &Filter, hw_counter: &HardwareCounterCell) -> usize;
+
     /// Return number of points, indexed by this field
     fn indexed_points(&self, field: PayloadKeyTypeRef) -> usize;
 
@@ -105,6 +111,10 @@ pub trait PayloadIndex {
     ) -> Box<dyn Iterator<Item = PayloadBlockCondition> + '_>;
 
     /// Overwrite payload for point_id. If payload already exists, replace it.
+    ///
+    /// Values of other types than the index of their field are stored, but not indexed, unless
+    /// validation is enabled, see
+    /// [`StructPayloadIndex::validate_payload`](super::struct_payload_index::StructPayloadIndex::validate_payload).
     fn overwrite_payload(
         &mut self,
         point_id: PointOffsetType,
@@ -113,6 +123,8 @@ pub trait PayloadIndex {
     ) -> OperationResult<()>;
 
     /// Assign payload to a concrete point with a concrete payload value
+    ///
+    /// Validated against types of field indexes, same as [`Self::overwrite_payload`].
     fn set_payload(
         &mut self,
         point_id: PointOffsetType,
@@ -172,6 +172,15 @@ impl PayloadIndex for PlainPayloadIndex {
             .collect()
     }
 
+    fn count_points(&self, query: &Filter, hw_counter: &HardwareCounterCell) -> usize {
+        let filter_context = self.filter_context(query, hw_counter);
+        self.id_tracker
+            .borrow()
+            .iter_ids()
+            .filter(|id| filter_context.check(*id))
+            .count()
+    }
+
     fn indexed_points(&self, _field: PayloadKeyTypeRef) -> usize {
         0 // No points are indexed in the plain index
     }
@@ -3,13 +3,51 @@
 //! Filter query is used e.g. for determining how would be faster to process the query:
 //! - use vector index or payload index first
 
+use std::cell::RefCell;
 use std::cmp::{max, min};
+use std::collections::HashMap;
 
 use itertools::Itertools;
 
 use crate::index::field_index::{CardinalityEstimation, PrimaryCondition};
 use crate::types::{Condition, Filter, MinShould};
 
+/// Cardinality estimations of conditions, computed during a single query.
+///
+/// Allows to reuse estimations of the query for optimizing the filter, instead of estimating
+/// every condition again. Conditions are identified by their address, so the cache must only be
+/// used for a single filter and should be dropped together with the query.
+/// Short lifetime also makes sure that changes of the index can't make estimations stale.
+#[derive(Default)]
+pub struct EstimationCache {
+    estimations: RefCell<HashMap<usize, CardinalityEstimation>>,
+}
+
+impl EstimationCache {
+    pub fn get_or_estimate<F>(&self, condition: &Condition, estimate: F) -> CardinalityEstimation
+    where
+        F: FnOnce(&Condition) -> CardinalityEstimation,
+    {
+        let key = std::ptr::from_ref(condition) as usize;
+        if let Some(estimation) = self.estimations.borrow().get(&key) {
+            return estimation.clone();
+        }
+        let estimation = estimate(condition);
+        self.estimations
+            .borrow_mut()
+            .insert(key, estimation.clone());
+        estimation
+    }
+
+    pub fn len(&self) -> usize {
+        self.estimations.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.estimations.borrow().is_empty()
+    }
+}
+
 /// Re-estimate cardinality based on number of available vectors
 /// Assuming that deleted vectors are not correlated with the filter
 ///
@@ -150,10 +188,19 @@ pub fn combine_must_estimations(
 
     let exp_estimation = (exp_estimation_prob * (total as f64)).round() as usize;
 
+    // Primary clauses are used to select points, other conditions are only checked on them.
+    // Clauses which can select points are always preferred over the ones which would require
+    // a full scan, then the most selective ones, and finally the most precisely estimated ones.
     let clauses = estimations
         .iter()
         .filter(|x| !x.primary_clauses.is_empty())
-        .min_by_key(|x| x.exp)
+        .min_by_key(|x| {
+            let can_select_points = x
+                .primary_clauses
+                .iter()
+                .all(PrimaryCondition::can_select_points);
+            (!can_select_points, x.exp, x.max.saturating_sub(x.min))
+        })
         .map(|x| x.primary_clauses.clone())
         .unwrap_or_default();
 
@@ -298,6 +345,7 @@ mod tests {
             key: JsonPath::new(key),
             r#match: None,
             range: None,
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             values_count: None,
@@ -312,6 +360,9 @@ mod tests {
             Condition::Filter(_) => panic!("unexpected Filter"),
             Condition::Nested(_) => panic!("unexpected Nested"),
             Condition::CustomIdChecker(_) => panic!("unexpected CustomIdChecker"),
+            Condition::FieldVsField(_) => CardinalityEstimation::unknown(TOTAL),
+            Condition::VersionAtMost(_) => CardinalityEstimation::unknown(TOTAL),
+            Condition::HasIdRange(_) => CardinalityEstimation::unknown(TOTAL),
             Condition::Field(field) => match field.key.to_string().as_str() {
                 "color" => CardinalityEstimation {
                     primary_clauses: vec![PrimaryCondition::Condition(Box::new(field.clone()))],
@@ -584,6 +635,106 @@ mod tests {
         eprintln!("res = {res:#?}");
     }
 
+    #[test]
+    fn test_must_primary_clause_selection() {
+        let field_estimation = |key: &str, min, exp, max| CardinalityEstimation {
+            primary_clauses: vec![PrimaryCondition::Condition(Box::new(
+                FieldCondition::new_is_empty(JsonPath::new(key), false),
+            ))],
+            min,
+            exp,
+            max,
+        };
+        let has_vector = CardinalityEstimation::exact(10)
+            .with_primary_clause(PrimaryCondition::HasVector("image".into()));
+
+        // Indexed clause is preferred over a more selective one, which can't select points
+        let estimation = combine_must_estimations(
+            &[
+                has_vector,
+                field_estimation("color", 50, 50, 50),
+                CardinalityEstimation::unknown(TOTAL),
+            ],
+            TOTAL,
+        );
+        assert_eq!(
+            estimation.primary_clauses,
+            field_estimation("color", 50, 50, 50).primary_clauses,
+        );
+
+        // Exact estimation is preferred over an equally selective approximate one
+        let estimation = combine_must_estimations(
+            &[
+                field_estimation("color", 0, 50, 200),
+                field_estimation("size", 50, 50, 50),
+            ],
+            TOTAL,
+        );
+        assert_eq!(
+            estimation.primary_clauses,
+            field_estimation("size", 50, 50, 50).primary_clauses,
+        );
+    }
+
+    #[test]
+    fn test_must_has_id_with_field_condition() {
+        let has_id = Condition::HasId(HasIdCondition {
+            has_id: [1, 2, 3, 4, 5].into_iter().map(|x| x.into()).collect(),
+        });
+        // All ids might match `color` as well, estimation must not exceed the smaller side
+        let query = Filter::new_must(Condition::Filter(Filter {
+            should: None,
+            min_should: None,
+            must: Some(vec![has_id.clone(), test_condition("color")]),
+            must_not: None,
+        }));
+
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+        assert!(estimation.exp <= 5);
+        assert_eq!(estimation.max, 5);
+        assert_eq!(
+            estimation.primary_clauses,
+            test_estimator(&has_id).primary_clauses,
+        );
+    }
+
+    #[test]
+    fn test_three_level_nested_filter() {
+        // must: [should: [must: [color, price], size]], must_not: [size]
+        let inner = Filter::new_must(Condition::Filter(Filter {
+            should: None,
+            min_should: None,
+            must: Some(vec![test_condition("color"), test_condition("price")]),
+            must_not: None,
+        }));
+        let query = Filter {
+            should: None,
+            min_should: None,
+            must: Some(vec![Condition::Filter(Filter {
+                should: Some(vec![Condition::Filter(inner), test_condition("size")]),
+                min_should: None,
+                must: None,
+                must_not: None,
+            })]),
+            must_not: Some(vec![test_condition("size")]),
+        };
+
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+
+        // Inner AND is bounded by its most selective clause, which is selected as primary,
+        // OR of it with `size` adds up, and `must_not` is applied as a complement
+        assert_eq!(estimation.min, 0);
+        assert_eq!(estimation.exp, 93);
+        assert_eq!(estimation.max, 120);
+        assert_eq!(
+            estimation.primary_clauses,
+            [test_condition("price"), test_condition("size")]
+                .iter()
+                .flat_map(|condition| test_estimator(condition).primary_clauses)
+                .collect_vec(),
+        );
+    }
+
     #[test]
     fn test_adjust_to_available_vectors() {
         let estimation = CardinalityEstimation {
@@ -599,4 +750,37 @@ mod tests {
         assert_eq!(new_estimation.exp, 16);
         assert_eq!(new_estimation.max, 50);
     }
+
+    #[test]
+    fn test_estimation_cache_reuses_estimations() {
+        let query = Filter {
+            should: None,
+            min_should: None,
+            must: Some(vec![test_condition("color"), test_condition("size")]),
+            must_not: Some(vec![test_condition("price")]),
+        };
+
+        let calls = std::cell::Cell::new(0);
+        let estimation_cache = EstimationCache::default();
+        let estimator = |condition: &Condition| {
+            estimation_cache.get_or_estimate(condition, |condition| {
+                calls.set(calls.get() + 1);
+                test_estimator(condition)
+            })
+        };
+
+        let estimation = estimate_filter(&estimator, &query, TOTAL);
+        assert_eq!(calls.get(), 3);
+        assert_eq!(estimation_cache.len(), 3);
+
+        // Same filter is estimated again without calling the estimator
+        let cached_estimation = estimate_filter(&estimator, &query, TOTAL);
+        assert_eq!(calls.get(), 3);
+        assert_eq!(cached_estimation, estimation);
+
+        // Equal condition of another filter is a different entry
+        let other_query = Filter::new_must(test_condition("color"));
+        estimate_filter(&estimator, &other_query, TOTAL);
+        assert_eq!(calls.get(), 4);
+    }
 }
@@ -0,0 +1,389 @@
+use common::counter::hardware_counter::HardwareCounterCell;
+use common::types::PointOffsetType;
+
+use crate::index::PayloadIndex;
+use crate::index::field_index::{CardinalityEstimation, PrimaryCondition};
+use crate::index::query_estimator::EstimationCache;
+use crate::index::struct_payload_index::StructPayloadIndex;
+use crate::types::{Condition, Filter};
+
+/// How [`PayloadIndex::query_points`] selects candidate points for a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryStrategy {
+    /// Primary clauses can't be used, or are not expected to be cheaper than a full scan with
+    /// the configured `full_scan_threshold`. Every point is checked against the filter
+    FullScan,
+    /// Candidates are selected with primary clauses
+    PrimaryClauses {
+        /// Whether candidates have to be checked against the filter, which is not required if
+        /// all conditions of the filter are primary clauses
+        post_filter: bool,
+    },
+}
+
+/// Plan of a query, as it would be executed by [`PayloadIndex::query_points`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryExplanation {
+    /// Cardinality estimation of the query, including selected primary clauses
+    pub estimation: CardinalityEstimation,
+    pub strategy: QueryStrategy,
+    /// Expected number of candidate points, before they are checked against the filter
+    pub estimated_candidates: usize,
+    /// Expected number of candidate points selected by each primary clause, in order of
+    /// `estimation.primary_clauses`. Empty for a full scan.
+    pub clause_candidates: Vec<usize>,
+}
+
+impl QueryExplanation {
+    /// Whether the query falls back to checking every point, as primary clauses can't be used
+    pub fn is_fallback(&self) -> bool {
+        self.strategy == QueryStrategy::FullScan
+    }
+}
+
+impl StructPayloadIndex {
+    /// Explain how the query would be executed, without checking or collecting any points.
+    pub fn explain_query(
+        &self,
+        query: &Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> QueryExplanation {
+        let estimation = self.estimate_cardinality(query, hw_counter);
+        self.explain_estimation(query, estimation, hw_counter)
+    }
+
+    /// Query points matching the filter, together with the explanation of how they were queried.
+    ///
+    /// The explanation is based on the same estimation, which is used for querying the points.
+    pub fn query_points_explain(
+        &self,
+        query: &Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> (Vec<PointOffsetType>, QueryExplanation) {
+        let estimation_cache = EstimationCache::default();
+        let estimation = self.estimate_cardinality_with_cache(query, &estimation_cache, hw_counter);
+        let points = {
+            let id_tracker = self.id_tracker.borrow();
+            self.iter_filtered_points_until(
+                query,
+                &*id_tracker,
+                &estimation,
+                estimation_cache,
+                hw_counter,
+                || false,
+            )
+            .collect()
+        };
+        (
+            points,
+            self.explain_estimation(query, estimation, hw_counter),
+        )
+    }
+
+    fn explain_estimation(
+        &self,
+        query: &Filter,
+        estimation: CardinalityEstimation,
+        hw_counter: &HardwareCounterCell,
+    ) -> QueryExplanation {
+        let available_points = self.available_point_count();
+
+        // Same selection logic as in `iter_filtered_points`
+        let can_use_primary_clauses = !estimation.primary_clauses.is_empty()
+            && estimation
+                .primary_clauses
+                .iter()
+                .all(|clause| self.can_query_field(clause))
+            && !self.prefers_full_scan(&estimation, available_points);
+
+        if !can_use_primary_clauses {
+            return QueryExplanation {
+                estimation,
+                strategy: QueryStrategy::FullScan,
+                estimated_candidates: available_points,
+                clause_candidates: Vec::new(),
+            };
+        }
+
+        let post_filter = !query
+            .iter_conditions()
+            .all(|condition| estimation.is_primary(condition));
+
+        let clause_candidates: Vec<_> = estimation
+            .primary_clauses
+            .iter()
+            .map(|clause| self.primary_clause_candidates(clause, hw_counter))
+            .collect();
+
+        // Primary clauses may select the same points, so the sum is an upper bound
+        let estimated_candidates = clause_candidates
+            .iter()
+            .sum::<usize>()
+            .min(available_points);
+
+        QueryExplanation {
+            estimation,
+            strategy: QueryStrategy::PrimaryClauses { post_filter },
+            estimated_candidates,
+            clause_candidates,
+        }
+    }
+
+    /// Whether points matching the primary clause can be iterated directly
+    fn can_query_field(&self, clause: &PrimaryCondition) -> bool {
+        match clause {
+            PrimaryCondition::Condition(field_condition) => {
+                self.field_indexes.contains_key(&field_condition.key)
+            }
+            PrimaryCondition::Ids(_)
+            | PrimaryCondition::IdRange(_)
+            | PrimaryCondition::VersionAtMost(_)
+            | PrimaryCondition::Composite(_) => clause.can_select_points(),
+            // Served by a scan over the vector storage
+            PrimaryCondition::HasVector(_) => true,
+        }
+    }
+
+    /// Expected number of points selected by the primary clause
+    fn primary_clause_candidates(
+        &self,
+        clause: &PrimaryCondition,
+        hw_counter: &HardwareCounterCell,
+    ) -> usize {
+        match clause {
+            PrimaryCondition::Condition(field_condition) => {
+                let condition = Condition::Field(field_condition.as_ref().clone());
+                self.condition_cardinality(&condition, None, hw_counter).exp
+            }
+            PrimaryCondition::Ids(ids) => ids.resolved_point_offsets.len(),
+            PrimaryCondition::IdRange(id_range) => id_range.points_count,
+            PrimaryCondition::VersionAtMost(versions) => versions.points_count,
+            PrimaryCondition::Composite(composite) => composite.resolved_point_offsets.len(),
+            PrimaryCondition::HasVector(vector_name) => self.vector_points_count(vector_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tempfile::Builder;
+
+    use super::*;
+    use crate::data_types::vectors::only_default_vector;
+    use crate::entry::SegmentEntry;
+    use crate::json_path::JsonPath;
+    use crate::segment_constructor::simple_segment_constructor::build_simple_segment;
+    use crate::types::{
+        Distance, FieldCondition, Payload, PayloadFieldSchema, PayloadSchemaType, Range,
+    };
+
+    #[test]
+    fn test_explain_query() {
+        let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+        let hw_counter = HardwareCounterCell::new();
+        let color = JsonPath::from_str("color").unwrap();
+        let price = JsonPath::from_str("price").unwrap();
+
+        let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+        for idx in 0..100u64 {
+            segment
+                .upsert_point(
+                    idx,
+                    idx.into(),
+                    only_default_vector(&[1.0, 1.0]),
+                    &hw_counter,
+                )
+                .unwrap();
+            let color = if idx % 10 == 0 { "red" } else { "blue" };
+            let payload: Payload =
+                serde_json::from_value(serde_json::json!({ "color": color, "price": idx }))
+                    .unwrap();
+            segment
+                .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+                .unwrap();
+        }
+        segment
+            .create_field_index(
+                100,
+                &color,
+                Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+                &hw_counter,
+            )
+            .unwrap();
+
+        let red = Condition::Field(FieldCondition::new_match(
+            color.clone(),
+            "red".to_string().into(),
+        ));
+        let cheap = Condition::Field(FieldCondition::new_range(
+            price,
+            Range {
+                lt: Some(50.0),
+                ..Default::default()
+            },
+        ));
+        let payload_index = segment.payload_index.borrow();
+
+        // Served by the index alone
+        let filter = Filter::new_must(red.clone());
+        let explanation = payload_index.explain_query(&filter, &hw_counter);
+        assert_eq!(
+            explanation.strategy,
+            QueryStrategy::PrimaryClauses { post_filter: false }
+        );
+        assert_eq!(explanation.estimated_candidates, 10);
+        assert_eq!(explanation.clause_candidates, vec![10]);
+        assert_eq!(explanation.estimation.exp, 10);
+
+        // Unindexed condition is checked on candidates
+        let filter = Filter {
+            must: Some(vec![red, cheap.clone()]),
+            ..Default::default()
+        };
+        let explanation = payload_index.explain_query(&filter, &hw_counter);
+        assert_eq!(
+            explanation.strategy,
+            QueryStrategy::PrimaryClauses { post_filter: true }
+        );
+        assert_eq!(explanation.estimated_candidates, 10);
+        assert!(explanation.estimation.exp <= 10);
+
+        // No index can be used
+        let filter = Filter::new_must(cheap);
+        let explanation = payload_index.explain_query(&filter, &hw_counter);
+        assert_eq!(explanation.strategy, QueryStrategy::FullScan);
+        assert!(explanation.is_fallback());
+        assert_eq!(explanation.estimated_candidates, 100);
+        assert!(explanation.clause_candidates.is_empty());
+    }
+
+    #[test]
+    fn test_query_points_explain() {
+        let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+        let hw_counter = HardwareCounterCell::new();
+        let color = JsonPath::from_str("color").unwrap();
+        let price = JsonPath::from_str("price").unwrap();
+
+        let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+        for idx in 0..50u64 {
+            segment
+                .upsert_point(
+                    idx,
+                    idx.into(),
+                    only_default_vector(&[1.0, 1.0]),
+                    &hw_counter,
+                )
+                .unwrap();
+            let color = if idx % 5 == 0 { "red" } else { "blue" };
+            let payload: Payload =
+                serde_json::from_value(serde_json::json!({ "color": color, "price": idx }))
+                    .unwrap();
+            segment
+                .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+                .unwrap();
+        }
+        segment
+            .create_field_index(
+                50,
+                &color,
+                Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+                &hw_counter,
+            )
+            .unwrap();
+        let payload_index = segment.payload_index.borrow();
+
+        let red = Condition::Field(FieldCondition::new_match(color, "red".to_string().into()));
+        let cheap = Condition::Field(FieldCondition::new_range(
+            price,
+            Range {
+                lt: Some(20.0),
+                ..Default::default()
+            },
+        ));
+
+        let filter = Filter {
+            must: Some(vec![red, cheap.clone()]),
+            ..Default::default()
+        };
+        let (points, explanation) = payload_index.query_points_explain(&filter, &hw_counter);
+        assert_eq!(points, payload_index.query_points(&filter, &hw_counter));
+        assert_eq!(points, vec![0, 5, 10, 15]);
+        assert_eq!(
+            explanation.strategy,
+            QueryStrategy::PrimaryClauses { post_filter: true }
+        );
+        assert!(!explanation.is_fallback());
+        assert_eq!(explanation.clause_candidates, vec![10]);
+
+        let filter = Filter::new_must(cheap);
+        let (points, explanation) = payload_index.query_points_explain(&filter, &hw_counter);
+        assert_eq!(points.len(), 20);
+        assert!(explanation.is_fallback());
+        assert!(explanation.clause_candidates.is_empty());
+    }
+
+    #[test]
+    fn test_full_scan_threshold() {
+        let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+        let hw_counter = HardwareCounterCell::new();
+        let color = JsonPath::from_str("color").unwrap();
+
+        let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+        for idx in 0..50u64 {
+            segment
+                .upsert_point(
+                    idx,
+                    idx.into(),
+                    only_default_vector(&[1.0, 1.0]),
+                    &hw_counter,
+                )
+                .unwrap();
+            let color = if idx % 10 == 0 { "red" } else { "blue" };
+            let payload: Payload =
+                serde_json::from_value(serde_json::json!({ "color": color })).unwrap();
+            segment
+                .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+                .unwrap();
+        }
+        segment
+            .create_field_index(
+                50,
+                &color,
+                Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+                &hw_counter,
+            )
+            .unwrap();
+
+        let blue = Filter::new_must(Condition::Field(FieldCondition::new_match(
+            color.clone(),
+            "blue".to_string().into(),
+        )));
+        let red = Filter::new_must(Condition::Field(FieldCondition::new_match(
+            color,
+            "red".to_string().into(),
+        )));
+
+        let (blue_points, explanation) = segment
+            .payload_index
+            .borrow()
+            .query_points_explain(&blue, &hw_counter);
+        assert!(!explanation.is_fallback());
+
+        let mut payload_index = segment.payload_index.borrow_mut();
+        assert!(payload_index.set_full_scan_threshold(Some(0.0)).is_err());
+        assert!(payload_index.set_full_scan_threshold(Some(1.5)).is_err());
+        payload_index.set_full_scan_threshold(Some(0.5)).unwrap();
+
+        // Most points match, so they are scanned instead of selected by the index
+        let (points, explanation) = payload_index.query_points_explain(&blue, &hw_counter);
+        assert_eq!(explanation.strategy, QueryStrategy::FullScan);
+        assert_eq!(points, blue_points);
+
+        // Selective filters still use the index
+        let (points, explanation) = payload_index.query_points_explain(&red, &hw_counter);
+        assert!(!explanation.is_fallback());
+        assert_eq!(points, vec![0, 10, 20, 30, 40]);
+    }
+}
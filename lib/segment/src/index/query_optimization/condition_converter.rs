@@ -12,13 +12,15 @@ use crate::index::field_index::null_index::MutableNullIndex;
 use crate::index::query_optimization::optimized_filter::ConditionCheckerFn;
 use crate::index::query_optimization::payload_provider::PayloadProvider;
 use crate::index::struct_payload_index::StructPayloadIndex;
+use crate::index::value_transform::transform_field_condition;
 use crate::payload_storage::query_checker::{
-    check_field_condition, check_is_empty_condition, check_is_null_condition, check_payload,
-    select_nested_indexes,
+    check_is_empty_condition, check_is_null_condition, check_payload,
+    check_transformed_field_condition, select_nested_indexes,
 };
 use crate::types::{
     Condition, DateTimePayloadType, FieldCondition, FloatPayloadType, GeoBoundingBox, GeoPolygon,
-    GeoRadius, IntPayloadType, OwnedPayloadRef, PayloadContainer, Range, RangeInterface,
+    GeoRadius, IntPayloadType, OwnedPayloadRef, PayloadContainer, Range, RangeInterface, RangeMode,
+    ValuesCount,
 };
 use crate::vector_storage::VectorStorage;
 
@@ -34,26 +36,38 @@ impl StructPayloadIndex {
         let id_tracker = self.id_tracker.borrow();
         let field_indexes = &self.field_indexes;
         match condition {
-            Condition::Field(field_condition) => field_indexes
-                .get(&field_condition.key)
-                .and_then(|indexes| {
-                    indexes.iter().find_map(move |index| {
-                        let hw_acc = hw_counter.new_accumulator();
-                        field_condition_index(index, field_condition, hw_acc)
+            Condition::Field(field_condition) => {
+                let transforms = self.config().value_transforms(&field_condition.key);
+                let field_condition = transform_field_condition(transforms, field_condition);
+
+                field_indexes
+                    .get(&field_condition.key)
+                    .filter(|_| self.is_values_count_indexable(&field_condition))
+                    .and_then(|indexes| {
+                        indexes.iter().find_map(|index| {
+                            let hw_acc = hw_counter.new_accumulator();
+                            field_condition_index(index, &field_condition, hw_acc)
+                        })
                     })
-                })
-                .unwrap_or_else(|| {
-                    let hw = hw_counter.fork();
-                    Box::new(move |point_id| {
-                        payload_provider.with_payload(
-                            point_id,
-                            |payload| {
-                                check_field_condition(field_condition, &payload, field_indexes, &hw)
-                            },
-                            &hw,
-                        )
+                    .unwrap_or_else(|| {
+                        let hw = hw_counter.fork();
+                        Box::new(move |point_id| {
+                            payload_provider.with_payload(
+                                point_id,
+                                |payload| {
+                                    check_transformed_field_condition(
+                                        &field_condition,
+                                        &payload,
+                                        transforms,
+                                        field_indexes,
+                                        &hw,
+                                    )
+                                },
+                                &hw,
+                            )
+                        })
                     })
-                }),
+            }
             // Use dedicated null index for `is_empty` check if it is available
             // Otherwise we might use another index just to check if a field is not empty, if we
             // don't have an indexed value we must still check the payload to see if its empty
@@ -117,6 +131,16 @@ impl StructPayloadIndex {
                     .collect();
                 Box::new(move |point_id| segment_ids.contains(&point_id))
             }
+            Condition::HasIdRange(has_id_range) => {
+                let range = has_id_range.has_id_range;
+                let id_tracker = &self.id_tracker;
+                Box::new(move |point_id| {
+                    id_tracker
+                        .borrow()
+                        .external_id(point_id)
+                        .is_some_and(|id| range.contains(id))
+                })
+            }
             Condition::HasVector(has_vector) => {
                 if let Some(vector_storage) =
                     self.vector_storages.get(&has_vector.has_vector).cloned()
@@ -126,6 +150,27 @@ impl StructPayloadIndex {
                     Box::new(|_point_id| false)
                 }
             }
+            Condition::VersionAtMost(version_at_most) => {
+                let version_at_most = version_at_most.version_at_most;
+                let id_tracker = &self.id_tracker;
+                Box::new(move |point_id| {
+                    id_tracker
+                        .borrow()
+                        .internal_version(point_id)
+                        .is_some_and(|version| version <= version_at_most)
+                })
+            }
+            // Values of both fields are required, so payload has to be read for each point
+            Condition::FieldVsField(field_vs_field) => {
+                let hw = hw_counter.fork();
+                Box::new(move |point_id| {
+                    payload_provider.with_payload(
+                        point_id,
+                        |payload| field_vs_field.field_vs_field.check(&payload),
+                        &hw,
+                    )
+                })
+            }
             Condition::Nested(nested) => {
                 // Select indexes for nested fields. Trim nested part from key, so
                 // that nested condition can address fields without nested part.
@@ -194,6 +239,57 @@ impl StructPayloadIndex {
             Condition::Filter(_) => unreachable!(),
         }
     }
+
+    /// Whether [`Self::condition_converter`] checks the `condition` without reading payload.
+    ///
+    /// Mirrors the choice of checkers made by the converter, nested filters are checked
+    /// recursively.
+    pub fn is_condition_indexable(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Field(field_condition) => {
+                let transforms = self.config().value_transforms(&field_condition.key);
+                let field_condition = transform_field_condition(transforms, field_condition);
+                self.is_values_count_indexable(&field_condition)
+                    && self
+                        .field_indexes
+                        .get(&field_condition.key)
+                        .is_some_and(|indexes| {
+                            indexes.iter().any(|index| {
+                                let hw_acc = HwMeasurementAcc::disposable();
+                                field_condition_index(index, &field_condition, hw_acc).is_some()
+                            })
+                        })
+            }
+            Condition::IsEmpty(is_empty) => self
+                .field_indexes
+                .get(&is_empty.is_empty.key)
+                .is_some_and(|indexes| get_is_empty_indexes(indexes).0.is_some()),
+            Condition::IsNull(is_null) => {
+                self.field_indexes
+                    .get(&is_null.is_null.key)
+                    .is_some_and(|indexes| {
+                        indexes
+                            .iter()
+                            .any(|index| get_is_null_checker(index, true).is_some())
+                    })
+            }
+            Condition::HasId(_)
+            | Condition::HasIdRange(_)
+            | Condition::HasVector(_)
+            | Condition::VersionAtMost(_)
+            | Condition::CustomIdChecker(_) => true,
+            Condition::FieldVsField(_) | Condition::Nested(_) => false,
+            Condition::Filter(filter) => filter
+                .iter_conditions()
+                .all(|condition| self.is_condition_indexable(condition)),
+        }
+    }
+
+    /// Values counts of indexes skip values of other types, so `values_count` conditions are
+    /// only checked with indexes, if every value of the field is known to be indexed.
+    fn is_values_count_indexable(&self, field_condition: &FieldCondition) -> bool {
+        field_condition.values_count.is_none() || self.has_exact_values_count(&field_condition.key)
+    }
 }
 
 pub fn field_condition_index<'a>(
@@ -208,8 +304,10 @@ pub fn field_condition_index<'a>(
         } => get_match_checkers(index, cond_match.clone(), hw_acc),
 
         FieldCondition {
-            range: Some(cond), ..
-        } => get_range_checkers(index, cond.clone(), hw_acc),
+            range: Some(cond),
+            range_mode,
+            ..
+        } => get_range_checkers(index, cond.clone(), range_mode.unwrap_or_default(), hw_acc),
 
         FieldCondition {
             geo_radius: Some(geo_radius),
@@ -236,24 +334,42 @@ pub fn field_condition_index<'a>(
             ..
         } => get_is_null_checker(index, *is_null),
 
+        FieldCondition {
+            values_count: Some(values_count),
+            ..
+        } => get_values_count_checker(index, *values_count),
+
         FieldCondition {
             key: _,
             r#match: None,
             range: None,
+            range_mode: _,
             geo_radius: None,
             geo_bounding_box: None,
             geo_polygon: None,
-            // We can't use index for this condition, since some indices don't count values,
-            // like boolean index, where [true, true, true] is the same as [true]. Count should be 3 but they think is 1.
-            //
-            // TODO: Try to use the indices that actually support counting values.
-            values_count: _,
+            values_count: None,
             is_empty: None,
             is_null: None,
         } => None,
     }
 }
 
+/// Check number of values with an index, if the index counts all of them.
+///
+/// Some indexes don't count values, like boolean index, where `[true, true, true]` is the same
+/// as `[true]`. Such conditions have to be checked against payload.
+pub fn get_values_count_checker(
+    index: &FieldIndex,
+    values_count: ValuesCount,
+) -> Option<ConditionCheckerFn<'_>> {
+    if !index.counts_values() {
+        return None;
+    }
+    Some(Box::new(move |point_id: PointOffsetType| {
+        values_count.check_count(index.values_count(point_id))
+    }))
+}
+
 pub fn get_geo_polygon_checkers(
     index: &FieldIndex,
     geo_polygon: GeoPolygon,
@@ -331,17 +447,50 @@ pub fn get_geo_bounding_box_checkers(
 pub fn get_range_checkers(
     index: &FieldIndex,
     range: RangeInterface,
+    mode: RangeMode,
     hw_acc: HwMeasurementAcc,
 ) -> Option<ConditionCheckerFn<'_>> {
     match range {
-        RangeInterface::Float(range) => get_float_range_checkers(index, range, hw_acc),
-        RangeInterface::DateTime(range) => get_datetime_range_checkers(index, range, hw_acc),
+        RangeInterface::Float(range) => get_float_range_checkers(index, range, mode, hw_acc),
+        RangeInterface::DateTime(range) => get_datetime_range_checkers(index, range, mode, hw_acc),
+        RangeInterface::Keyword(range) => get_keyword_range_checkers(index, range, mode, hw_acc),
+    }
+}
+
+pub fn get_keyword_range_checkers(
+    index: &FieldIndex,
+    range: Range<String>,
+    mode: RangeMode,
+    hw_acc: HwMeasurementAcc,
+) -> Option<ConditionCheckerFn<'_>> {
+    match (index, mode) {
+        (FieldIndex::KeywordIndex(index), RangeMode::Any) => {
+            let hw_counter = hw_acc.get_counter_cell();
+            Some(Box::new(move |point_id: PointOffsetType| {
+                index.check_values_any(point_id, &hw_counter, |keyword| {
+                    range.check_keyword(keyword)
+                })
+            }))
+        }
+        // Only map index of keywords is ordered lexicographically
+        (FieldIndex::KeywordIndex(_), RangeMode::All)
+        | (FieldIndex::IntIndex(_), _)
+        | (FieldIndex::DatetimeIndex(_), _)
+        | (FieldIndex::IntMapIndex(_), _)
+        | (FieldIndex::FloatIndex(_), _)
+        | (FieldIndex::GeoIndex(_), _)
+        | (FieldIndex::FullTextIndex(_), _)
+        | (FieldIndex::BoolIndex(_), _)
+        | (FieldIndex::UuidIndex(_), _)
+        | (FieldIndex::UuidMapIndex(_), _)
+        | (FieldIndex::NullIndex(_), _) => None,
     }
 }
 
 pub fn get_float_range_checkers(
     index: &FieldIndex,
     range: Range<FloatPayloadType>,
+    mode: RangeMode,
     hw_acc: HwMeasurementAcc,
 ) -> Option<ConditionCheckerFn<'_>> {
     let hw_counter = hw_acc.get_counter_cell();
@@ -349,11 +498,21 @@ pub fn get_float_range_checkers(
         FieldIndex::IntIndex(num_index) => {
             let range = range.map(|f| f as IntPayloadType);
             Some(Box::new(move |point_id: PointOffsetType| {
-                num_index.check_values_any(point_id, |value| range.check_range(*value), &hw_counter)
+                num_index.check_values(
+                    point_id,
+                    mode,
+                    |value| range.check_range(*value),
+                    &hw_counter,
+                )
             }))
         }
         FieldIndex::FloatIndex(num_index) => Some(Box::new(move |point_id: PointOffsetType| {
-            num_index.check_values_any(point_id, |value| range.check_range(*value), &hw_counter)
+            num_index.check_values(
+                point_id,
+                mode,
+                |value| range.check_range(*value),
+                &hw_counter,
+            )
         })),
         FieldIndex::BoolIndex(_)
         | FieldIndex::DatetimeIndex(_)
@@ -370,6 +529,7 @@ pub fn get_float_range_checkers(
 pub fn get_datetime_range_checkers(
     index: &FieldIndex,
     range: Range<DateTimePayloadType>,
+    mode: RangeMode,
     hw_acc: HwMeasurementAcc,
 ) -> Option<ConditionCheckerFn<'_>> {
     match index {
@@ -377,7 +537,12 @@ pub fn get_datetime_range_checkers(
             let range = range.map(|dt| dt.timestamp());
             let hw_counter = hw_acc.get_counter_cell();
             Some(Box::new(move |point_id: PointOffsetType| {
-                num_index.check_values_any(point_id, |value| range.check_range(*value), &hw_counter)
+                num_index.check_values(
+                    point_id,
+                    mode,
+                    |value| range.check_range(*value),
+                    &hw_counter,
+                )
             }))
         }
         FieldIndex::BoolIndex(_)
@@ -7,8 +7,8 @@ use crate::index::field_index::FieldIndex;
 use crate::index::query_optimization::optimized_filter::ConditionCheckerFn;
 use crate::payload_storage::condition_checker::INDEXSET_ITER_THRESHOLD;
 use crate::types::{
-    AnyVariants, Match, MatchAny, MatchExcept, MatchPhrase, MatchText, MatchTextAny, MatchValue,
-    ValueVariants,
+    AnyVariants, Match, MatchAny, MatchExcept, MatchPhrase, MatchPrefix, MatchText, MatchTextAny,
+    MatchValue, ValueVariants,
 };
 
 pub fn get_match_checkers(
@@ -27,11 +27,37 @@ pub fn get_match_checkers(
         Match::Phrase(MatchPhrase { phrase }) => {
             get_match_text_checker(phrase, TextQueryType::Phrase, index, hw_acc)
         }
+        Match::Prefix(MatchPrefix { prefix }) => get_match_prefix_checker(prefix, index, hw_acc),
         Match::Any(MatchAny { any }) => get_match_any_checker(any, index, hw_acc),
         Match::Except(MatchExcept { except }) => get_match_except_checker(except, index, hw_acc),
     }
 }
 
+fn get_match_prefix_checker(
+    prefix: String,
+    index: &FieldIndex,
+    hw_acc: HwMeasurementAcc,
+) -> Option<ConditionCheckerFn<'_>> {
+    match index {
+        FieldIndex::KeywordIndex(index) => {
+            let hw_counter = hw_acc.get_counter_cell();
+            Some(Box::new(move |point_id: PointOffsetType| {
+                index.check_values_any(point_id, &hw_counter, |k| k.starts_with(prefix.as_str()))
+            }))
+        }
+        FieldIndex::IntIndex(_)
+        | FieldIndex::DatetimeIndex(_)
+        | FieldIndex::IntMapIndex(_)
+        | FieldIndex::FloatIndex(_)
+        | FieldIndex::GeoIndex(_)
+        | FieldIndex::FullTextIndex(_)
+        | FieldIndex::BoolIndex(_)
+        | FieldIndex::UuidIndex(_)
+        | FieldIndex::UuidMapIndex(_)
+        | FieldIndex::NullIndex(_) => None,
+    }
+}
+
 fn get_match_value_checker(
     value_variant: ValueVariants,
     index: &FieldIndex,
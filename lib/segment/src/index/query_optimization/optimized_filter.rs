@@ -79,3 +79,135 @@ fn check_must_not(must: &Option<Vec<OptimizedCondition>>, point_id: PointOffsetT
         Some(conditions) => conditions.iter().all(check),
     }
 }
+
+/// Batched version of [`check_optimized_filter`].
+///
+/// Points marked in `active` are checked against the filter, and stay marked only if they match.
+/// The filter tree is walked once per batch, and each condition is only evaluated for the points
+/// which are still undecided.
+pub fn check_optimized_filter_batch(
+    filter: &OptimizedFilter,
+    point_ids: &[PointOffsetType],
+    active: &mut [bool],
+) {
+    debug_assert_eq!(point_ids.len(), active.len());
+    check_should_batch(&filter.should, point_ids, active);
+    check_min_should_batch(&filter.min_should, point_ids, active);
+    check_must_batch(&filter.must, point_ids, active);
+    check_must_not_batch(&filter.must_not, point_ids, active);
+}
+
+/// Batched version of [`check_condition`], unmarks points in `active` which don't match
+fn check_condition_batch(
+    condition: &OptimizedCondition,
+    point_ids: &[PointOffsetType],
+    active: &mut [bool],
+) {
+    match condition {
+        OptimizedCondition::Filter(filter) => {
+            check_optimized_filter_batch(filter, point_ids, active)
+        }
+        OptimizedCondition::Checker(checker) => {
+            for (&point_id, is_active) in point_ids.iter().zip(active.iter_mut()) {
+                if *is_active {
+                    *is_active = checker(point_id);
+                }
+            }
+        }
+    }
+}
+
+fn check_should_batch(
+    should: &Option<Vec<OptimizedCondition>>,
+    point_ids: &[PointOffsetType],
+    active: &mut [bool],
+) {
+    let Some(conditions) = should else {
+        return;
+    };
+    let mut matched = vec![false; active.len()];
+    let mut pending = vec![false; active.len()];
+    for condition in conditions {
+        for ((pending, &is_active), &is_matched) in pending.iter_mut().zip(&*active).zip(&matched) {
+            *pending = is_active && !is_matched;
+        }
+        if !pending.contains(&true) {
+            break;
+        }
+        check_condition_batch(condition, point_ids, &mut pending);
+        for (is_matched, &pending) in matched.iter_mut().zip(&pending) {
+            *is_matched |= pending;
+        }
+    }
+    for (is_active, is_matched) in active.iter_mut().zip(matched) {
+        *is_active &= is_matched;
+    }
+}
+
+fn check_min_should_batch(
+    min_should: &Option<OptimizedMinShould>,
+    point_ids: &[PointOffsetType],
+    active: &mut [bool],
+) {
+    let Some(OptimizedMinShould {
+        conditions,
+        min_count,
+    }) = min_should
+    else {
+        return;
+    };
+    let mut counts = vec![0; active.len()];
+    let mut pending = vec![false; active.len()];
+    for condition in conditions {
+        for ((pending, &is_active), &count) in pending.iter_mut().zip(&*active).zip(&counts) {
+            *pending = is_active && count < *min_count;
+        }
+        if !pending.contains(&true) {
+            break;
+        }
+        check_condition_batch(condition, point_ids, &mut pending);
+        for (count, &pending) in counts.iter_mut().zip(&pending) {
+            *count += usize::from(pending);
+        }
+    }
+    for (is_active, count) in active.iter_mut().zip(counts) {
+        *is_active &= count == *min_count;
+    }
+}
+
+fn check_must_batch(
+    must: &Option<Vec<OptimizedCondition>>,
+    point_ids: &[PointOffsetType],
+    active: &mut [bool],
+) {
+    let Some(conditions) = must else {
+        return;
+    };
+    for condition in conditions {
+        if !active.contains(&true) {
+            break;
+        }
+        check_condition_batch(condition, point_ids, active);
+    }
+}
+
+fn check_must_not_batch(
+    must_not: &Option<Vec<OptimizedCondition>>,
+    point_ids: &[PointOffsetType],
+    active: &mut [bool],
+) {
+    let Some(conditions) = must_not else {
+        return;
+    };
+    let mut matched = vec![false; active.len()];
+    for condition in conditions {
+        if !active.contains(&true) {
+            break;
+        }
+        matched.copy_from_slice(active);
+        check_condition_batch(condition, point_ids, &mut matched);
+        for (is_active, &is_matched) in active.iter_mut().zip(&matched) {
+            *is_active &= !is_matched;
+        }
+    }
+}
@@ -5,8 +5,8 @@ use itertools::Itertools;
 
 use crate::index::field_index::CardinalityEstimation;
 use crate::index::query_estimator::{
-    combine_min_should_estimations, combine_must_estimations, combine_should_estimations,
-    invert_estimation,
+    EstimationCache, combine_min_should_estimations, combine_must_estimations,
+    combine_should_estimations, invert_estimation,
 };
 use crate::index::query_optimization::optimized_filter::{
     OptimizedCondition, OptimizedFilter, OptimizedMinShould,
@@ -31,6 +31,7 @@ impl StructPayloadIndex {
     /// * `filter` - original filter
     /// * `payload_provider` - provides the payload storage
     /// * `total` - total number of points in segment (used for cardinality estimation)
+    /// * `estimation_cache` - estimations of conditions, which were already computed for the query
     ///
     /// # Result
     ///
@@ -40,6 +41,7 @@ impl StructPayloadIndex {
         filter: &'a Filter,
         payload_provider: PayloadProvider,
         total: usize,
+        estimation_cache: &EstimationCache,
         hw_counter: &HardwareCounterCell,
     ) -> (OptimizedFilter<'a>, CardinalityEstimation) {
         let mut filter_estimations: Vec<CardinalityEstimation> = vec![];
@@ -51,6 +53,7 @@ impl StructPayloadIndex {
                         conditions,
                         payload_provider.clone(),
                         total,
+                        estimation_cache,
                         hw_counter,
                     );
                     filter_estimations.push(estimation);
@@ -70,6 +73,7 @@ impl StructPayloadIndex {
                             *min_count,
                             payload_provider.clone(),
                             total,
+                            estimation_cache,
                             hw_counter,
                         );
                         filter_estimations.push(estimation);
@@ -84,8 +88,13 @@ impl StructPayloadIndex {
             ),
             must: filter.must.as_ref().and_then(|conditions| {
                 if !conditions.is_empty() {
-                    let (optimized_conditions, estimation) =
-                        self.optimize_must(conditions, payload_provider.clone(), total, hw_counter);
+                    let (optimized_conditions, estimation) = self.optimize_must(
+                        conditions,
+                        payload_provider.clone(),
+                        total,
+                        estimation_cache,
+                        hw_counter,
+                    );
                     filter_estimations.push(estimation);
                     Some(optimized_conditions)
                 } else {
@@ -94,8 +103,13 @@ impl StructPayloadIndex {
             }),
             must_not: filter.must_not.as_ref().and_then(|conditions| {
                 if !conditions.is_empty() {
-                    let (optimized_conditions, estimation) =
-                        self.optimize_must_not(conditions, payload_provider, total, hw_counter);
+                    let (optimized_conditions, estimation) = self.optimize_must_not(
+                        conditions,
+                        payload_provider,
+                        total,
+                        estimation_cache,
+                        hw_counter,
+                    );
                     filter_estimations.push(estimation);
                     Some(optimized_conditions)
                 } else {
@@ -115,18 +129,26 @@ impl StructPayloadIndex {
         conditions: &'a [Condition],
         payload_provider: PayloadProvider,
         total: usize,
+        estimation_cache: &EstimationCache,
         hw_counter: &HardwareCounterCell,
     ) -> Vec<(OptimizedCondition<'a>, CardinalityEstimation)> {
         conditions
             .iter()
             .map(|condition| match condition {
                 Condition::Filter(filter) => {
-                    let (optimized_filter, estimation) =
-                        self.optimize_filter(filter, payload_provider.clone(), total, hw_counter);
+                    let (optimized_filter, estimation) = self.optimize_filter(
+                        filter,
+                        payload_provider.clone(),
+                        total,
+                        estimation_cache,
+                        hw_counter,
+                    );
                     (OptimizedCondition::Filter(optimized_filter), estimation)
                 }
                 _ => {
-                    let estimation = self.condition_cardinality(condition, None, hw_counter);
+                    let estimation = estimation_cache.get_or_estimate(condition, |condition| {
+                        self.condition_cardinality(condition, None, hw_counter)
+                    });
                     let condition_checker =
                         self.condition_converter(condition, payload_provider.clone(), hw_counter);
                     (OptimizedCondition::Checker(condition_checker), estimation)
@@ -140,10 +162,16 @@ impl StructPayloadIndex {
         conditions: &'a [Condition],
         payload_provider: PayloadProvider,
         total: usize,
+        estimation_cache: &EstimationCache,
         hw_counter: &HardwareCounterCell,
     ) -> (Vec<OptimizedCondition<'a>>, CardinalityEstimation) {
-        let mut converted =
-            self.convert_conditions(conditions, payload_provider, total, hw_counter);
+        let mut converted = self.convert_conditions(
+            conditions,
+            payload_provider,
+            total,
+            estimation_cache,
+            hw_counter,
+        );
         // More probable conditions first
         converted.sort_by_key(|(_, estimation)| Reverse(estimation.exp));
         let (conditions, estimations): (Vec<_>, Vec<_>) = converted.into_iter().unzip();
@@ -157,10 +185,16 @@ impl StructPayloadIndex {
         min_count: usize,
         payload_provider: PayloadProvider,
         total: usize,
+        estimation_cache: &EstimationCache,
         hw_counter: &HardwareCounterCell,
     ) -> (Vec<OptimizedCondition<'a>>, CardinalityEstimation) {
-        let mut converted =
-            self.convert_conditions(conditions, payload_provider, total, hw_counter);
+        let mut converted = self.convert_conditions(
+            conditions,
+            payload_provider,
+            total,
+            estimation_cache,
+            hw_counter,
+        );
         // More probable conditions first if min_count < number of conditions
         if min_count < conditions.len() / 2 {
             converted.sort_by_key(|(_, estimation)| Reverse(estimation.exp));
@@ -181,10 +215,16 @@ impl StructPayloadIndex {
         conditions: &'a [Condition],
         payload_provider: PayloadProvider,
         total: usize,
+        estimation_cache: &EstimationCache,
         hw_counter: &HardwareCounterCell,
     ) -> (Vec<OptimizedCondition<'a>>, CardinalityEstimation) {
-        let mut converted =
-            self.convert_conditions(conditions, payload_provider, total, hw_counter);
+        let mut converted = self.convert_conditions(
+            conditions,
+            payload_provider,
+            total,
+            estimation_cache,
+            hw_counter,
+        );
         // Less probable conditions first
         converted.sort_by_key(|(_, estimation)| estimation.exp);
         let (conditions, estimations): (Vec<_>, Vec<_>) = converted.into_iter().unzip();
@@ -197,10 +237,16 @@ impl StructPayloadIndex {
         conditions: &'a [Condition],
         payload_provider: PayloadProvider,
         total: usize,
+        estimation_cache: &EstimationCache,
         hw_counter: &HardwareCounterCell,
     ) -> (Vec<OptimizedCondition<'a>>, CardinalityEstimation) {
-        let mut converted =
-            self.convert_conditions(conditions, payload_provider, total, hw_counter);
+        let mut converted = self.convert_conditions(
+            conditions,
+            payload_provider,
+            total,
+            estimation_cache,
+            hw_counter,
+        );
         // More probable conditions first, as it will be reverted
         converted.sort_by_key(|(_, estimation)| estimation.exp);
         let (conditions, estimations): (Vec<_>, Vec<_>) = converted.into_iter().unzip();
@@ -12,6 +12,7 @@ use super::parsed_formula::{
 };
 use super::value_retriever::VariableRetrieverFn;
 use crate::common::operation_error::{OperationError, OperationResult};
+use crate::index::query_estimator::EstimationCache;
 use crate::index::query_optimization::optimized_filter::{OptimizedCondition, check_condition};
 use crate::index::query_optimization::payload_provider::PayloadProvider;
 use crate::index::struct_payload_index::StructPayloadIndex;
@@ -79,7 +80,13 @@ impl StructPayloadIndex {
         let payload_provider = PayloadProvider::new(self.payload.clone());
         let total = self.available_point_count();
         let condition_checkers = self
-            .convert_conditions(conditions, payload_provider, total, hw_counter)
+            .convert_conditions(
+                conditions,
+                payload_provider,
+                total,
+                &EstimationCache::default(),
+                hw_counter,
+            )
             .into_iter()
             .map(|(checker, _estimation)| checker)
             .collect();
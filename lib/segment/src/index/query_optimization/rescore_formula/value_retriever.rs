@@ -5,7 +5,7 @@ use common::counter::hardware_counter::HardwareCounterCell;
 use common::types::PointOffsetType;
 use serde_json::{Number, Value};
 
-use crate::common::utils::MultiValue;
+use crate::common::utils::{IndexesMap, MultiValue};
 use crate::index::field_index::FieldIndex;
 use crate::index::query_optimization::payload_provider::PayloadProvider;
 use crate::index::struct_payload_index::StructPayloadIndex;
@@ -46,7 +46,7 @@ impl StructPayloadIndex {
 }
 
 fn variable_retriever<'a, 'q>(
-    indices: &'a HashMap<JsonPath, Vec<FieldIndex>>,
+    indices: &'a IndexesMap,
     json_path: &JsonPath,
     payload_provider: PayloadProvider,
     hw_counter: &'q HardwareCounterCell,
@@ -224,12 +224,12 @@ mod tests {
     use crate::common::utils::MultiValue;
     use crate::index::field_index::geo_index::GeoMapIndex;
     use crate::index::field_index::numeric_index::NumericIndex;
-    use crate::index::field_index::{FieldIndex, FieldIndexBuilderTrait};
+    use crate::index::field_index::{FieldIndex, FieldIndexBuilderTrait, ValueIndexer};
     use crate::index::query_optimization::payload_provider::PayloadProvider;
     use crate::index::query_optimization::rescore_formula::value_retriever::variable_retriever;
     use crate::payload_storage::in_memory_payload_storage::InMemoryPayloadStorage;
     use crate::payload_storage::payload_storage_enum::PayloadStorageEnum;
-    use crate::types::Payload;
+    use crate::types::{FloatPayloadType, Payload};
 
     pub fn fixture_payload_provider() -> PayloadProvider {
         // Create an in-memory payload storage and populate it with some payload maps containing numbers and geo points.
@@ -380,9 +380,12 @@ mod tests {
         let datetime_index = FieldIndex::DatetimeIndex(datetime_index);
 
         let mut indices = HashMap::new();
-        indices.insert("value".try_into().unwrap(), vec![numeric_index]);
-        indices.insert("location".try_into().unwrap(), vec![geo_index]);
-        indices.insert("creation".try_into().unwrap(), vec![datetime_index]);
+        indices.insert("value".try_into().unwrap(), Arc::new(vec![numeric_index]));
+        indices.insert("location".try_into().unwrap(), Arc::new(vec![geo_index]));
+        indices.insert(
+            "creation".try_into().unwrap(),
+            Arc::new(vec![datetime_index]),
+        );
 
         let hw_counter = Default::default();
 
@@ -440,4 +443,41 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_variable_retriever_skips_non_finite_floats() {
+        let payload_provider = PayloadProvider::new(Arc::new(AtomicRefCell::new(
+            PayloadStorageEnum::InMemoryPayloadStorage(InMemoryPayloadStorage::default()),
+        )));
+        let hw_counter = HardwareCounterCell::new();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut float_index = NumericIndex::<FloatPayloadType, FloatPayloadType>::new_gridstore(
+            dir.path().to_path_buf(),
+            true,
+        )
+        .unwrap()
+        .unwrap();
+        float_index
+            .add_many(0, vec![1.5, f64::NAN, f64::INFINITY], &hw_counter)
+            .unwrap();
+        float_index
+            .add_many(1, vec![f64::NAN], &hw_counter)
+            .unwrap();
+
+        let mut indices = HashMap::new();
+        indices.insert(
+            "value".try_into().unwrap(),
+            Arc::new(vec![FieldIndex::FloatIndex(float_index)]),
+        );
+
+        let retriever = variable_retriever(
+            &indices,
+            &"value".try_into().unwrap(),
+            payload_provider,
+            &hw_counter,
+        );
+        assert_eq!(retriever(0), [json!(1.5)].into());
+        assert_eq!(retriever(1), MultiValue::<Value>::new());
+    }
 }
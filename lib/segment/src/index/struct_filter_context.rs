@@ -1,6 +1,8 @@
 use common::types::PointOffsetType;
 
-use crate::index::query_optimization::optimized_filter::{OptimizedFilter, check_optimized_filter};
+use crate::index::query_optimization::optimized_filter::{
+    OptimizedFilter, check_optimized_filter, check_optimized_filter_batch,
+};
 use crate::payload_storage::FilterContext;
 
 pub struct StructFilterContext<'a> {
@@ -17,4 +19,9 @@ impl FilterContext for StructFilterContext<'_> {
     fn check(&self, point_id: PointOffsetType) -> bool {
         check_optimized_filter(&self.optimized_filter, point_id)
     }
+
+    fn check_batch(&self, point_ids: &[PointOffsetType], out: &mut [bool]) {
+        out.fill(true);
+        check_optimized_filter_batch(&self.optimized_filter, point_ids, out);
+    }
 }
@@ -0,0 +1,447 @@
+use std::time::Instant;
+
+use common::counter::hardware_counter::HardwareCounterCell;
+
+use super::{DeadlineChecker, StructPayloadIndex};
+use crate::index::field_index::{
+    CardinalityEstimation, PrimaryCondition, ResolvedComposite, ResolvedHasId, ResolvedIdRange,
+    ResolvedVersionAtMost,
+};
+use crate::index::query_estimator::{EstimationCache, combine_must_estimations, estimate_filter};
+use crate::index::value_transform::transform_field_condition;
+use crate::index::values_count_histogram::ValuesCountHistogram;
+use crate::json_path::JsonPath;
+use crate::types::{
+    Condition, ExtendedPointId, FieldCondition, Filter, IsEmptyCondition, IsNullCondition, Match,
+    MatchValue, VectorNameBuf,
+};
+use crate::vector_storage::VectorStorage;
+
+impl StructPayloadIndex {
+    /// Estimate a field condition with the first index of the field, which supports it.
+    ///
+    /// All indexes of a field are built for a single schema, so any index supporting the
+    /// condition covers all indexed values, and there is nothing to merge from other indexes.
+    /// Values of other types are not indexed. Conditions on them, such as a numeric match on a
+    /// keyword field, are not estimated, and matching points are found by checking payloads.
+    pub fn estimate_field_condition(
+        &self,
+        condition: &FieldCondition,
+        nested_path: Option<&JsonPath>,
+        hw_counter: &HardwareCounterCell,
+    ) -> Option<CardinalityEstimation> {
+        let full_path = JsonPath::extend_or_new(nested_path, &condition.key);
+        self.field_indexes.get(&full_path).and_then(|indexes| {
+            // rewrite condition with fullpath to enable cardinality estimation
+            let full_path_condition = FieldCondition {
+                key: full_path,
+                ..condition.clone()
+            };
+            let full_path_condition = transform_field_condition(
+                self.config.value_transforms(&full_path_condition.key),
+                &full_path_condition,
+            );
+
+            indexes
+                .iter()
+                .find_map(|index| index.estimate_cardinality(&full_path_condition, hw_counter))
+        })
+    }
+
+    /// Exact estimation of `is_empty` conditions, if the field has a complete index.
+    ///
+    /// Only the null index, built with every field index, is treated as complete, see
+    /// [`FieldIndex::is_complete`]. It keeps a flag of points with values, so points without a
+    /// value are selected from it directly, without a full scan. Their number is exactly the
+    /// number of available points minus the points with values, so `min` is as tight as `max`.
+    ///
+    /// Other indexes skip values of types they can't hold, so their number of indexed points
+    /// only bounds the number of empty points from above. Such fields are estimated by the
+    /// field indexes instead.
+    fn estimate_is_empty(
+        &self,
+        condition: &FieldCondition,
+        nested_path: Option<&JsonPath>,
+        available_points: usize,
+    ) -> Option<CardinalityEstimation> {
+        // Nested conditions are estimated per array element, not per point
+        if nested_path.is_some() {
+            return None;
+        }
+
+        let non_empty_points = self
+            .field_indexes
+            .get(&condition.key)?
+            .iter()
+            .filter(|index| index.is_complete())
+            .find_map(|index| index.non_empty_points_count())?;
+
+        let empty_points = available_points.saturating_sub(non_empty_points);
+        Some(
+            CardinalityEstimation::exact(empty_points)
+                .with_primary_clause(PrimaryCondition::from(condition.clone())),
+        )
+    }
+
+    /// Expected number of points selected by a single primary clause.
+    pub(super) fn primary_clause_cardinality(
+        &self,
+        clause: &PrimaryCondition,
+        hw_counter: &HardwareCounterCell,
+    ) -> usize {
+        match clause {
+            PrimaryCondition::Condition(field_condition) => self
+                .estimate_field_condition(field_condition, None, hw_counter)
+                .map_or_else(|| self.available_point_count(), |estimation| estimation.exp),
+            PrimaryCondition::Ids(ids) => ids.resolved_point_offsets.len(),
+            PrimaryCondition::IdRange(id_range) => id_range.points_count,
+            PrimaryCondition::VersionAtMost(versions) => versions.points_count,
+            PrimaryCondition::Composite(composite) => composite.resolved_point_offsets.len(),
+            PrimaryCondition::HasVector(vector_name) => self.vector_points_count(vector_name),
+        }
+    }
+
+    /// Expected number of available points with a vector stored under `vector_name`
+    pub(in crate::index) fn vector_points_count(&self, vector_name: &VectorNameBuf) -> usize {
+        self.estimate_vector_points(vector_name).exp
+    }
+
+    /// Number of available points with a vector stored under `vector_name`.
+    ///
+    /// Vectors of deleted points may remain in the storage, so the number of stored vectors is
+    /// only exact if there are no deleted points. Each deleted point might hold one of them.
+    fn estimate_vector_points(&self, vector_name: &VectorNameBuf) -> CardinalityEstimation {
+        let Some(vector_storage) = self.vector_storages.get(vector_name) else {
+            return CardinalityEstimation::exact(0);
+        };
+        let vectors = vector_storage.borrow().available_vector_count();
+        let id_tracker = self.id_tracker.borrow();
+        let total_points = id_tracker.total_point_count();
+        let available_points = id_tracker.available_point_count();
+        let deleted_points = total_points.saturating_sub(available_points);
+
+        let max = vectors.min(available_points);
+        let min = vectors.saturating_sub(deleted_points).min(max);
+        // Assume vectors are spread evenly over available and deleted points
+        let exp = (vectors * available_points / total_points.max(1)).clamp(min, max);
+        CardinalityEstimation {
+            primary_clauses: vec![],
+            min,
+            exp,
+            max,
+        }
+    }
+
+    /// Estimation of the `must` conjunction of the filter, answered by a single composite index.
+    ///
+    /// Applies if the filter has an exact match condition on every field of a composite index.
+    /// If multiple composite indexes apply, the most selective one is used.
+    fn estimate_composite(&self, query: &Filter) -> Option<CardinalityEstimation> {
+        let must = query.must.as_ref()?;
+
+        self.composite_indexes
+            .iter()
+            // Values of transformed fields are not comparable with stored values
+            .filter(|index| {
+                index
+                    .fields()
+                    .iter()
+                    .all(|field| self.config.value_transforms(field).is_empty())
+            })
+            .filter_map(|index| {
+                let mut conditions = Vec::with_capacity(index.fields().len());
+                let mut key = Vec::with_capacity(index.fields().len());
+                for field in index.fields() {
+                    let (condition, value) = must.iter().find_map(|condition| {
+                        let Condition::Field(field_condition) = condition else {
+                            return None;
+                        };
+                        let Some(Match::Value(MatchValue { value })) = &field_condition.r#match
+                        else {
+                            return None;
+                        };
+                        // Other constraints of the condition can't be answered by the index
+                        let exact_match = FieldCondition::new_match(
+                            field.clone(),
+                            Match::Value(MatchValue {
+                                value: value.clone(),
+                            }),
+                        );
+                        (*field_condition == exact_match).then_some((field_condition, value))
+                    })?;
+                    conditions.push(condition.clone());
+                    key.push(value.clone());
+                }
+                Some((index, conditions, key))
+            })
+            .min_by_key(|(index, _, key)| index.points_count(key))
+            .map(|(index, conditions, key)| {
+                let resolved_point_offsets: Vec<_> = index.get_points(&key).collect();
+                CardinalityEstimation::exact(resolved_point_offsets.len()).with_primary_clause(
+                    PrimaryCondition::Composite(ResolvedComposite {
+                        conditions,
+                        resolved_point_offsets,
+                    }),
+                )
+            })
+    }
+
+    /// Estimate the filter with `estimate`, using a composite index for the conditions it covers.
+    ///
+    /// Covered conditions are answered exactly by the composite index, so only the remaining
+    /// conditions are passed to `estimate`.
+    pub(super) fn estimate_with_composite(
+        &self,
+        query: &Filter,
+        estimate: impl Fn(&Filter) -> CardinalityEstimation,
+    ) -> CardinalityEstimation {
+        let Some(composite) = self.estimate_composite(query) else {
+            return estimate(query);
+        };
+
+        let remaining = Filter {
+            must: query.must.as_ref().map(|must| {
+                must.iter()
+                    .filter(|condition| !composite.is_primary(condition))
+                    .cloned()
+                    .collect()
+            }),
+            ..query.clone()
+        };
+        combine_must_estimations(
+            &[estimate(&remaining), composite],
+            self.available_point_count(),
+        )
+    }
+
+    /// Estimation of `values_count` conditions, using the values count histogram.
+    ///
+    /// Points without the field are counted as having zero values, same as in condition check.
+    /// The estimation is exact only if every value of the field is indexed, see
+    /// [`ValuesCountHistogram::is_exact`]. Otherwise any point might have values, which the
+    /// histogram doesn't count, so only the expected number is taken from it.
+    fn estimate_values_count(
+        &self,
+        condition: &FieldCondition,
+        nested_path: Option<&JsonPath>,
+        available_points: usize,
+    ) -> Option<CardinalityEstimation> {
+        // Nested conditions are estimated per array element, not per point
+        if nested_path.is_some() {
+            return None;
+        }
+        let values_count = condition.values_count.as_ref()?;
+        let histogram = self.values_count_histograms.get(&condition.key)?;
+        let points_count = histogram.count_points(values_count, available_points);
+        if !histogram.is_exact() {
+            return Some(CardinalityEstimation {
+                primary_clauses: vec![],
+                min: 0,
+                exp: points_count,
+                max: available_points,
+            });
+        }
+        Some(
+            CardinalityEstimation::exact(points_count)
+                .with_primary_clause(PrimaryCondition::from(condition.clone())),
+        )
+    }
+
+    /// Same as [`crate::index::PayloadIndex::estimate_cardinality`], but stores estimations of
+    /// conditions in `estimation_cache`, so they can be reused for filtering.
+    pub(in crate::index) fn estimate_cardinality_with_cache(
+        &self,
+        query: &Filter,
+        estimation_cache: &EstimationCache,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        let available_points = self.available_point_count();
+        let estimator = |condition: &Condition| {
+            estimation_cache.get_or_estimate(condition, |condition| {
+                self.condition_cardinality(condition, None, hw_counter)
+            })
+        };
+        self.estimate_with_composite(query, |query| {
+            estimate_filter(&estimator, query, available_points)
+        })
+    }
+
+    pub(in crate::index) fn condition_cardinality(
+        &self,
+        condition: &Condition,
+        nested_path: Option<&JsonPath>,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        self.condition_cardinality_until(condition, nested_path, None, hw_counter)
+    }
+
+    /// Same as [`Self::condition_cardinality`], but gives up on expensive estimations once the
+    /// `checker` deadline is reached, returning an unknown estimation instead.
+    fn condition_cardinality_until(
+        &self,
+        condition: &Condition,
+        nested_path: Option<&JsonPath>,
+        checker: Option<&DeadlineChecker>,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        if checker.is_some_and(DeadlineChecker::is_expired) {
+            return CardinalityEstimation::unknown(self.available_point_count());
+        }
+
+        match condition {
+            Condition::Filter(_) => panic!("Unexpected branching"),
+            Condition::Nested(nested) => {
+                // propagate complete nested path in case of multiple nested layers
+                let full_path = JsonPath::extend_or_new(nested_path, &nested.array_key());
+                let estimator = |condition: &Condition| {
+                    self.condition_cardinality_until(
+                        condition,
+                        Some(&full_path),
+                        checker,
+                        hw_counter,
+                    )
+                };
+                estimate_filter(&estimator, nested.filter(), self.available_point_count())
+            }
+            Condition::IsEmpty(IsEmptyCondition { is_empty: field }) => {
+                let available_points = self.available_point_count();
+                let condition = FieldCondition::new_is_empty(field.key.clone(), true);
+
+                if let Some(estimation) =
+                    self.estimate_is_empty(&condition, nested_path, available_points)
+                {
+                    return estimation;
+                }
+
+                self.estimate_field_condition(&condition, nested_path, hw_counter)
+                    .unwrap_or_else(|| CardinalityEstimation::unknown(available_points))
+            }
+            Condition::IsNull(IsNullCondition { is_null: field }) => {
+                let available_points = self.available_point_count();
+                let condition = FieldCondition::new_is_null(field.key.clone(), true);
+
+                self.estimate_field_condition(&condition, nested_path, hw_counter)
+                    .unwrap_or_else(|| CardinalityEstimation::unknown(available_points))
+            }
+            Condition::HasId(has_id) => {
+                let point_ids = has_id.has_id.clone();
+                let id_tracker = self.id_tracker.borrow();
+                let mut resolved_point_offsets = Vec::new();
+                for external_id in point_ids.iter() {
+                    if checker.is_some_and(DeadlineChecker::is_expired) {
+                        return CardinalityEstimation::unknown(self.available_point_count());
+                    }
+                    if let Some(internal_id) = id_tracker.internal_id(*external_id) {
+                        resolved_point_offsets.push(internal_id);
+                    }
+                }
+                let num_ids = resolved_point_offsets.len();
+                CardinalityEstimation {
+                    primary_clauses: vec![PrimaryCondition::Ids(ResolvedHasId {
+                        point_ids,
+                        resolved_point_offsets,
+                    })],
+                    min: num_ids,
+                    exp: num_ids,
+                    max: num_ids,
+                }
+            }
+            // Only count points in the range, offsets are generated lazily on query
+            Condition::HasIdRange(has_id_range) => {
+                let range = has_id_range.has_id_range;
+                let id_tracker = self.id_tracker.borrow();
+                let mut points_count = 0;
+                for (external_id, _) in
+                    id_tracker.iter_from(Some(ExtendedPointId::NumId(range.gte.unwrap_or(0))))
+                {
+                    if checker.is_some_and(DeadlineChecker::is_expired) {
+                        return CardinalityEstimation::unknown(self.available_point_count());
+                    }
+                    if !range.contains(external_id) {
+                        break;
+                    }
+                    points_count += 1;
+                }
+                CardinalityEstimation::exact(points_count).with_primary_clause(
+                    PrimaryCondition::IdRange(ResolvedIdRange {
+                        range,
+                        points_count,
+                    }),
+                )
+            }
+            // Versions of all points are mirrored by the version index, which serves as an
+            // always present numeric index over them
+            Condition::VersionAtMost(version_at_most) => {
+                let version_at_most = version_at_most.version_at_most;
+                let available_points = self.available_point_count();
+                // Versions of deleted points may remain in the index until they are overwritten
+                let deleted_points = self.id_tracker.borrow().deleted_point_count();
+                let (min, exp, max) = self
+                    .point_versions
+                    .borrow()
+                    .estimate_at_most(version_at_most);
+                let estimation = CardinalityEstimation {
+                    primary_clauses: vec![],
+                    min: min.saturating_sub(deleted_points).min(available_points),
+                    exp: exp.min(available_points),
+                    max: max.min(available_points),
+                };
+                estimation.with_primary_clause(PrimaryCondition::VersionAtMost(
+                    ResolvedVersionAtMost {
+                        version_at_most,
+                        points_count: estimation.exp,
+                    },
+                ))
+            }
+            Condition::HasVector(has_vectors) => {
+                let estimation = self.estimate_vector_points(&has_vectors.has_vector);
+                if self.vector_storages.contains_key(&has_vectors.has_vector) {
+                    estimation.with_primary_clause(PrimaryCondition::HasVector(
+                        has_vectors.has_vector.clone(),
+                    ))
+                } else {
+                    estimation
+                }
+            }
+            Condition::Field(field_condition) => self
+                .estimate_values_count(field_condition, nested_path, self.available_point_count())
+                .or_else(|| self.estimate_field_condition(field_condition, nested_path, hw_counter))
+                .unwrap_or_else(|| CardinalityEstimation::unknown(self.available_point_count())),
+            // Field indexes can't compare values of different fields
+            Condition::FieldVsField(_) => {
+                CardinalityEstimation::unknown(self.available_point_count())
+            }
+
+            Condition::CustomIdChecker(cond) => {
+                cond.estimate_cardinality(self.id_tracker.borrow().available_point_count())
+            }
+        }
+    }
+
+    /// Estimate cardinality of the query, but give up once `deadline` is reached.
+    ///
+    /// If the estimation can't finish in time, an unknown estimation without primary clauses is
+    /// returned, which is still usable for querying points with a full scan.
+    pub fn estimate_cardinality_with_deadline(
+        &self,
+        query: &Filter,
+        deadline: Instant,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        let checker = DeadlineChecker::new(deadline);
+        let available_points = self.available_point_count();
+
+        let estimator = |condition: &Condition| {
+            self.condition_cardinality_until(condition, None, Some(&checker), hw_counter)
+        };
+        let estimation = self.estimate_with_composite(query, |query| {
+            estimate_filter(&estimator, query, available_points)
+        });
+
+        if checker.expired.get() {
+            CardinalityEstimation::unknown(available_points)
+        } else {
+            estimation
+        }
+    }
+}
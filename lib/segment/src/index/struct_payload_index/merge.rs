@@ -0,0 +1,135 @@
+use common::counter::hardware_counter::HardwareCounterCell;
+use common::types::PointOffsetType;
+
+use super::{StructPayloadIndex, field_indexes_mut, values_counting_index};
+use crate::common::operation_error::OperationResult;
+use crate::index::field_index::FieldIndex;
+use crate::index::values_count_histogram::ValuesCountHistogram;
+
+impl StructPayloadIndex {
+    /// Append the points of `other` to the field indexes of this index, shifting their offsets
+    /// by `id_offset`. Avoids rebuilding the indexes over the combined payload from scratch.
+    ///
+    /// Values are copied from the field indexes of `other`, payload of `other` is not read.
+    /// The payload of `other` is expected to already be present in this payload storage at the
+    /// shifted offsets, as it is used for fields, which can't be copied:
+    ///
+    /// - fields indexed in both indexes with the same schema and value transforms get values of
+    ///   `other` appended index by index
+    /// - fields indexed only here, with a different schema or transforms in `other`, or with
+    ///   indexes, which values can't be copied (like full-text) or which are immutable here, are
+    ///   rebuilt from payload storage
+    /// - fields indexed only in `other` are dropped, the schema of this index decides
+    ///
+    /// Composite indexes follow the same rules.
+    pub fn merge_from(
+        &mut self,
+        other: &StructPayloadIndex,
+        id_offset: PointOffsetType,
+    ) -> OperationResult<()> {
+        let hw_counter = HardwareCounterCell::disposable(); // Internal operation
+        self.check_indexes_writable(|_| true)?;
+
+        for field in other.config.indices.keys() {
+            if !self.config.indices.contains_key(field) {
+                log::debug!("Field `{field}` is not indexed in merge target, dropping its index");
+            }
+        }
+
+        let other_point_ids: Vec<_> = other.id_tracker.borrow().iter_ids().collect();
+
+        let mut rebuild_fields = Vec::new();
+        for (field, field_indexes) in &mut self.field_indexes {
+            let other_indexes = other
+                .field_indexes
+                .get(field)
+                .filter(|other_indexes| other_indexes.len() == field_indexes.len());
+            let is_same_config = self.config.indices.get(field).map(|c| &c.schema)
+                == other.config.indices.get(field).map(|c| &c.schema)
+                && self.config.value_transforms(field) == other.config.value_transforms(field);
+            let Some(other_indexes) = other_indexes.filter(|_| is_same_config) else {
+                rebuild_fields.push(field.clone());
+                continue;
+            };
+
+            let is_copied = merge_field_indexes(
+                field_indexes_mut(field, field_indexes)?,
+                other_indexes,
+                &other_point_ids,
+                id_offset,
+                self.values_count_histograms.get_mut(field),
+                &hw_counter,
+            )?;
+            if !is_copied {
+                rebuild_fields.push(field.clone());
+            } else if let Some(histogram) = self.values_count_histograms.get_mut(field) {
+                // Copied counts are only as exact as the counts of `other`
+                histogram.set_exact(histogram.is_exact() && other.has_exact_values_count(field));
+            }
+        }
+
+        for field in rebuild_fields {
+            log::debug!("Field `{field}` can't be merged from indexes, rebuilding from payload");
+            self.rebuild_index(&field)?;
+        }
+
+        let mut rebuild_composite = Vec::new();
+        for (position, composite_index) in self.composite_indexes.iter_mut().enumerate() {
+            let other_index = other
+                .composite_indexes
+                .iter()
+                .find(|other_index| other_index.fields() == composite_index.fields());
+            let Some(other_index) = other_index else {
+                rebuild_composite.push(position);
+                continue;
+            };
+            for &point_id in &other_point_ids {
+                composite_index.copy_point_from(other_index, point_id, point_id + id_offset);
+            }
+        }
+
+        for position in rebuild_composite {
+            let fields = self.composite_indexes[position].fields().to_vec();
+            self.composite_indexes[position] = self.build_composite_index(&fields)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Append values of `point_ids` from `other_indexes` to the indexes of the same field, with
+/// offsets shifted by `id_offset`.
+///
+/// Returns `false` without finishing, if values of any index can't be copied. Indexes are then
+/// left partially updated and must be rebuilt.
+fn merge_field_indexes(
+    field_indexes: &mut [FieldIndex],
+    other_indexes: &[FieldIndex],
+    point_ids: &[PointOffsetType],
+    id_offset: PointOffsetType,
+    mut histogram: Option<&mut ValuesCountHistogram>,
+    hw_counter: &HardwareCounterCell,
+) -> OperationResult<bool> {
+    for &point_id in point_ids {
+        let shifted_id = point_id + id_offset;
+
+        let old_values_count = values_counting_index(field_indexes)
+            .map(|index| index.values_count(shifted_id))
+            .unwrap_or_default();
+
+        for (index, other_index) in field_indexes.iter_mut().zip(other_indexes) {
+            match index.copy_point_from(other_index, point_id, shifted_id, hw_counter) {
+                Some(result) => result?,
+                None => return Ok(false),
+            }
+        }
+
+        if let (Some(histogram), Some(index)) = (
+            histogram.as_deref_mut(),
+            values_counting_index(field_indexes),
+        ) {
+            histogram.update(old_values_count, index.values_count(shifted_id));
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,3023 @@
+mod estimation;
+mod merge;
+mod rebuild;
+mod snapshot;
+#[cfg(test)]
+mod tests;
+
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{create_dir_all, remove_file};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use atomic_refcell::AtomicRefCell;
+use common::counter::hardware_counter::HardwareCounterCell;
+use common::counter::iterator_hw_measurement::HwMeasurementIteratorExt;
+use common::either_variant::EitherVariant;
+use common::types::PointOffsetType;
+use itertools::{Either, Itertools};
+use rayon::prelude::*;
+use schemars::_serde_json::Value;
+use serde::Serialize;
+
+use super::field_index::composite_index::CompositeIndex;
+use super::field_index::facet_index::FacetIndexEnum;
+use super::field_index::geo_index::GeoMapIndex;
+#[cfg(feature = "rocksdb")]
+use super::field_index::index_selector::IndexSelectorRocksDb;
+use super::field_index::index_selector::{
+    IndexSelector, IndexSelectorGridstore, IndexSelectorMmap,
+};
+use super::field_index::version_index::VersionIndex;
+use super::field_index::{FieldIndexBuilderTrait as _, ValueIndexer as _};
+use super::payload_config::{FullPayloadIndexType, PayloadFieldSchemaWithIndexType};
+use crate::common::Flusher;
+use crate::common::operation_error::{OperationError, OperationResult};
+use crate::common::utils::IndexesMap;
+use crate::data_types::facets::{FacetHit, FacetValueHit};
+use crate::data_types::order_by::{Direction, OrderValue};
+use crate::id_tracker::IdTrackerSS;
+use crate::index::field_index::numeric_index::StreamRange;
+use crate::index::field_index::{
+    CardinalityEstimation, FieldIndex, PayloadBlockCondition, PrimaryCondition,
+};
+use crate::index::index_health::{IndexHealth, IndexHealthThresholds};
+use crate::index::index_storage::IndexStorage;
+use crate::index::payload_config::{
+    self, IndexCompression, PAYLOAD_INDEX_CONFIG_FILE, PayloadConfig,
+};
+use crate::index::query_estimator::{EstimationCache, estimate_filter};
+use crate::index::query_optimization::payload_provider::PayloadProvider;
+use crate::index::struct_filter_context::StructFilterContext;
+use crate::index::struct_payload_index_builder::StructPayloadIndexBuilder;
+use crate::index::value_transform::{
+    ValueTransform, transform_field_condition, with_transformed_values,
+};
+use crate::index::values_count_histogram::ValuesCountHistogram;
+use crate::index::visited_pool::VisitedPool;
+use crate::index::{BuildIndexResult, PayloadIndex};
+use crate::json_path::JsonPath;
+use crate::payload_storage::payload_storage_enum::PayloadStorageEnum;
+use crate::payload_storage::{FilterContext, PayloadStorage};
+use crate::telemetry::PayloadIndexTelemetry;
+use crate::types::{
+    Condition, DateTimePayloadType, ExtendedPointId, FieldCondition, Filter, GeoPoint, IdRange,
+    Payload, PayloadContainer, PayloadFieldSchema, PayloadKeyType, PayloadKeyTypeRef,
+    PayloadSchemaType, Range, RangeInterface, SeqNumberType, VectorNameBuf,
+};
+use crate::vector_storage::{VectorStorage, VectorStorageEnum};
+
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
+enum StorageType {
+    #[cfg(feature = "rocksdb")]
+    RocksDbAppendable(std::sync::Arc<parking_lot::RwLock<rocksdb::DB>>),
+    GridstoreAppendable,
+    #[cfg(feature = "rocksdb")]
+    RocksDbNonAppendable(Arc<parking_lot::RwLock<rocksdb::DB>>),
+    GridstoreNonAppendable,
+}
+
+impl StorageType {
+    #[cfg(feature = "rocksdb")]
+    pub fn is_appendable(&self) -> bool {
+        match self {
+            StorageType::RocksDbAppendable(_) => true,
+            StorageType::GridstoreAppendable => true,
+            StorageType::RocksDbNonAppendable(_) => false,
+            StorageType::GridstoreNonAppendable => false,
+        }
+    }
+
+    #[cfg(feature = "rocksdb")]
+    pub fn is_rocksdb(&self) -> bool {
+        match self {
+            StorageType::RocksDbAppendable(_) => true,
+            StorageType::RocksDbNonAppendable(_) => true,
+            StorageType::GridstoreAppendable => false,
+            StorageType::GridstoreNonAppendable => false,
+        }
+    }
+}
+
+/// Number of candidate points to check between reading the clock in deadline-bound queries.
+/// Coarse enough for the clock not to dominate the filtering loop.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+/// Number of points indexed at once while building a field index, before releasing payload
+/// storage for other operations
+const BUILD_CHUNK_SIZE: usize = 10_000;
+
+/// Result of a query bound by a deadline
+#[derive(Debug, Default)]
+pub struct DeadlineQueryResult {
+    /// Points matched before the deadline
+    pub points: Vec<PointOffsetType>,
+    /// Whether the deadline was hit before all candidates were checked
+    pub truncated: bool,
+}
+
+/// Resource usage of all indexes of a single field
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FieldIndexStats {
+    /// Number of points with at least one indexed value
+    pub points_count: usize,
+    /// Approximate RAM usage, see [`FieldIndex::ram_usage_bytes`]
+    pub ram_usage_bytes: usize,
+    /// Size of the index files
+    pub disk_usage_bytes: usize,
+}
+
+/// Schema and stats of all indexed fields, see [`StructPayloadIndex::index_info`]
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PayloadIndexInfo {
+    pub fields: HashMap<PayloadKeyType, FieldIndexInfo>,
+}
+
+/// Schema and stats of the indexes of a single field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FieldIndexInfo {
+    pub schema: PayloadSchemaType,
+    /// Number of points with at least one indexed value
+    pub points_count: usize,
+    /// Whether all files of the indexes exist on disk.
+    /// Indexes stored in RocksDB have no files of their own.
+    pub files_exist: bool,
+}
+
+/// Disagreement between the indexes of a field and the payload storage,
+/// see [`StructPayloadIndex::verify`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexDiscrepancy {
+    pub field: PayloadKeyType,
+    /// Points with indexable values in payload, which are not indexed
+    pub missing: Vec<PointOffsetType>,
+    /// Indexed points without indexable values in payload
+    pub extra: Vec<PointOffsetType>,
+}
+
+/// Checks a deadline once every [`DEADLINE_CHECK_INTERVAL`] calls
+struct DeadlineChecker {
+    deadline: Instant,
+    calls: Cell<usize>,
+    expired: Cell<bool>,
+}
+
+impl DeadlineChecker {
+    fn new(deadline: Instant) -> Self {
+        Self {
+            deadline,
+            calls: Cell::new(0),
+            expired: Cell::new(Instant::now() >= deadline),
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        if self.expired.get() {
+            return true;
+        }
+        let calls = self.calls.get() + 1;
+        self.calls.set(calls);
+        if calls % DEADLINE_CHECK_INTERVAL == 0 && Instant::now() >= self.deadline {
+            self.expired.set(true);
+        }
+        self.expired.get()
+    }
+}
+
+/// `PayloadIndex` implementation, which actually uses index structures for providing faster search
+#[derive(Debug)]
+pub struct StructPayloadIndex {
+    /// Payload storage
+    pub(super) payload: Arc<AtomicRefCell<PayloadStorageEnum>>,
+    /// Used for `has_id` condition and estimating cardinality
+    pub(super) id_tracker: Arc<AtomicRefCell<IdTrackerSS>>,
+    /// Vector storages for each field, used for `has_vector` condition
+    pub(super) vector_storages: HashMap<VectorNameBuf, Arc<AtomicRefCell<VectorStorageEnum>>>,
+    /// Indexes, associated with fields
+    pub field_indexes: IndexesMap,
+    /// Indexes over combinations of fields, see [`CompositeIndex`]
+    composite_indexes: Vec<CompositeIndex>,
+    config: PayloadConfig,
+    /// Root of index persistence dir
+    path: PathBuf,
+    /// Persistence backend of index metadata
+    index_storage: Arc<dyn IndexStorage>,
+    /// Used to select unique point ids
+    visited_pool: VisitedPool,
+    /// Number of points per count of values, for indexed fields which count values
+    values_count_histograms: HashMap<PayloadKeyType, ValuesCountHistogram>,
+    /// Versions of points, used for `version_at_most` condition
+    point_versions: Arc<AtomicRefCell<VersionIndex>>,
+    /// Desired storage type for payload indices, used in builder to pick correct type
+    storage_type: StorageType,
+    /// RocksDB instance, if any index is using it
+    #[cfg(feature = "rocksdb")]
+    db: Option<Arc<parking_lot::RwLock<rocksdb::DB>>>,
+}
+
+impl StructPayloadIndex {
+    fn query_field<'a>(
+        &'a self,
+        condition: &'a PrimaryCondition,
+        hw_counter: &'a HardwareCounterCell,
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
+        match condition {
+            PrimaryCondition::Condition(field_condition) => {
+                let field_key = &field_condition.key;
+                let field_indexes = self.field_indexes.get(field_key)?;
+                self.query_is_empty(field_condition, field_indexes)
+                    .or_else(|| {
+                        field_indexes
+                            .iter()
+                            .find_map(|field_index| field_index.filter(field_condition, hw_counter))
+                    })
+                    .or_else(|| self.query_values_count(field_condition, field_indexes))
+            }
+            PrimaryCondition::Ids(ids) => {
+                Some(Box::new(ids.resolved_point_offsets.iter().copied()))
+            }
+            PrimaryCondition::IdRange(id_range) => {
+                Some(Box::new(self.iter_id_range(id_range.range)))
+            }
+            PrimaryCondition::VersionAtMost(versions) => Some(Box::new(
+                self.iter_version_at_most(versions.version_at_most),
+            )),
+            PrimaryCondition::Composite(composite) => {
+                Some(Box::new(composite.resolved_point_offsets.iter().copied()))
+            }
+            PrimaryCondition::HasVector(vector_name) => {
+                Some(Box::new(self.iter_points_with_vector(vector_name)))
+            }
+        }
+    }
+
+    /// Offsets of available points without values of the field, selected with a complete index
+    /// of the field, like the null index, without reading payload.
+    ///
+    /// The flags of a complete index only cover points, which were indexed since it was built.
+    /// Points added later without ever setting the field have no flags, so every point of the
+    /// id tracker is looked up, and points unknown to the index are empty.
+    fn query_is_empty<'a>(
+        &'a self,
+        condition: &FieldCondition,
+        field_indexes: &'a [FieldIndex],
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
+        if condition.is_empty != Some(true) {
+            return None;
+        }
+        let index = field_indexes.iter().find(|index| index.is_complete())?;
+        let total_points = self.id_tracker.borrow().total_point_count() as PointOffsetType;
+        Some(Box::new((0..total_points).filter(move |&point_id| {
+            index.values_is_empty(point_id) && !self.id_tracker.borrow().is_deleted_point(point_id)
+        })))
+    }
+
+    /// Offsets of available points with a vector stored under `vector_name`, in ascending order.
+    ///
+    /// Vectors of deleted points may remain in the storage, so the id tracker is checked as well.
+    /// Storages are borrowed for a single step only, so no borrow is held between items.
+    fn iter_points_with_vector(
+        &self,
+        vector_name: &VectorNameBuf,
+    ) -> impl Iterator<Item = PointOffsetType> + '_ {
+        let vector_storage = self.vector_storages.get(vector_name);
+        let total_points = match vector_storage {
+            Some(_) => self.id_tracker.borrow().total_point_count() as PointOffsetType,
+            None => 0,
+        };
+        (0..total_points).filter(move |&point_id| {
+            !self.id_tracker.borrow().is_deleted_point(point_id)
+                && vector_storage.is_some_and(|vector_storage| {
+                    !vector_storage.borrow().is_deleted_vector(point_id)
+                })
+        })
+    }
+
+    /// Offsets of points with a numeric ID in the `range`, in ascending order of IDs.
+    ///
+    /// The id tracker is borrowed for a single step only, so no borrow is held between items.
+    fn iter_id_range(&self, range: IdRange) -> impl Iterator<Item = PointOffsetType> + '_ {
+        let mut next_id = Some(range.gte.unwrap_or(0));
+        std::iter::from_fn(move || {
+            let from = next_id?;
+            let id_tracker = self.id_tracker.borrow();
+            match id_tracker
+                .iter_from(Some(ExtendedPointId::NumId(from)))
+                .next()
+            {
+                Some((ExtendedPointId::NumId(id), point_id)) if range.contains_num(id) => {
+                    next_id = id.checked_add(1);
+                    Some(point_id)
+                }
+                // UUIDs follow all numeric IDs
+                _ => {
+                    next_id = None;
+                    None
+                }
+            }
+        })
+    }
+
+    /// Available points with a version not greater than `version_at_most`, in order of versions
+    fn iter_version_at_most(
+        &self,
+        version_at_most: SeqNumberType,
+    ) -> impl Iterator<Item = PointOffsetType> + '_ {
+        // Collected, so the version index is not borrowed while points are checked
+        let point_ids: Vec<_> = self
+            .point_versions
+            .borrow()
+            .points_at_most(version_at_most)
+            .collect();
+        let id_tracker = self.id_tracker.borrow();
+        point_ids
+            .into_iter()
+            .filter(move |&point_id| !id_tracker.is_deleted_point(point_id))
+    }
+
+    /// Update version of the point in the version index, after it was set in the id tracker
+    pub fn update_point_version(&mut self, point_id: PointOffsetType, version: SeqNumberType) {
+        self.point_versions
+            .borrow_mut()
+            .set_version(point_id, version);
+    }
+
+    fn config_path(&self) -> PathBuf {
+        PayloadConfig::get_config_path(&self.path)
+    }
+
+    fn save_config(&self) -> OperationResult<()> {
+        let bytes = serde_json::to_vec(&self.config)?;
+        self.index_storage.put(PAYLOAD_INDEX_CONFIG_FILE, &bytes)
+    }
+
+    fn load_all_fields(&mut self, create_if_missing: bool) -> OperationResult<()> {
+        let mut field_indexes: IndexesMap = Default::default();
+
+        let mut indices = std::mem::take(&mut self.config.indices);
+        let mut is_dirty = false;
+
+        let mut to_rebuild = Vec::new();
+        for (field, payload_schema) in indices.iter_mut() {
+            let (field_index, dirty) =
+                self.load_from_db(field, payload_schema, create_if_missing)?;
+            is_dirty |= dirty;
+            match field_index {
+                Some(field_index) => {
+                    field_indexes.insert(field.clone(), Arc::new(field_index));
+                }
+                None => to_rebuild.push((field, payload_schema)),
+            }
+        }
+
+        // Indexes which could not be loaded are built from scratch. Each build only reads the
+        // payload storage, so fields are built in parallel.
+        let rebuilt = to_rebuild
+            .par_iter()
+            .map(|(field, payload_schema)| {
+                log::debug!("Rebuilding payload index for field `{field}`...");
+                self.build_field_indexes(
+                    field,
+                    &payload_schema.schema,
+                    &HardwareCounterCell::disposable(), // Internal operation
+                )
+            })
+            .collect::<OperationResult<Vec<_>>>()?;
+
+        for ((field, payload_schema), field_index) in to_rebuild.into_iter().zip(rebuilt) {
+            // Persist exact payload index types of newly built indices
+            is_dirty = true;
+            payload_schema.types = field_index
+                .iter()
+                .map(|i| i.get_full_index_type())
+                .collect();
+            field_indexes.insert(field.clone(), Arc::new(field_index));
+        }
+
+        // Put updated payload schemas back into the config
+        self.config.indices = indices;
+
+        if is_dirty {
+            self.save_config()?;
+        }
+
+        self.field_indexes = field_indexes;
+        self.values_count_histograms = self
+            .field_indexes
+            .iter()
+            .filter_map(|(field, indexes)| {
+                let histogram = self.build_values_count_histogram(indexes)?;
+                Some((field.clone(), histogram))
+            })
+            .collect();
+
+        self.composite_indexes = self
+            .config
+            .composite_indexes
+            .iter()
+            .map(
+                |fields| match CompositeIndex::load(fields.clone(), self.index_storage.clone())? {
+                    Some(index) => Ok(index),
+                    None => {
+                        log::debug!("Rebuilding composite payload index for fields {fields:?}...");
+                        self.build_composite_index(fields)
+                    }
+                },
+            )
+            .collect::<OperationResult<_>>()?;
+        Ok(())
+    }
+
+    /// Histogram of values counts of the field indexes, without reading payload.
+    ///
+    /// The histogram is inexact, see [`Self::check_values_counts`] to make it exact.
+    fn build_values_count_histogram(
+        &self,
+        field_indexes: &[FieldIndex],
+    ) -> Option<ValuesCountHistogram> {
+        let counting_index = values_counting_index(field_indexes)?;
+        let mut histogram = ValuesCountHistogram::default();
+        for point_id in self.id_tracker.borrow().iter_ids() {
+            histogram.add(counting_index.values_count(point_id));
+        }
+        Some(histogram)
+    }
+
+    /// Whether values counts of the field indexes match payload values of every point, see
+    /// [`ValuesCountHistogram::is_exact`].
+    ///
+    /// Reads payload of all points, so it is only done after building indexes, not on load.
+    fn check_values_counts(
+        &self,
+        field: PayloadKeyTypeRef,
+        field_indexes: &[FieldIndex],
+    ) -> OperationResult<bool> {
+        let Some(counting_index) = values_counting_index(field_indexes) else {
+            return Ok(false);
+        };
+        let hw_counter = HardwareCounterCell::disposable(); // Internal operation
+        let payload_storage = self.payload.borrow();
+        for point_id in self.id_tracker.borrow().iter_ids() {
+            let point_payload = payload_storage.get_sequential(point_id, &hw_counter)?;
+            if !is_values_count_exact(counting_index, point_id, &point_payload.get_value(field)) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether `values_count` conditions on the `field` can be answered by its indexes, as
+    /// their values counts are known to match payload.
+    pub(in crate::index) fn has_exact_values_count(&self, field: PayloadKeyTypeRef) -> bool {
+        self.values_count_histograms
+            .get(field)
+            .is_some_and(ValuesCountHistogram::is_exact)
+    }
+
+    /// Offsets of available points matching a `values_count` condition, read from the values
+    /// count of an index of the field, so payload is never read.
+    ///
+    /// Returns `None` unless every value of the field is indexed, as points with values the index
+    /// doesn't count would be missed. The query then falls back to checking payload of all points.
+    fn query_values_count<'a>(
+        &'a self,
+        condition: &FieldCondition,
+        field_indexes: &'a [FieldIndex],
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
+        let values_count = condition.values_count?;
+        if !self.has_exact_values_count(&condition.key) {
+            return None;
+        }
+        let index = values_counting_index(field_indexes)?;
+        let total_points = self.id_tracker.borrow().total_point_count() as PointOffsetType;
+        Some(Box::new((0..total_points).filter(move |&point_id| {
+            !self.id_tracker.borrow().is_deleted_point(point_id)
+                && values_count.check_count(index.values_count(point_id))
+        })))
+    }
+
+    /// Load indexes of the `field` from disk.
+    ///
+    /// Returns `None` if indexes have to be rebuilt, along with whether the config has changed.
+    #[cfg_attr(not(feature = "rocksdb"), allow(clippy::needless_pass_by_ref_mut))]
+    fn load_from_db(
+        &mut self,
+        field: PayloadKeyTypeRef,
+        // TODO: refactor this and remove the &mut reference.
+        payload_schema: &mut PayloadFieldSchemaWithIndexType,
+        create_if_missing: bool,
+    ) -> OperationResult<(Option<Vec<FieldIndex>>, bool)> {
+        let total_point_count = self.id_tracker.borrow().total_point_count();
+        let mut rebuild = false;
+        let mut is_dirty = false;
+
+        let indexes = if payload_schema.types.is_empty() {
+            let indexes = self.selector(&payload_schema.schema).new_index(
+                field,
+                &payload_schema.schema,
+                create_if_missing,
+            )?;
+
+            if let Some(mut indexes) = indexes {
+                debug_assert!(
+                    !indexes
+                        .iter()
+                        .any(|index| matches!(index, FieldIndex::NullIndex(_))),
+                    "index selector is not expected to provide null index",
+                );
+
+                // Special null index complements every index.
+                if let Some(null_index) = IndexSelector::new_null_index(
+                    &self.path,
+                    field,
+                    total_point_count,
+                    create_if_missing,
+                )? {
+                    indexes.push(null_index);
+                }
+
+                // Persist exact payload index types
+                is_dirty = true;
+                payload_schema.types = indexes.iter().map(|i| i.get_full_index_type()).collect();
+
+                indexes
+            } else {
+                rebuild = true;
+                vec![]
+            }
+        } else {
+            payload_schema
+                .types
+                .iter()
+                // Load each index
+                .map(|index| {
+                    self.selector_with_type(index).and_then(|selector| {
+                        selector.new_index_with_type(
+                            field,
+                            &payload_schema.schema,
+                            index,
+                            &self.path,
+                            total_point_count,
+                            create_if_missing,
+                        )
+                    })
+                })
+                // Interrupt loading indices if one fails to load
+                // Set rebuild flag if any index fails to load
+                .take_while(|index| {
+                    let is_loaded = match index {
+                        Ok(index) => index.is_some(),
+                        Err(err) => {
+                            log::warn!(
+                                "Failed to load payload index for field `{field}`, rebuilding: {err}"
+                            );
+                            false
+                        }
+                    };
+                    rebuild |= !is_loaded;
+                    is_loaded
+                })
+                .filter_map(|index| index.transpose())
+                .collect::<OperationResult<Vec<_>>>()?
+        };
+
+        // Actively migrate away from RocksDB indices
+        // Naively implemented by just rebuilding the indices from scratch
+        #[cfg(feature = "rocksdb")]
+        if common::flags::feature_flags().migrate_rocksdb_payload_indices
+            && indexes.iter().any(|index| index.is_rocksdb())
+        {
+            log::info!("Migrating away from RocksDB indices for field `{field}`");
+
+            // Change storage type, set skip RocksDB flag and persist
+            // Needed to not use RocksDB when rebuilding indices
+            match self.storage_type {
+                StorageType::RocksDbAppendable(_) => {
+                    self.storage_type = StorageType::GridstoreAppendable;
+                }
+                StorageType::GridstoreAppendable => {}
+                StorageType::RocksDbNonAppendable(_) => {
+                    self.storage_type = StorageType::GridstoreNonAppendable;
+                }
+                StorageType::GridstoreNonAppendable => {}
+            }
+            self.config.skip_rocksdb.replace(true);
+
+            // Clean-up all existing indices
+            for index in indexes {
+                index.cleanup().map_err(|err| {
+                    OperationError::service_error(format!(
+                        "Failed to clean up payload index for field `{field}` before rebuild: {err}"
+                    ))
+                })?;
+            }
+
+            return Ok((None, true));
+        }
+
+        // If index is not properly loaded, it has to be rebuilt
+        if rebuild {
+            // Remove partially loaded indices and unreadable files, so nothing is reused
+            for index in indexes {
+                index.cleanup()?;
+            }
+            IndexSelector::clear_field_dirs(&self.path, field)?;
+            return Ok((None, is_dirty));
+        }
+
+        Ok((Some(indexes), is_dirty))
+    }
+
+    /// Builder for opening the index with named options
+    pub fn builder() -> StructPayloadIndexBuilder {
+        StructPayloadIndexBuilder::new()
+    }
+
+    /// Open the index with positional options.
+    ///
+    /// Kept for compatibility, prefer [`Self::builder`] which names every option.
+    pub fn open(
+        payload: Arc<AtomicRefCell<PayloadStorageEnum>>,
+        id_tracker: Arc<AtomicRefCell<IdTrackerSS>>,
+        vector_storages: HashMap<VectorNameBuf, Arc<AtomicRefCell<VectorStorageEnum>>>,
+        path: &Path,
+        is_appendable: bool,
+        create: bool,
+    ) -> OperationResult<Self> {
+        Self::builder()
+            .payload(payload)
+            .id_tracker(id_tracker)
+            .vector_storages(vector_storages)
+            .path(path)
+            .appendable(is_appendable)
+            .create(create)
+            .build()
+    }
+
+    pub(super) fn open_with_storages(
+        payload: Arc<AtomicRefCell<PayloadStorageEnum>>,
+        id_tracker: Arc<AtomicRefCell<IdTrackerSS>>,
+        vector_storages: HashMap<VectorNameBuf, Arc<AtomicRefCell<VectorStorageEnum>>>,
+        path: &Path,
+        index_storage: Arc<dyn IndexStorage>,
+        is_appendable: bool,
+        create: bool,
+    ) -> OperationResult<Self> {
+        create_dir_all(path)?;
+        let stored_config = index_storage.get(PAYLOAD_INDEX_CONFIG_FILE)?;
+        let is_new_config = stored_config.is_none();
+        let config = if let Some(bytes) = stored_config {
+            serde_json::from_slice(&bytes)?
+        } else {
+            #[cfg(feature = "rocksdb")]
+            {
+                let mut new_config = PayloadConfig::default();
+                let skip_rocksdb = if is_appendable {
+                    common::flags::feature_flags().payload_index_skip_mutable_rocksdb
+                } else {
+                    common::flags::feature_flags().payload_index_skip_rocksdb
+                };
+                if skip_rocksdb {
+                    new_config.skip_rocksdb = Some(true);
+                }
+                new_config
+            }
+
+            #[cfg(not(feature = "rocksdb"))]
+            {
+                PayloadConfig::default()
+            }
+        };
+
+        #[cfg(feature = "rocksdb")]
+        let mut db = None;
+        let storage_type = if is_appendable {
+            #[cfg(feature = "rocksdb")]
+            {
+                let skip_rocksdb = config.skip_rocksdb.unwrap_or(false);
+                if !skip_rocksdb {
+                    let rocksdb = crate::common::rocksdb_wrapper::open_db_with_existing_cf(path)
+                        .map_err(|err| {
+                            OperationError::service_error(format!("RocksDB open error: {err}"))
+                        })?;
+                    db.replace(rocksdb.clone());
+                    StorageType::RocksDbAppendable(rocksdb)
+                } else {
+                    StorageType::GridstoreAppendable
+                }
+            }
+            #[cfg(not(feature = "rocksdb"))]
+            {
+                StorageType::GridstoreAppendable
+            }
+        } else {
+            #[cfg(feature = "rocksdb")]
+            {
+                let skip_rocksdb = config.skip_rocksdb.unwrap_or(false);
+                if !skip_rocksdb {
+                    let rocksdb = crate::common::rocksdb_wrapper::open_db_with_existing_cf(path)
+                        .map_err(|err| {
+                            OperationError::service_error(format!("RocksDB open error: {err}"))
+                        })?;
+                    db.replace(rocksdb.clone());
+                    StorageType::RocksDbNonAppendable(rocksdb)
+                } else {
+                    StorageType::GridstoreNonAppendable
+                }
+            }
+            #[cfg(not(feature = "rocksdb"))]
+            {
+                StorageType::GridstoreNonAppendable
+            }
+        };
+
+        // Also prematurely open RocksDB if any index is still using it
+        #[cfg(feature = "rocksdb")]
+        if db.is_none() && config.indices.any_is_rocksdb() {
+            log::debug!("Opening RocksDB to load old payload index");
+            let rocksdb =
+                crate::common::rocksdb_wrapper::open_db_with_existing_cf(path).map_err(|err| {
+                    OperationError::service_error(format!("RocksDB open error: {err}"))
+                })?;
+            db.replace(rocksdb);
+        }
+
+        let point_versions = VersionIndex::build(&id_tracker.borrow());
+        let mut index = StructPayloadIndex {
+            payload,
+            id_tracker,
+            vector_storages,
+            field_indexes: Default::default(),
+            composite_indexes: Default::default(),
+            config,
+            path: path.to_owned(),
+            index_storage,
+            visited_pool: Default::default(),
+            values_count_histograms: Default::default(),
+            point_versions: Arc::new(AtomicRefCell::new(point_versions)),
+            storage_type,
+            #[cfg(feature = "rocksdb")]
+            db,
+        };
+
+        if is_new_config {
+            // Save default config
+            index.save_config()?;
+        }
+
+        index.load_all_fields(create)?;
+
+        // If we have a RocksDB instance, but no index using it, completely delete it here
+        #[cfg(feature = "rocksdb")]
+        if !index.storage_type.is_rocksdb()
+            && !index.config.indices.any_is_rocksdb()
+            && let Some(db) = index.db.take()
+        {
+            match Arc::try_unwrap(db) {
+                Ok(db) => {
+                    log::trace!(
+                        "Deleting RocksDB for payload indices, no payload index uses it anymore"
+                    );
+
+                    // Close RocksDB instance
+                    let db = db.into_inner();
+                    drop(db);
+
+                    // Destroy all RocksDB files
+                    let options = crate::common::rocksdb_wrapper::make_db_options();
+                    match rocksdb::DB::destroy(&options, &index.path) {
+                        Ok(_) => log::debug!("Deleted RocksDB for payload indices"),
+                        Err(err) => {
+                            log::warn!("Failed to delete RocksDB for payload indices: {err}")
+                        }
+                    }
+                }
+                // Here we don't have exclusive ownership of RocksDB, which prevents us from
+                // controlling and closing the instance. Because of it, we cannot destroy the
+                // RocksDB files, and leave them behind. We don't consider this a problem, because
+                // a future optimization run will get rid of these files.
+                Err(db) => {
+                    log::warn!(
+                        "RocksDB for payload indices could not be deleted, does not have exclusive ownership"
+                    );
+                    index.db.replace(db);
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Build indexes of the `field` over all available points.
+    ///
+    /// Points are indexed in chunks, releasing the payload storage in between, so payload
+    /// mutations are not blocked for the whole build. Points added during the build are indexed
+    /// in a final pass. Changes to already indexed points are expected to be applied to the
+    /// resulting index by the caller, same as for any other update.
+    pub fn build_field_indexes(
+        &self,
+        field: PayloadKeyTypeRef,
+        payload_schema: &PayloadFieldSchema,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Vec<FieldIndex>> {
+        self.build_field_indexes_inner(
+            field,
+            payload_schema,
+            None,
+            &mut |_, _| {},
+            &self.path,
+            hw_counter,
+        )
+    }
+
+    /// Same as [`Self::build_field_indexes`], but reports progress of the build to
+    /// `on_progress` after every chunk of points, as `(processed, total)` number of points.
+    ///
+    /// The total grows if points are added during the build.
+    pub fn build_field_indexes_with_progress(
+        &self,
+        field: PayloadKeyTypeRef,
+        payload_schema: &PayloadFieldSchema,
+        mut on_progress: impl FnMut(usize, usize),
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Vec<FieldIndex>> {
+        self.build_field_indexes_inner(
+            field,
+            payload_schema,
+            None,
+            &mut on_progress,
+            &self.path,
+            hw_counter,
+        )
+    }
+
+    /// Same as [`Self::build_field_indexes`], but passes every value given to the index builders
+    /// to `on_value` as well, so other structures can be built without scanning the payload again.
+    ///
+    /// Values are passed after value transforms are applied, in order of point offsets.
+    /// Values of points changed during the build are passed as first read, they are not passed
+    /// again when such points are reindexed.
+    pub fn build_field_indexes_streaming(
+        &self,
+        field: PayloadKeyTypeRef,
+        payload_schema: &PayloadFieldSchema,
+        on_value: Option<&mut dyn FnMut(PointOffsetType, &Value)>,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Vec<FieldIndex>> {
+        self.build_field_indexes_inner(
+            field,
+            payload_schema,
+            on_value,
+            &mut |_, _| {},
+            &self.path,
+            hw_counter,
+        )
+    }
+
+    fn build_field_indexes_inner(
+        &self,
+        field: PayloadKeyTypeRef,
+        payload_schema: &PayloadFieldSchema,
+        mut on_value: Option<&mut dyn FnMut(PointOffsetType, &Value)>,
+        on_progress: &mut dyn FnMut(usize, usize),
+        dir: &Path,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Vec<FieldIndex>> {
+        let mut builders = self
+            .selector_in(payload_schema, dir)
+            .index_builder(field, payload_schema)?;
+
+        // Special null index complements every index.
+        let null_index = IndexSelector::null_builder(dir, field)?;
+        builders.push(null_index);
+
+        for index in &mut builders {
+            index.init()?;
+        }
+
+        let transforms = self.config.value_transforms(field);
+
+        let mut add_points = |point_ids: &[PointOffsetType]| -> OperationResult<()> {
+            // Only hold the payload borrow for a single chunk, so holders of the shared payload
+            // storage may change points between chunks, see `Self::reconcile_build`
+            let payload_storage = self.payload.borrow();
+            for &point_id in point_ids {
+                let point_payload = payload_storage.get_sequential(point_id, hw_counter)?;
+                let field_value = &point_payload.get_value(field);
+                with_transformed_values(transforms, field_value, |field_value| {
+                    if let Some(on_value) = on_value.as_mut() {
+                        for value in field_value {
+                            on_value(point_id, value);
+                        }
+                    }
+                    for builder in builders.iter_mut() {
+                        builder.add_point(point_id, field_value, hw_counter)?;
+                    }
+                    OperationResult::Ok(())
+                })?;
+            }
+            Ok(())
+        };
+
+        // Fetch ids once up front, so the id tracker is not borrowed during the build
+        let (point_ids, build_version) = {
+            let id_tracker = self.id_tracker.borrow();
+            let point_ids: Vec<_> = id_tracker.iter_ids().collect();
+            let build_version = point_ids
+                .iter()
+                .filter_map(|&point_id| id_tracker.internal_version(point_id))
+                .max();
+            (point_ids, build_version)
+        };
+        let mut visited_list = self.visited_pool.get(point_ids.len());
+        let mut processed = 0;
+        for chunk in point_ids.chunks(BUILD_CHUNK_SIZE) {
+            add_points(chunk)?;
+            for &point_id in chunk {
+                visited_list.check_and_update_visited(point_id);
+            }
+            processed += chunk.len();
+            on_progress(processed, point_ids.len());
+        }
+
+        // Final delta pass over points added while the index was being built
+        let added_point_ids: Vec<_> = self
+            .id_tracker
+            .borrow()
+            .iter_ids()
+            .filter(|&point_id| !visited_list.check(point_id))
+            .collect();
+        let total = point_ids.len() + added_point_ids.len();
+        for chunk in added_point_ids.chunks(BUILD_CHUNK_SIZE) {
+            add_points(chunk)?;
+            processed += chunk.len();
+            on_progress(processed, total);
+        }
+
+        let mut field_indexes = builders
+            .into_iter()
+            .map(|builder| builder.finalize())
+            .collect::<OperationResult<Vec<_>>>()?;
+
+        self.reconcile_build(
+            field,
+            &mut field_indexes,
+            &point_ids,
+            build_version,
+            hw_counter,
+        )?;
+
+        Ok(field_indexes)
+    }
+
+    /// Bring `field_indexes` built in chunks up to date with points changed during the build.
+    ///
+    /// Writers going through this index need `&mut self`, so they can't run while it is being
+    /// built. Payload storage and id tracker are shared with the segment though, so their other
+    /// holders may change points between chunks, e.g. from `on_progress`. Such points are found
+    /// by versions, which only grow: any point changed after the build started has a version
+    /// above `build_version`.
+    ///
+    /// Deleted points are removed from the indexes, changed points are indexed again with their
+    /// current payload. Immutable indexes can't index points again, so the build fails and
+    /// has to be repeated if changed points are found for them.
+    fn reconcile_build(
+        &self,
+        field: PayloadKeyTypeRef,
+        field_indexes: &mut [FieldIndex],
+        built_point_ids: &[PointOffsetType],
+        build_version: Option<SeqNumberType>,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let transforms = self.config.value_transforms(field);
+        let id_tracker = self.id_tracker.borrow();
+        let payload_storage = self.payload.borrow();
+        for &point_id in built_point_ids {
+            if id_tracker.is_deleted_point(point_id) {
+                for index in field_indexes.iter_mut() {
+                    index.remove_point(point_id)?;
+                }
+            } else if id_tracker.internal_version(point_id) > build_version {
+                let point_payload = payload_storage.get_sequential(point_id, hw_counter)?;
+                update_point_in_indexes(
+                    field_indexes,
+                    point_id,
+                    &point_payload.get_value(field),
+                    transforms,
+                    None,
+                    hw_counter,
+                )
+                .map_err(|err| {
+                    OperationError::service_error(format!(
+                        "Point {point_id} changed while building index for field `{field}`, \
+                         build has to be repeated: {err}"
+                    ))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of available points
+    ///
+    /// - excludes soft deleted points
+    pub fn available_point_count(&self) -> usize {
+        self.id_tracker.borrow().available_point_count()
+    }
+
+    /// Whether all conditions of the `filter` are answered by indexes, without reading payload.
+    ///
+    /// Allows to choose between filtering in the index and post-filtering while reading
+    /// payloads, before a filter context is constructed.
+    pub fn is_indexable(&self, filter: &Filter) -> bool {
+        filter
+            .iter_conditions()
+            .all(|condition| self.is_condition_indexable(condition))
+    }
+
+    pub fn struct_filtered_context<'a>(
+        &'a self,
+        filter: &'a Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> StructFilterContext<'a> {
+        self.struct_filtered_context_with_cache(filter, &EstimationCache::default(), hw_counter)
+    }
+
+    /// Same as [`Self::struct_filtered_context`], but reuses estimations of conditions,
+    /// which were already computed for the same `filter`.
+    fn struct_filtered_context_with_cache<'a>(
+        &'a self,
+        filter: &'a Filter,
+        estimation_cache: &EstimationCache,
+        hw_counter: &HardwareCounterCell,
+    ) -> StructFilterContext<'a> {
+        let payload_provider = PayloadProvider::new(self.payload.clone());
+
+        let (optimized_filter, _) = self.optimize_filter(
+            filter,
+            payload_provider,
+            self.available_point_count(),
+            estimation_cache,
+            hw_counter,
+        );
+
+        StructFilterContext::new(optimized_filter)
+    }
+
+    pub fn get_telemetry_data(&self) -> Vec<PayloadIndexTelemetry> {
+        self.field_indexes
+            .iter()
+            .flat_map(|(name, field)| -> Vec<PayloadIndexTelemetry> {
+                field
+                    .iter()
+                    .map(|field| field.get_telemetry_data().set_name(name.to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Resource usage of the indexes of every indexed field
+    pub fn field_index_stats(&self) -> HashMap<PayloadKeyType, FieldIndexStats> {
+        self.field_indexes
+            .iter()
+            .map(|(field, indexes)| {
+                let stats = FieldIndexStats {
+                    // Same as in `indexed_points`, indexes of a field cover the same points
+                    points_count: self.indexed_points(field),
+                    ram_usage_bytes: indexes.iter().map(FieldIndex::ram_usage_bytes).sum(),
+                    disk_usage_bytes: indexes.iter().map(FieldIndex::disk_usage_bytes).sum(),
+                };
+                (field.clone(), stats)
+            })
+            .collect()
+    }
+
+    /// Schema and stats of every indexed field, collected at once
+    pub fn index_info(&self) -> PayloadIndexInfo {
+        let fields = self
+            .config
+            .indices
+            .iter()
+            .map(|(field, indexed_field)| {
+                let files = self
+                    .field_indexes
+                    .get(field)
+                    .map(|indexes| indexes.iter().flat_map(FieldIndex::files).collect_vec())
+                    .unwrap_or_default();
+                let info = FieldIndexInfo {
+                    schema: indexed_field.schema.kind(),
+                    points_count: self.indexed_points(field),
+                    files_exist: !files.is_empty() && files.iter().all(|file| file.exists()),
+                };
+                (field.clone(), info)
+            })
+            .collect();
+        PayloadIndexInfo { fields }
+    }
+
+    #[cfg(feature = "rocksdb")]
+    pub fn restore_database_snapshot(
+        snapshot_path: &Path,
+        segment_path: &Path,
+    ) -> OperationResult<()> {
+        crate::rocksdb_backup::restore(snapshot_path, &segment_path.join("payload_index"))
+    }
+
+    /// Check that indexes of every field accepted by `filter` can be updated.
+    ///
+    /// Indexes shared with a query snapshot can't be updated, see [`field_indexes_mut`].
+    /// Called before anything is written, so a write either updates payload storage together
+    /// with all affected indexes, or fails without changing either of them.
+    fn check_indexes_writable(
+        &self,
+        mut filter: impl FnMut(&PayloadKeyType) -> bool,
+    ) -> OperationResult<()> {
+        // Snapshots are only captured through `&self`, so while we hold `&mut self` the number
+        // of references can only go down, and a passed check holds until the write is done
+        for (field, field_indexes) in &self.field_indexes {
+            if filter(field) && Arc::strong_count(field_indexes) > 1 {
+                return Err(shared_indexes_error(field));
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the point from all field indexes.
+    ///
+    /// Only field indexes are updated, payload storage is left untouched.
+    pub fn remove_point(&mut self, point_id: PointOffsetType) -> OperationResult<()> {
+        self.check_indexes_writable(|_| true)?;
+        for (field, field_indexes) in self.field_indexes.iter_mut() {
+            remove_point_from_indexes(
+                field_indexes_mut(field, field_indexes)?,
+                point_id,
+                self.values_count_histograms.get_mut(field),
+            )?;
+        }
+        for composite_index in &mut self.composite_indexes {
+            composite_index.remove_point(point_id);
+        }
+        Ok(())
+    }
+
+    /// Check that range conditions of `filter` have bounds matching the indexed field type.
+    ///
+    /// Fields indexed as keywords only support lexicographic ranges of keywords.
+    /// A numeric range on such field would silently match nothing, so it is rejected instead.
+    /// Checked by segment searches, ordered scrolls and facets before using the filter.
+    pub fn validate_range_conditions(&self, filter: &Filter) -> OperationResult<()> {
+        let mut ranges = Vec::new();
+        collect_numeric_ranges(filter, None, &mut ranges);
+
+        for key in ranges {
+            let Some(indexes) = self.field_indexes.get(&key) else {
+                continue;
+            };
+            // Null index complements every index, it doesn't tell the type of values
+            let mut value_indexes = indexes
+                .iter()
+                .filter(|index| !matches!(index, FieldIndex::NullIndex(_)))
+                .peekable();
+            let keyword_only = value_indexes.peek().is_some()
+                && value_indexes.all(|index| matches!(index, FieldIndex::KeywordIndex(_)));
+            if keyword_only {
+                return Err(OperationError::ValidationError {
+                    description: format!(
+                        "Field `{key}` is indexed as keyword and does not support numeric range bounds, use a `keyword` range for a lexicographic range",
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that values of indexed fields in the `payload` match types of their indexes.
+    ///
+    /// Index builders silently skip values of other types, so such points would be missing
+    /// from the index. Null values are not indexed either way and are not reported.
+    ///
+    /// Called on ingestion by [`PayloadIndex::set_payload`] and
+    /// [`PayloadIndex::overwrite_payload`], if enabled with
+    /// [`Self::set_validate_payload_types`]. Disabled by default, so existing data with mixed
+    /// types keeps loading.
+    pub fn validate_payload(&self, payload: &Payload) -> OperationResult<()> {
+        let mismatches = self
+            .config
+            .indices
+            .iter()
+            .filter_map(|(field, index)| {
+                let kind = index.schema.kind();
+                payload
+                    .get_value(field)
+                    .into_iter()
+                    .flat_map(|value| match value {
+                        Value::Array(values) => Either::Left(values.iter()),
+                        value => Either::Right(std::iter::once(value)),
+                    })
+                    .any(|value| !value.is_null() && !value_matches_schema(value, kind))
+                    .then_some((field, kind))
+            })
+            .sorted_by_key(|(field, _)| field.to_string())
+            .map(|(field, kind)| format!("`{field}` expected {}", kind.name()))
+            .join(", ");
+
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+        Err(OperationError::ValidationError {
+            description: format!(
+                "Payload values do not match types of indexed fields: {mismatches}"
+            ),
+        })
+    }
+
+    pub fn config(&self) -> &PayloadConfig {
+        &self.config
+    }
+
+    /// Set value transforms of a field and persist them.
+    ///
+    /// If the field is already indexed, its index is rebuilt with the new transforms.
+    pub fn set_value_transforms(
+        &mut self,
+        field: PayloadKeyTypeRef,
+        transforms: Vec<ValueTransform>,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        if self.config.value_transforms(field) == transforms.as_slice() {
+            return Ok(());
+        }
+
+        if transforms.is_empty() {
+            self.config.value_transforms.remove(field);
+        } else {
+            self.config
+                .value_transforms
+                .insert(field.to_owned(), transforms);
+        }
+        self.save_config()?;
+
+        if let Some(indexed_field) = self.config.indices.get(field) {
+            let payload_schema = indexed_field.schema.clone();
+            self.drop_index(field)?;
+            self.set_indexed(field, payload_schema, hw_counter)?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the fraction of available points, above which filters expected to match more points
+    /// are answered with a full scan, even if primary clauses are available. Persisted.
+    ///
+    /// `None` always uses primary clauses when available.
+    pub fn set_full_scan_threshold(&mut self, threshold: Option<f64>) -> OperationResult<()> {
+        if let Some(threshold) = threshold
+            && !(threshold > 0.0 && threshold <= 1.0)
+        {
+            return Err(OperationError::ValidationError {
+                description: format!(
+                    "Full scan threshold must be in range (0, 1], got {threshold}"
+                ),
+            });
+        }
+        if self.config.full_scan_threshold == threshold {
+            return Ok(());
+        }
+        self.config.full_scan_threshold = threshold;
+        self.save_config()
+    }
+
+    /// Set compression of index files persisted as a whole, like composite indexes. Persisted.
+    ///
+    /// Applies to files written from now on, files written before keep loading either way.
+    pub fn set_compression(
+        &mut self,
+        compression: Option<IndexCompression>,
+    ) -> OperationResult<()> {
+        if let Some(IndexCompression { level }) = compression
+            && !zstd::compression_level_range().contains(&level)
+        {
+            return Err(OperationError::ValidationError {
+                description: format!(
+                    "Compression level must be in range {:?}, got {level}",
+                    zstd::compression_level_range(),
+                ),
+            });
+        }
+        if self.config.compression == compression {
+            return Ok(());
+        }
+        self.config.compression = compression;
+        self.save_config()
+    }
+
+    /// Enable or disable validation of ingested payloads against types of field indexes,
+    /// see [`Self::validate_payload`]. Persisted.
+    ///
+    /// Payloads stored before are not checked.
+    pub fn set_validate_payload_types(&mut self, validate: bool) -> OperationResult<()> {
+        if self.config.validate_payload_types == validate {
+            return Ok(());
+        }
+        self.config.validate_payload_types = validate;
+        self.save_config()
+    }
+
+    /// Whether the query is expected to match so many points, that scanning all of them is
+    /// cheaper than selecting them with primary clauses first.
+    pub(super) fn prefers_full_scan(
+        &self,
+        query_cardinality: &CardinalityEstimation,
+        available_points: usize,
+    ) -> bool {
+        self.config.full_scan_threshold.is_some_and(|threshold| {
+            query_cardinality.exp as f64 > threshold * available_points as f64
+        })
+    }
+
+    /// Build a composite index over the combination of `fields` and persist it.
+    ///
+    /// Filters with an exact match condition on every one of the `fields` select points with a
+    /// single lookup in the composite index. Does nothing if the index already exists.
+    pub fn set_composite_index(&mut self, fields: &[PayloadKeyType]) -> OperationResult<()> {
+        if fields.len() < 2 {
+            return Err(OperationError::ValidationError {
+                description: "Composite index requires at least two fields".to_string(),
+            });
+        }
+        if fields.iter().unique().count() != fields.len() {
+            return Err(OperationError::ValidationError {
+                description: "Fields of a composite index must be distinct".to_string(),
+            });
+        }
+        if self.config.composite_indexes.iter().any(|f| f == fields) {
+            return Ok(());
+        }
+
+        let composite_index = self.build_composite_index(fields)?;
+        // Persist the index before it is referenced by the config
+        let flusher = composite_index.flusher(self.config.compression);
+        flusher()?;
+
+        self.config.composite_indexes.push(fields.to_vec());
+        self.save_config()?;
+        self.composite_indexes.push(composite_index);
+        Ok(())
+    }
+
+    /// Drop the composite index over `fields`, returns whether it existed
+    pub fn drop_composite_index(&mut self, fields: &[PayloadKeyType]) -> OperationResult<bool> {
+        let Some(position) = self
+            .composite_indexes
+            .iter()
+            .position(|index| index.fields() == fields)
+        else {
+            return Ok(false);
+        };
+
+        self.config.composite_indexes.retain(|f| f != fields);
+        self.save_config()?;
+
+        self.composite_indexes.remove(position).cleanup()?;
+        Ok(true)
+    }
+
+    fn build_composite_index(&self, fields: &[PayloadKeyType]) -> OperationResult<CompositeIndex> {
+        let hw_counter = HardwareCounterCell::disposable(); // Internal operation
+        let mut composite_index = CompositeIndex::new(fields.to_vec(), self.index_storage.clone());
+
+        let payload_storage = self.payload.borrow();
+        for point_id in self.id_tracker.borrow().iter_ids() {
+            let payload = payload_storage.get_sequential(point_id, &hw_counter)?;
+            composite_index.add_point(point_id, &payload);
+        }
+        Ok(composite_index)
+    }
+
+    /// Re-index the point in composite indexes, whose fields are selected by `is_affected`
+    fn update_composite_indexes(
+        &mut self,
+        point_id: PointOffsetType,
+        payload: &Payload,
+        is_affected: impl Fn(&[PayloadKeyType]) -> bool,
+    ) {
+        for composite_index in &mut self.composite_indexes {
+            if is_affected(composite_index.fields()) {
+                composite_index.add_point(point_id, payload);
+            }
+        }
+    }
+
+    /// Index multiple fields at once.
+    ///
+    /// Indexes of all new fields are built in parallel, and config is saved only once after all
+    /// of them are built. Fields which are already indexed are left untouched, regardless of
+    /// their schema.
+    pub fn set_indexed_batch(
+        &mut self,
+        fields: &[(PayloadKeyType, PayloadSchemaType)],
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|(field, schema_type)| {
+                (field.clone(), PayloadFieldSchema::FieldType(*schema_type))
+            })
+            .collect();
+        self.set_indexed_schemas_batch(&fields, hw_counter)
+    }
+
+    /// Index all fields indexed in the `other` index, which are not indexed here yet.
+    ///
+    /// Only the schema is replicated, including params of the indexes. Indexes are built over
+    /// payloads of this index, same as with [`Self::set_indexed_batch`].
+    pub fn copy_schema_from(&mut self, other: &StructPayloadIndex) -> OperationResult<()> {
+        let fields = other
+            .indexed_fields()
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect_vec();
+        let hw_counter = HardwareCounterCell::disposable(); // Internal operation
+        self.set_indexed_schemas_batch(&fields, &hw_counter)
+    }
+
+    fn set_indexed_schemas_batch(
+        &mut self,
+        fields: &[(PayloadKeyType, PayloadFieldSchema)],
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let mut seen = HashSet::new();
+        let new_fields: Vec<_> = fields
+            .iter()
+            .filter(|(field, _)| !self.config.indices.contains_key(field))
+            // First occurrence of a field in the batch wins
+            .filter(|(field, _)| seen.insert(field))
+            .collect();
+
+        if new_fields.is_empty() {
+            return Ok(());
+        }
+
+        // Counter cells are not shared between threads, each build reports to the accumulator
+        let hw_acc = hw_counter.new_accumulator();
+        let built = new_fields
+            .par_iter()
+            .map(|(field, payload_schema)| {
+                let hw_counter = HardwareCounterCell::new_with_accumulator(hw_acc.clone());
+                self.build_field_indexes(field, payload_schema, &hw_counter)
+            })
+            .collect::<OperationResult<Vec<_>>>()?;
+
+        for ((field, payload_schema), field_index) in new_fields.into_iter().zip(built) {
+            let index_types = field_index
+                .iter()
+                .map(|i| i.get_full_index_type())
+                .collect();
+            if let Some(mut histogram) = self.build_values_count_histogram(&field_index) {
+                histogram.set_exact(self.check_values_counts(field, &field_index)?);
+                self.values_count_histograms
+                    .insert(field.clone(), histogram);
+            }
+            self.field_indexes
+                .insert(field.clone(), Arc::new(field_index));
+            self.config.indices.insert(
+                field.clone(),
+                PayloadFieldSchemaWithIndexType::new(payload_schema.clone(), index_types),
+            );
+        }
+
+        self.save_config()?;
+
+        Ok(())
+    }
+
+    /// Drop indexes of multiple fields at once.
+    ///
+    /// Config is saved once, before any index files are removed, so an interrupted drop can only
+    /// leave orphan files behind, but never config entries without their index.
+    /// Fields which are not indexed are skipped.
+    pub fn drop_indexes(&mut self, fields: &[PayloadKeyType]) -> OperationResult<()> {
+        let mut is_removed = false;
+        let mut removed_indexes = Vec::new();
+        for field in fields {
+            is_removed |= self.config.indices.remove(field).is_some();
+            self.values_count_histograms.remove(field);
+            if let Some(indexes) = self.field_indexes.remove(field) {
+                is_removed = true;
+                removed_indexes.push(indexes);
+            }
+        }
+
+        if !is_removed {
+            return Ok(());
+        }
+
+        self.save_config()?;
+
+        for indexes in removed_indexes {
+            cleanup_field_indexes(indexes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Drop indexes of all fields and all composite indexes at once, reclaiming their disk space.
+    ///
+    /// Leftover directories of the dropped fields are removed as well, files which are already
+    /// gone are skipped. Dropping again is a no-op.
+    pub fn drop_all_indexes(&mut self) -> OperationResult<()> {
+        let fields = self
+            .config
+            .indices
+            .keys()
+            .chain(self.field_indexes.keys())
+            .unique()
+            .cloned()
+            .collect_vec();
+
+        self.drop_indexes(&fields)?;
+
+        for field in &fields {
+            IndexSelector::clear_field_dirs(&self.path, field)?;
+        }
+
+        if !self.config.composite_indexes.is_empty() {
+            self.config.composite_indexes.clear();
+            self.save_config()?;
+        }
+        for composite_index in std::mem::take(&mut self.composite_indexes) {
+            composite_index.cleanup()?;
+        }
+
+        Ok(())
+    }
+
+    /// Add values of the point `payload` to all field indexes, without rescanning other points.
+    ///
+    /// Only field indexes are updated, payload storage is expected to hold the `payload` already.
+    /// Fields missing from the payload are indexed as missing values. Changes are persisted by
+    /// the regular [`PayloadIndex::flusher`].
+    pub fn add_point(
+        &mut self,
+        point_id: PointOffsetType,
+        payload: &Payload,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        self.check_indexes_writable(|_| true)?;
+        for (field, field_index) in &mut self.field_indexes {
+            update_point_in_indexes(
+                field_indexes_mut(field, field_index)?,
+                point_id,
+                &payload.get_value(field),
+                self.config.value_transforms(field),
+                self.values_count_histograms.get_mut(field),
+                hw_counter,
+            )?;
+        }
+        for composite_index in &mut self.composite_indexes {
+            composite_index.add_point(point_id, payload);
+        }
+        Ok(())
+    }
+
+    /// Update indexes of a single `field` of the point, after its value changed from `old` to
+    /// `new`.
+    ///
+    /// Only field indexes are updated, payload storage is expected to hold the `new` value already.
+    /// `None` stands for a missing value, so `old: None` indexes the point and `new: None`
+    /// removes it. Does nothing if the field is not indexed.
+    ///
+    /// Indexes are only written, if the values they keep differ between `old` and `new`. E.g.
+    /// adding a null to a keyword array only updates the null index.
+    pub fn update_point(
+        &mut self,
+        point_id: PointOffsetType,
+        field: PayloadKeyTypeRef,
+        old: Option<&Value>,
+        new: Option<&Value>,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        if old == new {
+            return Ok(());
+        }
+        self.check_indexes_writable(|indexed| indexed == field)?;
+        if self
+            .composite_indexes
+            .iter()
+            .any(|index| index.fields().contains(field))
+        {
+            let payload = self.payload.borrow().get(point_id, hw_counter)?;
+            self.update_composite_indexes(point_id, &payload, |fields| fields.contains(field));
+        }
+        let Some(field_index) = self.field_indexes.get_mut(field) else {
+            return Ok(());
+        };
+        let old_value: Vec<&Value> = old.into_iter().collect();
+        let new_value: Vec<&Value> = new.into_iter().collect();
+        diff_point_in_indexes(
+            field_indexes_mut(field, field_index)?,
+            point_id,
+            &old_value,
+            &new_value,
+            self.config.value_transforms(field),
+            self.values_count_histograms.get_mut(field),
+            hw_counter,
+        )
+    }
+
+    /// Payload types of the indexes actually loaded for the `field`.
+    ///
+    /// Unlike the declared schema, this is inferred from the loaded index variants, so it can be
+    /// compared with [`Self::config`] to detect indexes built differently from their declaration.
+    /// The null index is not reported, as it does not serve any particular type.
+    /// Returns `None` if the field is not indexed.
+    pub fn loaded_index_type(&self, field: &PayloadKeyType) -> Option<HashSet<PayloadSchemaType>> {
+        let indexes = self.field_indexes.get(field)?;
+        Some(
+            indexes
+                .iter()
+                .filter_map(|index| index.get_full_index_type().index_type.schema_type())
+                .collect(),
+        )
+    }
+
+    pub fn is_tenant(&self, field: &PayloadKeyType) -> bool {
+        self.config
+            .indices
+            .get(field)
+            .map(|indexed_field| indexed_field.schema.is_tenant())
+            .unwrap_or(false)
+    }
+
+    pub fn iter_filtered_points<'a>(
+        &'a self,
+        filter: &'a Filter,
+        id_tracker: &'a IdTrackerSS,
+        query_cardinality: &'a CardinalityEstimation,
+        hw_counter: &'a HardwareCounterCell,
+    ) -> impl Iterator<Item = PointOffsetType> + 'a {
+        self.iter_filtered_points_until(
+            filter,
+            id_tracker,
+            query_cardinality,
+            EstimationCache::default(),
+            hw_counter,
+            || false,
+        )
+    }
+
+    /// Same as [`Self::iter_filtered_points`], but stops iterating candidates once `stop` returns
+    /// true. `stop` is called for every candidate point before it is checked.
+    /// Estimations from `estimation_cache` are reused to build the filter context.
+    pub(super) fn iter_filtered_points_until<'a, F>(
+        &'a self,
+        filter: &'a Filter,
+        id_tracker: &'a IdTrackerSS,
+        query_cardinality: &'a CardinalityEstimation,
+        estimation_cache: EstimationCache,
+        hw_counter: &'a HardwareCounterCell,
+        stop: F,
+    ) -> impl Iterator<Item = PointOffsetType> + 'a
+    where
+        F: Fn() -> bool + 'a,
+    {
+        if query_cardinality.primary_clauses.is_empty()
+            || self.prefers_full_scan(query_cardinality, id_tracker.available_point_count())
+        {
+            let full_scan_iterator = id_tracker.iter_ids().take_while(move |_| !stop());
+            let struct_filtered_context =
+                self.struct_filtered_context_with_cache(filter, &estimation_cache, hw_counter);
+            // Worst case: query expected to return few matches, but index can't be used
+            let matched_points =
+                full_scan_iterator.filter(move |i| struct_filtered_context.check(*i));
+
+            EitherVariant::A(matched_points)
+        } else {
+            // CPU-optimized strategy here: points are made unique before applying other filters.
+            let mut visited_list = self.visited_pool.get(id_tracker.total_point_count());
+
+            // Iterate the most selective clauses first, so points shared with broader clauses
+            // are already visited by the time those are enumerated.
+            let mut primary_clauses = query_cardinality.primary_clauses.iter().collect::<Vec<_>>();
+            if primary_clauses.len() > 1 {
+                primary_clauses.sort_by_cached_key(|clause| {
+                    self.primary_clause_cardinality(clause, hw_counter)
+                });
+            }
+
+            // If even one iterator is None, we should replace the whole thing with
+            // an iterator over all ids.
+            let primary_clause_iterators: Option<Vec<_>> = primary_clauses
+                .into_iter()
+                .map(move |clause| self.query_field(clause, hw_counter))
+                .collect();
+
+            if let Some(primary_iterators) = primary_clause_iterators {
+                let all_conditions_are_primary = filter
+                    .iter_conditions()
+                    .all(|condition| query_cardinality.is_primary(condition));
+
+                let joined_primary_iterator = primary_iterators
+                    .into_iter()
+                    .flatten()
+                    .take_while(move |_| !stop());
+
+                return if all_conditions_are_primary {
+                    // All conditions are primary clauses,
+                    // We can avoid post-filtering
+                    let iter = joined_primary_iterator
+                        .filter(move |&id| !visited_list.check_and_update_visited(id));
+                    EitherVariant::B(iter)
+                } else {
+                    // Some conditions are primary clauses, some are not
+                    let struct_filtered_context = self.struct_filtered_context_with_cache(
+                        filter,
+                        &estimation_cache,
+                        hw_counter,
+                    );
+                    let iter = joined_primary_iterator.filter(move |&id| {
+                        !visited_list.check_and_update_visited(id)
+                            && struct_filtered_context.check(id)
+                    });
+                    EitherVariant::C(iter)
+                };
+            }
+
+            // We can't use primary conditions, so we fall back to iterating over all ids
+            // and applying full filter.
+            let struct_filtered_context =
+                self.struct_filtered_context_with_cache(filter, &estimation_cache, hw_counter);
+
+            let iter = id_tracker
+                .iter_ids()
+                .take_while(move |_| !stop())
+                .measure_hw_with_cell(hw_counter, size_of::<PointOffsetType>(), |i| {
+                    i.cpu_counter()
+                })
+                .filter(move |&id| {
+                    !visited_list.check_and_update_visited(id) && struct_filtered_context.check(id)
+                });
+
+            EitherVariant::D(iter)
+        }
+    }
+
+    /// Return at most `limit` point ids which satisfy the filter.
+    ///
+    /// Candidates are checked lazily, so no more points are checked once `limit` matches are found.
+    pub fn query_points_limited(
+        &self,
+        query: &Filter,
+        limit: usize,
+        hw_counter: &HardwareCounterCell,
+    ) -> Vec<PointOffsetType> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let query_cardinality = self.estimate_cardinality(query, hw_counter);
+        let id_tracker = self.id_tracker.borrow();
+        self.iter_filtered_points(query, &*id_tracker, &query_cardinality, hw_counter)
+            .take(limit)
+            .collect()
+    }
+
+    /// Return the `candidates` which satisfy the filter, in order of `candidates`.
+    ///
+    /// Only the given offsets are checked, so no points are selected from indexes or scanned.
+    /// Useful to post-filter points found by other means, e.g. by a vector search.
+    /// Deleted points are never returned.
+    pub fn query_points_within(
+        &self,
+        query: &Filter,
+        candidates: &[PointOffsetType],
+        hw_counter: &HardwareCounterCell,
+    ) -> Vec<PointOffsetType> {
+        let candidates = {
+            let id_tracker = self.id_tracker.borrow();
+            candidates
+                .iter()
+                .copied()
+                .filter(|&point_id| !id_tracker.is_deleted_point(point_id))
+                .collect_vec()
+        };
+        let filter_context = self.struct_filtered_context(query, hw_counter);
+        let mut matches = vec![false; candidates.len()];
+        filter_context.check_batch(&candidates, &mut matches);
+        candidates
+            .into_iter()
+            .zip(matches)
+            .filter_map(|(point_id, is_match)| is_match.then_some(point_id))
+            .collect()
+    }
+
+    /// Return up to `limit` points ordered by the values of a numeric `field`.
+    ///
+    /// Points are read from the sorted numeric index, so only the returned page is checked
+    /// against the `filter`. Scrolling starts from `offset` (inclusive), which allows to resume
+    /// from the last value of a previous page.
+    /// Points with multiple values are returned once per value.
+    pub fn scroll_by_field(
+        &self,
+        field: PayloadKeyTypeRef,
+        direction: Direction,
+        filter: Option<&Filter>,
+        offset: Option<OrderValue>,
+        limit: usize,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Vec<(OrderValue, PointOffsetType)>> {
+        let numeric_index = self
+            .field_indexes
+            .get(field)
+            .and_then(|indexes| indexes.iter().find_map(|index| index.as_numeric()))
+            .ok_or_else(|| OperationError::MissingRangeIndexForOrderBy {
+                key: field.to_string(),
+            })?;
+
+        let range_iter = numeric_index.stream_range_from(direction, offset);
+        let directed_range_iter = match direction {
+            Direction::Asc => Either::Left(range_iter),
+            Direction::Desc => Either::Right(range_iter.rev()),
+        };
+
+        let filter_context = filter.map(|filter| self.filter_context(filter, hw_counter));
+
+        Ok(directed_range_iter
+            .filter(|(_, point_id)| {
+                filter_context
+                    .as_ref()
+                    .is_none_or(|context| context.check(*point_id))
+            })
+            .take(limit)
+            .collect())
+    }
+
+    /// Order `candidates` by the distance from `center` to their values of the geo `field`.
+    ///
+    /// Returns pairs of point id and distance in meters, closest first.
+    /// For points with multiple values, the closest value is used.
+    /// Candidates without values in the index are skipped.
+    pub fn order_by_geo_distance(
+        &self,
+        field: PayloadKeyTypeRef,
+        center: GeoPoint,
+        candidates: impl Iterator<Item = PointOffsetType>,
+    ) -> OperationResult<Vec<(PointOffsetType, f64)>> {
+        let geo_index = self
+            .field_indexes
+            .get(field)
+            .and_then(|indexes| {
+                indexes.iter().find_map(|index| match index {
+                    FieldIndex::GeoIndex(geo_index) => Some(geo_index),
+                    _ => None,
+                })
+            })
+            .ok_or_else(|| OperationError::MissingGeoIndexForOrdering {
+                key: field.to_string(),
+            })?;
+
+        let mut ordered = candidates
+            .filter_map(|point_id| {
+                geo_index
+                    .min_distance(point_id, center)
+                    .map(|distance| (point_id, distance))
+            })
+            .collect::<Vec<_>>();
+        ordered.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        Ok(ordered)
+    }
+
+    /// Return point ids which satisfy the filter, but give up once `deadline` is reached.
+    ///
+    /// Points matched so far are returned with the `truncated` flag set if the deadline was hit.
+    /// If there is no time left to estimate the query, the cheapest estimation is used instead.
+    pub fn query_points_with_deadline(
+        &self,
+        query: &Filter,
+        deadline: Instant,
+        hw_counter: &HardwareCounterCell,
+    ) -> DeadlineQueryResult {
+        let query_cardinality =
+            self.estimate_cardinality_with_deadline(query, deadline, hw_counter);
+        let checker = DeadlineChecker::new(deadline);
+
+        let id_tracker = self.id_tracker.borrow();
+        let points = self
+            .iter_filtered_points_until(
+                query,
+                &*id_tracker,
+                &query_cardinality,
+                EstimationCache::default(),
+                hw_counter,
+                || checker.is_expired(),
+            )
+            .collect();
+
+        DeadlineQueryResult {
+            points,
+            truncated: checker.expired.get(),
+        }
+    }
+
+    /// Count distinct points, which match at least one of the `conditions`.
+    ///
+    /// If every condition is served by a map index, posting lists are unioned without
+    /// materializing the matched offsets, which gives an exact count.
+    /// Otherwise, falls back to scanning candidates of the equivalent `should` filter.
+    pub fn count_distinct(
+        &self,
+        conditions: &[FieldCondition],
+        hw_counter: &HardwareCounterCell,
+    ) -> usize {
+        if conditions.is_empty() {
+            return 0;
+        }
+
+        let id_tracker = self.id_tracker.borrow();
+
+        let posting_iterators: Option<Vec<_>> = conditions
+            .iter()
+            .map(|condition| {
+                let condition = transform_field_condition(
+                    self.config.value_transforms(&condition.key),
+                    condition,
+                );
+                self.field_indexes
+                    .get(&condition.key)?
+                    .iter()
+                    .find_map(|index| match index {
+                        FieldIndex::KeywordIndex(_)
+                        | FieldIndex::IntMapIndex(_)
+                        | FieldIndex::UuidMapIndex(_) => index.filter(&condition, hw_counter),
+                        _ => None,
+                    })
+            })
+            .collect();
+
+        if let Some(posting_iterators) = posting_iterators {
+            let mut visited_list = self.visited_pool.get(id_tracker.total_point_count());
+            return posting_iterators
+                .into_iter()
+                .flatten()
+                .filter(|&id| {
+                    !visited_list.check_and_update_visited(id) && !id_tracker.is_deleted_point(id)
+                })
+                .count();
+        }
+
+        let filter = Filter {
+            should: Some(conditions.iter().cloned().map(Condition::Field).collect()),
+            min_should: None,
+            must: None,
+            must_not: None,
+        };
+        let query_cardinality = self.estimate_cardinality(&filter, hw_counter);
+        self.iter_filtered_points(&filter, &*id_tracker, &query_cardinality, hw_counter)
+            .count()
+    }
+
+    /// Offsets of points with values in any index of the `field`, in ascending order.
+    ///
+    /// Union of posting lists of the indexes, merged without duplicates. Full-text indexes don't
+    /// expose their posting lists, so their points are checked one by one instead.
+    /// The null index is not taken into account, as it tracks values of any type. So points
+    /// with values, which the indexes of the field can't hold, are not returned.
+    /// Returns `None` if the field is not indexed.
+    pub fn indexed_points_iter(
+        &self,
+        field: PayloadKeyTypeRef,
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + '_>> {
+        let indexes = self.field_indexes.get(field)?;
+        // Reads are not measured, the iterator is meant for consistency checks
+        let hw_counter = HardwareCounterCell::disposable();
+        let total_points = self.id_tracker.borrow().total_point_count() as PointOffsetType;
+        let postings = indexes
+            .iter()
+            .filter(|index| !matches!(index, FieldIndex::NullIndex(_)))
+            .map(|index| match index.iter_indexed_points(&hw_counter) {
+                Some(points) => points.collect_vec(),
+                None => (0..total_points)
+                    .filter(|&point_id| !index.values_is_empty(point_id))
+                    .collect_vec(),
+            })
+            .collect_vec();
+        Some(Box::new(postings.into_iter().kmerge().dedup()))
+    }
+
+    /// Same as [`PayloadIndex::payload_blocks`], but fails if the `field` is not indexed.
+    ///
+    /// Allows to tell an unindexed field apart from an indexed one without blocks above the
+    /// `threshold`, as both give no blocks.
+    pub fn payload_blocks_checked(
+        &self,
+        field: PayloadKeyTypeRef,
+        threshold: usize,
+    ) -> OperationResult<Box<dyn Iterator<Item = PayloadBlockCondition> + '_>> {
+        let indexes =
+            self.field_indexes
+                .get(field)
+                .ok_or_else(|| OperationError::MissingPayloadIndex {
+                    key: field.to_string(),
+                })?;
+        let field_clone = field.to_owned();
+        Ok(Box::new(indexes.iter().flat_map(move |field_index| {
+            field_index.payload_blocks(threshold, field_clone.clone())
+        })))
+    }
+
+    /// Same as [`Self::payload_blocks_checked`], but blocks are bounded by `max` points as well.
+    ///
+    /// Blocks of values with more than `max` points are split by ranges of point offsets into
+    /// parts of balanced size, see [`PayloadBlockCondition::point_offsets`]. Parts of a split
+    /// value may have fewer than `min` points.
+    pub fn payload_blocks_bounded(
+        &self,
+        field: PayloadKeyTypeRef,
+        min: usize,
+        max: usize,
+    ) -> OperationResult<Vec<PayloadBlockCondition>> {
+        if max == 0 || min > max {
+            return Err(OperationError::ValidationError {
+                description: format!(
+                    "Invalid bounds of payload blocks: min {min}, max {max}, \
+                     expected 0 < max and min <= max"
+                ),
+            });
+        }
+
+        let indexes =
+            self.field_indexes
+                .get(field)
+                .ok_or_else(|| OperationError::MissingPayloadIndex {
+                    key: field.to_string(),
+                })?;
+        let hw_counter = HardwareCounterCell::disposable(); // Internal operation
+
+        let mut blocks = Vec::new();
+        for block in self.payload_blocks_checked(field, min)? {
+            // Cardinality of some blocks is estimated, so actual points are counted
+            if block.cardinality <= max {
+                blocks.push(block);
+                continue;
+            }
+            let mut points = indexes
+                .iter()
+                .find_map(|index| index.filter(&block.condition, &hw_counter))
+                .map(|points| points.collect_vec())
+                .unwrap_or_default();
+            if points.len() <= max {
+                blocks.push(block);
+                continue;
+            }
+            points.sort_unstable();
+            points.dedup();
+
+            let parts = points.len().div_ceil(max);
+            for part in 0..parts {
+                let chunk = &points[part * points.len() / parts..(part + 1) * points.len() / parts];
+                let (Some(&first), Some(&last)) = (chunk.first(), chunk.last()) else {
+                    continue;
+                };
+                blocks.push(PayloadBlockCondition {
+                    condition: block.condition.clone(),
+                    cardinality: chunk.len(),
+                    point_offsets: Some(first..last + 1),
+                });
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Values of the `field` with the most points, counted over points matching the `filter`.
+    ///
+    /// Without a filter, counts are read from posting lists of the field index directly.
+    /// With a filter, posting list of every value is intersected with the points matching it.
+    /// Hits are sorted by count in descending order, ties by value, and truncated to `top`.
+    pub fn facet(
+        &self,
+        field: PayloadKeyTypeRef,
+        filter: Option<&Filter>,
+        top: usize,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Vec<FacetValueHit>> {
+        let facet_index = self.get_facet_index(field)?;
+
+        let mut hits: Vec<_> = match filter {
+            None => facet_index
+                .iter_counts_per_value()
+                .filter(|hit| hit.count > 0)
+                .map(|hit| FacetHit {
+                    value: hit.value.to_owned(),
+                    count: hit.count,
+                })
+                .collect(),
+            Some(filter) => {
+                let mut candidates = self
+                    .visited_pool
+                    .get(self.id_tracker.borrow().total_point_count());
+                for point_id in self.query_points(filter, hw_counter) {
+                    candidates.check_and_update_visited(point_id);
+                }
+                facet_index
+                    .iter_values_map(hw_counter)
+                    .filter_map(|(value, point_ids)| {
+                        let count = point_ids
+                            .unique()
+                            .filter(|&point_id| candidates.check(point_id))
+                            .count();
+                        (count > 0).then(|| FacetHit {
+                            value: value.to_owned(),
+                            count,
+                        })
+                    })
+                    .collect()
+            }
+        };
+
+        hits.sort_unstable_by(|a, b| b.cmp(a));
+        hits.truncate(top);
+        Ok(hits)
+    }
+
+    /// Smallest and largest values of the integer or float `field`, among points matching
+    /// the `filter` if given.
+    ///
+    /// Without a filter, values are read from the ends of the sorted numeric index.
+    /// With a filter, values of all matching points are scanned.
+    /// Returns `None` if the field has no integer or float index, or if no matching point has
+    /// a value in the field.
+    pub fn min_max(
+        &self,
+        field: PayloadKeyTypeRef,
+        filter: Option<&Filter>,
+        hw_counter: &HardwareCounterCell,
+    ) -> Option<(f64, f64)> {
+        let numeric_index =
+            self.field_indexes
+                .get(field)?
+                .iter()
+                .find_map(|index| match index {
+                    FieldIndex::IntIndex(_) | FieldIndex::FloatIndex(_) => index.as_numeric(),
+                    _ => None,
+                })?;
+
+        let (min, max) = match filter {
+            None => {
+                let mut values =
+                    numeric_index.stream_range(&RangeInterface::Float(Range::default()));
+                let (min, _) = values.next()?;
+                let max = values.next_back().map_or(min, |(max, _)| max);
+                (min, max)
+            }
+            Some(filter) => self
+                .query_points(filter, hw_counter)
+                .into_iter()
+                .flat_map(|point_id| numeric_index.get_ordering_values(point_id))
+                .minmax()
+                .into_option()?,
+        };
+
+        let as_float = |value| match value {
+            OrderValue::Int(value) => value as f64,
+            OrderValue::Float(value) => value,
+        };
+        Some((as_float(min), as_float(max)))
+    }
+
+    /// Number of distinct values of the `field`, read from the key count of its map index.
+    ///
+    /// Returns `None` if the field has no map index. Numeric indexes don't keep values as keys,
+    /// so they are not scanned for distinct values.
+    pub fn distinct_count(&self, field: PayloadKeyTypeRef) -> Option<usize> {
+        self.field_indexes
+            .get(field)?
+            .iter()
+            .find_map(|index| match index {
+                FieldIndex::KeywordIndex(index) => Some(index.get_unique_values_count()),
+                FieldIndex::IntMapIndex(index) => Some(index.get_unique_values_count()),
+                FieldIndex::UuidMapIndex(index) => Some(index.get_unique_values_count()),
+                _ => None,
+            })
+    }
+
+    /// Select which type of PayloadIndex to use for the field
+    fn selector(&self, payload_schema: &PayloadFieldSchema) -> IndexSelector<'_> {
+        self.selector_in(payload_schema, &self.path)
+    }
+
+    /// Selector of indexes of the `payload_schema`, which keeps index files in the `dir`
+    fn selector_in<'a>(
+        &'a self,
+        payload_schema: &PayloadFieldSchema,
+        dir: &'a Path,
+    ) -> IndexSelector<'a> {
+        let is_on_disk = payload_schema.is_on_disk();
+
+        match &self.storage_type {
+            #[cfg(feature = "rocksdb")]
+            StorageType::RocksDbAppendable(db) => IndexSelector::RocksDb(IndexSelectorRocksDb {
+                db,
+                is_appendable: true,
+            }),
+            StorageType::GridstoreAppendable => {
+                IndexSelector::Gridstore(IndexSelectorGridstore { dir })
+            }
+            #[cfg(feature = "rocksdb")]
+            StorageType::RocksDbNonAppendable(db) => {
+                // legacy logic: we keep rocksdb, but load mmap indexes
+                if !is_on_disk {
+                    return IndexSelector::RocksDb(IndexSelectorRocksDb {
+                        db,
+                        is_appendable: false,
+                    });
+                }
+
+                IndexSelector::Mmap(IndexSelectorMmap { dir, is_on_disk })
+            }
+            StorageType::GridstoreNonAppendable => {
+                IndexSelector::Mmap(IndexSelectorMmap { dir, is_on_disk })
+            }
+        }
+    }
+
+    fn selector_with_type(
+        &self,
+        index_type: &FullPayloadIndexType,
+    ) -> OperationResult<IndexSelector<'_>> {
+        let selector = match index_type.storage_type {
+            payload_config::StorageType::Gridstore => {
+                IndexSelector::Gridstore(IndexSelectorGridstore { dir: &self.path })
+            }
+            payload_config::StorageType::RocksDb => {
+                #[cfg(feature = "rocksdb")]
+                {
+                    let db = match (&self.storage_type, &self.db) {
+                        (
+                            StorageType::RocksDbAppendable(db)
+                            | StorageType::RocksDbNonAppendable(db),
+                            _,
+                        ) => db,
+                        (
+                            StorageType::GridstoreAppendable | StorageType::GridstoreNonAppendable,
+                            Some(db),
+                        ) => db,
+                        (
+                            StorageType::GridstoreAppendable | StorageType::GridstoreNonAppendable,
+                            None,
+                        ) => {
+                            return Err(OperationError::service_error(
+                                "Loading payload index failed: Configured storage type and payload schema mismatch!",
+                            ));
+                        }
+                    };
+
+                    return Ok(IndexSelector::RocksDb(IndexSelectorRocksDb {
+                        db,
+                        is_appendable: self.storage_type.is_appendable(),
+                    }));
+                }
+
+                #[cfg(not(feature = "rocksdb"))]
+                return Err(OperationError::service_error(
+                    "Loading payload index failed: Index is RocksDB but RocksDB feature is disabled.",
+                ));
+            }
+            payload_config::StorageType::Mmap { is_on_disk } => {
+                IndexSelector::Mmap(IndexSelectorMmap {
+                    dir: &self.path,
+                    is_on_disk,
+                })
+            }
+        };
+
+        Ok(selector)
+    }
+
+    pub fn get_facet_index(&self, key: &JsonPath) -> OperationResult<FacetIndexEnum<'_>> {
+        self.field_indexes
+            .get(key)
+            .and_then(|index| index.iter().find_map(|index| index.as_facet_index()))
+            .ok_or_else(|| OperationError::MissingMapIndexForFacet {
+                key: key.to_string(),
+            })
+    }
+
+    /// Check skew of posting lists of the `field`, to detect indexes which don't pay off.
+    ///
+    /// Returns `None` if the field has no index with posting lists per value.
+    pub fn index_health(
+        &self,
+        field: &PayloadKeyType,
+        thresholds: &IndexHealthThresholds,
+    ) -> Option<IndexHealth> {
+        let facet_index = self.get_facet_index(field).ok()?;
+        let posting_sizes = facet_index.iter_counts_per_value().map(|hit| hit.count);
+        Some(IndexHealth::from_posting_sizes(
+            posting_sizes,
+            self.available_point_count(),
+            thresholds,
+        ))
+    }
+
+    pub fn populate(&self) -> OperationResult<()> {
+        for (_, field_indexes) in self.field_indexes.iter() {
+            for index in field_indexes.iter() {
+                index.populate()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read on-disk field indexes into the page cache ahead of the first query.
+    ///
+    /// Only a latency hint, which can be called concurrently with queries. In-memory indexes
+    /// are already loaded and are skipped. Returns the number of bytes of index files read.
+    pub fn warm_up(&self) -> OperationResult<usize> {
+        let mut bytes = 0;
+        for field_indexes in self.field_indexes.values() {
+            for index in field_indexes.iter().filter(|index| index.is_on_disk()) {
+                index.populate()?;
+                bytes += index.disk_usage_bytes();
+            }
+        }
+        Ok(bytes)
+    }
+
+    pub fn clear_cache(&self) -> OperationResult<()> {
+        for (_, field_indexes) in self.field_indexes.iter() {
+            for index in field_indexes.iter() {
+                index.clear_cache()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear_cache_if_on_disk(&self) -> OperationResult<()> {
+        for (_, field_indexes) in self.field_indexes.iter() {
+            for index in field_indexes.iter() {
+                if index.is_on_disk() {
+                    index.clear_cache()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `estimation` is the exact number of points matching `query`.
+///
+/// Only a single primary clause, which resolves matching points up front, gives an exact count.
+/// Other estimations may have equal `min` and `max` without being exact, as estimations of
+/// field indexes don't account for every value, and combined estimations are approximations.
+fn is_exact_count(query: &Filter, estimation: &CardinalityEstimation) -> bool {
+    let Filter {
+        should: None,
+        min_should: None,
+        must: Some(must),
+        must_not: None,
+    } = query
+    else {
+        return false;
+    };
+    let [clause] = estimation.primary_clauses.as_slice() else {
+        return false;
+    };
+    matches!(
+        clause,
+        PrimaryCondition::Ids(_) | PrimaryCondition::Composite(_)
+    ) && must
+        .iter()
+        .all(|condition| estimation.is_primary(condition))
+}
+
+/// Index, which values count can be used for `values_count` conditions.
+fn values_counting_index(field_indexes: &[FieldIndex]) -> Option<&FieldIndex> {
+    field_indexes.iter().find(|index| index.counts_values())
+}
+
+/// Whether the values count of the point in `index` is the count, which `values_count`
+/// conditions see in its payload `field_value`.
+///
+/// Indexes skip values of other types, so counts differ if any value was not indexed.
+/// Conditions count elements of every value separately, so multiple values of a point, like
+/// from a path through nested arrays, can't be compared with a single count.
+fn is_values_count_exact(
+    index: &FieldIndex,
+    point_id: PointOffsetType,
+    field_value: &[&Value],
+) -> bool {
+    let payload_count = match field_value {
+        [] | [Value::Null] => 0,
+        [Value::Array(values)] => values.len(),
+        [_] => 1,
+        _ => return false,
+    };
+    index.values_count(point_id) == payload_count
+}
+
+/// Mutable access to the indexes of a field.
+///
+/// Fails if the indexes are still shared with a query snapshot, as indexes can't be copied.
+/// Writers check this with [`StructPayloadIndex::check_indexes_writable`] before changing
+/// anything, so a failure here doesn't leave payload storage and indexes out of sync.
+fn field_indexes_mut<'a>(
+    field: &PayloadKeyType,
+    field_indexes: &'a mut Arc<Vec<FieldIndex>>,
+) -> OperationResult<&'a mut Vec<FieldIndex>> {
+    Arc::get_mut(field_indexes).ok_or_else(|| shared_indexes_error(field))
+}
+
+fn shared_indexes_error(field: &PayloadKeyType) -> OperationError {
+    OperationError::service_error(format!(
+        "Payload index of field `{field}` can't be updated while held by a query snapshot"
+    ))
+}
+
+/// Collect full keys of all numeric or datetime range conditions in the filter.
+fn collect_numeric_ranges(
+    filter: &Filter,
+    nested_path: Option<&JsonPath>,
+    keys: &mut Vec<JsonPath>,
+) {
+    for condition in filter.iter_conditions() {
+        match condition {
+            Condition::Field(field_condition) => match &field_condition.range {
+                Some(RangeInterface::Float(_) | RangeInterface::DateTime(_)) => {
+                    keys.push(JsonPath::extend_or_new(nested_path, &field_condition.key));
+                }
+                Some(RangeInterface::Keyword(_)) | None => {}
+            },
+            Condition::Nested(nested) => {
+                let full_path = JsonPath::extend_or_new(nested_path, &nested.array_key());
+                collect_numeric_ranges(nested.filter(), Some(&full_path), keys);
+            }
+            Condition::Filter(filter) => collect_numeric_ranges(filter, nested_path, keys),
+            Condition::IsEmpty(_)
+            | Condition::IsNull(_)
+            | Condition::FieldVsField(_)
+            | Condition::HasId(_)
+            | Condition::HasIdRange(_)
+            | Condition::HasVector(_)
+            | Condition::VersionAtMost(_)
+            | Condition::CustomIdChecker(_) => {}
+        }
+    }
+}
+
+/// Whether a single payload value can be indexed by an index of the `kind`
+fn value_matches_schema(value: &Value, kind: PayloadSchemaType) -> bool {
+    match kind {
+        PayloadSchemaType::Keyword | PayloadSchemaType::Text => value.is_string(),
+        PayloadSchemaType::Integer => value.is_i64(),
+        PayloadSchemaType::Float => value.is_number(),
+        PayloadSchemaType::Bool => value.is_boolean(),
+        PayloadSchemaType::Geo => GeoMapIndex::get_value(value).is_some(),
+        PayloadSchemaType::Datetime => value
+            .as_str()
+            .is_some_and(|value| DateTimePayloadType::from_str(value).is_ok()),
+        PayloadSchemaType::Uuid => value
+            .as_str()
+            .is_some_and(|value| uuid::Uuid::parse_str(value).is_ok()),
+    }
+}
+
+/// Remove storage of dropped indexes of a field.
+///
+/// If the indexes are still held by a query snapshot, only their files are removed. Their
+/// memory is released once the last snapshot holding them is dropped.
+fn cleanup_field_indexes(field_indexes: Arc<Vec<FieldIndex>>) -> OperationResult<()> {
+    match Arc::try_unwrap(field_indexes) {
+        Ok(field_indexes) => {
+            for index in field_indexes {
+                index.cleanup()?;
+            }
+        }
+        Err(field_indexes) => {
+            for file in field_indexes.iter().flat_map(|index| index.files()) {
+                if file.is_file() {
+                    remove_file(&file)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove a single point from all indexes of a field
+fn remove_point_from_indexes(
+    field_indexes: &mut [FieldIndex],
+    point_id: PointOffsetType,
+    histogram: Option<&mut ValuesCountHistogram>,
+) -> OperationResult<()> {
+    if let (Some(histogram), Some(index)) = (histogram, values_counting_index(field_indexes)) {
+        histogram.remove(index.values_count(point_id));
+    }
+    for index in field_indexes {
+        index.remove_point(point_id)?;
+    }
+    Ok(())
+}
+
+/// Index or remove the value of a single point in all indexes of a field
+fn update_point_in_indexes(
+    field_indexes: &mut [FieldIndex],
+    point_id: PointOffsetType,
+    field_value: &[&Value],
+    transforms: &[ValueTransform],
+    histogram: Option<&mut ValuesCountHistogram>,
+    hw_counter: &HardwareCounterCell,
+) -> OperationResult<()> {
+    if field_value.is_empty() {
+        return remove_point_from_indexes(field_indexes, point_id, histogram);
+    }
+
+    let old_values_count = values_counting_index(field_indexes)
+        .map(|index| index.values_count(point_id))
+        .unwrap_or_default();
+
+    with_transformed_values(transforms, field_value, |field_value| {
+        for index in field_indexes.iter_mut() {
+            index.add_point(point_id, field_value, hw_counter)?;
+        }
+        OperationResult::Ok(())
+    })?;
+
+    if let (Some(histogram), Some(index)) = (histogram, values_counting_index(field_indexes)) {
+        histogram.update(old_values_count, index.values_count(point_id));
+        histogram
+            .set_exact(histogram.is_exact() && is_values_count_exact(index, point_id, field_value));
+    }
+    Ok(())
+}
+
+/// Update values of a single point in all indexes of a field from `old_value` to `new_value`,
+/// writing only indexes, which values changed
+fn diff_point_in_indexes(
+    field_indexes: &mut [FieldIndex],
+    point_id: PointOffsetType,
+    old_value: &[&Value],
+    new_value: &[&Value],
+    transforms: &[ValueTransform],
+    histogram: Option<&mut ValuesCountHistogram>,
+    hw_counter: &HardwareCounterCell,
+) -> OperationResult<()> {
+    if old_value.is_empty() || new_value.is_empty() {
+        return update_point_in_indexes(
+            field_indexes,
+            point_id,
+            new_value,
+            transforms,
+            histogram,
+            hw_counter,
+        );
+    }
+
+    let old_values_count = values_counting_index(field_indexes)
+        .map(|index| index.values_count(point_id))
+        .unwrap_or_default();
+
+    with_transformed_values(transforms, old_value, |old_value| {
+        with_transformed_values(transforms, new_value, |new_value| {
+            for index in field_indexes.iter_mut() {
+                index.update_point(point_id, old_value, new_value, hw_counter)?;
+            }
+            OperationResult::Ok(())
+        })
+    })?;
+
+    if let (Some(histogram), Some(index)) = (histogram, values_counting_index(field_indexes)) {
+        histogram.update(old_values_count, index.values_count(point_id));
+        histogram
+            .set_exact(histogram.is_exact() && is_values_count_exact(index, point_id, new_value));
+    }
+    Ok(())
+}
+
+impl PayloadIndex for StructPayloadIndex {
+    fn indexed_fields(&self) -> HashMap<PayloadKeyType, PayloadFieldSchema> {
+        self.config.indices.to_schemas()
+    }
+
+    fn build_index(
+        &self,
+        field: PayloadKeyTypeRef,
+        payload_schema: &PayloadFieldSchema,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<BuildIndexResult> {
+        if let Some(prev_schema) = self.config.indices.get(field) {
+            // the field is already indexed with the same schema
+            // no need to rebuild index and to save the config
+            return if prev_schema.schema == *payload_schema {
+                Ok(BuildIndexResult::AlreadyBuilt)
+            } else {
+                Ok(BuildIndexResult::IncompatibleSchema)
+            };
+        }
+        let indexes = self.build_field_indexes(field, payload_schema, hw_counter)?;
+        Ok(BuildIndexResult::Built(indexes))
+    }
+
+    fn apply_index(
+        &mut self,
+        field: PayloadKeyType,
+        payload_schema: PayloadFieldSchema,
+        field_index: Vec<FieldIndex>,
+    ) -> OperationResult<()> {
+        let index_types: Vec<_> = field_index
+            .iter()
+            .map(|i| i.get_full_index_type())
+            .collect();
+        match self.build_values_count_histogram(&field_index) {
+            Some(mut histogram) => {
+                histogram.set_exact(self.check_values_counts(&field, &field_index)?);
+                self.values_count_histograms
+                    .insert(field.clone(), histogram)
+            }
+            None => self.values_count_histograms.remove(&field),
+        };
+        self.field_indexes
+            .insert(field.clone(), Arc::new(field_index));
+
+        self.config.indices.insert(
+            field,
+            PayloadFieldSchemaWithIndexType::new(payload_schema, index_types),
+        );
+
+        self.save_config()?;
+
+        Ok(())
+    }
+
+    fn set_indexed(
+        &mut self,
+        field: PayloadKeyTypeRef,
+        payload_schema: impl Into<PayloadFieldSchema>,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        let payload_schema = payload_schema.into();
+
+        self.drop_index_if_incompatible(field, &payload_schema)?;
+
+        let field_index = match self.build_index(field, &payload_schema, hw_counter)? {
+            BuildIndexResult::Built(field_index) => field_index,
+            BuildIndexResult::AlreadyBuilt => {
+                // Index already built, no need to do anything
+                return Ok(());
+            }
+            BuildIndexResult::IncompatibleSchema => {
+                // We should have fixed it by now explicitly
+                // If it is not fixed, it is a bug
+                return Err(OperationError::service_error(format!(
+                    "Incompatible schema for field `{field}`. Please drop the index first."
+                )));
+            }
+        };
+
+        self.apply_index(field.to_owned(), payload_schema, field_index)?;
+
+        Ok(())
+    }
+
+    fn drop_index(&mut self, field: PayloadKeyTypeRef) -> OperationResult<bool> {
+        let removed_config = self.config.indices.remove(field);
+        let removed_indexes = self.field_indexes.remove(field);
+        self.values_count_histograms.remove(field);
+
+        let is_removed = removed_config.is_some() || removed_indexes.is_some();
+
+        if let Some(indexes) = removed_indexes {
+            cleanup_field_indexes(indexes)?;
+        }
+
+        self.save_config()?;
+
+        Ok(is_removed)
+    }
+
+    fn drop_index_if_incompatible(
+        &mut self,
+        field: PayloadKeyTypeRef,
+        new_payload_schema: &PayloadFieldSchema,
+    ) -> OperationResult<bool> {
+        let Some(current_schema) = self.config.indices.get(field) else {
+            return Ok(false);
+        };
+
+        // the field is already indexed with the same schema
+        // no need to rebuild index and to save the config
+        if current_schema.schema == *new_payload_schema {
+            return Ok(false);
+        }
+
+        self.drop_index(field)
+    }
+
+    fn estimate_cardinality(
+        &self,
+        query: &Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        let available_points = self.available_point_count();
+        let estimator =
+            |condition: &Condition| self.condition_cardinality(condition, None, hw_counter);
+        self.estimate_with_composite(query, |query| {
+            estimate_filter(&estimator, query, available_points)
+        })
+    }
+
+    fn estimate_nested_cardinality(
+        &self,
+        query: &Filter,
+        nested_path: &JsonPath,
+        hw_counter: &HardwareCounterCell,
+    ) -> CardinalityEstimation {
+        let available_points = self.available_point_count();
+        let estimator = |condition: &Condition| {
+            self.condition_cardinality(condition, Some(nested_path), hw_counter)
+        };
+        estimate_filter(&estimator, query, available_points)
+    }
+
+    fn query_points(
+        &self,
+        query: &Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> Vec<PointOffsetType> {
+        // Assume query is already estimated to be small enough so we can iterate over all matched ids
+        let estimation_cache = EstimationCache::default();
+        let query_cardinality =
+            self.estimate_cardinality_with_cache(query, &estimation_cache, hw_counter);
+        let id_tracker = self.id_tracker.borrow();
+        self.iter_filtered_points_until(
+            query,
+            &*id_tracker,
+            &query_cardinality,
+            estimation_cache,
+            hw_counter,
+            || false,
+        )
+        .collect()
+    }
+
+    fn count_points(&self, query: &Filter, hw_counter: &HardwareCounterCell) -> usize {
+        let estimation_cache = EstimationCache::default();
+        let query_cardinality =
+            self.estimate_cardinality_with_cache(query, &estimation_cache, hw_counter);
+        if is_exact_count(query, &query_cardinality) {
+            return query_cardinality.exp;
+        }
+        let id_tracker = self.id_tracker.borrow();
+        self.iter_filtered_points_until(
+            query,
+            &*id_tracker,
+            &query_cardinality,
+            estimation_cache,
+            hw_counter,
+            || false,
+        )
+        .count()
+    }
+
+    fn indexed_points(&self, field: PayloadKeyTypeRef) -> usize {
+        self.field_indexes.get(field).map_or(0, |indexes| {
+            // Assume that multiple field indexes are applied to the same data type,
+            // so the points indexed with those indexes are the same.
+            // We will return minimal number as a worst case, to highlight possible errors in the index early.
+            indexes
+                .iter()
+                .map(|index| index.count_indexed_points())
+                .min()
+                .unwrap_or(0)
+        })
+    }
+
+    fn filter_context<'a>(
+        &'a self,
+        filter: &'a Filter,
+        hw_counter: &HardwareCounterCell,
+    ) -> Box<dyn FilterContext + 'a> {
+        Box::new(self.struct_filtered_context(filter, hw_counter))
+    }
+
+    fn payload_blocks(
+        &self,
+        field: PayloadKeyTypeRef,
+        threshold: usize,
+    ) -> Box<dyn Iterator<Item = PayloadBlockCondition> + '_> {
+        self.payload_blocks_checked(field, threshold)
+            .unwrap_or_else(|_| Box::new(std::iter::empty()))
+    }
+
+    fn overwrite_payload(
+        &mut self,
+        point_id: PointOffsetType,
+        payload: &Payload,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        self.check_indexes_writable(|_| true)?;
+        if self.config.validate_payload_types {
+            self.validate_payload(payload)?;
+        }
+        self.payload
+            .borrow_mut()
+            .overwrite(point_id, payload, hw_counter)?;
+
+        for (field, field_index) in &mut self.field_indexes {
+            update_point_in_indexes(
+                field_indexes_mut(field, field_index)?,
+                point_id,
+                &payload.get_value(field),
+                self.config.value_transforms(field),
+                self.values_count_histograms.get_mut(field),
+                hw_counter,
+            )?;
+        }
+        self.update_composite_indexes(point_id, payload, |_| true);
+        Ok(())
+    }
+
+    fn set_payload(
+        &mut self,
+        point_id: PointOffsetType,
+        payload: &Payload,
+        key: &Option<JsonPath>,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<()> {
+        self.check_indexes_writable(|field| {
+            field.is_affected_by_value_set(&payload.0, key.as_ref())
+        })?;
+        if self.config.validate_payload_types {
+            // Partial payload is checked as it is going to be stored, merged into the current one
+            let mut merged_payload = self.get_payload(point_id, hw_counter)?;
+            match key {
+                Some(key) => merged_payload.merge_by_key(payload, key),
+                None => merged_payload.merge(payload),
+            }
+            self.validate_payload(&merged_payload)?;
+        }
+        if let Some(key) = key {
+            self.payload
+                .borrow_mut()
+                .set_by_key(point_id, payload, key, hw_counter)?;
+        } else {
+            self.payload
+                .borrow_mut()
+                .set(point_id, payload, hw_counter)?;
+        };
+
+        let updated_payload = self.get_payload(point_id, hw_counter)?;
+        for (field, field_index) in &mut self.field_indexes {
+            if !field.is_affected_by_value_set(&payload.0, key.as_ref()) {
+                continue;
+            }
+            update_point_in_indexes(
+                field_indexes_mut(field, field_index)?,
+                point_id,
+                &updated_payload.get_value(field),
+                self.config.value_transforms(field),
+                self.values_count_histograms.get_mut(field),
+                hw_counter,
+            )?;
+        }
+        self.update_composite_indexes(point_id, &updated_payload, |fields| {
+            fields
+                .iter()
+                .any(|field| field.is_affected_by_value_set(&payload.0, key.as_ref()))
+        });
+        Ok(())
+    }
+
+    fn get_payload(
+        &self,
+        point_id: PointOffsetType,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Payload> {
+        self.payload.borrow().get(point_id, hw_counter)
+    }
+
+    fn get_payload_sequential(
+        &self,
+        point_id: PointOffsetType,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Payload> {
+        self.payload.borrow().get_sequential(point_id, hw_counter)
+    }
+
+    fn delete_payload(
+        &mut self,
+        point_id: PointOffsetType,
+        key: PayloadKeyTypeRef,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Vec<Value>> {
+        self.check_indexes_writable(|field| field == key)?;
+        if let Some(indexes) = self.field_indexes.get_mut(key) {
+            remove_point_from_indexes(
+                field_indexes_mut(key, indexes)?,
+                point_id,
+                self.values_count_histograms.get_mut(key),
+            )?;
+        }
+        let deleted = self
+            .payload
+            .borrow_mut()
+            .delete(point_id, key, hw_counter)?;
+        if !deleted.is_empty() {
+            let payload = self.get_payload(point_id, hw_counter)?;
+            self.update_composite_indexes(point_id, &payload, |fields| {
+                fields.iter().any(|field| field.compatible(key))
+            });
+        }
+        Ok(deleted)
+    }
+
+    fn clear_payload(
+        &mut self,
+        point_id: PointOffsetType,
+        hw_counter: &HardwareCounterCell,
+    ) -> OperationResult<Option<Payload>> {
+        self.remove_point(point_id)?;
+        self.payload.borrow_mut().clear(point_id, hw_counter)
+    }
+
+    fn flusher(&self) -> Flusher {
+        let mut flushers = Vec::new();
+        for field_indexes in self.field_indexes.values() {
+            for index in field_indexes.iter() {
+                flushers.push(index.flusher());
+            }
+        }
+        for composite_index in &self.composite_indexes {
+            flushers.push(composite_index.flusher(self.config.compression));
+        }
+        flushers.push(self.payload.borrow().flusher());
+        Box::new(move || {
+            for flusher in flushers {
+                match flusher() {
+                    Ok(_) => {}
+                    Err(OperationError::RocksDbColumnFamilyNotFound { name }) => {
+                        // It is possible, that the index was removed during the flush by user or another thread.
+                        // In this case, non-existing column family is not an error, but an expected behavior.
+
+                        // Still we want to log this event, for potential debugging.
+                        log::warn!(
+                            "Flush: RocksDB cf_handle error: Cannot find column family {name}. Assume index is removed.",
+                        );
+                        debug_assert!(
+                            false,
+                            "Missing column family should not happen during testing"
+                        );
+                    }
+                    Err(err) => {
+                        return Err(OperationError::service_error(format!(
+                            "Failed to flush payload_index: {err}"
+                        )));
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "rocksdb")]
+    fn take_database_snapshot(&self, path: &Path) -> OperationResult<()> {
+        match &self.storage_type {
+            StorageType::RocksDbAppendable(db) | StorageType::RocksDbNonAppendable(db) => {
+                let db_guard = db.read();
+                crate::rocksdb_backup::create(&db_guard, path)
+            }
+            StorageType::GridstoreAppendable | StorageType::GridstoreNonAppendable => Ok(()),
+        }
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = self
+            .field_indexes
+            .values()
+            .flat_map(|indexes| indexes.iter().flat_map(|index| index.files().into_iter()))
+            .collect::<Vec<PathBuf>>();
+        files.extend(
+            self.composite_indexes
+                .iter()
+                .map(|index| self.path.join(CompositeIndex::file_name(index.fields()))),
+        );
+        files.push(self.config_path());
+        files
+    }
+
+    fn immutable_files(&self) -> Vec<(PayloadKeyType, PathBuf)> {
+        self.field_indexes
+            .iter()
+            .flat_map(|(key, indexes)| {
+                indexes.iter().flat_map(|index| {
+                    index
+                        .immutable_files()
+                        .into_iter()
+                        .map(|file| (key.clone(), file))
+                })
+            })
+            .collect()
+    }
+}
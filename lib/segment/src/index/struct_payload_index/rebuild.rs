@@ -0,0 +1,187 @@
+use std::fs::{create_dir_all, remove_dir_all};
+
+use common::counter::hardware_counter::HardwareCounterCell;
+use common::types::PointOffsetType;
+use itertools::{Either, Itertools};
+use schemars::_serde_json::Value;
+
+#[cfg(feature = "rocksdb")]
+use super::cleanup_field_indexes;
+use super::{IndexDiscrepancy, StructPayloadIndex, value_matches_schema};
+use crate::common::operation_error::{OperationError, OperationResult};
+use crate::index::PayloadIndex;
+use crate::index::field_index::index_selector::IndexSelector;
+use crate::index::payload_config::PayloadFieldSchemaWithIndexType;
+use crate::index::value_transform::with_transformed_values;
+use crate::payload_storage::PayloadStorage;
+use crate::types::{PayloadContainer, PayloadKeyTypeRef};
+
+/// Directory, in which indexes of a field are built by [`StructPayloadIndex::rebuild_index`]
+pub(super) const REBUILD_STAGING_DIR: &str = "rebuild-staging";
+
+/// Directory, to which replaced indexes are moved until a rebuild is complete
+pub(super) const REBUILD_BACKUP_DIR: &str = "rebuild-backup";
+
+impl StructPayloadIndex {
+    /// Rebuild indexes of an indexed `field` from the current payloads, keeping its schema.
+    ///
+    /// Useful when payloads were changed without updating the index. New indexes are built in
+    /// a staging directory, while the old ones keep serving queries. Files are then swapped and
+    /// the new indexes are loaded from their final location. The old indexes are removed only
+    /// after the swap succeeded, on failure their files are moved back and they stay in use.
+    ///
+    /// Legacy RocksDB indexes can't be staged elsewhere, those are rebuilt in place.
+    pub fn rebuild_index(&mut self, field: PayloadKeyTypeRef) -> OperationResult<()> {
+        let Some(payload_schema) = self.config.indices.get(field).map(|c| c.schema.clone()) else {
+            return Err(OperationError::ValidationError {
+                description: format!("Field `{field}` is not indexed, nothing to rebuild"),
+            });
+        };
+        let hw_counter = HardwareCounterCell::disposable(); // Internal operation
+
+        #[cfg(feature = "rocksdb")]
+        if matches!(self.selector(&payload_schema), IndexSelector::RocksDb(_)) {
+            if let Some(indexes) = self.field_indexes.remove(field) {
+                cleanup_field_indexes(indexes)?;
+            }
+            let field_index = self.build_field_indexes(field, &payload_schema, &hw_counter)?;
+            return self.apply_index(field.to_owned(), payload_schema, field_index);
+        }
+
+        let staging_dir = self.path.join(REBUILD_STAGING_DIR);
+        let backup_dir = self.path.join(REBUILD_BACKUP_DIR);
+        // Leftovers of an interrupted rebuild
+        for dir in [&staging_dir, &backup_dir] {
+            if dir.exists() {
+                remove_dir_all(dir)?;
+            }
+        }
+        create_dir_all(&staging_dir)?;
+
+        let staged = self.build_field_indexes_inner(
+            field,
+            &payload_schema,
+            None,
+            &mut |_, _| {},
+            &staging_dir,
+            &hw_counter,
+        )?;
+        for index in &staged {
+            index.flusher()()?;
+        }
+        let index_types = staged.iter().map(|i| i.get_full_index_type()).collect();
+        // Staged indexes are loaded again from their final location
+        drop(staged);
+
+        // Swap files, old indexes keep their memory maps while being moved aside
+        IndexSelector::move_field_dirs(&self.path, &backup_dir, field)?;
+        IndexSelector::move_field_dirs(&staging_dir, &self.path, field)?;
+
+        let mut new_schema = PayloadFieldSchemaWithIndexType::new(payload_schema, index_types);
+        let loaded =
+            self.load_from_db(field, &mut new_schema, false)
+                .and_then(|(field_index, _)| {
+                    field_index.ok_or_else(|| {
+                        OperationError::service_error(format!(
+                            "Failed to load rebuilt payload index of field `{field}`"
+                        ))
+                    })
+                });
+        let field_index = match loaded {
+            Ok(field_index) => field_index,
+            Err(err) => {
+                // Old indexes stay in use, put their files back
+                IndexSelector::clear_field_dirs(&self.path, field)?;
+                IndexSelector::move_field_dirs(&backup_dir, &self.path, field)?;
+                return Err(err);
+            }
+        };
+
+        // Swap indexes in memory, files of the old ones are gone from their original location,
+        // so they are dropped without cleanup
+        drop(self.field_indexes.remove(field));
+        self.apply_index(field.to_owned(), new_schema.schema, field_index)?;
+
+        remove_dir_all(&staging_dir)?;
+        remove_dir_all(&backup_dir)?;
+        Ok(())
+    }
+
+    /// Compare indexes of every indexed field against the current payloads.
+    ///
+    /// Only the set of indexed points is compared, not the indexed values. A point is expected
+    /// in the index if its payload holds at least one value of the field schema type, after
+    /// value transforms. Returns one entry per field, which doesn't agree with payload storage.
+    pub fn verify(&self) -> OperationResult<Vec<IndexDiscrepancy>> {
+        let hw_counter = HardwareCounterCell::disposable(); // Internal operation
+        let id_tracker = self.id_tracker.borrow();
+        let payload_storage = self.payload.borrow();
+
+        let mut discrepancies = Vec::new();
+        for (field, indexed_field) in self.config.indices.iter() {
+            let kind = indexed_field.schema.kind();
+            let transforms = self.config.value_transforms(field);
+
+            let mut expected = Vec::new();
+            for point_id in id_tracker.iter_ids() {
+                let point_payload = payload_storage.get_sequential(point_id, &hw_counter)?;
+                let has_value = with_transformed_values(
+                    transforms,
+                    &point_payload.get_value(field),
+                    |values| {
+                        values
+                            .iter()
+                            .flat_map(|value| match value {
+                                Value::Array(values) => Either::Left(values.iter()),
+                                value => Either::Right(std::iter::once(*value)),
+                            })
+                            .any(|value| value_matches_schema(value, kind))
+                    },
+                );
+                if has_value {
+                    expected.push(point_id);
+                }
+            }
+            expected.sort_unstable();
+
+            let indexed = self
+                .indexed_points_iter(field)
+                .map(|points| points.collect_vec())
+                .unwrap_or_default();
+
+            let missing = sorted_difference(&expected, &indexed);
+            let extra = sorted_difference(&indexed, &expected);
+            if !missing.is_empty() || !extra.is_empty() {
+                discrepancies.push(IndexDiscrepancy {
+                    field: field.clone(),
+                    missing,
+                    extra,
+                });
+            }
+        }
+        discrepancies.sort_by_key(|discrepancy| discrepancy.field.to_string());
+        Ok(discrepancies)
+    }
+
+    /// Rebuild indexes of every field, which disagrees with payload storage.
+    ///
+    /// Returns discrepancies found before the repair, see [`Self::verify`].
+    pub fn repair(&mut self) -> OperationResult<Vec<IndexDiscrepancy>> {
+        let discrepancies = self.verify()?;
+        for discrepancy in &discrepancies {
+            self.rebuild_index(&discrepancy.field)?;
+        }
+        Ok(discrepancies)
+    }
+}
+
+/// Elements of sorted `left`, which are not in sorted `right`
+fn sorted_difference(left: &[PointOffsetType], right: &[PointOffsetType]) -> Vec<PointOffsetType> {
+    left.iter()
+        .merge_join_by(right, |a, b| a.cmp(b))
+        .filter_map(|item| match item {
+            itertools::EitherOrBoth::Left(&point_id) => Some(point_id),
+            itertools::EitherOrBoth::Both(..) | itertools::EitherOrBoth::Right(_) => None,
+        })
+        .collect()
+}
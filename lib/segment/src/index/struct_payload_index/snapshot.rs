@@ -0,0 +1,97 @@
+use super::StructPayloadIndex;
+use crate::common::utils::IndexesMap;
+use crate::index::index_read_guard::IndexReadGuard;
+use crate::json_path::JsonPath;
+use crate::types::{Condition, FieldComparison, Filter};
+
+impl StructPayloadIndex {
+    /// Capture a read-only view of the current field indexes.
+    ///
+    /// The view keeps returning results of the captured indexes, even if a field is re-indexed
+    /// or dropped afterwards. Only field indexes are captured, payload storage, id tracker and
+    /// point versions are shared with the live index, see [`IndexReadGuard`] for what that
+    /// means for queries. Payload updates of captured fields fail while any view is alive,
+    /// without changing payload storage.
+    ///
+    /// Capturing a view is cheap, as indexes are shared by reference counting and nothing is
+    /// copied. However, indexes which are replaced or dropped while a view is alive are only
+    /// released together with the last view holding them. A long-lived view may therefore keep
+    /// up to twice the memory of re-indexed fields, as old and new indexes coexist.
+    pub fn query_snapshot(&self) -> IndexReadGuard {
+        self.snapshot_with_indexes(self.field_indexes.clone())
+    }
+
+    /// Capture a read-only view of only those field indexes, which may be used by `filter`.
+    ///
+    /// Unlike [`Self::query_snapshot`], point updates of fields not referenced by the filter
+    /// keep working while the view is alive, so writers of other fields are not blocked.
+    /// Querying the view with conditions on other fields falls back to reading payload.
+    ///
+    /// This is not a lock. Updates of captured fields fail instead of waiting for the view to be
+    /// dropped, and all writers still need `&mut self`, so they are serialized with each other
+    /// regardless of the fields they touch.
+    pub fn query_snapshot_for(&self, filter: &Filter) -> IndexReadGuard {
+        let mut keys = Vec::new();
+        collect_filter_keys(filter, None, &mut keys);
+
+        let field_indexes = self
+            .field_indexes
+            .iter()
+            .filter(|(field, _)| keys.iter().any(|key| key.compatible(field)))
+            .map(|(field, indexes)| (field.clone(), indexes.clone()))
+            .collect();
+        self.snapshot_with_indexes(field_indexes)
+    }
+
+    fn snapshot_with_indexes(&self, field_indexes: IndexesMap) -> IndexReadGuard {
+        IndexReadGuard::new(StructPayloadIndex {
+            payload: self.payload.clone(),
+            id_tracker: self.id_tracker.clone(),
+            vector_storages: self.vector_storages.clone(),
+            field_indexes,
+            // Composite indexes are not shared, views use indexes of individual fields instead
+            composite_indexes: Vec::new(),
+            config: self.config.clone(),
+            path: self.path.clone(),
+            index_storage: self.index_storage.clone(),
+            visited_pool: Default::default(),
+            values_count_histograms: self.values_count_histograms.clone(),
+            point_versions: self.point_versions.clone(),
+            storage_type: self.storage_type.clone(),
+            #[cfg(feature = "rocksdb")]
+            db: self.db.clone(),
+        })
+    }
+}
+
+/// Collect payload keys referenced by conditions of the filter, including nested filters
+fn collect_filter_keys(filter: &Filter, nested_path: Option<&JsonPath>, keys: &mut Vec<JsonPath>) {
+    for condition in filter.iter_conditions() {
+        match condition {
+            Condition::Field(field_condition) => {
+                keys.push(JsonPath::extend_or_new(nested_path, &field_condition.key));
+            }
+            Condition::IsEmpty(is_empty) => {
+                keys.push(JsonPath::extend_or_new(nested_path, &is_empty.is_empty.key));
+            }
+            Condition::IsNull(is_null) => {
+                keys.push(JsonPath::extend_or_new(nested_path, &is_null.is_null.key));
+            }
+            Condition::FieldVsField(field_vs_field) => {
+                let FieldComparison { left, op: _, right } = &field_vs_field.field_vs_field;
+                keys.push(JsonPath::extend_or_new(nested_path, left));
+                keys.push(JsonPath::extend_or_new(nested_path, right));
+            }
+            Condition::Nested(nested) => {
+                let full_path = JsonPath::extend_or_new(nested_path, &nested.array_key());
+                collect_filter_keys(nested.filter(), Some(&full_path), keys);
+            }
+            Condition::Filter(filter) => collect_filter_keys(filter, nested_path, keys),
+            Condition::HasId(_)
+            | Condition::HasIdRange(_)
+            | Condition::HasVector(_)
+            | Condition::VersionAtMost(_)
+            | Condition::CustomIdChecker(_) => {}
+        }
+    }
+}
@@ -0,0 +1,4346 @@
+use std::fs;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+
+use tempfile::{Builder, TempDir};
+
+use super::rebuild::REBUILD_STAGING_DIR;
+use super::*;
+use crate::data_types::facets::FacetValue;
+use crate::data_types::index::{
+    GeoIndexParams, GeoIndexType, KeywordIndexParams, KeywordIndexType,
+};
+use crate::data_types::vectors::{DEFAULT_VECTOR_NAME, only_default_vector};
+use crate::entry::SegmentEntry;
+use crate::index::field_index::ResolvedHasId;
+use crate::index::index_health::IndexRecommendation;
+use crate::index::payload_config::{IndexMutability, PayloadIndexType};
+use crate::segment::Segment;
+use crate::segment_constructor::load_segment;
+use crate::segment_constructor::simple_segment_constructor::build_simple_segment;
+use crate::types::{
+    Distance, GeoRadius, HasIdCondition, HasIdRangeCondition, HasVectorCondition, IntPayloadType,
+    IsEmptyCondition, IsNullCondition, Match, MatchExcept, PayloadField, PayloadSchemaParams,
+    PayloadSchemaType, Range, RangeMode, ValuesCount, VersionAtMostCondition, WithPayload,
+};
+
+/// Segment in a new temporary directory, with a point for each of the `payloads`.
+///
+/// Ids and versions of the points are their positions in `payloads`.
+fn segment_with_payloads(
+    payloads: impl IntoIterator<Item = serde_json::Value>,
+) -> (TempDir, Segment) {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    for (idx, payload) in payloads.into_iter().enumerate() {
+        let idx = idx as u64;
+        segment
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+        let payload: Payload = serde_json::from_value(payload).unwrap();
+        segment
+            .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+            .unwrap();
+    }
+    (dir, segment)
+}
+
+#[test]
+fn test_load_payload_index() {
+    let data = r#"
+           {
+               "name": "John Doe"
+           }"#;
+
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let dim = 2;
+
+    let hw_counter = HardwareCounterCell::new();
+
+    let key = JsonPath::from_str("name").unwrap();
+
+    let full_segment_path = {
+        let mut segment = build_simple_segment(dir.path(), dim, Distance::Dot).unwrap();
+        segment
+            .upsert_point(0, 0.into(), only_default_vector(&[1.0, 1.0]), &hw_counter)
+            .unwrap();
+
+        let payload: Payload = serde_json::from_str(data).unwrap();
+
+        segment
+            .set_full_payload(0, 0.into(), &payload, &hw_counter)
+            .unwrap();
+
+        segment
+            .create_field_index(
+                0,
+                &key,
+                Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+                &HardwareCounterCell::new(),
+            )
+            .unwrap();
+
+        segment.current_path.clone()
+    };
+
+    let check_index_types = |index_types: &[FullPayloadIndexType]| -> bool {
+        index_types.len() == 2
+            && index_types[0].index_type == PayloadIndexType::KeywordIndex
+            && index_types[0].mutability == IndexMutability::Mutable
+            && index_types[1].index_type == PayloadIndexType::NullIndex
+            && index_types[1].mutability == IndexMutability::Mutable
+    };
+
+    let payload_config_path = full_segment_path.join("payload_index/config.json");
+    let mut payload_config = PayloadConfig::load(&payload_config_path).unwrap();
+
+    assert_eq!(payload_config.indices.len(), 1);
+
+    let schema = payload_config.indices.get_mut(&key).unwrap();
+    check_index_types(&schema.types);
+
+    // Clear index types to check loading from an old segment.
+    schema.types.clear();
+    payload_config.save(&payload_config_path).unwrap();
+    drop(payload_config);
+
+    // Load once and drop.
+    {
+        load_segment(&full_segment_path, &AtomicBool::new(false))
+            .unwrap()
+            .unwrap();
+    }
+
+    // Check that index type has been written to disk again.
+    // Proves we'll always persist the exact index type if it wasn't known yet at that time
+    let payload_config = PayloadConfig::load(&payload_config_path).unwrap();
+    assert_eq!(payload_config.indices.len(), 1);
+
+    let schema = payload_config.indices.get(&key).unwrap();
+    check_index_types(&schema.types);
+}
+
+#[test]
+fn test_merge_from() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+    let color = JsonPath::from_str("color").unwrap();
+    let number = JsonPath::from_str("number").unwrap();
+    // Indexed only in the merge target
+    let rank = JsonPath::from_str("rank").unwrap();
+    // Indexed only in the merged segment
+    let tag = JsonPath::from_str("tag").unwrap();
+    let keyword_schema = PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword);
+    let integer_schema = PayloadFieldSchema::FieldType(PayloadSchemaType::Integer);
+
+    let colors = ["red", "green", "blue"];
+    let payload_for = |idx: u64| -> Payload {
+        if idx % 4 == 0 {
+            return Payload::default();
+        }
+        let color = if idx % 6 == 1 {
+            serde_json::json!([colors[idx as usize % 3], colors[(idx as usize + 1) % 3]])
+        } else {
+            serde_json::json!(colors[idx as usize % 3])
+        };
+        serde_json::from_value(serde_json::json!({
+            "color": color,
+            "number": idx % 7,
+            "rank": idx % 5,
+            "tag": "x",
+        }))
+        .unwrap()
+    };
+    let create_indexes = |segment: &mut Segment, fields: &[(&JsonPath, &PayloadFieldSchema)]| {
+        for (field, schema) in fields {
+            segment
+                .create_field_index(100, field, Some(schema), &hw_counter)
+                .unwrap();
+        }
+    };
+
+    let first_count = 50u64;
+    let second_count = 30u64;
+
+    // Reference: everything indexed from scratch
+    let mut reference = build_simple_segment(&dir.path().join("ref"), 2, Distance::Dot).unwrap();
+    for idx in 0..first_count + second_count {
+        reference
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+        reference
+            .set_full_payload(idx, idx.into(), &payload_for(idx), &hw_counter)
+            .unwrap();
+    }
+    create_indexes(
+        &mut reference,
+        &[
+            (&color, &keyword_schema),
+            (&number, &integer_schema),
+            (&rank, &integer_schema),
+        ],
+    );
+
+    // Second part in a separate segment
+    let mut second = build_simple_segment(&dir.path().join("second"), 2, Distance::Dot).unwrap();
+    for idx in 0..second_count {
+        second
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+        second
+            .set_full_payload(
+                idx,
+                idx.into(),
+                &payload_for(idx + first_count),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+    create_indexes(
+        &mut second,
+        &[
+            (&color, &keyword_schema),
+            (&number, &integer_schema),
+            (&tag, &keyword_schema),
+        ],
+    );
+
+    // Merge target: indexed first part, raw payload of the second part
+    let mut merged = build_simple_segment(&dir.path().join("merged"), 2, Distance::Dot).unwrap();
+    for idx in 0..first_count {
+        merged
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+        merged
+            .set_full_payload(idx, idx.into(), &payload_for(idx), &hw_counter)
+            .unwrap();
+    }
+    create_indexes(
+        &mut merged,
+        &[
+            (&color, &keyword_schema),
+            (&number, &integer_schema),
+            (&rank, &integer_schema),
+        ],
+    );
+    for idx in first_count..first_count + second_count {
+        merged
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+        merged
+            .payload_index
+            .borrow()
+            .payload
+            .borrow_mut()
+            .overwrite(idx as PointOffsetType, &payload_for(idx), &hw_counter)
+            .unwrap();
+    }
+
+    merged
+        .payload_index
+        .borrow_mut()
+        .merge_from(
+            &second.payload_index.borrow(),
+            first_count as PointOffsetType,
+        )
+        .unwrap();
+
+    let merged_index = merged.payload_index.borrow();
+    let reference_index = reference.payload_index.borrow();
+
+    assert_eq!(
+        merged_index.indexed_fields(),
+        reference_index.indexed_fields()
+    );
+
+    let mut conditions: Vec<_> = colors
+        .iter()
+        .map(|value| {
+            Condition::Field(FieldCondition::new_match(
+                color.clone(),
+                value.to_string().into(),
+            ))
+        })
+        .collect();
+    conditions.push(Condition::IsEmpty(IsEmptyCondition {
+        is_empty: PayloadField { key: color.clone() },
+    }));
+    for count in 0..3 {
+        conditions.push(Condition::Field(FieldCondition::new_values_count(
+            color.clone(),
+            ValuesCount::exact(count),
+        )));
+    }
+    conditions.push(Condition::Field(FieldCondition::new_range(
+        number.clone(),
+        Range {
+            lt: None,
+            gt: None,
+            gte: Some(2.0),
+            lte: Some(4.0),
+        },
+    )));
+    for value in 0..5i64 {
+        conditions.push(Condition::Field(FieldCondition::new_match(
+            rank.clone(),
+            value.into(),
+        )));
+    }
+
+    for condition in conditions {
+        let filter = Filter::new_must(condition);
+        let mut expected = reference_index.query_points(&filter, &hw_counter);
+        let mut actual = merged_index.query_points(&filter, &hw_counter);
+        expected.sort_unstable();
+        actual.sort_unstable();
+        assert_eq!(expected, actual, "filter: {filter:?}");
+        assert_eq!(
+            reference_index.estimate_cardinality(&filter, &hw_counter),
+            merged_index.estimate_cardinality(&filter, &hw_counter),
+            "filter: {filter:?}",
+        );
+    }
+}
+
+#[test]
+fn test_query_points_with_deadline() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("number").unwrap();
+
+    let (_dir, segment) =
+        segment_with_payloads((0..5_000u64).map(|idx| serde_json::json!({ "number": idx % 10 })));
+
+    let payload_index = segment.payload_index.borrow();
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        3_i64.into(),
+    )));
+
+    let expected = payload_index.query_points(&filter, &hw_counter);
+
+    let in_time = payload_index.query_points_with_deadline(
+        &filter,
+        Instant::now() + std::time::Duration::from_secs(3600),
+        &hw_counter,
+    );
+    assert!(!in_time.truncated);
+    assert_eq!(in_time.points, expected);
+
+    let expired = payload_index.query_points_with_deadline(&filter, Instant::now(), &hw_counter);
+    assert!(expired.truncated);
+    assert!(expired.points.is_empty());
+}
+
+#[test]
+fn test_estimate_cardinality_with_deadline() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    for idx in 0..100u64 {
+        segment
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    let payload_index = segment.payload_index.borrow();
+    let ids: ahash::AHashSet<_> = (0..10_000u64).map(Into::into).collect();
+    let filter = Filter::new_must(Condition::HasId(ids.into()));
+
+    let in_time = payload_index.estimate_cardinality_with_deadline(
+        &filter,
+        Instant::now() + std::time::Duration::from_secs(3600),
+        &hw_counter,
+    );
+    assert_eq!(
+        in_time,
+        payload_index.estimate_cardinality(&filter, &hw_counter)
+    );
+    assert_eq!(in_time.exp, 100);
+
+    // Degraded estimation can't select points, but still allows a full scan
+    let expired =
+        payload_index.estimate_cardinality_with_deadline(&filter, Instant::now(), &hw_counter);
+    assert!(expired.primary_clauses.is_empty());
+    assert_eq!(expired.min, 0);
+    assert_eq!(expired.max, 100);
+
+    let id_tracker = segment.id_tracker.borrow();
+    let points: Vec<_> = payload_index
+        .iter_filtered_points(&filter, &*id_tracker, &expired, &hw_counter)
+        .collect();
+    assert_eq!(points.len(), 100);
+}
+
+#[test]
+fn test_count_distinct() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::from_str("color").unwrap();
+    let number_key = JsonPath::from_str("number").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let colors = match idx % 4 {
+            0 => serde_json::json!(["red", "blue"]),
+            1 => serde_json::json!("red"),
+            2 => serde_json::json!("green"),
+            _ => serde_json::json!(null),
+        };
+        serde_json::json!({
+            "color": colors,
+            "number": idx % 10,
+        })
+    }));
+    segment.delete_point(101, 0.into(), &hw_counter).unwrap();
+
+    let conditions = vec![
+        FieldCondition::new_match(color_key.clone(), "red".to_string().into()),
+        FieldCondition::new_match(color_key.clone(), "blue".to_string().into()),
+        FieldCondition::new_match(number_key.clone(), 3_i64.into()),
+    ];
+    let should_filter = Filter {
+        should: Some(conditions.iter().cloned().map(Condition::Field).collect()),
+        min_should: None,
+        must: None,
+        must_not: None,
+    };
+
+    // Fallback, no indexes
+    let expected = segment
+        .payload_index
+        .borrow()
+        .query_points(&should_filter, &hw_counter)
+        .len();
+    assert_eq!(
+        segment
+            .payload_index
+            .borrow()
+            .count_distinct(&conditions, &hw_counter),
+        expected,
+    );
+
+    // Union of posting lists
+    segment
+        .create_field_index(
+            102,
+            &color_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    segment
+        .create_field_index(
+            103,
+            &number_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+    assert_eq!(
+        segment
+            .payload_index
+            .borrow()
+            .count_distinct(&conditions, &hw_counter),
+        expected,
+    );
+    assert_eq!(
+        segment
+            .payload_index
+            .borrow()
+            .count_distinct(&[], &hw_counter),
+        0,
+    );
+}
+
+#[test]
+fn test_distinct_count() {
+    let hw_counter = HardwareCounterCell::new();
+    let country_key = JsonPath::from_str("country").unwrap();
+    let rating_key = JsonPath::from_str("rating").unwrap();
+    let price_key = JsonPath::from_str("price").unwrap();
+
+    let countries = ["de", "fr", "nl", "us"];
+    let (_dir, mut segment) = segment_with_payloads((0..40u64).map(|idx| {
+        serde_json::json!({
+            "country": countries[idx as usize % countries.len()],
+            "rating": idx % 3,
+            "price": idx as f64 * 1.5,
+        })
+    }));
+    for (key, schema_type) in [
+        (&country_key, PayloadSchemaType::Keyword),
+        (&rating_key, PayloadSchemaType::Integer),
+        (&price_key, PayloadSchemaType::Float),
+    ] {
+        segment
+            .create_field_index(
+                40,
+                key,
+                Some(&PayloadFieldSchema::FieldType(schema_type)),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    let payload_index = segment.payload_index.borrow();
+    assert_eq!(payload_index.distinct_count(&country_key), Some(4));
+    assert_eq!(payload_index.distinct_count(&rating_key), Some(3));
+    // Float index has no map of values
+    assert_eq!(payload_index.distinct_count(&price_key), None);
+    // Not indexed
+    assert_eq!(
+        payload_index.distinct_count(&JsonPath::from_str("missing").unwrap()),
+        None,
+    );
+}
+
+#[test]
+fn test_facet() {
+    let hw_counter = HardwareCounterCell::new();
+    let country_key = JsonPath::from_str("country").unwrap();
+    let price_key = JsonPath::from_str("price").unwrap();
+
+    // 6 x "de", 3 x "fr", 1 x "nl"
+    let countries = ["de", "de", "fr", "de", "fr", "de", "nl", "de", "fr", "de"];
+    let (_dir, mut segment) =
+        segment_with_payloads(countries.iter().enumerate().map(|(idx, country)| {
+            serde_json::json!({
+                "country": country,
+                "price": idx,
+            })
+        }));
+    segment
+        .create_field_index(
+            10,
+            &country_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let hit = |value: &str, count| FacetHit {
+        value: FacetValue::Keyword(value.to_string()),
+        count,
+    };
+
+    let hits = payload_index
+        .facet(&country_key, None, 2, &hw_counter)
+        .unwrap();
+    assert_eq!(hits, vec![hit("de", 6), hit("fr", 3)]);
+
+    // Only points with price < 5: 3 x "de", 2 x "fr"
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_range(
+        price_key.clone(),
+        Range {
+            lt: Some(5.0),
+            gt: None,
+            gte: None,
+            lte: None,
+        },
+    )));
+    let hits = payload_index
+        .facet(&country_key, Some(&filter), 10, &hw_counter)
+        .unwrap();
+    assert_eq!(hits, vec![hit("de", 3), hit("fr", 2)]);
+
+    // Field without a map index
+    assert!(
+        payload_index
+            .facet(&price_key, None, 10, &hw_counter)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_payload_blocks_checked() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..20u64).map(|idx| {
+        let color = if idx % 4 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color })
+    }));
+    segment
+        .create_field_index(
+            20,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    let payload_index = segment.payload_index.borrow();
+
+    // Indexed field, only "blue" is above the threshold
+    let blocks: Vec<_> = payload_index
+        .payload_blocks_checked(&key, 10)
+        .unwrap()
+        .collect();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].cardinality, 15);
+
+    // Indexed field without blocks above the threshold
+    assert_eq!(
+        payload_index
+            .payload_blocks_checked(&key, 100)
+            .unwrap()
+            .count(),
+        0,
+    );
+
+    // Unindexed field is an error, naming the field
+    let missing = JsonPath::from_str("size").unwrap();
+    let Err(err) = payload_index.payload_blocks_checked(&missing, 10) else {
+        panic!("unindexed field must be an error");
+    };
+    assert_eq!(
+        err,
+        OperationError::MissingPayloadIndex {
+            key: "size".to_string(),
+        },
+    );
+    assert!(err.to_string().contains("size"));
+    assert_eq!(payload_index.payload_blocks(&missing, 10).count(), 0);
+}
+
+#[test]
+fn test_loaded_index_type() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+    let number_key = JsonPath::from_str("number").unwrap();
+    let color_key = JsonPath::from_str("color").unwrap();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    segment
+        .create_field_index(
+            1,
+            &number_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+    segment
+        .create_field_index(
+            2,
+            &color_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    assert_eq!(
+        payload_index.loaded_index_type(&number_key),
+        Some(HashSet::from([PayloadSchemaType::Integer])),
+    );
+    assert_eq!(
+        payload_index.loaded_index_type(&color_key),
+        Some(HashSet::from([PayloadSchemaType::Keyword])),
+    );
+    assert_eq!(
+        payload_index.loaded_index_type(&JsonPath::from_str("missing").unwrap()),
+        None,
+    );
+}
+
+#[test]
+fn test_exact_values_count() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("tags").unwrap();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    segment
+        .create_field_index(
+            1,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    for idx in 0..50u64 {
+        segment
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+        let payload = match idx % 5 {
+            0 => serde_json::json!({}),
+            1 => serde_json::json!({ "tags": [] }),
+            2 => serde_json::json!({ "tags": "a" }),
+            3 => serde_json::json!({ "tags": ["a", "b"] }),
+            _ => serde_json::json!({ "tags": ["a", "b", "c"] }),
+        };
+        let payload: Payload = serde_json::from_value(payload).unwrap();
+        segment
+            .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+            .unwrap();
+    }
+
+    // Updated and deleted points are accounted for
+    let payload: Payload =
+        serde_json::from_value(serde_json::json!({ "tags": ["a", "b"] })).unwrap();
+    segment
+        .set_full_payload(100, 4.into(), &payload, &hw_counter)
+        .unwrap();
+    segment.delete_point(101, 3.into(), &hw_counter).unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    for count in 0..5 {
+        let filter = Filter::new_must(Condition::Field(FieldCondition::new_values_count(
+            key.clone(),
+            ValuesCount::exact(count),
+        )));
+        let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+        let matched = payload_index.query_points(&filter, &hw_counter).len();
+
+        assert_eq!(estimation.min, matched, "values count {count}");
+        assert_eq!(estimation.exp, matched, "values count {count}");
+        assert_eq!(estimation.max, matched, "values count {count}");
+        // Points are selected by values counts of the index, instead of a full scan
+        assert!(
+            !estimation.primary_clauses.is_empty(),
+            "values count {count}"
+        );
+    }
+
+    // Exact zero values is the same as `is_empty`
+    let is_empty = Filter::new_must(Condition::IsEmpty(IsEmptyCondition {
+        is_empty: PayloadField { key: key.clone() },
+    }));
+    let zero_values = Filter::new_must(Condition::Field(FieldCondition::new_values_count(
+        key,
+        ValuesCount::exact(0),
+    )));
+    assert_eq!(
+        payload_index.query_points(&is_empty, &hw_counter),
+        payload_index.query_points(&zero_values, &hw_counter),
+    );
+}
+
+#[test]
+fn test_inexact_values_count() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("tags").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| {
+        let tags: Vec<_> = (0..idx % 3).map(|tag| format!("tag{tag}")).collect();
+        serde_json::json!({ "tags": tags })
+    }));
+    // Numbers are not indexed by the keyword index, which counts a single value for the point
+    let mixed: Payload =
+        serde_json::from_value(serde_json::json!({ "tags": ["tag0", 1] })).unwrap();
+    segment
+        .set_full_payload(10, 0.into(), &mixed, &hw_counter)
+        .unwrap();
+    segment
+        .create_field_index(
+            11,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let two_values = Filter::new_must(Condition::Field(FieldCondition::new_values_count(
+        key,
+        ValuesCount::exact(2),
+    )));
+    let estimation = payload_index.estimate_cardinality(&two_values, &hw_counter);
+    let mut points = payload_index.query_points(&two_values, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![0, 2, 5, 8]);
+
+    // Histogram misses the point with mixed values, so it can't bound the estimation
+    assert_eq!(estimation.exp, 3);
+    assert_eq!(estimation.min, 0);
+    assert_eq!(estimation.max, 10);
+    assert!(estimation.primary_clauses.is_empty());
+}
+
+#[test]
+fn test_build_field_index_in_chunks() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("number").unwrap();
+
+    let num_points = BUILD_CHUNK_SIZE as u64 * 2 + 10;
+    let (_dir, mut segment) = segment_with_payloads((0..num_points).map(|idx| {
+        if idx % 7 == 0 {
+            // Some points without payload
+            return serde_json::json!({});
+        }
+        serde_json::json!({ "number": idx % 13 })
+    }));
+    segment
+        .delete_point(num_points, 1.into(), &hw_counter)
+        .unwrap();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        5_i64.into(),
+    )));
+    let expected = segment
+        .payload_index
+        .borrow()
+        .query_points(&filter, &hw_counter);
+
+    segment
+        .create_field_index(
+            num_points + 1,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let mut indexed = segment
+        .payload_index
+        .borrow()
+        .query_points(&filter, &hw_counter);
+    indexed.sort_unstable();
+    assert_eq!(indexed, expected);
+}
+
+#[test]
+fn test_indexed_condition_is_primary() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::from_str("color").unwrap();
+    let number_key = JsonPath::from_str("number").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..1_000u64).map(|idx| {
+        let color = if idx % 100 == 0 { "red" } else { "blue" };
+        serde_json::json!({
+            "color": color,
+            "number": idx % 3,
+        })
+    }));
+    segment
+        .create_field_index(
+            1_000,
+            &color_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let indexed = FieldCondition::new_match(color_key, "red".to_string().into());
+    let unindexed = FieldCondition::new_match(number_key, 0_i64.into());
+    let filter = Filter {
+        should: None,
+        min_should: None,
+        must: Some(vec![
+            Condition::Field(unindexed.clone()),
+            Condition::Field(indexed.clone()),
+        ]),
+        must_not: None,
+    };
+
+    let payload_index = segment.payload_index.borrow();
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(
+        estimation.primary_clauses,
+        vec![PrimaryCondition::from(indexed)],
+    );
+    assert!(!estimation.is_primary(&Condition::Field(unindexed)));
+
+    let points = payload_index.query_points(&filter, &hw_counter);
+    assert_eq!(points.len(), 4);
+    assert!(points.iter().all(|&idx| idx % 300 == 0));
+}
+
+#[test]
+fn test_has_id_intersects_field_condition() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let color = if idx % 2 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color })
+    }));
+    segment
+        .create_field_index(
+            100,
+            &color_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let ids: ahash::AHashSet<_> = [1u64, 2, 3, 4].into_iter().map(Into::into).collect();
+    let filter = Filter {
+        should: None,
+        min_should: None,
+        must: Some(vec![
+            Condition::HasId(ids.into()),
+            Condition::Field(FieldCondition::new_match(
+                color_key,
+                "red".to_string().into(),
+            )),
+        ]),
+        must_not: None,
+    };
+
+    let payload_index = segment.payload_index.borrow();
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+
+    // Only the id set drives the scan, the field condition is checked on its points
+    let [PrimaryCondition::Ids(has_id)] = estimation.primary_clauses.as_slice() else {
+        panic!("expected a single ids primary clause: {estimation:?}");
+    };
+    assert_eq!(has_id.resolved_point_offsets.len(), 4);
+
+    let mut points = payload_index.query_points(&filter, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![2, 4]);
+}
+
+#[test]
+fn test_range_all_values() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("sizes").unwrap();
+
+    let payloads = [
+        // Fully within the range
+        serde_json::json!({ "sizes": [1, 2, 3] }),
+        // Straddling the range
+        serde_json::json!({ "sizes": [3, 8] }),
+        // Outside of the range
+        serde_json::json!({ "sizes": [10] }),
+        // No values
+        serde_json::json!({ "sizes": [] }),
+    ];
+    let (_dir, mut segment) = segment_with_payloads(payloads);
+
+    let range = Range {
+        lt: None,
+        gt: None,
+        gte: Some(0.0),
+        lte: Some(5.0),
+    };
+    let any_filter = Filter::new_must(Condition::Field(FieldCondition::new_range(
+        key.clone(),
+        range.clone(),
+    )));
+    let all_filter = Filter::new_must(Condition::Field(
+        FieldCondition::new_range(key.clone(), range).with_range_mode(RangeMode::All),
+    ));
+
+    let check = |segment: &Segment| {
+        let payload_index = segment.payload_index.borrow();
+        let mut any_points = payload_index.query_points(&any_filter, &hw_counter);
+        any_points.sort_unstable();
+        assert_eq!(any_points, vec![0, 1]);
+
+        let all_points = payload_index.query_points(&all_filter, &hw_counter);
+        assert_eq!(all_points, vec![0]);
+
+        let any_estimation = payload_index.estimate_cardinality(&any_filter, &hw_counter);
+        let all_estimation = payload_index.estimate_cardinality(&all_filter, &hw_counter);
+        assert!(all_estimation.min <= 1 && 1 <= all_estimation.max);
+        assert!(all_estimation.max <= any_estimation.max);
+    };
+
+    // Unindexed, payload is checked
+    check(&segment);
+
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    // Index selects candidates, all values are checked
+    check(&segment);
+}
+
+#[test]
+fn test_add_point() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("status").unwrap();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    for idx in 0..10u64 {
+        segment
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let open_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "open".to_string().into(),
+    )));
+    let open: Payload =
+        serde_json::from_value(serde_json::json!({ "status": "open", "other": 1 })).unwrap();
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    assert!(
+        payload_index
+            .query_points(&open_filter, &hw_counter)
+            .is_empty()
+    );
+
+    // Indexes are updated without touching the payload storage
+    payload_index.add_point(3, &open, &hw_counter).unwrap();
+    payload_index.add_point(5, &open, &hw_counter).unwrap();
+    assert_eq!(
+        payload_index.query_points(&open_filter, &hw_counter),
+        vec![3, 5]
+    );
+    assert_eq!(payload_index.indexed_points(&key), 2);
+    assert!(
+        payload_index
+            .get_payload(3, &hw_counter)
+            .unwrap()
+            .is_empty()
+    );
+
+    // Payload without indexed fields removes the point from indexes
+    payload_index
+        .add_point(3, &Payload::default(), &hw_counter)
+        .unwrap();
+    assert_eq!(
+        payload_index.query_points(&open_filter, &hw_counter),
+        vec![5]
+    );
+}
+
+#[test]
+fn test_remove_point() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("status").unwrap();
+
+    let (_dir, mut segment) =
+        segment_with_payloads((0..10u64).map(|_| serde_json::json!({ "status": "open" })));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let open_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "open".to_string().into(),
+    )));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    payload_index.remove_point(4).unwrap();
+    // Removing twice is fine
+    payload_index.remove_point(4).unwrap();
+
+    assert_eq!(payload_index.indexed_points(&key), 9);
+    assert_eq!(
+        payload_index
+            .estimate_cardinality(&open_filter, &hw_counter)
+            .exp,
+        9
+    );
+    let points = payload_index.query_points(&open_filter, &hw_counter);
+    assert_eq!(points.len(), 9);
+    assert!(!points.contains(&4));
+
+    // Payload is still there
+    assert!(
+        !payload_index
+            .get_payload(4, &hw_counter)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_filter_context_uses_all_field_indexes() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("number").unwrap();
+
+    let (_dir, mut segment) =
+        segment_with_payloads((0..10u64).map(|idx| serde_json::json!({ "number": idx })));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    // Lookup index goes first, range conditions can only be served by the second one
+    let indexes = &payload_index.field_indexes[&key];
+    assert!(matches!(indexes[0], FieldIndex::IntMapIndex(_)));
+    assert!(matches!(indexes[1], FieldIndex::IntIndex(_)));
+
+    let range_filter = Filter::new_must(Condition::Field(FieldCondition::new_range(
+        key.clone(),
+        Range {
+            gte: Some(7.0),
+            ..Default::default()
+        },
+    )));
+    let match_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(key, 3.into())));
+
+    let range_context = payload_index.filter_context(&range_filter, &hw_counter);
+    let match_context = payload_index.filter_context(&match_filter, &hw_counter);
+    for point_id in 0..10 {
+        assert_eq!(range_context.check(point_id), point_id >= 7);
+        assert_eq!(match_context.check(point_id), point_id == 3);
+    }
+}
+
+#[test]
+fn test_values_count_checked_with_index() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("tags").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| {
+        let tags: Vec<_> = (0..idx % 5).map(|tag| format!("tag{tag}")).collect();
+        serde_json::json!({ "tags": tags })
+    }));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let more_than_two = Condition::Field(FieldCondition::new_values_count(
+        key.clone(),
+        ValuesCount {
+            lt: None,
+            gt: Some(2),
+            gte: None,
+            lte: None,
+        },
+    ));
+    let filter = Filter::new_must(more_than_two.clone());
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    assert!(payload_index.is_condition_indexable(&more_than_two));
+    assert_eq!(
+        payload_index.query_points(&filter, &hw_counter),
+        vec![3, 4, 8, 9]
+    );
+
+    // Numbers are not indexed by the keyword index, which counts a single value for the point
+    let mixed: Payload =
+        serde_json::from_value(serde_json::json!({ "tags": ["tag0", 1, 2] })).unwrap();
+    payload_index
+        .set_payload(0, &mixed, &None, &hw_counter)
+        .unwrap();
+    assert_eq!(payload_index.field_indexes[&key][0].values_count(0), 1);
+
+    // Values count of the field is checked against payload from now on
+    assert!(!payload_index.is_condition_indexable(&more_than_two));
+    let Condition::Field(field_condition) = &more_than_two else {
+        unreachable!();
+    };
+    let primary_clause = PrimaryCondition::from(field_condition.clone());
+    assert!(
+        payload_index
+            .query_field(&primary_clause, &hw_counter)
+            .is_none()
+    );
+    let mut points = payload_index.query_points(&filter, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![0, 3, 4, 8, 9]);
+    let filter_context = payload_index.filter_context(&filter, &hw_counter);
+    assert!(filter_context.check(0));
+    assert!(!filter_context.check(5));
+}
+
+#[test]
+fn test_rebuild_corrupt_index_on_load() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("price").unwrap();
+
+    let (_dir, full_segment_path) = {
+        let (dir, mut segment) =
+            segment_with_payloads((0..20u64).map(|idx| serde_json::json!({ "price": idx })));
+        segment
+            .create_field_index(
+                20,
+                &key,
+                Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+                &hw_counter,
+            )
+            .unwrap();
+        segment.flush(true, false).unwrap();
+        (dir, segment.current_path.clone())
+    };
+
+    // Overwrite all files of the numeric index with garbage
+    let payload_index_path = full_segment_path.join("payload_index");
+    let mut corrupted = 0;
+    for entry in fs::read_dir(&payload_index_path).unwrap() {
+        let entry = entry.unwrap();
+        if !entry.file_name().to_string_lossy().ends_with("-numeric") {
+            continue;
+        }
+        for file in fs::read_dir(entry.path()).unwrap() {
+            let file = file.unwrap();
+            if file.file_type().unwrap().is_file() {
+                fs::write(file.path(), b"corrupt").unwrap();
+                corrupted += 1;
+            }
+        }
+    }
+    assert!(corrupted > 0);
+
+    let segment = load_segment(&full_segment_path, &AtomicBool::new(false))
+        .unwrap()
+        .unwrap();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_range(
+        key.clone(),
+        Range {
+            gte: Some(15.0),
+            ..Default::default()
+        },
+    )));
+    let payload_index = segment.payload_index.borrow();
+    assert!(payload_index.field_indexes.contains_key(&key));
+    assert_eq!(
+        payload_index.query_points(&filter, &hw_counter),
+        vec![15, 16, 17, 18, 19]
+    );
+}
+
+#[test]
+fn test_count_points() {
+    let hw_counter = HardwareCounterCell::new();
+    let color = JsonPath::from_str("color").unwrap();
+    let price = JsonPath::from_str("price").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let color = if idx % 10 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color, "price": idx })
+    }));
+    segment
+        .create_field_index(
+            100,
+            &color,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let red = Condition::Field(FieldCondition::new_match(color, "red".to_string().into()));
+    let cheap = Condition::Field(FieldCondition::new_range(
+        price,
+        Range {
+            lt: Some(50.0),
+            ..Default::default()
+        },
+    ));
+
+    let filters = [
+        Filter::new_must(red.clone()),
+        Filter::new_must(cheap.clone()),
+        Filter::new_must_not(red.clone()),
+        Filter {
+            must: Some(vec![red, cheap]),
+            ..Default::default()
+        },
+    ];
+
+    let payload_index = segment.payload_index.borrow();
+    for filter in &filters {
+        assert_eq!(
+            payload_index.count_points(filter, &hw_counter),
+            payload_index.query_points(filter, &hw_counter).len(),
+        );
+    }
+    assert_eq!(payload_index.count_points(&filters[0], &hw_counter), 10);
+    assert_eq!(payload_index.count_points(&filters[3], &hw_counter), 5);
+}
+
+#[test]
+fn test_is_exact_count() {
+    let key = JsonPath::from_str("color").unwrap();
+    let red = FieldCondition::new_match(key, "red".to_string().into());
+    let ids: ahash::AHashSet<_> = (0..3u64).map(Into::into).collect();
+    let has_id = HasIdCondition::from(ids);
+
+    let by_ids =
+        CardinalityEstimation::exact(3).with_primary_clause(PrimaryCondition::Ids(ResolvedHasId {
+            point_ids: has_id.has_id.clone(),
+            resolved_point_offsets: vec![0, 1, 2],
+        }));
+    let has_id = Condition::HasId(has_id);
+    let by_field =
+        CardinalityEstimation::exact(3).with_primary_clause(PrimaryCondition::from(red.clone()));
+
+    assert!(is_exact_count(&Filter::new_must(has_id.clone()), &by_ids));
+    // Estimations of field indexes are not trusted, even if `min` equals `max`
+    assert!(!is_exact_count(
+        &Filter::new_must(Condition::Field(red.clone())),
+        &by_field,
+    ));
+    // Other conditions are not covered by the clause
+    let with_other = Filter {
+        must: Some(vec![has_id.clone(), Condition::Field(red)]),
+        ..Default::default()
+    };
+    assert!(!is_exact_count(&with_other, &by_ids));
+    assert!(!is_exact_count(&Filter::new_must_not(has_id), &by_ids));
+}
+
+#[test]
+fn test_iter_filtered_points_partially_consumed() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let color = if idx % 2 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color })
+    }));
+    segment
+        .create_field_index(
+            100,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        "red".to_string().into(),
+    )));
+    let payload_index = segment.payload_index.borrow();
+    let id_tracker = payload_index.id_tracker.borrow();
+    let query_cardinality = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert!(!query_cardinality.primary_clauses.is_empty());
+
+    // Points are yielded lazily, stop after a few of them
+    let first: Vec<_> = payload_index
+        .iter_filtered_points(&filter, &*id_tracker, &query_cardinality, &hw_counter)
+        .take(5)
+        .collect();
+    assert_eq!(first.len(), 5);
+
+    // Visited list is returned to the pool on drop, and must not affect the next query
+    let mut all: Vec<_> = payload_index
+        .iter_filtered_points(&filter, &*id_tracker, &query_cardinality, &hw_counter)
+        .collect();
+    all.sort_unstable();
+    assert_eq!(all, (0..100).step_by(2).collect::<Vec<PointOffsetType>>());
+    assert!(first.iter().all(|id| all.contains(id)));
+}
+
+#[test]
+fn test_query_points_limited() {
+    let hw_counter = HardwareCounterCell::new();
+    let color = JsonPath::from_str("color").unwrap();
+    let price = JsonPath::from_str("price").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let color = if idx % 10 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color, "price": idx })
+    }));
+    segment
+        .create_field_index(
+            100,
+            &color,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    // Served by the index
+    let red = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        color,
+        "red".to_string().into(),
+    )));
+    // Full scan
+    let cheap = Filter::new_must(Condition::Field(FieldCondition::new_range(
+        price,
+        Range {
+            lt: Some(50.0),
+            ..Default::default()
+        },
+    )));
+
+    let payload_index = segment.payload_index.borrow();
+    for filter in [&red, &cheap] {
+        let all = payload_index.query_points(filter, &hw_counter);
+        for limit in [0, 1, 3, all.len(), all.len() + 10] {
+            let limited = payload_index.query_points_limited(filter, limit, &hw_counter);
+            assert_eq!(limited.len(), limit.min(all.len()));
+            assert!(limited.iter().all(|id| all.contains(id)));
+        }
+    }
+}
+
+#[test]
+fn test_is_null_distinct_from_missing() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let payloads = [
+        serde_json::json!({}),
+        serde_json::json!({ "color": null }),
+        serde_json::json!({ "color": "red" }),
+        serde_json::json!({ "color": [] }),
+    ];
+
+    let (_dir, mut segment) = segment_with_payloads(payloads);
+
+    let is_null = Filter::new_must(Condition::IsNull(IsNullCondition::from(key.clone())));
+    let is_empty = Filter::new_must(Condition::IsEmpty(IsEmptyCondition::from(key.clone())));
+
+    let check = |segment: &Segment| {
+        let payload_index = segment.payload_index.borrow();
+        let mut null_points = payload_index.query_points(&is_null, &hw_counter);
+        null_points.sort_unstable();
+        let mut empty_points = payload_index.query_points(&is_empty, &hw_counter);
+        empty_points.sort_unstable();
+
+        // Explicit null is not the same as a missing field
+        assert_eq!(null_points, vec![1]);
+        assert_eq!(empty_points, vec![0, 1, 3]);
+
+        for (filter, matched) in [(&is_null, &null_points), (&is_empty, &empty_points)] {
+            let estimation = payload_index.estimate_cardinality(filter, &hw_counter);
+            assert!(estimation.min <= matched.len());
+            assert!(estimation.max >= matched.len());
+        }
+    };
+
+    // Checked on payload
+    check(&segment);
+
+    // Checked with the null index
+    segment
+        .create_field_index(
+            4,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    check(&segment);
+}
+
+#[test]
+fn test_is_empty_estimation_with_null_index() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..30u64).map(|idx| match idx % 3 {
+        0 => serde_json::json!({}),
+        1 => serde_json::json!({ "color": [] }),
+        _ => serde_json::json!({ "color": "red" }),
+    }));
+    segment
+        .create_field_index(
+            30,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    // Deleted points are not counted
+    segment.delete_point(31, 0.into(), &hw_counter).unwrap();
+    segment.delete_point(32, 2.into(), &hw_counter).unwrap();
+
+    let is_empty = Filter::new_must(Condition::IsEmpty(IsEmptyCondition::from(key)));
+    let payload_index = segment.payload_index.borrow();
+    let estimation = payload_index.estimate_cardinality(&is_empty, &hw_counter);
+    let matched = payload_index.query_points(&is_empty, &hw_counter).len();
+    assert_eq!(matched, 19);
+    assert_eq!(estimation.min, matched);
+    assert_eq!(estimation.max, matched);
+    assert!(!estimation.primary_clauses.is_empty());
+}
+
+#[test]
+fn test_is_empty_selected_with_null_index() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| match idx % 2 {
+        0 => serde_json::json!({}),
+        _ => serde_json::json!({ "color": "red" }),
+    }));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    // Added after the index was built, without ever setting the field
+    for idx in 10..13u64 {
+        segment
+            .upsert_point(
+                idx + 1,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+    segment.delete_point(20, 2.into(), &hw_counter).unwrap();
+
+    let is_empty = Filter::new_must(Condition::IsEmpty(IsEmptyCondition::from(key.clone())));
+    let payload_index = segment.payload_index.borrow();
+
+    let estimation = payload_index.estimate_cardinality(&is_empty, &hw_counter);
+    let [primary_clause] = estimation.primary_clauses.as_slice() else {
+        panic!("expected a single primary clause: {estimation:?}");
+    };
+    let mut selected = payload_index
+        .query_field(primary_clause, &hw_counter)
+        .expect("is_empty is selected with the null index")
+        .collect_vec();
+    selected.sort_unstable();
+    assert_eq!(selected, vec![0, 4, 6, 8, 10, 11, 12]);
+
+    let mut matched = payload_index.query_points(&is_empty, &hw_counter);
+    matched.sort_unstable();
+    assert_eq!(matched, selected);
+    assert_eq!(estimation.min, matched.len());
+    assert_eq!(estimation.max, matched.len());
+}
+
+#[test]
+fn test_datetime_index_skips_invalid_values() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("created_at").unwrap();
+
+    let values = [
+        serde_json::json!("2024-01-01T00:00:00Z"),
+        serde_json::json!("not a date"),
+        serde_json::json!("2024-03-15T12:30:00+02:00"),
+        serde_json::json!(["2023-12-31T23:59:59Z", "2024-02-30T00:00:00Z"]),
+        serde_json::json!("2024-06-01T00:00:00Z"),
+    ];
+
+    let (_dir, mut segment) = segment_with_payloads(
+        values
+            .into_iter()
+            .map(|value| serde_json::json!({ "created_at": value })),
+    );
+
+    // Invalid values don't abort the build
+    segment
+        .create_field_index(
+            5,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Datetime)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    assert_eq!(payload_index.indexed_points(&key), 4);
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_datetime_range(
+        key,
+        Range {
+            lt: Some("2024-04-01T00:00:00Z".parse().unwrap()),
+            gt: None,
+            gte: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+            lte: None,
+        },
+    )));
+    let mut points = payload_index.query_points(&filter, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![0, 2]);
+}
+
+#[test]
+fn test_uuid_index_skips_invalid_values() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("uuid").unwrap();
+
+    let values = [
+        serde_json::json!("a4b1c5e0-8f3e-4c2b-9d6a-1f2e3d4c5b6a"),
+        serde_json::json!("not-a-uuid"),
+        serde_json::json!(42),
+        serde_json::json!("A4B1C5E08F3E4C2B9D6A1F2E3D4C5B6A"),
+        serde_json::json!("0f0e0d0c-0b0a-0908-0706-050403020100"),
+    ];
+
+    let (_dir, mut segment) = segment_with_payloads(
+        values
+            .into_iter()
+            .map(|value| serde_json::json!({ "uuid": value })),
+    );
+
+    // Invalid values don't abort the build
+    segment
+        .create_field_index(
+            5,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Uuid)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    assert_eq!(payload_index.indexed_points(&key), 3);
+
+    // Different representations of the same UUID match
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        "a4b1c5e0-8f3e-4c2b-9d6a-1f2e3d4c5b6a".to_string().into(),
+    )));
+    let mut points = payload_index.query_points(&filter, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![0, 3]);
+}
+
+#[test]
+fn test_index_nested_object_path() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("address.city").unwrap();
+
+    let payloads = [
+        serde_json::json!({ "address": { "city": "Berlin" } }),
+        serde_json::json!({ "address": [{ "city": "Paris" }, { "city": "Berlin" }] }),
+        serde_json::json!({ "address": { "city": ["Rome", "Berlin"] } }),
+        serde_json::json!({ "address": { "street": "Main st." } }),
+        serde_json::json!({ "city": "Berlin" }),
+    ];
+
+    let (_dir, mut segment) = segment_with_payloads(payloads);
+    segment
+        .create_field_index(
+            5,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    assert_eq!(payload_index.indexed_points(&key), 3);
+
+    // Arrays along the path expand into multiple values
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        "Berlin".to_string().into(),
+    )));
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.exp, 3);
+    assert!(!estimation.primary_clauses.is_empty());
+
+    let mut points = payload_index.query_points(&filter, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_field_index_stats() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..20).map(|idx| {
+        if idx % 4 == 0 {
+            serde_json::json!({})
+        } else {
+            serde_json::json!({ "color": "red" })
+        }
+    }));
+    segment
+        .create_field_index(
+            20,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    segment.flush(true, false).unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let stats = payload_index.field_index_stats();
+    assert_eq!(stats.len(), 1);
+
+    let field_stats = stats[&key];
+    assert_eq!(field_stats.points_count, 15);
+    assert!(field_stats.disk_usage_bytes > 0);
+    assert!(field_stats.ram_usage_bytes <= field_stats.disk_usage_bytes);
+}
+
+#[test]
+fn test_match_any_single_primary_clause() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let colors = ["red", "green", "blue", "black", "white"];
+    let (_dir, mut segment) = segment_with_payloads((0..50u64).map(|idx| {
+        let color = colors[idx as usize % colors.len()];
+        serde_json::json!({ "color": color })
+    }));
+    segment
+        .create_field_index(
+            50,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        vec!["red".to_string(), "green".to_string(), "blue".to_string()].into(),
+    )));
+
+    let payload_index = segment.payload_index.borrow();
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    // All listed values are served by a single primary clause
+    assert_eq!(estimation.primary_clauses.len(), 1);
+    assert!(estimation.min <= 30 && estimation.max >= 30);
+    assert!(estimation.max <= 50);
+
+    let points = payload_index.query_points(&filter, &hw_counter);
+    assert_eq!(points.len(), 30);
+    assert!(points.iter().all(|&id| id % 5 < 3));
+}
+
+#[test]
+fn test_match_except_with_missing_field() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("status").unwrap();
+
+    let statuses = [Some("active"), Some("deleted"), Some("archived"), None];
+    let (_dir, mut segment) =
+        segment_with_payloads((0..40).map(|idx| match statuses[idx % statuses.len()] {
+            Some(status) => serde_json::json!({ "status": status }),
+            None => serde_json::json!({}),
+        }));
+    segment
+        .create_field_index(
+            40,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let excluded = vec!["deleted".to_string(), "archived".to_string()];
+    let payload_index = segment.payload_index.borrow();
+
+    // `except` requires a value outside of the list, so points without the field don't match
+    let except = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        Match::Except(MatchExcept::from(excluded.clone())),
+    )));
+    let points = payload_index.query_points(&except, &hw_counter);
+    assert_eq!(points.len(), 10);
+    assert!(points.iter().all(|&id| id % 4 == 0));
+    let estimation = payload_index.estimate_cardinality(&except, &hw_counter);
+    assert!(estimation.min <= 10 && estimation.max >= 10);
+
+    // Negated `any` matches points without the field as well
+    let not_any = Filter::new_must_not(Condition::Field(FieldCondition::new_match(
+        key,
+        excluded.into(),
+    )));
+    let points = payload_index.query_points(&not_any, &hw_counter);
+    assert_eq!(points.len(), 20);
+    assert!(points.iter().all(|&id| id % 4 == 0 || id % 4 == 3));
+}
+
+#[test]
+fn test_match_text_substring_on_keyword_field() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("name").unwrap();
+
+    let names = [
+        "boots",
+        "Stiefel größe",
+        "Schönheit",
+        "root",
+        "日本語のテキスト",
+    ];
+    let (_dir, mut segment) = segment_with_payloads(
+        names
+            .into_iter()
+            .map(|name| serde_json::json!({ "name": name })),
+    );
+    segment
+        .create_field_index(
+            5,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let query = |text: &str| {
+        let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+            key.clone(),
+            Match::new_text(text),
+        )));
+        // Keyword index can't answer substring queries, so the estimation is loose
+        let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+        assert!(estimation.primary_clauses.is_empty());
+        assert_eq!(estimation.min, 0);
+        assert_eq!(estimation.max, names.len());
+
+        let mut points = payload_index.query_points(&filter, &hw_counter);
+        points.sort_unstable();
+        points
+    };
+
+    assert_eq!(query("oot"), vec![0, 3]);
+    assert_eq!(query("ö"), vec![1, 2]);
+    assert_eq!(query("öße"), vec![1]);
+    assert_eq!(query("本語"), vec![4]);
+    assert!(query("oots!").is_empty());
+}
+
+#[test]
+fn test_match_prefix_on_keyword_field() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("name").unwrap();
+
+    let names = ["john", "johanna", "joseph", "mary", "mark"];
+    let (_dir, mut segment) = segment_with_payloads((0..60).map(|idx| {
+        // Last 10 points have no name
+        if idx < 50 {
+            let name = names[idx % names.len()];
+            serde_json::json!({ "name": name })
+        } else {
+            serde_json::json!({})
+        }
+    }));
+    segment
+        .create_field_index(
+            60,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        Match::new_prefix("joh"),
+    )));
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.primary_clauses.len(), 1);
+    assert!(estimation.min <= 20 && estimation.max >= 20);
+
+    let points = payload_index.query_points(&filter, &hw_counter);
+    assert_eq!(points.len(), 20);
+    assert!(points.iter().all(|&id| id < 50 && id % 5 < 2));
+
+    // Empty prefix selects all points with indexed values
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        Match::new_prefix(""),
+    )));
+    let points = payload_index.query_points(&filter, &hw_counter);
+    assert_eq!(points.len(), 50);
+
+    // No keyword matches the prefix
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        Match::new_prefix("zed"),
+    )));
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.max, 0);
+    assert!(payload_index.query_points(&filter, &hw_counter).is_empty());
+}
+
+#[test]
+fn test_keyword_range_lexicographic() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("name").unwrap();
+
+    let names = ["alice", "bob", "mike", "zed"];
+    let (_dir, mut segment) = segment_with_payloads((0..40u64).map(|idx| {
+        let name = names[idx as usize % names.len()];
+        serde_json::json!({ "name": name })
+    }));
+    segment
+        .create_field_index(
+            40,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_keyword_range(
+        key.clone(),
+        Range {
+            lt: Some("n".to_string()),
+            gt: None,
+            gte: Some("b".to_string()),
+            lte: None,
+        },
+    )));
+    payload_index.validate_range_conditions(&filter).unwrap();
+
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.primary_clauses.len(), 1);
+    assert!(estimation.min <= 20 && estimation.max >= 20);
+
+    // Selects "bob" and "mike"
+    let points = payload_index.query_points(&filter, &hw_counter);
+    assert_eq!(points.len(), 20);
+    assert!(points.iter().all(|&id| matches!(id % 4, 1 | 2)));
+
+    // Numeric bounds are rejected on keyword fields
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_range(
+        key,
+        Range {
+            lt: Some(10.0),
+            gt: None,
+            gte: None,
+            lte: None,
+        },
+    )));
+    assert!(matches!(
+        payload_index.validate_range_conditions(&filter),
+        Err(OperationError::ValidationError { .. }),
+    ));
+    drop(payload_index);
+
+    // Same check is done by segment reads
+    let query_vector = [1.0, 1.0].into();
+    let result = segment.search(
+        DEFAULT_VECTOR_NAME,
+        &query_vector,
+        &WithPayload::default(),
+        &false.into(),
+        Some(&filter),
+        10,
+        None,
+    );
+    assert!(
+        matches!(result, Err(OperationError::ValidationError { .. })),
+        "{result:?}",
+    );
+}
+
+#[test]
+fn test_scroll_by_field() {
+    let hw_counter = HardwareCounterCell::new();
+    let price_key = JsonPath::from_str("price").unwrap();
+    let color_key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..20u64).map(|idx| {
+        // Prices are inserted in reverse order of point ids
+        let color = if idx % 2 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "price": 19 - idx as i64, "color": color })
+    }));
+    segment
+        .create_field_index(
+            20,
+            &price_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+    segment
+        .create_field_index(
+            21,
+            &color_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let values = |page: Vec<(OrderValue, PointOffsetType)>| {
+        page.into_iter().map(|(value, _)| value).collect::<Vec<_>>()
+    };
+
+    let first_page = payload_index
+        .scroll_by_field(&price_key, Direction::Asc, None, None, 5, &hw_counter)
+        .unwrap();
+    assert_eq!(
+        first_page.iter().map(|(_, id)| *id).collect::<Vec<_>>(),
+        vec![19, 18, 17, 16, 15],
+    );
+
+    // Resume from the last value of the previous page, which is included again
+    let (last_value, _) = *first_page.last().unwrap();
+    let second_page = payload_index
+        .scroll_by_field(
+            &price_key,
+            Direction::Asc,
+            None,
+            Some(last_value),
+            5,
+            &hw_counter,
+        )
+        .unwrap();
+    assert_eq!(
+        values(second_page),
+        (4..9).map(OrderValue::Int).collect::<Vec<_>>(),
+    );
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        color_key.clone(),
+        "red".to_string().into(),
+    )));
+    let desc_page = payload_index
+        .scroll_by_field(
+            &price_key,
+            Direction::Desc,
+            Some(&filter),
+            Some(OrderValue::Int(10)),
+            3,
+            &hw_counter,
+        )
+        .unwrap();
+    // Red points have odd prices
+    assert_eq!(
+        values(desc_page),
+        vec![OrderValue::Int(9), OrderValue::Int(7), OrderValue::Int(5)],
+    );
+
+    // Keyword index can't be used for ordering
+    let result =
+        payload_index.scroll_by_field(&color_key, Direction::Asc, None, None, 5, &hw_counter);
+    assert!(matches!(
+        result,
+        Err(OperationError::MissingRangeIndexForOrderBy { .. })
+    ));
+}
+
+#[test]
+fn test_scroll_by_field_int_offset_is_exact() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("counter").unwrap();
+
+    // Neighbouring values near `i64::MAX` are not distinguishable as floats
+    let (_dir, mut segment) = segment_with_payloads(
+        (0..4).map(|shift| serde_json::json!({ "counter": IntPayloadType::MAX - 3 + shift })),
+    );
+    segment
+        .create_field_index(
+            4,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let scroll = |direction, offset| {
+        payload_index
+            .scroll_by_field(
+                &key,
+                direction,
+                None,
+                Some(OrderValue::Int(offset)),
+                10,
+                &hw_counter,
+            )
+            .unwrap()
+    };
+
+    assert_eq!(
+        scroll(Direction::Asc, IntPayloadType::MAX - 1),
+        vec![
+            (OrderValue::Int(IntPayloadType::MAX - 1), 2),
+            (OrderValue::Int(IntPayloadType::MAX), 3),
+        ],
+    );
+    assert_eq!(
+        scroll(Direction::Desc, IntPayloadType::MAX - 2),
+        vec![
+            (OrderValue::Int(IntPayloadType::MAX - 2), 1),
+            (OrderValue::Int(IntPayloadType::MAX - 3), 0),
+        ],
+    );
+}
+
+#[test]
+fn test_order_by_geo_distance() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("location").unwrap();
+
+    let center = GeoPoint::new(13.40, 52.52).unwrap();
+    let locations = [
+        // Far away
+        serde_json::json!({ "lon": 2.35, "lat": 48.85 }),
+        // Close
+        serde_json::json!({ "lon": 13.41, "lat": 52.52 }),
+        // Far away, but the second location is the closest one
+        serde_json::json!([
+            { "lon": -74.0, "lat": 40.71 },
+            { "lon": 13.40, "lat": 52.52 },
+        ]),
+        // Medium
+        serde_json::json!({ "lon": 13.70, "lat": 52.40 }),
+    ];
+
+    let (_dir, mut segment) = segment_with_payloads((0..5).map(|idx| {
+        // Last point has no location
+        match locations.get(idx) {
+            Some(location) => serde_json::json!({ "location": location }),
+            None => serde_json::json!({}),
+        }
+    }));
+    segment
+        .create_field_index(
+            5,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Geo)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let ordered = payload_index
+        .order_by_geo_distance(&key, center, 0..5)
+        .unwrap();
+
+    let ids = ordered.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![2, 1, 3, 0]);
+    assert!(ordered[0].1 < 1.0);
+    assert!(ordered.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+    // Only the given candidates are ordered
+    let ordered = payload_index
+        .order_by_geo_distance(&key, center, [0, 3].into_iter())
+        .unwrap();
+    assert_eq!(
+        ordered.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![3, 0],
+    );
+
+    let result =
+        payload_index.order_by_geo_distance(&JsonPath::from_str("missing").unwrap(), center, 0..5);
+    assert!(matches!(
+        result,
+        Err(OperationError::MissingGeoIndexForOrdering { .. })
+    ));
+}
+
+#[test]
+fn test_selective_primary_clause_iterated_first() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("price").unwrap();
+
+    let (_dir, mut segment) =
+        segment_with_payloads((0..50u64).map(|idx| serde_json::json!({ "price": idx })));
+    segment
+        .create_field_index(
+            50,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let ids: ahash::AHashSet<_> = [45u64, 46].into_iter().map(Into::into).collect();
+    let filter = Filter {
+        should: Some(vec![
+            // Broad range goes first in the filter
+            Condition::Field(FieldCondition::new_range(
+                key,
+                Range {
+                    gte: Some(0.0),
+                    ..Default::default()
+                },
+            )),
+            Condition::HasId(ids.into()),
+        ]),
+        min_should: None,
+        must: None,
+        must_not: None,
+    };
+
+    let payload_index = segment.payload_index.borrow();
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.primary_clauses.len(), 2);
+    assert!(matches!(
+        estimation.primary_clauses[0],
+        PrimaryCondition::Condition(_)
+    ));
+
+    let points = payload_index.query_points(&filter, &hw_counter);
+    assert_eq!(points.len(), 50);
+    // Tight ids clause is iterated before the broad range
+    let mut first = points[..2].to_vec();
+    first.sort_unstable();
+    assert_eq!(first, vec![45, 46]);
+    // Every point is returned once
+    assert_eq!(points.iter().collect::<HashSet<_>>().len(), 50);
+}
+
+#[test]
+fn test_composite_index() {
+    let hw_counter = HardwareCounterCell::new();
+    let tenant_key = JsonPath::from_str("tenant_id").unwrap();
+    let status_key = JsonPath::from_str("status").unwrap();
+    let fields = vec![tenant_key.clone(), status_key.clone()];
+
+    let statuses = ["active", "archived"];
+    let (_dir, segment) = segment_with_payloads((0..60u64).map(|idx| {
+        serde_json::json!({
+            "tenant_id": idx % 3,
+            "status": statuses[idx as usize % 2],
+        })
+    }));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    payload_index.set_composite_index(&fields).unwrap();
+    // Persisted under a name derived from the fields
+    let file_name = CompositeIndex::file_name(&fields);
+    assert_eq!(file_name, "composite-tenant_id+status.json");
+    let composite_file = payload_index.path.join(&file_name);
+    assert!(composite_file.exists());
+    assert!(payload_index.files().contains(&composite_file));
+
+    let filter = Filter {
+        should: None,
+        min_should: None,
+        must: Some(vec![
+            Condition::Field(FieldCondition::new_match(tenant_key.clone(), 1_i64.into())),
+            Condition::Field(FieldCondition::new_match(
+                status_key,
+                "active".to_string().into(),
+            )),
+        ]),
+        must_not: None,
+    };
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.min, 10);
+    assert_eq!(estimation.max, 10);
+    assert!(matches!(
+        estimation.primary_clauses.as_slice(),
+        [PrimaryCondition::Composite(_)],
+    ));
+
+    let points = payload_index.query_points(&filter, &hw_counter);
+    assert_eq!(points.len(), 10);
+    assert!(points.iter().all(|&id| id % 3 == 1 && id % 2 == 0));
+
+    // Updated payload is re-indexed
+    payload_index
+        .overwrite_payload(
+            4,
+            &serde_json::from_value(serde_json::json!({
+                "tenant_id": 1,
+                "status": "archived",
+            }))
+            .unwrap(),
+            &hw_counter,
+        )
+        .unwrap();
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.exp, 9);
+    assert_eq!(payload_index.query_points(&filter, &hw_counter).len(), 9);
+
+    // Conditions on other fields don't use the composite index
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        tenant_key,
+        1_i64.into(),
+    )));
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert!(estimation.primary_clauses.is_empty());
+
+    assert!(payload_index.drop_composite_index(&fields).unwrap());
+    assert!(!payload_index.drop_composite_index(&fields).unwrap());
+    assert!(payload_index.config().composite_indexes.is_empty());
+    assert!(!composite_file.exists());
+}
+
+#[test]
+fn test_on_disk_immutable_index_is_memory_mapped() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("name").unwrap();
+
+    let names = ["john", "mary", "mark"];
+    let (dir, segment) = segment_with_payloads((0..30u64).map(|idx| {
+        let name = names[idx as usize % names.len()];
+        serde_json::json!({ "name": name })
+    }));
+
+    let payload = segment.payload_index.borrow().payload.clone();
+    let id_tracker = segment.id_tracker.clone();
+    let index_dir = dir.path().join("immutable_payload_index");
+    let open_index = |create| {
+        StructPayloadIndex::open(
+            payload.clone(),
+            id_tracker.clone(),
+            HashMap::new(),
+            &index_dir,
+            false,
+            create,
+        )
+        .unwrap()
+    };
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "mary".to_string().into(),
+    )));
+    let check_index = |payload_index: &StructPayloadIndex| {
+        let indexes = &payload_index.field_indexes[&key];
+        let keyword_index = indexes
+            .iter()
+            .find(|index| matches!(index, FieldIndex::KeywordIndex(_)))
+            .unwrap();
+        assert!(keyword_index.is_on_disk());
+        assert_eq!(
+            keyword_index.get_full_index_type().mutability,
+            IndexMutability::Immutable,
+        );
+
+        let points = payload_index.query_points(&filter, &hw_counter);
+        assert_eq!(points.len(), 10);
+        assert!(points.iter().all(|&id| id % 3 == 1));
+    };
+
+    {
+        let mut payload_index = open_index(true);
+        payload_index
+            .set_indexed(
+                &key,
+                PayloadFieldSchema::FieldParams(PayloadSchemaParams::Keyword(KeywordIndexParams {
+                    r#type: KeywordIndexType::Keyword,
+                    is_tenant: None,
+                    on_disk: Some(true),
+                    exists_only: None,
+                })),
+                &hw_counter,
+            )
+            .unwrap();
+        payload_index.flusher()().unwrap();
+        check_index(&payload_index);
+    }
+
+    // Reopened index is read from the memory mapped files, without rebuilding
+    let payload_index = open_index(false);
+    payload_index.clear_cache().unwrap();
+    let warmed_bytes = payload_index.warm_up().unwrap();
+    assert!(warmed_bytes > 0);
+    assert!(warmed_bytes <= payload_index.field_index_stats()[&key].disk_usage_bytes);
+    check_index(&payload_index);
+
+    let info = payload_index.index_info();
+    assert_eq!(
+        info.fields[&key],
+        FieldIndexInfo {
+            schema: PayloadSchemaType::Keyword,
+            points_count: 30,
+            files_exist: true,
+        },
+    );
+    let info_json = serde_json::to_value(&info).unwrap();
+    assert_eq!(info_json["fields"]["name"]["schema"], "keyword");
+}
+
+#[test]
+fn test_update_point() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("status").unwrap();
+
+    let (_dir, mut segment) =
+        segment_with_payloads((0..10u64).map(|_| serde_json::json!({ "status": "open" })));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let status_filter = |status: &str| {
+        Filter::new_must(Condition::Field(FieldCondition::new_match(
+            key.clone(),
+            status.to_string().into(),
+        )))
+    };
+    let open = serde_json::json!("open");
+    let closed = serde_json::json!("closed");
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    payload_index
+        .update_point(0, &key, Some(&open), Some(&closed), &hw_counter)
+        .unwrap();
+    assert_eq!(
+        payload_index.query_points(&status_filter("closed"), &hw_counter),
+        vec![0]
+    );
+    assert_eq!(
+        payload_index
+            .estimate_cardinality(&status_filter("open"), &hw_counter)
+            .exp,
+        9
+    );
+
+    // Missing old value behaves like add
+    payload_index
+        .update_point(1, &key, None, Some(&closed), &hw_counter)
+        .unwrap();
+    // Missing new value behaves like remove
+    payload_index
+        .update_point(0, &key, Some(&closed), None, &hw_counter)
+        .unwrap();
+
+    assert_eq!(
+        payload_index.query_points(&status_filter("closed"), &hw_counter),
+        vec![1]
+    );
+    assert_eq!(
+        payload_index
+            .estimate_cardinality(&status_filter("open"), &hw_counter)
+            .exp,
+        8
+    );
+    assert_eq!(payload_index.indexed_points(&key), 9);
+}
+
+#[test]
+fn test_update_point_skips_unchanged_indexes() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("status").unwrap();
+
+    let (_dir, mut segment) =
+        segment_with_payloads((0..10u64).map(|_| serde_json::json!({ "status": "open" })));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let open = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "open".to_string().into(),
+    )));
+    let is_null = Filter::new_must(Condition::IsNull(IsNullCondition::from(key.clone())));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+
+    // Keyword values stay the same, only the null index is written
+    let update_counter = HardwareCounterCell::new();
+    payload_index
+        .update_point(
+            2,
+            &key,
+            Some(&serde_json::json!("open")),
+            Some(&serde_json::json!(["open", null])),
+            &update_counter,
+        )
+        .unwrap();
+    assert_eq!(update_counter.payload_index_io_write_counter().get(), 2);
+
+    // Nothing changed, nothing is written
+    let update_counter = HardwareCounterCell::new();
+    payload_index
+        .update_point(
+            3,
+            &key,
+            Some(&serde_json::json!("open")),
+            Some(&serde_json::json!(["open"])),
+            &update_counter,
+        )
+        .unwrap();
+    assert_eq!(update_counter.payload_index_io_write_counter().get(), 0);
+
+    assert_eq!(
+        payload_index
+            .query_points(&open, &hw_counter)
+            .into_iter()
+            .sorted()
+            .collect_vec(),
+        (0..10).collect_vec()
+    );
+    assert_eq!(payload_index.query_points(&is_null, &hw_counter), vec![2]);
+}
+
+#[test]
+fn test_set_indexed_reindexes_on_schema_change() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("code").unwrap();
+
+    let (_dir, segment) =
+        segment_with_payloads((0..20u64).map(|idx| serde_json::json!({ "code": idx % 5 })));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    payload_index
+        .set_indexed(&key, PayloadSchemaType::Keyword, &hw_counter)
+        .unwrap();
+    assert_eq!(
+        payload_index.loaded_index_type(&key),
+        Some(HashSet::from([PayloadSchemaType::Keyword])),
+    );
+    let keyword_files = payload_index.files();
+
+    payload_index
+        .set_indexed(&key, PayloadSchemaType::Integer, &hw_counter)
+        .unwrap();
+    assert_eq!(
+        payload_index.indexed_fields()[&key],
+        PayloadFieldSchema::FieldType(PayloadSchemaType::Integer),
+    );
+    assert_eq!(
+        payload_index.loaded_index_type(&key),
+        Some(HashSet::from([PayloadSchemaType::Integer])),
+    );
+
+    // Files of the stale index are removed
+    let integer_files = payload_index.files();
+    for file in keyword_files {
+        assert!(integer_files.contains(&file) || !file.exists());
+    }
+
+    // Queries are answered by the new index
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        3_i64.into(),
+    )));
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.primary_clauses.len(), 1);
+    assert_eq!(estimation.exp, 4);
+    assert_eq!(payload_index.query_points(&filter, &hw_counter).len(), 4);
+}
+
+#[test]
+fn test_set_indexed_batch() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::from_str("color").unwrap();
+    let size_key = JsonPath::from_str("size").unwrap();
+    let price_key = JsonPath::from_str("price").unwrap();
+
+    let (_dir, segment) = segment_with_payloads((0..20u64).map(|idx| {
+        serde_json::json!({
+            "color": if idx % 2 == 0 { "red" } else { "blue" },
+            "size": idx % 4,
+            "price": idx as f64 / 2.0,
+        })
+    }));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    payload_index
+        .set_indexed(&color_key, PayloadSchemaType::Keyword, &hw_counter)
+        .unwrap();
+
+    payload_index
+        .set_indexed_batch(
+            &[
+                (color_key.clone(), PayloadSchemaType::Integer),
+                (size_key.clone(), PayloadSchemaType::Integer),
+                (price_key.clone(), PayloadSchemaType::Float),
+            ],
+            &hw_counter,
+        )
+        .unwrap();
+
+    // Already indexed field is left untouched
+    assert_eq!(
+        payload_index.indexed_fields()[&color_key],
+        PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword),
+    );
+    assert_eq!(
+        payload_index.indexed_fields()[&size_key],
+        PayloadFieldSchema::FieldType(PayloadSchemaType::Integer),
+    );
+    assert_eq!(payload_index.indexed_points(&size_key), 20);
+    assert_eq!(payload_index.indexed_points(&price_key), 20);
+
+    // Config is persisted
+    let stored_config: PayloadConfig = serde_json::from_slice(
+        &payload_index
+            .index_storage
+            .get(PAYLOAD_INDEX_CONFIG_FILE)
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stored_config.indices.len(), 3);
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        size_key,
+        1_i64.into(),
+    )));
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.primary_clauses.len(), 1);
+    assert_eq!(payload_index.query_points(&filter, &hw_counter).len(), 5);
+}
+
+#[test]
+fn test_drop_indexes() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::from_str("color").unwrap();
+    let number_key = JsonPath::from_str("number").unwrap();
+    let size_key = JsonPath::from_str("size").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| {
+        serde_json::json!({
+            "color": "red",
+            "number": idx,
+            "size": idx,
+        })
+    }));
+    for (op_num, (key, schema)) in [
+        (&color_key, PayloadSchemaType::Keyword),
+        (&number_key, PayloadSchemaType::Integer),
+        (&size_key, PayloadSchemaType::Integer),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        segment
+            .create_field_index(
+                10 + op_num as u64,
+                key,
+                Some(&PayloadFieldSchema::FieldType(schema)),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    let dropped_files: Vec<_> = [&color_key, &number_key]
+        .into_iter()
+        .flat_map(|key| payload_index.field_indexes[key].iter())
+        .flat_map(|index| index.files())
+        .collect();
+    assert!(!dropped_files.is_empty());
+
+    payload_index
+        .drop_indexes(&[
+            color_key.clone(),
+            number_key.clone(),
+            JsonPath::from_str("missing").unwrap(),
+        ])
+        .unwrap();
+
+    assert!(dropped_files.iter().all(|file| !file.exists()));
+    let indexed_fields = payload_index.indexed_fields();
+    assert_eq!(indexed_fields.len(), 1);
+    assert!(indexed_fields.contains_key(&size_key));
+
+    let config_path = segment.current_path.join("payload_index/config.json");
+    let payload_config = PayloadConfig::load(&config_path).unwrap();
+    assert_eq!(payload_config.indices.len(), 1);
+    assert!(payload_config.indices.get(&size_key).is_some());
+}
+
+#[test]
+fn test_query_snapshot_during_migration() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("number").unwrap();
+
+    let (_dir, mut segment) =
+        segment_with_payloads((0..10u64).map(|idx| serde_json::json!({ "number": idx })));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let range_filter = Filter::new_must(Condition::Field(FieldCondition::new_range(
+        key.clone(),
+        Range {
+            gte: Some(5.0),
+            ..Default::default()
+        },
+    )));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    let guard = payload_index.query_snapshot();
+
+    // Migrate the field to a keyword index, which can't serve range conditions
+    payload_index
+        .set_indexed(&key, PayloadSchemaType::Keyword, &hw_counter)
+        .unwrap();
+    assert_eq!(
+        payload_index.indexed_fields()[&key],
+        PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword),
+    );
+    assert!(
+        payload_index
+            .estimate_cardinality(&range_filter, &hw_counter)
+            .primary_clauses
+            .is_empty()
+    );
+
+    // Guard still uses the integer index
+    assert_eq!(
+        guard.indexed_fields()[&key],
+        PayloadFieldSchema::FieldType(PayloadSchemaType::Integer),
+    );
+    let estimation = guard.estimate_cardinality(&range_filter, &hw_counter);
+    assert!(!estimation.primary_clauses.is_empty());
+    assert!(estimation.min <= 5 && 5 <= estimation.max);
+
+    let mut points = guard.query_points(&range_filter, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![5, 6, 7, 8, 9]);
+
+    drop(guard);
+
+    // Indexes are not shared anymore, so updates are accepted again
+    let payload: Payload = serde_json::from_value(serde_json::json!({ "number": "ten" })).unwrap();
+    payload_index
+        .overwrite_payload(0, &payload, &hw_counter)
+        .unwrap();
+}
+
+#[test]
+fn test_query_snapshot_keeps_dropped_index() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let color = if idx % 10 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color })
+    }));
+    segment
+        .create_field_index(
+            100,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let red_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "red".to_string().into(),
+    )));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    let guard = payload_index.query_snapshot();
+    assert!(payload_index.drop_index(&key).unwrap());
+
+    // Live index falls back to reading payload
+    assert_eq!(payload_index.indexed_points(&key), 0);
+    assert!(
+        payload_index
+            .estimate_cardinality(&red_filter, &hw_counter)
+            .primary_clauses
+            .is_empty()
+    );
+    assert_eq!(
+        payload_index.query_points(&red_filter, &hw_counter).len(),
+        10
+    );
+
+    // Dropped index stays alive as long as the guard holds it
+    assert_eq!(guard.indexed_points(&key), 100);
+    let estimation = guard.estimate_cardinality(&red_filter, &hw_counter);
+    assert_eq!(estimation.primary_clauses.len(), 1);
+    assert_eq!(guard.query_points(&red_filter, &hw_counter).len(), 10);
+}
+
+#[test]
+fn test_write_while_query_snapshot_held() {
+    let hw_counter = HardwareCounterCell::new();
+    let color = JsonPath::from_str("color").unwrap();
+    let count = JsonPath::from_str("count").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let color = if idx % 10 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color, "count": 0 })
+    }));
+    segment
+        .create_field_index(
+            100,
+            &color,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let red_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        color.clone(),
+        "red".to_string().into(),
+    )));
+    let count_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        count.clone(),
+        5.into(),
+    )));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    let guard = payload_index.query_snapshot_for(&red_filter);
+    let original = payload_index.get_payload(0, &hw_counter).unwrap();
+
+    // Writes touching the captured index fail without changing payload storage
+    let blue: Payload = serde_json::from_value(serde_json::json!({ "color": "blue" })).unwrap();
+    assert!(
+        payload_index
+            .set_payload(0, &blue, &None, &hw_counter)
+            .is_err()
+    );
+    assert!(
+        payload_index
+            .overwrite_payload(0, &blue, &hw_counter)
+            .is_err()
+    );
+    assert!(payload_index.clear_payload(0, &hw_counter).is_err());
+    assert!(
+        payload_index
+            .delete_payload(0, &color, &hw_counter)
+            .is_err()
+    );
+    assert_eq!(payload_index.get_payload(0, &hw_counter).unwrap(), original);
+
+    // Payload of other fields is shared with the view
+    let five: Payload = serde_json::from_value(serde_json::json!({ "count": 5 })).unwrap();
+    payload_index
+        .set_payload(0, &five, &None, &hw_counter)
+        .unwrap();
+    assert_eq!(guard.query_points(&count_filter, &hw_counter), vec![0]);
+    assert_eq!(guard.query_points(&red_filter, &hw_counter).len(), 10);
+
+    drop(guard);
+
+    payload_index
+        .set_payload(0, &blue, &None, &hw_counter)
+        .unwrap();
+    assert_eq!(
+        payload_index.query_points(&red_filter, &hw_counter).len(),
+        9,
+    );
+}
+
+#[test]
+fn test_query_snapshot_for_concurrent_writers() {
+    const READERS: usize = 4;
+    const ROUNDS: usize = 100;
+
+    let hw_counter = HardwareCounterCell::new();
+    let color = JsonPath::from_str("color").unwrap();
+    let count = JsonPath::from_str("count").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        let color = if idx % 10 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color, "count": 0 })
+    }));
+    segment
+        .create_field_index(
+            100,
+            &color,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    segment
+        .create_field_index(
+            101,
+            &count,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let red_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        color.clone(),
+        "red".to_string().into(),
+    )));
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    let guards: Vec<_> = (0..READERS)
+        .map(|_| payload_index.query_snapshot_for(&red_filter))
+        .collect();
+    assert_eq!(guards[0].indexed_points(&count), 0);
+
+    std::thread::scope(|scope| {
+        let readers: Vec<_> = guards
+            .iter()
+            .map(|guard| {
+                let red_filter = &red_filter;
+                scope.spawn(move || {
+                    let hw_counter = HardwareCounterCell::new();
+                    for _ in 0..ROUNDS {
+                        let points = guard.query_points(red_filter, &hw_counter);
+                        assert_eq!(points.len(), 10);
+                    }
+                })
+            })
+            .collect();
+
+        // Field which is not captured by the readers can be updated concurrently
+        for round in 0..ROUNDS {
+            let old = serde_json::json!(round);
+            let new = serde_json::json!(round + 1);
+            for point_id in 0..100 {
+                payload_index
+                    .update_point(point_id, &count, Some(&old), Some(&new), &hw_counter)
+                    .unwrap();
+            }
+        }
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    });
+
+    // Captured field can't be updated until the readers are gone
+    let red = serde_json::json!("red");
+    let blue = serde_json::json!("blue");
+    assert!(
+        payload_index
+            .update_point(0, &color, Some(&red), Some(&blue), &hw_counter)
+            .is_err()
+    );
+    drop(guards);
+    payload_index
+        .update_point(0, &color, Some(&red), Some(&blue), &hw_counter)
+        .unwrap();
+
+    let count_filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        count,
+        (ROUNDS as i64).into(),
+    )));
+    assert_eq!(
+        payload_index.query_points(&count_filter, &hw_counter).len(),
+        100
+    );
+    assert_eq!(
+        payload_index.query_points(&red_filter, &hw_counter).len(),
+        9
+    );
+}
+
+#[test]
+fn test_build_field_indexes_streaming() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("tags").unwrap();
+
+    let (_dir, segment) = segment_with_payloads((0..5u64).map(|idx| {
+        // Point 2 has no tags
+        if idx == 2 {
+            return serde_json::json!({});
+        }
+        serde_json::json!({
+            "tags": format!("tag-{idx}"),
+        })
+    }));
+
+    let payload_index = segment.payload_index.borrow();
+    let schema = PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword);
+
+    let mut streamed = Vec::new();
+    let mut on_value = |point_id: PointOffsetType, value: &Value| {
+        streamed.push((point_id, value.clone()));
+    };
+    let indexes = payload_index
+        .build_field_indexes_streaming(&key, &schema, Some(&mut on_value), &hw_counter)
+        .unwrap();
+
+    assert_eq!(
+        streamed,
+        vec![
+            (0, Value::from("tag-0")),
+            (1, Value::from("tag-1")),
+            (3, Value::from("tag-3")),
+            (4, Value::from("tag-4")),
+        ],
+    );
+
+    // Same indexes are built as without the callback
+    let indexed_points = indexes
+        .iter()
+        .map(|index| index.count_indexed_points())
+        .collect::<Vec<_>>();
+    for index in indexes {
+        index.cleanup().unwrap();
+    }
+    let plain_indexes = payload_index
+        .build_field_indexes(&key, &schema, &hw_counter)
+        .unwrap();
+    let plain_indexed_points = plain_indexes
+        .iter()
+        .map(|index| index.count_indexed_points())
+        .collect::<Vec<_>>();
+    assert_eq!(indexed_points, plain_indexed_points);
+}
+
+#[test]
+fn test_build_field_indexes_with_progress() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("number").unwrap();
+
+    let num_points = BUILD_CHUNK_SIZE as u64 * 2 + 10;
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    for idx in 0..num_points {
+        segment
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    let payload_index = segment.payload_index.borrow();
+    let schema = PayloadFieldSchema::FieldType(PayloadSchemaType::Integer);
+
+    let mut reports = Vec::new();
+    let indexes = payload_index
+        .build_field_indexes_with_progress(
+            &key,
+            &schema,
+            |processed, total| reports.push((processed, total)),
+            &hw_counter,
+        )
+        .unwrap();
+    for index in indexes {
+        index.cleanup().unwrap();
+    }
+
+    let total = num_points as usize;
+    assert_eq!(
+        reports,
+        vec![
+            (BUILD_CHUNK_SIZE, total),
+            (BUILD_CHUNK_SIZE * 2, total),
+            (total, total),
+        ],
+    );
+}
+
+#[test]
+fn test_build_field_indexes_reconciles_changes_during_build() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("number").unwrap();
+    let schema = PayloadFieldSchema::FieldType(PayloadSchemaType::Integer);
+
+    let num_points = BUILD_CHUNK_SIZE as u64 * 2 + 10;
+    let (_dir, segment) =
+        segment_with_payloads((0..num_points).map(|idx| serde_json::json!({"number": idx % 13})));
+    let payload_index = segment.payload_index.borrow();
+    let payload_storage = payload_index.payload.clone();
+    let id_tracker = payload_index.id_tracker.clone();
+
+    let changed_point = id_tracker.borrow().internal_id(0.into()).unwrap();
+    let deleted_point = id_tracker.borrow().internal_id(1.into()).unwrap();
+    let changed_payload: Payload =
+        serde_json::from_value(serde_json::json!({"number": 100})).unwrap();
+
+    // Change and delete already indexed points after the first chunk, like writers sharing the
+    // storages with the segment can
+    let indexes = payload_index
+        .build_field_indexes_with_progress(
+            &key,
+            &schema,
+            |processed, _| {
+                if processed != BUILD_CHUNK_SIZE {
+                    return;
+                }
+                payload_storage
+                    .borrow_mut()
+                    .overwrite(changed_point, &changed_payload, &hw_counter)
+                    .unwrap();
+                let mut id_tracker = id_tracker.borrow_mut();
+                id_tracker
+                    .set_internal_version(changed_point, num_points + 1)
+                    .unwrap();
+                id_tracker.drop(1.into()).unwrap();
+            },
+            &hw_counter,
+        )
+        .unwrap();
+
+    let summarize = |indexes: Vec<FieldIndex>| {
+        let summary = indexes
+            .iter()
+            .map(|index| {
+                let values_counts = (0..num_points as PointOffsetType)
+                    .map(|point_id| index.values_count(point_id))
+                    .collect_vec();
+                (index.count_indexed_points(), values_counts)
+            })
+            .collect_vec();
+        for index in indexes {
+            index.cleanup().unwrap();
+        }
+        summary
+    };
+
+    let matches_changed_value = |indexes: &[FieldIndex]| {
+        let condition = FieldCondition::new_match(key.clone(), Match::from(100 as IntPayloadType));
+        indexes
+            .iter()
+            .find_map(|index| index.filter(&condition, &hw_counter))
+            .unwrap()
+            .collect_vec()
+    };
+
+    assert_eq!(matches_changed_value(&indexes), vec![changed_point]);
+    assert_eq!(indexes[0].values_count(deleted_point), 0);
+    let built = summarize(indexes);
+
+    let fresh = summarize(
+        payload_index
+            .build_field_indexes(&key, &schema, &hw_counter)
+            .unwrap(),
+    );
+    assert_eq!(built, fresh);
+}
+
+#[test]
+fn test_index_health() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::from_str("color").unwrap();
+    let number_key = JsonPath::from_str("number").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..20u64).map(|idx| {
+        // Almost all points are red
+        let color = if idx == 0 { "blue" } else { "red" };
+        serde_json::json!({
+            "color": color,
+            "number": idx % 4,
+        })
+    }));
+    for (op_num, (key, schema)) in [
+        (&color_key, PayloadSchemaType::Keyword),
+        (&number_key, PayloadSchemaType::Integer),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        segment
+            .create_field_index(
+                20 + op_num as u64,
+                key,
+                Some(&PayloadFieldSchema::FieldType(schema)),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    let payload_index = segment.payload_index.borrow();
+    let thresholds = IndexHealthThresholds::default();
+
+    let health = payload_index.index_health(&color_key, &thresholds).unwrap();
+    assert_eq!(health.distinct_values, 2);
+    assert_eq!(health.max_posting_fraction, 0.95);
+    assert_eq!(health.recommendation, IndexRecommendation::DropIndex);
+
+    let health = payload_index
+        .index_health(&number_key, &thresholds)
+        .unwrap();
+    assert_eq!(health.distinct_values, 4);
+    assert_eq!(health.max_posting_fraction, 0.25);
+    assert_eq!(health.recommendation, IndexRecommendation::Healthy);
+
+    assert!(
+        payload_index
+            .index_health(&JsonPath::from_str("missing").unwrap(), &thresholds)
+            .is_none()
+    );
+}
+
+#[test]
+fn test_value_transforms() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads(
+        [" Red", "red", "RED ", "blue"].map(|color| serde_json::json!({ "color": color })),
+    );
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "  rEd".to_string().into(),
+    )));
+    let transforms = vec![ValueTransform::Trim, ValueTransform::Lowercase];
+
+    // Fallback to payload check, without index
+    segment
+        .payload_index
+        .borrow_mut()
+        .set_value_transforms(&key, transforms.clone(), &hw_counter)
+        .unwrap();
+    assert_eq!(
+        segment
+            .payload_index
+            .borrow()
+            .query_points(&filter, &hw_counter)
+            .len(),
+        3,
+    );
+
+    // Indexed values are transformed
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    assert_eq!(
+        segment
+            .payload_index
+            .borrow()
+            .query_points(&filter, &hw_counter)
+            .len(),
+        3,
+    );
+
+    // Transforms are persisted
+    let config_path = segment.current_path.join("payload_index/config.json");
+    let payload_config = PayloadConfig::load(&config_path).unwrap();
+    assert_eq!(payload_config.value_transforms(&key), transforms.as_slice());
+}
+
+#[test]
+fn test_case_insensitive_keyword_reload() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+    let colors = ["Red", "red", "RED", "blue"];
+
+    let full_segment_path = {
+        let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+        for (idx, color) in colors.into_iter().enumerate() {
+            let idx = idx as u64;
+            segment
+                .upsert_point(
+                    idx,
+                    idx.into(),
+                    only_default_vector(&[1.0, 1.0]),
+                    &hw_counter,
+                )
+                .unwrap();
+            let payload: Payload =
+                serde_json::from_value(serde_json::json!({ "color": color })).unwrap();
+            segment
+                .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+                .unwrap();
+        }
+        segment
+            .payload_index
+            .borrow_mut()
+            .set_value_transforms(&key, vec![ValueTransform::Lowercase], &hw_counter)
+            .unwrap();
+        segment
+            .create_field_index(
+                10,
+                &key,
+                Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+                &hw_counter,
+            )
+            .unwrap();
+        segment.current_path.clone()
+    };
+
+    let segment = load_segment(&full_segment_path, &AtomicBool::new(false))
+        .unwrap()
+        .unwrap();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "RED".to_string().into(),
+    )));
+    let payload_index = segment.payload_index.borrow();
+
+    // Estimated with the posting list of the normalized value
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.min, 3);
+    assert_eq!(estimation.max, 3);
+
+    let mut points = payload_index.query_points(&filter, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, vec![0, 1, 2]);
+
+    // Payload keeps the original casing
+    for (idx, color) in colors.into_iter().enumerate() {
+        let payload = payload_index
+            .get_payload(idx as PointOffsetType, &hw_counter)
+            .unwrap();
+        assert_eq!(payload.0["color"], serde_json::json!(color));
+    }
+}
+
+#[test]
+fn test_exists_only_index() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..10).map(|idx| {
+        if idx % 3 == 0 {
+            serde_json::json!({ "color": "red" })
+        } else {
+            serde_json::json!({})
+        }
+    }));
+
+    let schema =
+        PayloadFieldSchema::FieldParams(PayloadSchemaParams::Keyword(KeywordIndexParams {
+            r#type: KeywordIndexType::Keyword,
+            is_tenant: None,
+            on_disk: None,
+            exists_only: Some(true),
+        }));
+    segment
+        .create_field_index(10, &key, Some(&schema), &hw_counter)
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+
+    // Only the presence is indexed
+    let indexes = payload_index.field_indexes.get(&key).unwrap();
+    assert_eq!(indexes.len(), 1);
+    assert!(matches!(indexes[0], FieldIndex::NullIndex(_)));
+    assert!(indexes[0].is_complete());
+
+    let is_empty = Filter::new_must(Condition::IsEmpty(IsEmptyCondition {
+        is_empty: PayloadField { key: key.clone() },
+    }));
+    let estimation = payload_index.estimate_cardinality(&is_empty, &hw_counter);
+    assert!(estimation.equals_min_exp_max(&CardinalityEstimation::exact(6)));
+    assert_eq!(payload_index.query_points(&is_empty, &hw_counter).len(), 6);
+
+    let is_not_empty = Filter::new_must_not(Condition::IsEmpty(IsEmptyCondition {
+        is_empty: PayloadField { key },
+    }));
+    assert_eq!(
+        payload_index.query_points(&is_not_empty, &hw_counter).len(),
+        4
+    );
+}
+
+#[test]
+fn test_version_at_most() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("parity").unwrap();
+
+    // Version of each point equals its id
+    let (_dir, mut segment) = segment_with_payloads((0..10).map(|idx| {
+        let parity = if idx % 2 == 0 { "even" } else { "odd" };
+        serde_json::json!({ "parity": parity })
+    }));
+    // Bump version of a single point
+    segment
+        .upsert_point(20, 2.into(), only_default_vector(&[1.0, 1.0]), &hw_counter)
+        .unwrap();
+
+    let version_at_most = Condition::VersionAtMost(VersionAtMostCondition::from(4));
+    let payload_index = segment.payload_index.borrow();
+
+    let filter = Filter::new_must(version_at_most.clone());
+    // Estimated from the histogram of versions, same as a numeric range
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert!(estimation.min <= 4);
+    assert!(estimation.max >= 4);
+    assert!(estimation.max <= 10);
+    assert!(estimation.is_primary(&version_at_most));
+    assert_eq!(
+        payload_index.query_points(&filter, &hw_counter),
+        vec![0, 1, 3, 4]
+    );
+
+    // Combined with other conditions, versions are used as a primary clause
+    let filter = Filter {
+        must: Some(vec![
+            version_at_most.clone(),
+            Condition::Field(FieldCondition::new_match(key, "odd".to_string().into())),
+        ]),
+        ..Default::default()
+    };
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert!(estimation.is_primary(&version_at_most));
+    assert_eq!(payload_index.query_points(&filter, &hw_counter), vec![1, 3]);
+
+    let filter_context = payload_index.filter_context(&filter, &hw_counter);
+    assert!(filter_context.check(3));
+    assert!(!filter_context.check(2));
+    assert!(!filter_context.check(7));
+}
+
+#[test]
+fn test_min_max() {
+    let hw_counter = HardwareCounterCell::new();
+    let price = JsonPath::from_str("price").unwrap();
+    let color = JsonPath::from_str("color").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| {
+        let color = if idx % 2 == 0 { "red" } else { "blue" };
+        serde_json::json!({
+            "price": [idx * 10, idx * 10 + 5],
+            "color": color,
+        })
+    }));
+    segment
+        .create_field_index(
+            11,
+            &price,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+    segment
+        .create_field_index(
+            12,
+            &color,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    let payload_index = segment.payload_index.borrow();
+
+    assert_eq!(
+        payload_index.min_max(&price, None, &hw_counter),
+        Some((0.0, 95.0))
+    );
+
+    let blue = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        color.clone(),
+        "blue".to_string().into(),
+    )));
+    assert_eq!(
+        payload_index.min_max(&price, Some(&blue), &hw_counter),
+        Some((10.0, 95.0))
+    );
+
+    // No point matches
+    let green = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        color.clone(),
+        "green".to_string().into(),
+    )));
+    assert_eq!(
+        payload_index.min_max(&price, Some(&green), &hw_counter),
+        None
+    );
+
+    // Not a numeric field
+    assert_eq!(payload_index.min_max(&color, None, &hw_counter), None);
+}
+
+#[test]
+fn test_validate_payload() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    for (field, schema) in [
+        ("count", PayloadSchemaType::Integer),
+        ("city", PayloadSchemaType::Keyword),
+        ("created", PayloadSchemaType::Datetime),
+    ] {
+        segment
+            .create_field_index(
+                0,
+                &JsonPath::from_str(field).unwrap(),
+                Some(&PayloadFieldSchema::FieldType(schema)),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+    let payload_index = segment.payload_index.borrow();
+
+    let payload: Payload = serde_json::from_value(serde_json::json!({
+        "count": [1, 2],
+        "city": "Berlin",
+        "created": "2024-01-01T00:00:00Z",
+        "other": "not indexed",
+    }))
+    .unwrap();
+    payload_index.validate_payload(&payload).unwrap();
+
+    // Null values are not indexed, same as missing ones
+    let payload: Payload = serde_json::from_value(serde_json::json!({ "count": null })).unwrap();
+    payload_index.validate_payload(&payload).unwrap();
+
+    let payload: Payload = serde_json::from_value(serde_json::json!({
+        "count": [1, "two"],
+        "city": 5,
+        "created": "2024-01-01T00:00:00Z",
+    }))
+    .unwrap();
+    let err = payload_index.validate_payload(&payload).unwrap_err();
+    let OperationError::ValidationError { description } = err else {
+        panic!("unexpected error: {err}");
+    };
+    assert!(description.contains("`city` expected keyword"));
+    assert!(description.contains("`count` expected integer"));
+    assert!(!description.contains("created"));
+}
+
+#[test]
+fn test_validate_payload_on_ingestion() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::from_str("count").unwrap();
+
+    let (_dir, mut segment) = segment_with_payloads([serde_json::json!({ "count": 1 })]);
+    segment
+        .create_field_index(
+            1,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload = |value: serde_json::Value| -> Payload { serde_json::from_value(value).unwrap() };
+    let mut payload_index = segment.payload_index.borrow_mut();
+
+    // Disabled by default, mismatching values are stored
+    payload_index
+        .set_payload(
+            0,
+            &payload(serde_json::json!({ "count": "one" })),
+            &None,
+            &hw_counter,
+        )
+        .unwrap();
+
+    payload_index.set_validate_payload_types(true).unwrap();
+    payload_index
+        .overwrite_payload(0, &payload(serde_json::json!({ "count": 2 })), &hw_counter)
+        .unwrap();
+
+    let result = payload_index.overwrite_payload(
+        0,
+        &payload(serde_json::json!({ "count": "two" })),
+        &hw_counter,
+    );
+    assert!(matches!(
+        result,
+        Err(OperationError::ValidationError { .. })
+    ));
+
+    // Partial payload is validated merged into the current one
+    let result = payload_index.set_payload(
+        0,
+        &payload(serde_json::json!({ "count": [3, "three"] })),
+        &None,
+        &hw_counter,
+    );
+    assert!(matches!(
+        result,
+        Err(OperationError::ValidationError { .. })
+    ));
+    payload_index
+        .set_payload(
+            0,
+            &payload(serde_json::json!({ "other": "x" })),
+            &None,
+            &hw_counter,
+        )
+        .unwrap();
+
+    // Rejected payloads are not stored
+    assert_eq!(
+        payload_index.get_payload(0, &hw_counter).unwrap(),
+        payload(serde_json::json!({ "count": 2, "other": "x" })),
+    );
+    assert!(payload_index.config().validate_payload_types);
+}
+
+#[test]
+fn test_has_id_range() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    for idx in 0..10u64 {
+        segment
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+    // UUIDs are never in a range of numeric ids
+    segment
+        .upsert_point(
+            10,
+            ExtendedPointId::Uuid(uuid::Uuid::new_v4()),
+            only_default_vector(&[1.0, 1.0]),
+            &hw_counter,
+        )
+        .unwrap();
+    segment.delete_point(11, 5.into(), &hw_counter).unwrap();
+
+    let has_id_range = Condition::HasIdRange(HasIdRangeCondition::from(IdRange {
+        gte: Some(3),
+        lt: Some(8),
+    }));
+    let payload_index = segment.payload_index.borrow();
+
+    let filter = Filter::new_must(has_id_range.clone());
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert_eq!(estimation.exp, 4);
+    assert_eq!(estimation.max, 4);
+    assert!(estimation.is_primary(&has_id_range));
+    assert_eq!(
+        payload_index.query_points(&filter, &hw_counter),
+        vec![3, 4, 6, 7]
+    );
+
+    let filter_context = payload_index.filter_context(&filter, &hw_counter);
+    assert!(filter_context.check(3));
+    assert!(!filter_context.check(8));
+    assert!(!filter_context.check(10));
+
+    // Open range covers all numeric ids from the lower bound
+    let filter = Filter::new_must(Condition::HasIdRange(HasIdRangeCondition::from(IdRange {
+        gte: Some(8),
+        lt: None,
+    })));
+    assert_eq!(payload_index.query_points(&filter, &hw_counter), vec![8, 9]);
+}
+
+#[test]
+fn test_has_vector() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    for idx in 0..10u64 {
+        segment
+            .upsert_point(
+                idx,
+                idx.into(),
+                only_default_vector(&[1.0, 1.0]),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+    for idx in [2u64, 5] {
+        segment
+            .delete_vector(10 + idx, idx.into(), DEFAULT_VECTOR_NAME)
+            .unwrap();
+    }
+    // Vector of a deleted point stays in the storage
+    segment.delete_point(20, 7.into(), &hw_counter).unwrap();
+
+    let has_vector = Condition::HasVector(HasVectorCondition::from(DEFAULT_VECTOR_NAME.to_owned()));
+    let payload_index = segment.payload_index.borrow();
+
+    let filter = Filter::new_must(has_vector.clone());
+    let estimation = payload_index.estimate_cardinality(&filter, &hw_counter);
+    assert!(estimation.is_primary(&has_vector));
+    // Vector of the deleted point may or may not be counted
+    assert_eq!((estimation.min, estimation.max), (7, 8));
+    assert!(estimation.min <= estimation.exp && estimation.exp <= estimation.max);
+    assert_eq!(
+        payload_index.query_points(&filter, &hw_counter),
+        vec![0, 1, 3, 4, 6, 8, 9]
+    );
+
+    let filter = Filter::new_must_not(has_vector);
+    assert_eq!(payload_index.query_points(&filter, &hw_counter), vec![2, 5]);
+
+    // Unknown vector name matches no points
+    let filter = Filter::new_must(Condition::HasVector(HasVectorCondition::from(
+        "unknown".to_owned(),
+    )));
+    assert!(payload_index.query_points(&filter, &hw_counter).is_empty());
+}
+
+#[test]
+fn test_drop_all_indexes() {
+    let hw_counter = HardwareCounterCell::new();
+
+    let (_dir, mut segment) = segment_with_payloads(
+        (0..10u64).map(|idx| serde_json::json!({"color": "red", "price": idx})),
+    );
+
+    let fields = [
+        (JsonPath::new("color"), PayloadSchemaType::Keyword),
+        (JsonPath::new("price"), PayloadSchemaType::Integer),
+    ];
+    for (field, schema) in &fields {
+        segment
+            .create_field_index(
+                10,
+                field,
+                Some(&PayloadFieldSchema::FieldType(*schema)),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    let composite_fields = fields.iter().map(|(field, _)| field.clone()).collect_vec();
+    payload_index
+        .set_composite_index(&composite_fields)
+        .unwrap();
+
+    payload_index.drop_all_indexes().unwrap();
+    // Dropping again doesn't fail
+    payload_index.drop_all_indexes().unwrap();
+
+    assert!(payload_index.config.indices.is_empty());
+    assert!(payload_index.field_indexes.is_empty());
+    assert!(payload_index.config.composite_indexes.is_empty());
+    assert!(payload_index.composite_indexes.is_empty());
+
+    // Nothing but the config is left in the directory
+    let files = fs::read_dir(&payload_index.path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect_vec();
+    assert_eq!(files, vec![PAYLOAD_INDEX_CONFIG_FILE.to_string()]);
+
+    let stored_config = payload_index
+        .index_storage
+        .get(PAYLOAD_INDEX_CONFIG_FILE)
+        .unwrap()
+        .unwrap();
+    let config: PayloadConfig = serde_json::from_slice(&stored_config).unwrap();
+    assert!(config.indices.is_empty());
+}
+
+#[test]
+fn test_rebuild_index() {
+    let hw_counter = HardwareCounterCell::new();
+
+    let (_dir, mut segment) =
+        segment_with_payloads((0..10u64).map(|_| serde_json::json!({"color": "red"})));
+
+    let key = JsonPath::new("color");
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+
+    // Change payloads behind the back of the index
+    let blue: Payload = serde_json::from_value(serde_json::json!({"color": "blue"})).unwrap();
+    for point_id in 0..3 {
+        payload_index
+            .payload
+            .borrow_mut()
+            .overwrite(point_id, &blue, &hw_counter)
+            .unwrap();
+    }
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "blue".to_string().into(),
+    )));
+    assert!(payload_index.query_points(&filter, &hw_counter).is_empty());
+
+    payload_index.rebuild_index(&key).unwrap();
+    assert_eq!(
+        payload_index.query_points(&filter, &hw_counter),
+        vec![0, 1, 2]
+    );
+    assert!(payload_index.config.indices.contains_key(&key));
+
+    // New indexes live in their final location, staging and backup are removed
+    assert!(!payload_index.path.join(REBUILD_STAGING_DIR).exists());
+    assert!(!payload_index.path.join(REBUILD_BACKUP_DIR).exists());
+    for index in payload_index.field_indexes[&key].iter() {
+        for file in index.files() {
+            assert!(file.starts_with(&payload_index.path), "{file:?}");
+            assert!(!file.to_string_lossy().contains("rebuild-"), "{file:?}");
+        }
+    }
+
+    let err = payload_index
+        .rebuild_index(&JsonPath::new("unknown"))
+        .unwrap_err();
+    assert!(err.to_string().contains("unknown"), "{err}");
+}
+
+#[test]
+fn test_is_indexable() {
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let hw_counter = HardwareCounterCell::new();
+
+    let mut segment = build_simple_segment(dir.path(), 2, Distance::Dot).unwrap();
+    segment
+        .upsert_point(0, 0.into(), only_default_vector(&[1.0, 1.0]), &hw_counter)
+        .unwrap();
+    let indexed = JsonPath::new("color");
+    segment
+        .create_field_index(
+            1,
+            &indexed,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let match_indexed = Condition::Field(FieldCondition::new_match(
+        indexed.clone(),
+        "red".to_string().into(),
+    ));
+    let match_unindexed = Condition::Field(FieldCondition::new_match(
+        JsonPath::new("size"),
+        "large".to_string().into(),
+    ));
+    let ids: ahash::AHashSet<_> = [0u64].into_iter().map(Into::into).collect();
+    let payload_index = segment.payload_index.borrow();
+
+    assert!(payload_index.is_indexable(&Filter::new_must(match_indexed.clone())));
+    assert!(payload_index.is_indexable(&Filter {
+        should: Some(vec![
+            Condition::IsEmpty(IsEmptyCondition {
+                is_empty: PayloadField { key: indexed },
+            }),
+            Condition::HasId(ids.into()),
+        ]),
+        must_not: Some(vec![match_indexed.clone()]),
+        ..Default::default()
+    }));
+
+    assert!(!payload_index.is_indexable(&Filter::new_must(match_unindexed.clone())));
+    // Any condition reading payload makes the whole filter not indexable
+    assert!(
+        !payload_index.is_indexable(&Filter::new_must(Condition::Filter(Filter {
+            must: Some(vec![match_indexed]),
+            must_not: Some(vec![match_unindexed]),
+            ..Default::default()
+        })))
+    );
+}
+
+#[test]
+fn test_copy_schema_from() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::from_str("color").unwrap();
+    let size_key = JsonPath::from_str("size").unwrap();
+
+    let build_segment = |dir: &Path, points: u64| {
+        let mut segment = build_simple_segment(dir, 2, Distance::Dot).unwrap();
+        for idx in 0..points {
+            segment
+                .upsert_point(
+                    idx,
+                    idx.into(),
+                    only_default_vector(&[1.0, 1.0]),
+                    &hw_counter,
+                )
+                .unwrap();
+            let payload: Payload = serde_json::from_value(serde_json::json!({
+                "color": if idx % 2 == 0 { "red" } else { "blue" },
+                "size": idx % 4,
+            }))
+            .unwrap();
+            segment
+                .set_full_payload(idx, idx.into(), &payload, &hw_counter)
+                .unwrap();
+        }
+        segment
+    };
+
+    let reference_dir = Builder::new().prefix("reference_dir").tempdir().unwrap();
+    let reference = build_segment(reference_dir.path(), 10);
+    let color_schema =
+        PayloadFieldSchema::FieldParams(PayloadSchemaParams::Keyword(KeywordIndexParams {
+            r#type: KeywordIndexType::Keyword,
+            is_tenant: Some(true),
+            on_disk: None,
+            exists_only: None,
+        }));
+    {
+        let mut reference_index = reference.payload_index.borrow_mut();
+        reference_index
+            .set_indexed(&color_key, color_schema.clone(), &hw_counter)
+            .unwrap();
+        reference_index
+            .set_indexed(&size_key, PayloadSchemaType::Integer, &hw_counter)
+            .unwrap();
+    }
+
+    let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+    let segment = build_segment(dir.path(), 20);
+    let mut payload_index = segment.payload_index.borrow_mut();
+    payload_index
+        .set_indexed(&size_key, PayloadSchemaType::Float, &hw_counter)
+        .unwrap();
+
+    payload_index
+        .copy_schema_from(&reference.payload_index.borrow())
+        .unwrap();
+
+    // Params are copied, already indexed field is left untouched
+    let indexed_fields = payload_index.indexed_fields();
+    assert_eq!(indexed_fields[&color_key], color_schema);
+    assert_eq!(
+        indexed_fields[&size_key],
+        PayloadFieldSchema::FieldType(PayloadSchemaType::Float),
+    );
+
+    // Index is built over own payloads
+    assert_eq!(payload_index.indexed_points(&color_key), 20);
+}
+
+#[test]
+fn test_payload_blocks_bounded() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::new("color");
+
+    let (_dir, mut segment) = segment_with_payloads((0..30u64).map(|idx| {
+        // 25 red points, 5 blue points
+        let color = if idx % 6 == 0 { "blue" } else { "red" };
+        serde_json::json!({ "color": color })
+    }));
+    segment
+        .create_field_index(
+            30,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    let blocks = payload_index.payload_blocks_bounded(&key, 5, 10).unwrap();
+
+    let red = FieldCondition::new_match(key.clone(), "red".to_string().into());
+    let (red_blocks, other_blocks): (Vec<_>, Vec<_>) =
+        blocks.into_iter().partition(|block| block.condition == red);
+
+    // Small value is kept whole
+    assert_eq!(other_blocks.len(), 1);
+    assert_eq!(other_blocks[0].cardinality, 5);
+    assert!(other_blocks[0].point_offsets.is_none());
+
+    // Large value is split into balanced, disjoint parts covering all of its points
+    assert_eq!(
+        red_blocks.iter().map(|b| b.cardinality).collect_vec(),
+        vec![8, 8, 9],
+    );
+    let mut covered = Vec::new();
+    for block in &red_blocks {
+        assert!(block.point_offsets.is_some());
+        let points = payload_index
+            .query_points(
+                &Filter::new_must(Condition::Field(red.clone())),
+                &hw_counter,
+            )
+            .into_iter()
+            .filter(|&point_id| block.contains_point(point_id))
+            .collect_vec();
+        assert_eq!(points.len(), block.cardinality);
+        covered.extend(points);
+    }
+    covered.sort_unstable();
+    covered.dedup();
+    assert_eq!(covered.len(), 25);
+
+    assert!(payload_index.payload_blocks_bounded(&key, 10, 5).is_err());
+}
+
+#[test]
+fn test_query_points_within() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::new("color");
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| {
+        let color = if idx % 2 == 0 { "red" } else { "blue" };
+        serde_json::json!({ "color": color })
+    }));
+    segment.delete_point(10, 4.into(), &hw_counter).unwrap();
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key,
+        "red".to_string().into(),
+    )));
+    let payload_index = segment.payload_index.borrow();
+
+    // Order of candidates is kept, deleted and not matching points are skipped
+    assert_eq!(
+        payload_index.query_points_within(&filter, &[8, 3, 4, 0, 5, 2], &hw_counter),
+        vec![8, 0, 2],
+    );
+    assert!(
+        payload_index
+            .query_points_within(&filter, &[], &hw_counter)
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_three_level_nested_filter() {
+    let hw_counter = HardwareCounterCell::new();
+    let color_key = JsonPath::new("color");
+    let size_key = JsonPath::new("size");
+    let price_key = JsonPath::new("price");
+
+    let colors = ["red", "green", "blue"];
+    let (_dir, mut segment) = segment_with_payloads((0..100u64).map(|idx| {
+        serde_json::json!({
+            "color": colors[idx as usize % 3],
+            "size": idx % 5,
+            "price": idx,
+        })
+    }));
+
+    let color = |value: &str| {
+        Condition::Field(FieldCondition::new_match(
+            color_key.clone(),
+            value.to_string().into(),
+        ))
+    };
+    let size =
+        |value: i64| Condition::Field(FieldCondition::new_match(size_key.clone(), value.into()));
+    let cheap = Condition::Field(FieldCondition::new_range(
+        price_key.clone(),
+        Range {
+            lt: Some(30.0),
+            gt: None,
+            gte: None,
+            lte: None,
+        },
+    ));
+
+    // (red AND cheap OR size 0) AND NOT (size 0 AND blue)
+    let query = Filter {
+        should: None,
+        min_should: None,
+        must: Some(vec![Condition::Filter(Filter {
+            should: Some(vec![
+                Condition::Filter(Filter::new_must(Condition::Filter(Filter {
+                    should: None,
+                    min_should: None,
+                    must: Some(vec![color("red"), cheap]),
+                    must_not: None,
+                }))),
+                size(0),
+            ]),
+            min_should: None,
+            must: None,
+            must_not: None,
+        })]),
+        must_not: Some(vec![Condition::Filter(Filter {
+            should: None,
+            min_should: None,
+            must: Some(vec![size(0), color("blue")]),
+            must_not: None,
+        })]),
+    };
+    let expected = (0..100)
+        .filter(|&idx| {
+            let is_red = idx % 3 == 0;
+            let is_blue = idx % 3 == 2;
+            let is_size_0 = idx % 5 == 0;
+            ((is_red && idx < 30) || is_size_0) && !(is_size_0 && is_blue)
+        })
+        .collect_vec();
+
+    for (key, schema) in [
+        (&color_key, PayloadSchemaType::Keyword),
+        (&size_key, PayloadSchemaType::Integer),
+        (&price_key, PayloadSchemaType::Integer),
+    ] {
+        segment
+            .create_field_index(
+                100,
+                key,
+                Some(&PayloadFieldSchema::FieldType(schema)),
+                &hw_counter,
+            )
+            .unwrap();
+    }
+
+    let payload_index = segment.payload_index.borrow();
+    let estimation = payload_index.estimate_cardinality(&query, &hw_counter);
+    assert!(estimation.min <= expected.len() && expected.len() <= estimation.max);
+
+    // Branches of `should` are selected by their own primary clauses, instead of a full scan
+    assert!(!estimation.primary_clauses.is_empty());
+    assert!(
+        estimation
+            .primary_clauses
+            .iter()
+            .all(|clause| matches!(clause, PrimaryCondition::Condition(_)))
+    );
+
+    let mut points = payload_index.query_points(&query, &hw_counter);
+    points.sort_unstable();
+    assert_eq!(points, expected);
+}
+
+#[test]
+fn test_mixed_type_field() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::new("id");
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| {
+        // Same logical id is stored either as a number or as a string
+        let id = if idx % 2 == 0 {
+            serde_json::json!(idx % 3)
+        } else {
+            serde_json::json!((idx % 3).to_string())
+        };
+        serde_json::json!({ "id": id })
+    }));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+
+    // String values are selected by the keyword index
+    let keyword_match = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "1".to_string().into(),
+    )));
+    let estimation = payload_index.estimate_cardinality(&keyword_match, &hw_counter);
+    assert!(!estimation.primary_clauses.is_empty());
+    assert_eq!(
+        payload_index.query_points(&keyword_match, &hw_counter),
+        vec![1, 7]
+    );
+
+    // Numeric values are not indexed, but still found in payloads
+    let numeric_match =
+        Filter::new_must(Condition::Field(FieldCondition::new_match(key, 1.into())));
+    let estimation = payload_index.estimate_cardinality(&numeric_match, &hw_counter);
+    assert!(estimation.primary_clauses.is_empty());
+    assert_eq!(
+        payload_index.query_points(&numeric_match, &hw_counter),
+        vec![4]
+    );
+}
+
+#[test]
+fn test_indexed_points_iter() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::new("color");
+    let count_key = JsonPath::new("count");
+
+    let (_dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| match idx % 3 {
+        0 => serde_json::json!({ "color": "red", "count": [idx, idx + 1] }),
+        // Not a keyword, can't be indexed
+        1 => serde_json::json!({ "color": 1, "count": idx }),
+        _ => serde_json::json!({}),
+    }));
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+    // Both lookup and range indexes, posting lists of both are merged
+    segment
+        .create_field_index(
+            11,
+            &count_key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)),
+            &hw_counter,
+        )
+        .unwrap();
+    segment.delete_point(12, 3.into(), &hw_counter).unwrap();
+
+    let payload_index = segment.payload_index.borrow();
+    assert_eq!(
+        payload_index
+            .indexed_points_iter(&key)
+            .unwrap()
+            .collect_vec(),
+        vec![0, 6, 9],
+    );
+    assert_eq!(
+        payload_index
+            .indexed_points_iter(&count_key)
+            .unwrap()
+            .collect_vec(),
+        vec![0, 1, 4, 6, 7, 9],
+    );
+    assert!(
+        payload_index
+            .indexed_points_iter(&JsonPath::new("unknown"))
+            .is_none()
+    );
+}
+
+#[test]
+fn test_verify_and_repair() {
+    let hw_counter = HardwareCounterCell::new();
+
+    let (_dir, mut segment) = segment_with_payloads((0..10).map(|idx| {
+        // Last point has no payload
+        if idx < 9 {
+            serde_json::json!({"color": "red"})
+        } else {
+            serde_json::json!({})
+        }
+    }));
+
+    let key = JsonPath::new("color");
+    segment
+        .create_field_index(
+            10,
+            &key,
+            Some(&PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword)),
+            &hw_counter,
+        )
+        .unwrap();
+
+    let mut payload_index = segment.payload_index.borrow_mut();
+    assert!(payload_index.verify().unwrap().is_empty());
+
+    // Change payloads behind the back of the index
+    let red: Payload = serde_json::from_value(serde_json::json!({"color": "red"})).unwrap();
+    let number: Payload = serde_json::from_value(serde_json::json!({"color": 5})).unwrap();
+    payload_index
+        .payload
+        .borrow_mut()
+        .overwrite(9, &red, &hw_counter)
+        .unwrap();
+    payload_index
+        .payload
+        .borrow_mut()
+        .overwrite(0, &number, &hw_counter)
+        .unwrap();
+
+    let expected = vec![IndexDiscrepancy {
+        field: key.clone(),
+        missing: vec![9],
+        extra: vec![0],
+    }];
+    assert_eq!(payload_index.verify().unwrap(), expected);
+
+    assert_eq!(payload_index.repair().unwrap(), expected);
+    assert!(payload_index.verify().unwrap().is_empty());
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_match(
+        key.clone(),
+        "red".to_string().into(),
+    )));
+    assert_eq!(
+        payload_index.query_points(&filter, &hw_counter),
+        (1..10).collect::<Vec<PointOffsetType>>()
+    );
+}
+
+#[test]
+fn test_geo_max_query_regions_persisted() {
+    let hw_counter = HardwareCounterCell::new();
+    let key = JsonPath::new("location");
+
+    let max_query_regions = |payload_index: &StructPayloadIndex| {
+        let indexes = payload_index.field_indexes.get(&key).unwrap();
+        indexes
+            .iter()
+            .find_map(|index| match index {
+                FieldIndex::GeoIndex(index) => Some(index.max_query_regions()),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let (_dir, full_segment_path) = {
+        let (dir, mut segment) = segment_with_payloads((0..10u64).map(|idx| {
+            serde_json::json!({
+                "location": {"lon": 13.4 + idx as f64 * 0.01, "lat": 52.5},
+            })
+        }));
+        segment
+            .create_field_index(
+                20,
+                &key,
+                Some(&PayloadFieldSchema::FieldParams(PayloadSchemaParams::Geo(
+                    GeoIndexParams {
+                        r#type: GeoIndexType::Geo,
+                        on_disk: None,
+                        max_query_regions: Some(64),
+                    },
+                ))),
+                &hw_counter,
+            )
+            .unwrap();
+        assert_eq!(max_query_regions(&segment.payload_index.borrow()), 64);
+        segment.flush(true, false).unwrap();
+        (dir, segment.current_path.clone())
+    };
+
+    let segment = load_segment(&full_segment_path, &AtomicBool::new(false))
+        .unwrap()
+        .unwrap();
+    let payload_index = segment.payload_index.borrow();
+    assert_eq!(max_query_regions(&payload_index), 64);
+
+    let filter = Filter::new_must(Condition::Field(FieldCondition::new_geo_radius(
+        key.clone(),
+        GeoRadius {
+            center: GeoPoint::new(13.4, 52.5).unwrap(),
+            radius: 1_000.0,
+        },
+    )));
+    assert_eq!(payload_index.query_points(&filter, &hw_counter), vec![0, 1]);
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use atomic_refcell::AtomicRefCell;
+
+use crate::common::operation_error::{OperationError, OperationResult};
+use crate::id_tracker::IdTrackerSS;
+use crate::index::index_storage::{FsIndexStorage, IndexStorage};
+use crate::index::struct_payload_index::StructPayloadIndex;
+use crate::payload_storage::payload_storage_enum::PayloadStorageEnum;
+use crate::types::VectorNameBuf;
+use crate::vector_storage::VectorStorageEnum;
+
+/// Configuration for opening a [`StructPayloadIndex`].
+///
+/// Payload storage, id tracker and path are required, everything else is optional.
+///
+/// ```ignore
+/// let index = StructPayloadIndexBuilder::new()
+///     .payload(payload_storage)
+///     .id_tracker(id_tracker)
+///     .path(path)
+///     .appendable(true)
+///     .create(true)
+///     .build()?;
+/// ```
+#[derive(Debug, Default)]
+pub struct StructPayloadIndexBuilder {
+    payload: Option<Arc<AtomicRefCell<PayloadStorageEnum>>>,
+    id_tracker: Option<Arc<AtomicRefCell<IdTrackerSS>>>,
+    vector_storages: HashMap<VectorNameBuf, Arc<AtomicRefCell<VectorStorageEnum>>>,
+    path: Option<PathBuf>,
+    index_storage: Option<Arc<dyn IndexStorage>>,
+    is_appendable: bool,
+    create: bool,
+}
+
+impl StructPayloadIndexBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Payload storage to index. Required.
+    pub fn payload(mut self, payload: Arc<AtomicRefCell<PayloadStorageEnum>>) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// Id tracker of the segment, used for `has_id` conditions and estimations. Required.
+    pub fn id_tracker(mut self, id_tracker: Arc<AtomicRefCell<IdTrackerSS>>) -> Self {
+        self.id_tracker = Some(id_tracker);
+        self
+    }
+
+    /// Vector storages, used for `has_vector` conditions. Replaces previously added storages.
+    pub fn vector_storages(
+        mut self,
+        vector_storages: HashMap<VectorNameBuf, Arc<AtomicRefCell<VectorStorageEnum>>>,
+    ) -> Self {
+        self.vector_storages = vector_storages;
+        self
+    }
+
+    /// Add a single vector storage, used for `has_vector` conditions
+    pub fn vector_storage(
+        mut self,
+        vector_name: impl Into<VectorNameBuf>,
+        vector_storage: Arc<AtomicRefCell<VectorStorageEnum>>,
+    ) -> Self {
+        self.vector_storages
+            .insert(vector_name.into(), vector_storage);
+        self
+    }
+
+    /// Directory of the payload index persistence. Required.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Persistence backend of index metadata. Default: [`FsIndexStorage`] in `path`.
+    pub fn index_storage(mut self, index_storage: Arc<dyn IndexStorage>) -> Self {
+        self.index_storage = Some(index_storage);
+        self
+    }
+
+    /// Whether the index accepts updates. Default: false.
+    pub fn appendable(mut self, is_appendable: bool) -> Self {
+        self.is_appendable = is_appendable;
+        self
+    }
+
+    /// Whether to create field indexes missing on disk. Default: false.
+    pub fn create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    pub fn build(self) -> OperationResult<StructPayloadIndex> {
+        let Self {
+            payload,
+            id_tracker,
+            vector_storages,
+            path,
+            index_storage,
+            is_appendable,
+            create,
+        } = self;
+
+        let missing = |option: &str| {
+            OperationError::service_error(format!(
+                "Failed to open payload index: {option} is not set"
+            ))
+        };
+
+        let payload = payload.ok_or_else(|| missing("payload storage"))?;
+        let id_tracker = id_tracker.ok_or_else(|| missing("id tracker"))?;
+        let path = path.ok_or_else(|| missing("path"))?;
+        let index_storage =
+            index_storage.unwrap_or_else(|| Arc::new(FsIndexStorage::new(path.clone())));
+
+        StructPayloadIndex::open_with_storages(
+            payload,
+            id_tracker,
+            vector_storages,
+            &path,
+            index_storage,
+            is_appendable,
+            create,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use common::counter::hardware_counter::HardwareCounterCell;
+    use parking_lot::Mutex;
+    use tempfile::Builder;
+
+    use super::*;
+    use crate::fixtures::payload_context_fixture::{
+        FixtureIdTracker, create_payload_storage_fixture,
+    };
+    use crate::fixtures::payload_fixtures::{INT_KEY, STR_KEY};
+    use crate::index::PayloadIndex;
+    use crate::index::field_index::composite_index::CompositeIndex;
+    use crate::index::payload_config::{PAYLOAD_INDEX_CONFIG_FILE, PayloadConfig};
+    use crate::json_path::JsonPath;
+    use crate::types::PayloadSchemaType;
+
+    #[derive(Debug, Default)]
+    struct MemoryIndexStorage {
+        entries: Mutex<BTreeMap<String, Vec<u8>>>,
+    }
+
+    impl IndexStorage for MemoryIndexStorage {
+        fn put(&self, name: &str, bytes: &[u8]) -> OperationResult<()> {
+            self.entries.lock().insert(name.to_string(), bytes.to_vec());
+            Ok(())
+        }
+
+        fn get(&self, name: &str) -> OperationResult<Option<Vec<u8>>> {
+            Ok(self.entries.lock().get(name).cloned())
+        }
+
+        fn delete(&self, name: &str) -> OperationResult<()> {
+            self.entries.lock().remove(name);
+            Ok(())
+        }
+
+        fn list(&self) -> OperationResult<Vec<String>> {
+            Ok(self.entries.lock().keys().cloned().collect())
+        }
+    }
+
+    #[test]
+    fn test_custom_index_storage() {
+        let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+        let payload = Arc::new(AtomicRefCell::new(
+            create_payload_storage_fixture(10, 42).into(),
+        ));
+        let id_tracker = Arc::new(AtomicRefCell::new(FixtureIdTracker::new(10)));
+        let index_storage = Arc::new(MemoryIndexStorage::default());
+        let key: JsonPath = STR_KEY.parse().unwrap();
+        let composite_fields: Vec<JsonPath> = vec![key.clone(), INT_KEY.parse().unwrap()];
+
+        let open = || {
+            StructPayloadIndexBuilder::new()
+                .payload(payload.clone())
+                .id_tracker(id_tracker.clone())
+                .path(dir.path())
+                .index_storage(index_storage.clone())
+                .appendable(true)
+                .create(true)
+                .build()
+                .unwrap()
+        };
+
+        let mut index = open();
+        index
+            .set_indexed(
+                &key,
+                PayloadSchemaType::Keyword,
+                &HardwareCounterCell::new(),
+            )
+            .unwrap();
+        index.set_composite_index(&composite_fields).unwrap();
+        drop(index);
+
+        let composite_file = CompositeIndex::file_name(&composite_fields);
+        assert!(!PayloadConfig::get_config_path(dir.path()).exists());
+        assert!(!dir.path().join(&composite_file).exists());
+        assert_eq!(
+            index_storage.list().unwrap(),
+            vec![composite_file, PAYLOAD_INDEX_CONFIG_FILE.to_string()]
+        );
+
+        let index = open();
+        assert!(index.indexed_fields().contains_key(&key));
+    }
+
+    #[test]
+    fn test_missing_required_option() {
+        let dir = Builder::new().prefix("payload_dir").tempdir().unwrap();
+
+        let err = StructPayloadIndexBuilder::new()
+            .path(dir.path())
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("payload storage is not set"));
+    }
+}
@@ -0,0 +1,233 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::{AnyVariants, FieldCondition, Match, MatchExcept, MatchValue, ValueVariants};
+
+/// Normalization applied to string values of a payload field.
+///
+/// Transforms are applied to values when they are indexed, and to values of the conditions
+/// when the index is queried, so that differently formatted values collapse into one.
+/// Payload itself is stored unchanged, only the indexed representation is transformed.
+/// Indexed values can therefore not be used to restore the original ones.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueTransform {
+    /// Remove leading and trailing whitespace
+    Trim,
+    /// Convert to lowercase
+    Lowercase,
+    /// Replace each sequence of whitespace characters with a single space
+    CollapseWhitespace,
+}
+
+impl ValueTransform {
+    fn apply(self, value: &str) -> String {
+        match self {
+            ValueTransform::Trim => value.trim().to_string(),
+            ValueTransform::Lowercase => value.to_lowercase(),
+            ValueTransform::CollapseWhitespace => {
+                let mut collapsed = String::with_capacity(value.len());
+                let mut in_whitespace = false;
+                for char in value.chars() {
+                    if char.is_whitespace() {
+                        if !in_whitespace {
+                            collapsed.push(' ');
+                        }
+                        in_whitespace = true;
+                    } else {
+                        collapsed.push(char);
+                        in_whitespace = false;
+                    }
+                }
+                collapsed
+            }
+        }
+    }
+}
+
+/// Apply all transforms to a string, in order
+pub fn transform_str(transforms: &[ValueTransform], value: &str) -> String {
+    transforms
+        .iter()
+        .fold(value.to_string(), |value, transform| {
+            transform.apply(&value)
+        })
+}
+
+/// Apply all transforms to string values, including strings within arrays.
+/// Values of other types are returned unchanged.
+pub fn transform_value(transforms: &[ValueTransform], value: &Value) -> Value {
+    match value {
+        Value::String(string) => Value::String(transform_str(transforms, string)),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| transform_value(transforms, value))
+                .collect(),
+        ),
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::Object(_) => value.clone(),
+    }
+}
+
+/// Call `f` with transformed `values`, avoiding allocations if there are no transforms
+pub fn with_transformed_values<R>(
+    transforms: &[ValueTransform],
+    values: &[&Value],
+    f: impl FnOnce(&[&Value]) -> R,
+) -> R {
+    if transforms.is_empty() {
+        return f(values);
+    }
+
+    let transformed: Vec<Value> = values
+        .iter()
+        .map(|value| transform_value(transforms, value))
+        .collect();
+    let transformed_refs: Vec<&Value> = transformed.iter().collect();
+    f(&transformed_refs)
+}
+
+/// Transform string values of a `match` condition, so they can be compared to indexed values
+pub fn transform_field_condition<'a>(
+    transforms: &[ValueTransform],
+    condition: &'a FieldCondition,
+) -> Cow<'a, FieldCondition> {
+    if transforms.is_empty() {
+        return Cow::Borrowed(condition);
+    }
+
+    let transform_any = |any: &AnyVariants| match any {
+        AnyVariants::Strings(strings) => AnyVariants::Strings(
+            strings
+                .iter()
+                .map(|string| transform_str(transforms, string))
+                .collect(),
+        ),
+        AnyVariants::Integers(_) => any.clone(),
+    };
+
+    let r#match = match &condition.r#match {
+        Some(Match::Value(MatchValue {
+            value: ValueVariants::String(string),
+        })) => Match::Value(MatchValue {
+            value: ValueVariants::String(transform_str(transforms, string)),
+        }),
+        Some(Match::Any(any)) => Match::new_any(transform_any(&any.any)),
+        Some(Match::Except(MatchExcept { except })) => Match::new_except(transform_any(except)),
+        Some(_) | None => return Cow::Borrowed(condition),
+    };
+
+    Cow::Owned(FieldCondition {
+        r#match: Some(r#match),
+        ..condition.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_path::JsonPath;
+    use crate::types::Range;
+
+    #[test]
+    fn test_transforms_collapse_values() {
+        let transforms = [
+            ValueTransform::Trim,
+            ValueTransform::Lowercase,
+            ValueTransform::CollapseWhitespace,
+        ];
+
+        for value in [" Red", "red", "RED ", "  rEd\t"] {
+            assert_eq!(transform_str(&transforms, value), "red");
+        }
+        assert_eq!(transform_str(&transforms, " Dark \t  Red "), "dark red");
+
+        let value = serde_json::json!([" A ", 1, "b"]);
+        assert_eq!(
+            transform_value(&transforms, &value),
+            serde_json::json!(["a", 1, "b"]),
+        );
+    }
+
+    #[test]
+    fn test_transform_field_condition() {
+        let transforms = [ValueTransform::Trim, ValueTransform::Lowercase];
+        let key = JsonPath::new("color");
+        let strings = |values: &[&str]| {
+            AnyVariants::Strings(values.iter().map(|value| value.to_string()).collect())
+        };
+
+        let condition = FieldCondition::new_match(key.clone(), " Red".to_string().into());
+        let transformed = transform_field_condition(&transforms, &condition);
+        assert_eq!(transformed.r#match, Some("red".to_string().into()));
+        assert_eq!(transformed.key, key);
+
+        let condition =
+            FieldCondition::new_match(key.clone(), Match::new_any(strings(&["Red ", "BLUE"])));
+        let transformed = transform_field_condition(&transforms, &condition);
+        assert_eq!(
+            transformed.r#match,
+            Some(Match::new_any(strings(&["red", "blue"]))),
+        );
+
+        let condition =
+            FieldCondition::new_match(key.clone(), Match::new_except(strings(&["GREEN"])));
+        let transformed = transform_field_condition(&transforms, &condition);
+        assert_eq!(
+            transformed.r#match,
+            Some(Match::new_except(strings(&["green"]))),
+        );
+
+        // Integers are not transformed
+        let condition = FieldCondition::new_match(
+            key.clone(),
+            Match::new_any(AnyVariants::Integers([1, 2].into_iter().collect())),
+        );
+        let transformed = transform_field_condition(&transforms, &condition);
+        assert_eq!(transformed.r#match, condition.r#match);
+
+        // Conditions without string values are borrowed
+        let condition = FieldCondition::new_range(
+            key.clone(),
+            Range {
+                lt: None,
+                gt: None,
+                gte: Some(1.0),
+                lte: None,
+            },
+        );
+        assert!(matches!(
+            transform_field_condition(&transforms, &condition),
+            Cow::Borrowed(_),
+        ));
+        let condition = FieldCondition::new_match(key, " Red".to_string().into());
+        assert!(matches!(
+            transform_field_condition(&[], &condition),
+            Cow::Borrowed(_),
+        ));
+    }
+
+    #[test]
+    fn test_with_transformed_values() {
+        let transforms = [ValueTransform::CollapseWhitespace];
+        let first = serde_json::json!("a  b");
+        let second = serde_json::json!(["c \t d", 5]);
+
+        let transformed = with_transformed_values(&transforms, &[&first, &second], |values| {
+            values
+                .iter()
+                .map(|&value| value.clone())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            transformed,
+            vec![serde_json::json!("a b"), serde_json::json!(["c d", 5])],
+        );
+
+        // Values are passed through without transforms
+        let untouched = with_transformed_values(&[], &[&first], |values| values[0].clone());
+        assert_eq!(untouched, first);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::types::ValuesCount;
+
+/// Number of points for each count of values of a field.
+///
+/// Allows exact cardinality estimation of `values_count` conditions.
+/// Points without values are not tracked explicitly, their number is derived from the number
+/// of available points instead, so points without the field count as having zero values.
+///
+/// Counts are taken from a field index, which skips values of other types than the indexed one.
+/// They only match counts seen by `values_count` conditions if every value was indexed, which
+/// is tracked by [`Self::is_exact`].
+#[derive(Debug, Default, Clone)]
+pub struct ValuesCountHistogram {
+    /// `points[n - 1]` is the number of points with exactly `n` values
+    points: Vec<usize>,
+    /// Whether counts of all points are known to match counts of their payload values
+    exact: bool,
+}
+
+impl ValuesCountHistogram {
+    /// Whether every counted point had all of its values indexed.
+    ///
+    /// Histograms start inexact, as they are built from indexes without reading payload.
+    /// Once inexact, a histogram stays so until it is built again.
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    pub fn set_exact(&mut self, exact: bool) {
+        self.exact = exact;
+    }
+
+    pub fn add(&mut self, values_count: usize) {
+        if values_count == 0 {
+            return;
+        }
+        if self.points.len() < values_count {
+            self.points.resize(values_count, 0);
+        }
+        self.points[values_count - 1] += 1;
+    }
+
+    pub fn remove(&mut self, values_count: usize) {
+        if values_count == 0 {
+            return;
+        }
+        if let Some(points) = self.points.get_mut(values_count - 1) {
+            *points = points.saturating_sub(1);
+        }
+        while self.points.last() == Some(&0) {
+            self.points.pop();
+        }
+    }
+
+    pub fn update(&mut self, old_values_count: usize, new_values_count: usize) {
+        if old_values_count != new_values_count {
+            self.remove(old_values_count);
+            self.add(new_values_count);
+        }
+    }
+
+    /// Number of points with at least one value
+    pub fn points_with_values(&self) -> usize {
+        self.points.iter().sum()
+    }
+
+    /// Number of points, out of `available_points`, which counts of indexed values satisfy the
+    /// `condition`. Equals the number of points matching the condition if [`Self::is_exact`].
+    pub fn count_points(&self, condition: &ValuesCount, available_points: usize) -> usize {
+        let empty_points = if condition.check_count(0) {
+            available_points.saturating_sub(self.points_with_values())
+        } else {
+            0
+        };
+
+        self.points
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| condition.check_count(idx + 1))
+            .map(|(_, points)| points)
+            .sum::<usize>()
+            + empty_points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_count_histogram() {
+        let mut histogram = ValuesCountHistogram::default();
+        for values_count in [0, 1, 2, 2, 3] {
+            histogram.add(values_count);
+        }
+        histogram.update(3, 2);
+
+        assert_eq!(histogram.count_points(&ValuesCount::exact(0), 10), 6);
+        assert_eq!(histogram.count_points(&ValuesCount::exact(1), 10), 1);
+        assert_eq!(histogram.count_points(&ValuesCount::exact(2), 10), 3);
+        assert_eq!(histogram.count_points(&ValuesCount::exact(3), 10), 0);
+        assert_eq!(histogram.count_points(&(1..3).into(), 10), 4);
+    }
+
+    #[test]
+    fn test_values_count_histogram_remove() {
+        let mut histogram = ValuesCountHistogram::default();
+        for values_count in [1, 4, 4] {
+            histogram.add(values_count);
+        }
+        assert_eq!(histogram.points_with_values(), 3);
+
+        // Points without values are not tracked
+        histogram.remove(0);
+        assert_eq!(histogram.points_with_values(), 3);
+
+        histogram.remove(4);
+        histogram.remove(4);
+        assert_eq!(histogram.points_with_values(), 1);
+        assert_eq!(histogram.points, vec![1]);
+
+        // Removing an untracked count doesn't underflow
+        histogram.remove(2);
+        histogram.remove(7);
+        assert_eq!(histogram.points, vec![1]);
+
+        histogram.update(1, 0);
+        assert_eq!(histogram.points_with_values(), 0);
+        assert_eq!(histogram.count_points(&ValuesCount::exact(0), 5), 5);
+    }
+}
@@ -156,4 +156,25 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_visited_list_grows() {
+        let pool = VisitedPool::new();
+        {
+            let mut visited_list = pool.get(10);
+            assert!(!visited_list.check_and_update_visited(9));
+        }
+
+        // Pooled list is reused for a larger number of points, e.g. after points were added
+        let mut visited_list = pool.get(100);
+        assert_eq!(visited_list.visited_list.visit_counters.len(), 100);
+        assert!(!visited_list.check(9));
+        assert!(!visited_list.check_and_update_visited(99));
+        assert!(visited_list.check(99));
+
+        // Points added after the list was requested are tracked as well
+        assert!(!visited_list.check(150));
+        assert!(!visited_list.check_and_update_visited(150));
+        assert!(visited_list.check(150));
+    }
 }
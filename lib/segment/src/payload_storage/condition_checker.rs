@@ -6,8 +6,8 @@ use serde_json::Value;
 
 use crate::types::{
     AnyVariants, DateTimePayloadType, FieldCondition, FloatPayloadType, GeoBoundingBox, GeoPoint,
-    GeoPolygon, GeoRadius, Match, MatchAny, MatchExcept, MatchPhrase, MatchText, MatchTextAny,
-    MatchValue, Range, RangeInterface, ValueVariants, ValuesCount,
+    GeoPolygon, GeoRadius, Match, MatchAny, MatchExcept, MatchPhrase, MatchPrefix, MatchText,
+    MatchTextAny, MatchValue, Range, RangeInterface, ValueVariants, ValuesCount,
 };
 
 /// Threshold representing the point to which iterating through an IndexSet is more efficient than using hashing.
@@ -59,12 +59,79 @@ fn check_is_null(is_null: bool, payload_value: &Value) -> bool {
     }
 }
 
-impl ValueChecker for FieldCondition {
+/// Check a condition with `range_mode: all`: the field has values, and every one of them,
+/// including array elements, lies in the range
+pub fn check_range_all<'a>(
+    condition: &FieldCondition,
+    values: impl IntoIterator<Item = &'a Value>,
+) -> bool {
+    let mut has_values = false;
+    for value in values {
+        let elements = match value {
+            Value::Array(array) => array.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        for element in elements {
+            if !condition.check_match(element) {
+                return false;
+            }
+            has_values = true;
+        }
+    }
+    has_values
+}
+
+/// `is_empty` sub-condition of a [`FieldCondition`], checked over the whole value of the field
+struct IsEmptyChecker(bool);
+
+static IS_EMPTY: IsEmptyChecker = IsEmptyChecker(true);
+static IS_NOT_EMPTY: IsEmptyChecker = IsEmptyChecker(false);
+
+impl ValueChecker for IsEmptyChecker {
     fn check_match(&self, payload: &Value) -> bool {
-        // Destructuring so compiler can check that we don't forget a condition
+        check_is_empty(self.0, payload)
+    }
+
+    fn check(&self, payload: &Value) -> bool {
+        check_is_empty(self.0, payload)
+    }
+
+    fn check_empty(&self) -> bool {
+        self.0
+    }
+}
+
+/// `is_null` sub-condition of a [`FieldCondition`], checked over the whole value of the field
+struct IsNullChecker(bool);
+
+static IS_NULL: IsNullChecker = IsNullChecker(true);
+static IS_NOT_NULL: IsNullChecker = IsNullChecker(false);
+
+impl ValueChecker for IsNullChecker {
+    fn check_match(&self, payload: &Value) -> bool {
+        check_is_null(self.0, payload)
+    }
+
+    fn check(&self, payload: &Value) -> bool {
+        check_is_null(self.0, payload)
+    }
+
+    fn check_empty(&self) -> bool {
+        !self.0
+    }
+}
+
+impl FieldCondition {
+    /// Checkers of all sub-conditions set in the condition.
+    ///
+    /// This is the only place where sub-conditions are listed for checking. The condition is
+    /// destructured, so the compiler reports a new kind of sub-condition, which is not listed.
+    fn value_checkers(&self) -> impl Iterator<Item = &dyn ValueChecker> {
         let FieldCondition {
             r#match,
             range,
+            // Applied over all values of the field, see `check_range_all`
+            range_mode: _,
             geo_radius,
             geo_bounding_box,
             geo_polygon,
@@ -74,77 +141,63 @@ impl ValueChecker for FieldCondition {
             is_null,
         } = self;
 
-        r#match
-            .as_ref()
-            .is_some_and(|condition| condition.check_match(payload))
-            || range
-                .as_ref()
-                .is_some_and(|range_interface| match range_interface {
-                    RangeInterface::Float(condition) => condition.check_match(payload),
-                    RangeInterface::DateTime(condition) => condition.check_match(payload),
-                })
-            || geo_radius
+        let checkers: [Option<&dyn ValueChecker>; 8] = [
+            r#match.as_ref().map(|checker| checker as &dyn ValueChecker),
+            range.as_ref().map(|checker| checker as &dyn ValueChecker),
+            geo_radius
                 .as_ref()
-                .is_some_and(|condition| condition.check_match(payload))
-            || geo_bounding_box
+                .map(|checker| checker as &dyn ValueChecker),
+            geo_bounding_box
                 .as_ref()
-                .is_some_and(|condition| condition.check_match(payload))
-            || geo_polygon
+                .map(|checker| checker as &dyn ValueChecker),
+            geo_polygon
                 .as_ref()
-                .is_some_and(|condition| condition.check_match(payload))
-            || values_count
+                .map(|checker| checker as &dyn ValueChecker),
+            values_count
                 .as_ref()
-                .is_some_and(|condition| condition.check_match(payload))
-            || is_empty.is_some_and(|is_empty| check_is_empty(is_empty, payload))
-            || is_null.is_some_and(|is_null| check_is_null(is_null, payload))
+                .map(|checker| checker as &dyn ValueChecker),
+            is_empty.map(|is_empty| {
+                if is_empty {
+                    &IS_EMPTY as &dyn ValueChecker
+                } else {
+                    &IS_NOT_EMPTY as &dyn ValueChecker
+                }
+            }),
+            is_null.map(|is_null| {
+                if is_null {
+                    &IS_NULL as &dyn ValueChecker
+                } else {
+                    &IS_NOT_NULL as &dyn ValueChecker
+                }
+            }),
+        ];
+        checkers.into_iter().flatten()
     }
+}
 
-    fn check(&self, payload: &Value) -> bool {
-        let FieldCondition {
-            r#match: _,
-            range: _,
-            geo_radius: _,
-            geo_bounding_box: _,
-            geo_polygon: _,
-            values_count,
-            key: _,
-            is_empty,
-            is_null,
-        } = self;
+impl ValueChecker for FieldCondition {
+    fn check_match(&self, payload: &Value) -> bool {
+        self.value_checkers()
+            .any(|checker| checker.check_match(payload))
+    }
 
-        if values_count.is_some() {
-            self.values_count
-                .as_ref()
-                .unwrap()
-                .check_count_from(payload)
-        } else if is_empty.is_some() {
-            check_is_empty(is_empty.unwrap(), payload)
-        } else if is_null.is_some() {
-            check_is_null(is_null.unwrap(), payload)
-        } else {
-            self._check(payload)
-        }
+    fn check(&self, payload: &Value) -> bool {
+        self.value_checkers().any(|checker| checker.check(payload))
     }
 
     fn check_empty(&self) -> bool {
-        let FieldCondition {
-            r#match: _,
-            range: _,
-            geo_radius: _,
-            geo_bounding_box: _,
-            geo_polygon: _,
-            values_count: _,
-            key: _,
-            is_empty,
-            is_null,
-        } = self;
-        if let Some(is_empty) = is_empty {
-            return *is_empty;
-        }
-        if let Some(is_null) = is_null {
-            return !*is_null;
+        // Missing field is considered to have zero values
+        self.value_checkers().any(|checker| checker.check_empty())
+    }
+}
+
+impl ValueChecker for RangeInterface {
+    fn check_match(&self, payload: &Value) -> bool {
+        match self {
+            RangeInterface::Float(condition) => condition.check_match(payload),
+            RangeInterface::DateTime(condition) => condition.check_match(payload),
+            RangeInterface::Keyword(condition) => condition.check_match(payload),
         }
-        false
     }
 }
 
@@ -165,6 +218,10 @@ impl ValueChecker for Match {
                     _ => false,
                 }
             }
+            Match::Prefix(MatchPrefix { prefix }) => match payload {
+                Value::String(stored) => stored.starts_with(prefix.as_str()),
+                _ => false,
+            },
             Match::TextAny(MatchTextAny { text_any }) => match payload {
                 Value::String(stored) => text_any
                     .split_whitespace()
@@ -241,6 +298,14 @@ impl ValueChecker for Range<DateTimePayloadType> {
     }
 }
 
+impl ValueChecker for Range<String> {
+    fn check_match(&self, payload: &Value) -> bool {
+        payload
+            .as_str()
+            .is_some_and(|keyword| self.check_keyword(keyword))
+    }
+}
+
 impl ValueChecker for GeoBoundingBox {
     fn check_match(&self, payload: &Value) -> bool {
         match payload {
@@ -312,7 +377,7 @@ mod tests {
 
     use super::*;
     use crate::json_path::JsonPath;
-    use crate::types::GeoPoint;
+    use crate::types::{GeoPoint, RangeMode};
 
     #[test]
     fn test_geo_matching() {
@@ -382,6 +447,76 @@ mod tests {
         assert!(gte_two_countries_query.check(&countries));
     }
 
+    #[test]
+    fn test_check_range_all() {
+        let scores = json!([1, 5, 9]);
+        let high_scores = json!([7, 8, 9]);
+
+        let at_least_seven = Range {
+            lt: None,
+            gt: None,
+            gte: Some(7.0),
+            lte: None,
+        };
+        let any = FieldCondition::new_range(JsonPath::new("scores"), at_least_seven);
+        let all = any.clone().with_range_mode(RangeMode::All);
+
+        // Any element in the range is enough by default
+        assert!(any.check(&scores));
+        assert!(!check_range_all(&all, [&scores]));
+        assert!(check_range_all(&all, [&high_scores]));
+
+        // Field without values never matches all of them
+        assert!(!check_range_all(&all, [&json!([])]));
+        assert!(!check_range_all(&all, []));
+    }
+
+    #[test]
+    fn test_check_empty() {
+        let key = JsonPath::new("tags");
+
+        // Missing field has zero values
+        let no_values = FieldCondition::new_values_count(key.clone(), ValuesCount::exact(0));
+        assert!(no_values.check_empty());
+        let some_values = FieldCondition::new_values_count(key.clone(), (1..3).into());
+        assert!(!some_values.check_empty());
+
+        let prefix = FieldCondition::new_match(key, Match::new_prefix("a"));
+        assert!(!prefix.check_empty());
+    }
+
+    #[test]
+    fn test_match_prefix() {
+        let condition = FieldCondition::new_match(JsonPath::new("name"), Match::new_prefix("jo"));
+
+        assert!(condition.check(&json!("john")));
+        assert!(condition.check(&json!(["mary", "joseph"])));
+        assert!(!condition.check(&json!("mary")));
+        // Prefix is case-sensitive
+        assert!(!condition.check(&json!("John")));
+        assert!(!condition.check(&json!(42)));
+    }
+
+    #[test]
+    fn test_keyword_range() {
+        let range = Range {
+            lt: Some("d".to_string()),
+            gt: None,
+            gte: Some("b".to_string()),
+            lte: None,
+        };
+        let condition = FieldCondition::new_keyword_range(JsonPath::new("name"), range);
+
+        assert!(condition.check(&json!("b")));
+        assert!(condition.check(&json!("cherry")));
+        assert!(condition.check(&json!(["apple", "cherry"])));
+        assert!(!condition.check(&json!("d")));
+        assert!(!condition.check(&json!("apple")));
+        // Compared lexicographically, never as numbers
+        assert!(!condition.check(&json!("10")));
+        assert!(!condition.check(&json!(2)));
+    }
+
     #[test]
     fn test_value_checker_for_null_or_empty() {
         let array = json!([]);
@@ -396,6 +531,7 @@ mod tests {
         let key = JsonPath::new("key");
 
         let is_empty = FieldCondition {
+            range_mode: None,
             r#match: None,
             range: None,
             geo_radius: None,
@@ -408,6 +544,7 @@ mod tests {
         };
 
         let is_not_empty = FieldCondition {
+            range_mode: None,
             r#match: None,
             range: None,
             geo_radius: None,
@@ -420,6 +557,7 @@ mod tests {
         };
 
         let is_null = FieldCondition {
+            range_mode: None,
             r#match: None,
             range: None,
             geo_radius: None,
@@ -432,6 +570,7 @@ mod tests {
         };
 
         let is_not_null = FieldCondition {
+            range_mode: None,
             r#match: None,
             range: None,
             geo_radius: None,
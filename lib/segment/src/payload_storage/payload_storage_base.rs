@@ -102,6 +102,17 @@ pub trait ConditionChecker {
 pub trait FilterContext {
     /// Check if point satisfies filter condition. Return true if satisfies
     fn check(&self, point_id: PointOffsetType) -> bool;
+
+    /// Check a batch of points at once, writing the result for `point_ids[i]` into `out[i]`.
+    ///
+    /// Results are the same as of [`FilterContext::check`], but implementations may amortize
+    /// the cost of filter evaluation over the batch.
+    fn check_batch(&self, point_ids: &[PointOffsetType], out: &mut [bool]) {
+        debug_assert_eq!(point_ids.len(), out.len());
+        for (&point_id, result) in point_ids.iter().zip(out.iter_mut()) {
+            *result = self.check(point_id);
+        }
+    }
 }
 
 pub type PayloadStorageSS = dyn PayloadStorage + Sync + Send;
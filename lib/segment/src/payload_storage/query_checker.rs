@@ -8,11 +8,13 @@ use std::sync::Arc;
 use atomic_refcell::AtomicRefCell;
 use common::counter::hardware_counter::HardwareCounterCell;
 use common::types::PointOffsetType;
+use serde_json::Value;
 
 use crate::common::utils::{IndexesMap, check_is_empty, check_is_null};
 use crate::id_tracker::IdTrackerSS;
 use crate::index::field_index::FieldIndex;
-use crate::payload_storage::condition_checker::ValueChecker;
+use crate::index::value_transform::{ValueTransform, transform_value};
+use crate::payload_storage::condition_checker::{ValueChecker, check_range_all};
 use crate::payload_storage::payload_storage_enum::PayloadStorageEnum;
 use crate::payload_storage::{ConditionChecker, PayloadStorage};
 use crate::types::{
@@ -136,6 +138,9 @@ where
         Condition::HasId(has_id) => id_tracker
             .and_then(|id_tracker| id_tracker.external_id(point_id))
             .is_some_and(|id| has_id.has_id.contains(&id)),
+        Condition::HasIdRange(has_id_range) => id_tracker
+            .and_then(|id_tracker| id_tracker.external_id(point_id))
+            .is_some_and(|id| has_id_range.has_id_range.contains(id)),
         Condition::HasVector(has_vector) => {
             if let Some(vector_storage) = vector_storages.get(&has_vector.has_vector) {
                 !vector_storage.borrow().is_deleted_vector(point_id)
@@ -143,6 +148,12 @@ where
                 false
             }
         }
+        Condition::VersionAtMost(version_at_most) => id_tracker
+            .and_then(|id_tracker| id_tracker.internal_version(point_id))
+            .is_some_and(|version| version <= version_at_most.version_at_most),
+        Condition::FieldVsField(field_vs_field) => {
+            field_vs_field.field_vs_field.check(get_payload().deref())
+        }
         Condition::Nested(nested) => {
             let nested_path = nested.array_key();
             let nested_indexes = select_nested_indexes(&nested_path, field_indexes);
@@ -194,15 +205,55 @@ where
     R: AsRef<Vec<FieldIndex>>,
 {
     let field_values = payload.get_value(&field_condition.key);
+    check_field_values(field_condition, &field_values, field_indexes, hw_counter)
+}
+
+/// Same as [`check_field_condition`], but value transforms of the field are applied to the
+/// payload values first. `field_condition` is expected to be transformed already.
+pub fn check_transformed_field_condition<R>(
+    field_condition: &FieldCondition,
+    payload: &impl PayloadContainer,
+    transforms: &[ValueTransform],
+    field_indexes: &HashMap<PayloadKeyType, R>,
+    hw_counter: &HardwareCounterCell,
+) -> bool
+where
+    R: AsRef<Vec<FieldIndex>>,
+{
+    if transforms.is_empty() {
+        return check_field_condition(field_condition, payload, field_indexes, hw_counter);
+    }
+    let transformed: Vec<Value> = payload
+        .get_value(&field_condition.key)
+        .iter()
+        .map(|value| transform_value(transforms, value))
+        .collect();
+    let field_values: Vec<&Value> = transformed.iter().collect();
+    check_field_values(field_condition, &field_values, field_indexes, hw_counter)
+}
+
+fn check_field_values<R>(
+    field_condition: &FieldCondition,
+    field_values: &[&Value],
+    field_indexes: &HashMap<PayloadKeyType, R>,
+    hw_counter: &HardwareCounterCell,
+) -> bool
+where
+    R: AsRef<Vec<FieldIndex>>,
+{
     let field_indexes = field_indexes.get(&field_condition.key);
 
     if field_values.is_empty() {
         return field_condition.check_empty();
     }
 
+    if field_condition.is_range_all() {
+        return check_range_all(field_condition, field_values.iter().copied());
+    }
+
     // This covers a case, when a field index affects the result of the condition.
     if let Some(field_indexes) = field_indexes {
-        for p in field_values {
+        for &p in field_values {
             let mut index_checked = false;
             for index in field_indexes.as_ref() {
                 if let Some(index_check_res) =
@@ -229,7 +280,7 @@ where
         false
     } else {
         // Fallback to regular condition check if there are no indexes for the field
-        field_values.into_iter().any(|p| field_condition.check(p))
+        field_values.iter().any(|p| field_condition.check(p))
     }
 }
 
@@ -337,7 +388,8 @@ mod tests {
     use crate::payload_storage::PayloadStorage;
     use crate::payload_storage::in_memory_payload_storage::InMemoryPayloadStorage;
     use crate::types::{
-        DateTimeWrapper, FieldCondition, GeoBoundingBox, GeoPoint, PayloadField, Range, ValuesCount,
+        ComparisonOperator, DateTimeWrapper, FieldComparison, FieldCondition,
+        FieldVsFieldCondition, GeoBoundingBox, GeoPoint, PayloadField, Range, ValuesCount,
     };
 
     #[test]
@@ -660,4 +712,95 @@ mod tests {
         let query = Filter::new_must(Condition::HasId(ids.into()));
         assert!(payload_checker.check(2, &query));
     }
+
+    #[test]
+    fn test_field_vs_field_condition() {
+        let payload = payload_json! {
+            "created_at": "2020-02-15T00:00:00Z",
+            "updated_at": "2020-03-01T12:00:00+01:00",
+            "min_price": 10,
+            "price": 12.5,
+            "max_price": 12.5,
+            "sizes": [38, 40, 42],
+            "size": 40,
+            "color": "red",
+            "accent": "red",
+            "tags": ["sale", "new"],
+            "allowed_tags": ["new", "used"],
+            "banned_tags": ["used", "broken"],
+            "no_tags": [],
+            "nothing": null,
+            "also_nothing": [null],
+        };
+
+        let hw_counter = HardwareCounterCell::new();
+
+        let mut payload_storage: PayloadStorageEnum =
+            PayloadStorageEnum::InMemoryPayloadStorage(InMemoryPayloadStorage::default());
+        let mut id_tracker = InMemoryIdTracker::new();
+        id_tracker.set_link(0.into(), 0).unwrap();
+        payload_storage.overwrite(0, &payload, &hw_counter).unwrap();
+
+        let payload_checker = SimpleConditionChecker::new(
+            Arc::new(AtomicRefCell::new(payload_storage)),
+            Arc::new(AtomicRefCell::new(id_tracker)),
+            HashMap::new(),
+        );
+
+        let compare = |left: &str, op: ComparisonOperator, right: &str| {
+            Filter::new_must(Condition::FieldVsField(FieldVsFieldCondition {
+                field_vs_field: FieldComparison {
+                    left: JsonPath::new(left),
+                    op,
+                    right: JsonPath::new(right),
+                },
+            }))
+        };
+
+        // Datetimes
+        assert!(payload_checker.check(
+            0,
+            &compare("created_at", ComparisonOperator::Lt, "updated_at")
+        ));
+        assert!(!payload_checker.check(
+            0,
+            &compare("created_at", ComparisonOperator::Gte, "updated_at")
+        ));
+
+        // Numbers, integer against float
+        assert!(payload_checker.check(0, &compare("min_price", ComparisonOperator::Lte, "price")));
+        assert!(payload_checker.check(0, &compare("price", ComparisonOperator::Eq, "max_price")));
+        assert!(!payload_checker.check(0, &compare("price", ComparisonOperator::Gt, "max_price")));
+
+        // Any of the array values
+        assert!(payload_checker.check(0, &compare("size", ComparisonOperator::Eq, "sizes")));
+        assert!(!payload_checker.check(0, &compare("sizes", ComparisonOperator::Lt, "max_price")));
+
+        // Other values only support equality
+        assert!(payload_checker.check(0, &compare("color", ComparisonOperator::Eq, "accent")));
+        assert!(!payload_checker.check(0, &compare("color", ComparisonOperator::Lt, "accent")));
+
+        // Arrays on both sides: one of the left values is one of the right values
+        assert!(payload_checker.check(0, &compare("tags", ComparisonOperator::Eq, "allowed_tags")));
+        assert!(payload_checker.check(0, &compare("allowed_tags", ComparisonOperator::Eq, "tags")));
+        assert!(!payload_checker.check(0, &compare("tags", ComparisonOperator::Eq, "banned_tags")));
+        assert!(payload_checker.check(0, &compare("sizes", ComparisonOperator::Eq, "sizes")));
+        // Ordering of arrays compares the smallest left value with the largest right value
+        assert!(payload_checker.check(0, &compare("sizes", ComparisonOperator::Lt, "sizes")));
+        assert!(payload_checker.check(0, &compare("sizes", ComparisonOperator::Gt, "sizes")));
+        assert!(!payload_checker.check(0, &compare("sizes", ComparisonOperator::Lt, "min_price")));
+
+        // Empty arrays and nulls have no values to compare
+        assert!(!payload_checker.check(0, &compare("no_tags", ComparisonOperator::Eq, "no_tags")));
+        assert!(!payload_checker.check(0, &compare("tags", ComparisonOperator::Eq, "no_tags")));
+        assert!(!payload_checker.check(0, &compare("nothing", ComparisonOperator::Eq, "nothing")));
+        assert!(!payload_checker.check(
+            0,
+            &compare("nothing", ComparisonOperator::Eq, "also_nothing")
+        ));
+
+        // Missing fields never match
+        assert!(!payload_checker.check(0, &compare("price", ComparisonOperator::Eq, "missing")));
+        assert!(!payload_checker.check(0, &compare("missing", ComparisonOperator::Eq, "missing")));
+    }
 }
@@ -57,6 +57,7 @@ impl SegmentEntry for Segment {
         query_context: &SegmentQueryContext,
     ) -> OperationResult<Vec<Vec<ScoredPoint>>> {
         check_query_vectors(vector_name, query_vectors, &self.segment_config)?;
+        self.check_filter(filter)?;
         let vector_data = &self.vector_data[vector_name];
         let vector_query_context = query_context.get_vector_context(vector_name);
         let internal_results = vector_data.vector_index.borrow().search(
@@ -393,6 +394,7 @@ impl SegmentEntry for Segment {
         is_stopped: &AtomicBool,
         hw_counter: &HardwareCounterCell,
     ) -> OperationResult<Vec<(OrderValue, PointIdType)>> {
+        self.check_filter(filter)?;
         match filter {
             None => {
                 self.filtered_read_by_value_stream(order_by, limit, None, is_stopped, hw_counter)
@@ -498,6 +500,7 @@ impl SegmentEntry for Segment {
         is_stopped: &AtomicBool,
         hw_counter: &HardwareCounterCell,
     ) -> OperationResult<std::collections::BTreeSet<FacetValue>> {
+        self.check_filter(filter)?;
         self.facet_values(key, filter, is_stopped, hw_counter)
     }
 
@@ -507,6 +510,7 @@ impl SegmentEntry for Segment {
         is_stopped: &AtomicBool,
         hw_counter: &HardwareCounterCell,
     ) -> OperationResult<HashMap<FacetValue, usize>> {
+        self.check_filter(request.filter.as_ref())?;
         self.approximate_facet(request, is_stopped, hw_counter)
     }
 
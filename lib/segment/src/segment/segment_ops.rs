@@ -20,7 +20,7 @@ use crate::data_types::vectors::VectorInternal;
 use crate::entry::entry_point::SegmentEntry;
 use crate::index::{PayloadIndex, VectorIndex};
 use crate::types::{
-    Payload, PayloadFieldSchema, PayloadKeyType, PointIdType, SegmentState, SeqNumberType,
+    Filter, Payload, PayloadFieldSchema, PayloadKeyType, PointIdType, SegmentState, SeqNumberType,
     SnapshotFormat, VectorName,
 };
 use crate::utils;
@@ -294,6 +294,9 @@ impl Segment {
             self.id_tracker
                 .borrow_mut()
                 .set_internal_version(internal_id, op_num)?;
+            self.payload_index
+                .borrow_mut()
+                .update_point_version(internal_id, op_num);
         }
 
         Ok(applied)
@@ -324,6 +327,17 @@ impl Segment {
         }
     }
 
+    /// Reject filters with conditions, which contradict types of indexed fields
+    pub(super) fn check_filter(&self, filter: Option<&Filter>) -> OperationResult<()> {
+        match filter {
+            Some(filter) => self
+                .payload_index
+                .borrow()
+                .validate_range_conditions(filter),
+            None => Ok(()),
+        }
+    }
+
     pub(super) fn get_state(&self) -> SegmentState {
         SegmentState {
             initial_version: self.initial_version,
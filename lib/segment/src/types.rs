@@ -1968,6 +1968,20 @@ impl PayloadSchemaParams {
             PayloadSchemaParams::Bool(i) => i.on_disk.unwrap_or_default(),
         }
     }
+
+    /// Whether only the presence of the field should be indexed, without its values
+    pub fn is_exists_only(&self) -> bool {
+        match self {
+            PayloadSchemaParams::Keyword(i) => i.exists_only.unwrap_or_default(),
+            PayloadSchemaParams::Integer(_)
+            | PayloadSchemaParams::Float(_)
+            | PayloadSchemaParams::Geo(_)
+            | PayloadSchemaParams::Text(_)
+            | PayloadSchemaParams::Bool(_)
+            | PayloadSchemaParams::Datetime(_)
+            | PayloadSchemaParams::Uuid(_) => false,
+        }
+    }
 }
 
 impl Validate for PayloadSchemaParams {
@@ -1976,7 +1990,7 @@ impl Validate for PayloadSchemaParams {
             PayloadSchemaParams::Keyword(_) => Ok(()),
             PayloadSchemaParams::Integer(integer_index_params) => integer_index_params.validate(),
             PayloadSchemaParams::Float(_) => Ok(()),
-            PayloadSchemaParams::Geo(_) => Ok(()),
+            PayloadSchemaParams::Geo(geo_index_params) => geo_index_params.validate(),
             PayloadSchemaParams::Text(_) => Ok(()),
             PayloadSchemaParams::Bool(_) => Ok(()),
             PayloadSchemaParams::Datetime(_) => Ok(()),
@@ -2028,6 +2042,11 @@ impl Display for PayloadFieldSchema {
         match self {
             PayloadFieldSchema::FieldType(t) => write!(f, "{}", t.name()),
             PayloadFieldSchema::FieldParams(params) => match params {
+                PayloadSchemaParams::Keyword(keyword_params)
+                    if keyword_params.exists_only.unwrap_or_default() =>
+                {
+                    write!(f, "keyword (with exists_only: true)")
+                }
                 PayloadSchemaParams::Keyword(_)
                 | PayloadSchemaParams::Float(_)
                 | PayloadSchemaParams::Geo(_)
@@ -2085,6 +2104,13 @@ impl PayloadFieldSchema {
         }
     }
 
+    pub fn is_exists_only(&self) -> bool {
+        match self {
+            PayloadFieldSchema::FieldType(_) => false,
+            PayloadFieldSchema::FieldParams(params) => params.is_exists_only(),
+        }
+    }
+
     pub fn kind(&self) -> PayloadSchemaType {
         match self {
             PayloadFieldSchema::FieldType(t) => *t,
@@ -2106,7 +2132,9 @@ impl PayloadFieldSchema {
                 PayloadSchemaType::Datetime => false,
             },
             PayloadFieldSchema::FieldParams(payload_schema_params) => match payload_schema_params {
-                PayloadSchemaParams::Keyword(_) => true,
+                PayloadSchemaParams::Keyword(keyword_index_params) => {
+                    !keyword_index_params.exists_only.unwrap_or_default()
+                }
                 PayloadSchemaParams::Integer(integer_index_params) => {
                     integer_index_params.lookup == Some(true)
                 }
@@ -2280,6 +2308,22 @@ impl<S: Into<String>> From<S> for MatchPhrase {
     }
 }
 
+/// Match of keywords, which start with the given prefix.
+/// Empty prefix matches any keyword.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub struct MatchPrefix {
+    pub prefix: String,
+}
+
+impl<S: Into<String>> From<S> for MatchPrefix {
+    fn from(prefix: S) -> Self {
+        MatchPrefix {
+            prefix: prefix.into(),
+        }
+    }
+}
+
 /// Exact match on any of the given values
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -2302,6 +2346,7 @@ pub enum MatchInterface {
     Text(MatchText),
     TextAny(MatchTextAny),
     Phrase(MatchPhrase),
+    Prefix(MatchPrefix),
     Any(MatchAny),
     Except(MatchExcept),
 }
@@ -2314,6 +2359,7 @@ pub enum Match {
     Text(MatchText),
     TextAny(MatchTextAny),
     Phrase(MatchPhrase),
+    Prefix(MatchPrefix),
     Any(MatchAny),
     Except(MatchExcept),
 }
@@ -2333,6 +2379,12 @@ impl Match {
         })
     }
 
+    pub fn new_prefix(prefix: &str) -> Self {
+        Self::Prefix(MatchPrefix {
+            prefix: prefix.into(),
+        })
+    }
+
     pub fn new_any(any: AnyVariants) -> Self {
         Self::Any(MatchAny { any })
     }
@@ -2361,6 +2413,7 @@ impl From<MatchInterface> for Match {
                 except: except.except,
             }),
             MatchInterface::Phrase(MatchPhrase { phrase }) => Self::Phrase(MatchPhrase { phrase }),
+            MatchInterface::Prefix(MatchPrefix { prefix }) => Self::Prefix(MatchPrefix { prefix }),
         }
     }
 }
@@ -2439,13 +2492,62 @@ impl From<Vec<IntPayloadType>> for MatchExcept {
     }
 }
 
+/// Which values of a field have to lie in the range for a point to match
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeMode {
+    /// At least one value is in the range
+    #[default]
+    Any,
+    /// The field has values and all of them are in the range
+    All,
+}
+
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum RangeInterface {
+    // Lexicographic range of keywords, tagged as `{"keyword": {...}}`.
+    // Untagged string bounds are parsed as a datetime range, so this variant has to go first,
+    // as untagged bounds of any type would accept the tagged form too.
+    Keyword(
+        #[serde(with = "KeywordRangeTagged")]
+        #[schemars(with = "KeywordRangeTagged")]
+        Range<String>,
+    ),
     Float(Range<FloatPayloadType>),
     DateTime(Range<DateTimePayloadType>),
 }
 
+// Use via `#[serde(with = "KeywordRangeTagged")]` and `#[schemars(with = "KeywordRangeTagged")]`
+// field attributes
+/// Range of keywords, compared lexicographically
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct KeywordRangeTagged {
+    keyword: Range<String>,
+}
+
+impl KeywordRangeTagged {
+    pub fn serialize<S>(range: &Range<String>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct Tagged<'a> {
+            keyword: &'a Range<String>,
+        }
+
+        Tagged { keyword: range }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Range<String>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        <Self as Deserialize>::deserialize(deserializer).map(|tagged| tagged.keyword)
+    }
+}
+
 impl Hash for RangeInterface {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         match self {
@@ -2463,13 +2565,20 @@ impl Hash for RangeInterface {
                 gte.hash(state);
                 lte.hash(state);
             }
+            RangeInterface::Keyword(range) => {
+                let Range { lt, gt, gte, lte } = range;
+                lt.hash(state);
+                gt.hash(state);
+                gte.hash(state);
+                lte.hash(state);
+            }
         }
     }
 }
 
 /// Range filter request
 #[macro_rules_attribute::macro_rules_derive(crate::common::macros::schemars_rename_generics)]
-#[derive_args(< FloatPayloadType > => "Range", < DateTimePayloadType > => "DatetimeRange")]
+#[derive_args(< FloatPayloadType > => "Range", < DateTimePayloadType > => "DatetimeRange", < String > => "KeywordRange")]
 #[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct Range<T> {
@@ -2497,6 +2606,10 @@ impl<T: Copy> Range<T> {
 }
 
 impl<T: Copy + PartialOrd> Range<T> {
+    /// Check whether the number lies in the range.
+    ///
+    /// NaN is not ordered, so a NaN number or a NaN bound never matches a bounded range.
+    /// Payload values can't be NaN, as JSON has no representation for it.
     pub fn check_range(&self, number: T) -> bool {
         let Self { lt, gt, gte, lte } = self;
         lt.is_none_or(|x| number < x)
@@ -2506,6 +2619,17 @@ impl<T: Copy + PartialOrd> Range<T> {
     }
 }
 
+impl Range<String> {
+    /// Check whether the keyword lies in the range, comparing lexicographically
+    pub fn check_keyword(&self, keyword: &str) -> bool {
+        let Self { lt, gt, gte, lte } = self;
+        lt.as_deref().is_none_or(|x| keyword < x)
+            && gt.as_deref().is_none_or(|x| keyword > x)
+            && lte.as_deref().is_none_or(|x| keyword <= x)
+            && gte.as_deref().is_none_or(|x| keyword >= x)
+    }
+}
+
 /// Values count filter request
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Copy, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -2521,6 +2645,16 @@ pub struct ValuesCount {
 }
 
 impl ValuesCount {
+    /// Condition for exactly `count` values
+    pub fn exact(count: usize) -> Self {
+        Self {
+            lt: None,
+            gt: None,
+            gte: Some(count),
+            lte: Some(count),
+        }
+    }
+
     pub fn check_count(&self, count: usize) -> bool {
         let Self { lt, gt, gte, lte } = self;
         lt.is_none_or(|x| count < x)
@@ -2620,7 +2754,12 @@ pub struct PolygonWrapper {
 impl PolygonWrapper {
     pub fn check_point(&self, point: &GeoPoint) -> bool {
         let point_new = Point::new(point.lon, point.lat);
+        // Polygons crossing the antimeridian are converted with longitudes in [0, 360)
         self.polygon.contains(&point_new)
+            || (point.lon < 0.0
+                && self
+                    .polygon
+                    .contains(&Point::new(point.lon + 360.0, point.lat)))
     }
 }
 
@@ -2665,28 +2804,40 @@ impl GeoPolygon {
         Ok(())
     }
 
+    /// Whether any edge of the exterior crosses the antimeridian.
+    ///
+    /// Edges are assumed to be the shortest way between their points, so an edge spanning
+    /// more than 180 degrees of longitude goes across the antimeridian instead.
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.exterior
+            .points
+            .iter()
+            .tuple_windows()
+            .any(|(a, b)| (b.lon - a.lon).abs() > 180.0)
+    }
+
     // convert GeoPolygon to Geo crate Polygon class for checking point intersection
     pub fn convert(&self) -> PolygonWrapper {
-        let exterior_line: LineString = LineString(
-            self.exterior
-                .points
-                .iter()
-                .map(|p| Coord { x: p.lon, y: p.lat })
-                .collect(),
-        );
+        // Shift the eastern part of a polygon crossing the antimeridian, so it stays continuous
+        let crosses_antimeridian = self.crosses_antimeridian();
+        let to_coord = |p: &GeoPoint| Coord {
+            x: if crosses_antimeridian && p.lon < 0.0 {
+                p.lon + 360.0
+            } else {
+                p.lon
+            },
+            y: p.lat,
+        };
+
+        let exterior_line: LineString =
+            LineString(self.exterior.points.iter().map(to_coord).collect());
 
         // Convert the interior points to coordinates (if any)
         let interior_lines: Vec<LineString> = match &self.interiors {
             None => vec![],
             Some(interiors) => interiors
                 .iter()
-                .map(|interior_points| {
-                    interior_points
-                        .points
-                        .iter()
-                        .map(|p| Coord { x: p.lon, y: p.lat })
-                        .collect()
-                })
+                .map(|interior_points| interior_points.points.iter().map(to_coord).collect())
                 .map(LineString)
                 .collect(),
         };
@@ -2732,6 +2883,9 @@ pub struct FieldCondition {
     /// Check if points value lies in a given range
     #[serde(skip_serializing_if = "Option::is_none")]
     pub range: Option<RangeInterface>,
+    /// Whether any or all values of the field have to lie in the `range`. Default: any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_mode: Option<RangeMode>,
     /// Check if points geolocation lies in a given area
     #[serde(skip_serializing_if = "Option::is_none")]
     pub geo_bounding_box: Option<GeoBoundingBox>,
@@ -2758,6 +2912,7 @@ impl FieldCondition {
             key,
             r#match: Some(r#match),
             range: None,
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             geo_polygon: None,
@@ -2772,6 +2927,7 @@ impl FieldCondition {
             key,
             r#match: None,
             range: Some(RangeInterface::Float(range)),
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             geo_polygon: None,
@@ -2789,6 +2945,22 @@ impl FieldCondition {
             key,
             r#match: None,
             range: Some(RangeInterface::DateTime(datetime_range)),
+            range_mode: None,
+            geo_bounding_box: None,
+            geo_radius: None,
+            geo_polygon: None,
+            values_count: None,
+            is_empty: None,
+            is_null: None,
+        }
+    }
+
+    pub fn new_keyword_range(key: PayloadKeyType, keyword_range: Range<String>) -> Self {
+        Self {
+            key,
+            r#match: None,
+            range: Some(RangeInterface::Keyword(keyword_range)),
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             geo_polygon: None,
@@ -2803,6 +2975,7 @@ impl FieldCondition {
             key,
             r#match: None,
             range: None,
+            range_mode: None,
             geo_bounding_box: Some(geo_bounding_box),
             geo_radius: None,
             geo_polygon: None,
@@ -2817,6 +2990,7 @@ impl FieldCondition {
             key,
             r#match: None,
             range: None,
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: Some(geo_radius),
             geo_polygon: None,
@@ -2831,6 +3005,7 @@ impl FieldCondition {
             key,
             r#match: None,
             range: None,
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             geo_polygon: Some(geo_polygon),
@@ -2845,6 +3020,7 @@ impl FieldCondition {
             key,
             r#match: None,
             range: None,
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             geo_polygon: None,
@@ -2859,6 +3035,7 @@ impl FieldCondition {
             key,
             r#match: None,
             range: None,
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             geo_polygon: None,
@@ -2873,6 +3050,7 @@ impl FieldCondition {
             key,
             r#match: None,
             range: None,
+            range_mode: None,
             geo_bounding_box: None,
             geo_radius: None,
             geo_polygon: None,
@@ -2882,12 +3060,24 @@ impl FieldCondition {
         }
     }
 
+    /// Require all values of the field to lie in the range, instead of any
+    pub fn with_range_mode(mut self, range_mode: RangeMode) -> Self {
+        self.range_mode = Some(range_mode);
+        self
+    }
+
+    /// Whether the range condition requires all values of the field to lie in the range
+    pub fn is_range_all(&self) -> bool {
+        self.range.is_some() && self.range_mode == Some(RangeMode::All)
+    }
+
     pub fn all_fields_none(&self) -> bool {
         matches!(
             self,
             FieldCondition {
                 r#match: None,
                 range: None,
+                range_mode: _,
                 geo_bounding_box: None,
                 geo_radius: None,
                 geo_polygon: None,
@@ -2911,6 +3101,7 @@ impl FieldCondition {
             Match::Text(_) => 0,
             Match::Phrase(_) => 0,
             Match::TextAny(_) => 0,
+            Match::Prefix(_) => 0,
         }
     }
 }
@@ -2973,6 +3164,45 @@ impl Hash for HasIdCondition {
     }
 }
 
+/// Range of numeric point IDs: `gte <= id < lt`
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct IdRange {
+    /// point.id >= range.gte
+    pub gte: Option<u64>,
+    /// point.id < range.lt
+    pub lt: Option<u64>,
+}
+
+impl IdRange {
+    /// Check whether the point ID lies in the range, UUIDs are never in the range
+    pub fn contains(&self, point_id: PointIdType) -> bool {
+        match point_id {
+            ExtendedPointId::NumId(id) => self.contains_num(id),
+            ExtendedPointId::Uuid(_) => false,
+        }
+    }
+
+    pub fn contains_num(&self, id: u64) -> bool {
+        let Self { gte, lt } = self;
+        gte.is_none_or(|gte| id >= gte) && lt.is_none_or(|lt| id < lt)
+    }
+}
+
+/// ID-based filtering condition over a contiguous range of numeric IDs
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HasIdRangeCondition {
+    pub has_id_range: IdRange,
+}
+
+impl From<IdRange> for HasIdRangeCondition {
+    fn from(range: IdRange) -> Self {
+        HasIdRangeCondition {
+            has_id_range: range,
+        }
+    }
+}
+
 /// Filter points which have specific vector assigned
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
 pub struct HasVectorCondition {
@@ -2985,6 +3215,128 @@ impl From<VectorNameBuf> for HasVectorCondition {
     }
 }
 
+/// Filter points which were last updated at or before a specific version
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VersionAtMostCondition {
+    pub version_at_most: SeqNumberType,
+}
+
+impl From<SeqNumberType> for VersionAtMostCondition {
+    fn from(version: SeqNumberType) -> Self {
+        VersionAtMostCondition {
+            version_at_most: version,
+        }
+    }
+}
+
+/// Select points by comparing values of two payload fields of the same point
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
+pub struct FieldVsFieldCondition {
+    pub field_vs_field: FieldComparison,
+}
+
+/// Comparison of two payload fields: `left <op> right`
+///
+/// Fields with multiple values, like arrays, match if any pair of a left and a right value
+/// matches. So `eq` selects points, where one of the left values is one of the right values,
+/// and `lt` selects points, where the smallest left value is less than the largest right value.
+/// Missing fields, empty arrays and null values never match.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
+pub struct FieldComparison {
+    /// Payload key of the left operand
+    pub left: PayloadKeyType,
+    /// Comparison operator
+    pub op: ComparisonOperator,
+    /// Payload key of the right operand
+    pub right: PayloadKeyType,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonOperator {
+    /// left == right
+    Eq,
+    /// left < right
+    Lt,
+    /// left <= right
+    Lte,
+    /// left > right
+    Gt,
+    /// left >= right
+    Gte,
+}
+
+impl ComparisonOperator {
+    fn compare<T: PartialOrd>(self, left: &T, right: &T) -> bool {
+        match self {
+            ComparisonOperator::Eq => left == right,
+            ComparisonOperator::Lt => left < right,
+            ComparisonOperator::Lte => left <= right,
+            ComparisonOperator::Gt => left > right,
+            ComparisonOperator::Gte => left >= right,
+        }
+    }
+}
+
+impl FieldComparison {
+    /// Compare a pair of payload values.
+    ///
+    /// Numbers are compared as numbers, strings are compared as datetimes if both can be parsed.
+    /// Any other values only support equality.
+    pub fn check_values(&self, left: &Value, right: &Value) -> bool {
+        match (left, right) {
+            (Value::Number(left), Value::Number(right)) => {
+                if let (Some(left), Some(right)) = (left.as_i64(), right.as_i64()) {
+                    return self.op.compare(&left, &right);
+                }
+                match (left.as_f64(), right.as_f64()) {
+                    (Some(left), Some(right)) => self.op.compare(&left, &right),
+                    _ => false,
+                }
+            }
+            (Value::String(left), Value::String(right)) => {
+                match (
+                    DateTimePayloadType::from_str(left),
+                    DateTimePayloadType::from_str(right),
+                ) {
+                    (Ok(left), Ok(right)) => self.op.compare(&left, &right),
+                    _ => self.op == ComparisonOperator::Eq && left == right,
+                }
+            }
+            _ => self.op == ComparisonOperator::Eq && left == right,
+        }
+    }
+
+    /// Check the comparison against payload of a single point.
+    ///
+    /// Array values are compared element-wise, the check passes if any pair of values matches.
+    /// Nulls are skipped, same as by field indexes.
+    pub fn check(&self, payload: &impl PayloadContainer) -> bool {
+        fn flatten<'a>(values: &[&'a Value]) -> Vec<&'a Value> {
+            values
+                .iter()
+                .flat_map(|value| match value {
+                    Value::Array(array) => array.iter().collect(),
+                    value => vec![*value],
+                })
+                .filter(|value| !value.is_null())
+                .collect()
+        }
+
+        let left_values = flatten(&payload.get_value(&self.left));
+        if left_values.is_empty() {
+            return false;
+        }
+        let right_values = flatten(&payload.get_value(&self.right));
+
+        left_values.iter().any(|left| {
+            right_values
+                .iter()
+                .any(|right| self.check_values(left, right))
+        })
+    }
+}
+
 /// Threshold determining when to use an `Arc` in `HasIdCondition` if the condition includes many points.
 /// Since we're cloning filters quite a lot, using an Arc for larger conditions reduces risk of memory leaks
 /// and potentially improves performance in some places.
@@ -3062,8 +3414,14 @@ pub enum Condition {
     IsNull(IsNullCondition),
     /// Check if points id is in a given set
     HasId(HasIdCondition),
+    /// Check if points id is in a given range of numeric ids
+    HasIdRange(HasIdRangeCondition),
     /// Check if point has vector assigned
     HasVector(HasVectorCondition),
+    /// Check if point version is not greater than the provided one
+    VersionAtMost(VersionAtMostCondition),
+    /// Compare values of two payload fields
+    FieldVsField(FieldVsFieldCondition),
     /// Nested filters
     Nested(NestedCondition),
     /// Nested filter
@@ -3089,9 +3447,18 @@ impl Hash for Condition {
             Condition::HasId(has_id_condition) => {
                 has_id_condition.hash(state);
             }
+            Condition::HasIdRange(has_id_range_condition) => {
+                has_id_range_condition.hash(state);
+            }
             Condition::HasVector(has_vector_condition) => {
                 has_vector_condition.hash(state);
             }
+            Condition::VersionAtMost(version_at_most_condition) => {
+                version_at_most_condition.hash(state);
+            }
+            Condition::FieldVsField(field_vs_field_condition) => {
+                field_vs_field_condition.hash(state);
+            }
             Condition::Nested(nested_condition) => {
                 nested_condition.hash(state);
             }
@@ -3115,7 +3482,10 @@ impl PartialEq for Condition {
             (Self::IsEmpty(this), Self::IsEmpty(other)) => this == other,
             (Self::IsNull(this), Self::IsNull(other)) => this == other,
             (Self::HasId(this), Self::HasId(other)) => this == other,
+            (Self::HasIdRange(this), Self::HasIdRange(other)) => this == other,
             (Self::HasVector(this), Self::HasVector(other)) => this == other,
+            (Self::VersionAtMost(this), Self::VersionAtMost(other)) => this == other,
+            (Self::FieldVsField(this), Self::FieldVsField(other)) => this == other,
             (Self::Nested(this), Self::Nested(other)) => this == other,
             (Self::Filter(this), Self::Filter(other)) => this == other,
             (Self::CustomIdChecker(_), Self::CustomIdChecker(_)) => false,
@@ -3139,7 +3509,10 @@ impl Condition {
             Condition::Nested(nested) => nested.filter().max_condition_input_size(),
             Condition::IsEmpty(_)
             | Condition::IsNull(_)
+            | Condition::HasIdRange(_)
             | Condition::HasVector(_)
+            | Condition::VersionAtMost(_)
+            | Condition::FieldVsField(_)
             | Condition::CustomIdChecker(_) => 0,
         }
     }
@@ -3155,7 +3528,10 @@ impl Condition {
             | Condition::IsNull(_)
             | Condition::CustomIdChecker(_)
             | Condition::HasId(_)
-            | Condition::HasVector(_) => 1,
+            | Condition::HasIdRange(_)
+            | Condition::HasVector(_)
+            | Condition::VersionAtMost(_)
+            | Condition::FieldVsField(_) => 1,
         }
     }
 
@@ -3165,8 +3541,15 @@ impl Condition {
             Condition::IsEmpty(is_empty_condition) => Some(is_empty_condition.is_empty.key.clone()),
             Condition::IsNull(is_null_condition) => Some(is_null_condition.is_null.key.clone()),
             Condition::Nested(nested_condition) => Some(nested_condition.array_key()),
+            Condition::FieldVsField(field_vs_field) => {
+                Some(field_vs_field.field_vs_field.left.clone())
+            }
             Condition::Filter(filter) => filter.iter_conditions().find_map(|c| c.targeted_key()),
-            Condition::HasId(_) | Condition::HasVector(_) | Condition::CustomIdChecker(_) => None,
+            Condition::HasId(_)
+            | Condition::HasIdRange(_)
+            | Condition::HasVector(_)
+            | Condition::VersionAtMost(_)
+            | Condition::CustomIdChecker(_) => None,
         }
     }
 }
@@ -3176,9 +3559,12 @@ impl Validate for Condition {
     fn validate(&self) -> Result<(), ValidationErrors> {
         match self {
             Condition::HasId(_)
+            | Condition::HasIdRange(_)
             | Condition::IsEmpty(_)
             | Condition::IsNull(_)
-            | Condition::HasVector(_) => Ok(()),
+            | Condition::HasVector(_)
+            | Condition::VersionAtMost(_)
+            | Condition::FieldVsField(_) => Ok(()),
             Condition::Field(field_condition) => field_condition.validate(),
             Condition::Nested(nested_condition) => nested_condition.validate(),
             Condition::Filter(filter) => filter.validate(),
@@ -3776,6 +4162,31 @@ mod tests {
         assert_eq!(expected, transcoded);
     }
 
+    #[test]
+    fn test_range_interface_deserialization() {
+        let range: RangeInterface = serde_json::from_str(r#"{"gte": 1.5}"#).unwrap();
+        assert!(matches!(range, RangeInterface::Float(_)));
+
+        let range: RangeInterface = serde_json::from_str(r#"{"gte": "2020-03-01"}"#).unwrap();
+        assert!(matches!(range, RangeInterface::DateTime(_)));
+
+        // Keyword bounds, which look like datetime, stay keywords when tagged
+        let json = r#"{"keyword":{"lt":null,"gt":null,"gte":"2020-03-01","lte":null}}"#;
+        let range: RangeInterface = serde_json::from_str(json).unwrap();
+        let RangeInterface::Keyword(keyword_range) = &range else {
+            panic!("expected keyword range, got {range:?}");
+        };
+        assert_eq!(keyword_range.gte.as_deref(), Some("2020-03-01"));
+        assert_eq!(serde_json::to_string(&range).unwrap(), json);
+
+        // Untagged keyword bounds are not guessed
+        assert!(serde_json::from_str::<RangeInterface>(r#"{"gte": "apple"}"#).is_err());
+        assert!(
+            serde_json::from_str::<RangeInterface>(r#"{"keyword": {"gte": "a"}, "lt": 1}"#)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_timezone_ordering() {
         let datetimes = [
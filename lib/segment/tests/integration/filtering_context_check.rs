@@ -39,3 +39,34 @@ fn test_filtering_context_consistency() {
         assert_eq!(plain_result, struct_result, "filter: {filter:#?}");
     }
 }
+
+#[test]
+fn test_filtering_context_batch_consistency() {
+    let seed = 42;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let dir = Builder::new().prefix("storage_dir").tempdir().unwrap();
+    let struct_index = create_struct_payload_index(dir.path(), NUM_POINTS, seed);
+
+    let hw_counter = HardwareCounterCell::new();
+
+    let point_ids = (0..NUM_POINTS as PointOffsetType).collect_vec();
+
+    for _ in 0..ATTEMPTS {
+        let filter = random_filter(&mut rng, 3);
+
+        let struct_filter_context = struct_index.filter_context(&filter, &hw_counter);
+
+        let expected = point_ids
+            .iter()
+            .map(|&point_id| struct_filter_context.check(point_id))
+            .collect_vec();
+
+        // Uneven batch size, so the last batch is partial
+        let mut batch_result = vec![false; point_ids.len()];
+        for (ids, out) in point_ids.chunks(333).zip(batch_result.chunks_mut(333)) {
+            struct_filter_context.check_batch(ids, out);
+        }
+        assert_eq!(expected, batch_result, "filter: {filter:#?}");
+    }
+}
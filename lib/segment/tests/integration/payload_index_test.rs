@@ -232,7 +232,7 @@ impl TestSegments {
         }
 
         for (field, indexes) in struct_segment.payload_index.borrow().field_indexes.iter() {
-            for index in indexes {
+            for index in indexes.iter() {
                 assert!(index.count_indexed_points() <= num_points as usize);
                 if field.to_string() != FLICKING_KEY {
                     assert!(
@@ -304,6 +304,7 @@ impl TestSegments {
                         r#type: KeywordIndexType::Keyword,
                         is_tenant: None,
                         on_disk: Some(true),
+                        exists_only: None,
                     },
                 ))),
                 &hw_counter,
@@ -477,7 +478,7 @@ fn build_test_segments_nested_payload(path_struct: &Path, path_plain: &Path) ->
     }
 
     for (_field, indexes) in struct_segment.payload_index.borrow().field_indexes.iter() {
-        for index in indexes {
+        for index in indexes.iter() {
             assert!(index.count_indexed_points() <= num_points as usize);
             assert!(
                 index.count_indexed_points()
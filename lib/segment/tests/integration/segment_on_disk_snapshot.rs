@@ -80,6 +80,7 @@ fn test_on_disk_segment_snapshot(#[case] format: SnapshotFormat) {
                     r#type: segment::data_types::index::KeywordIndexType::Keyword,
                     is_tenant: None,
                     on_disk: Some(true),
+                    exists_only: None,
                 }),
             )),
             &hw_counter,